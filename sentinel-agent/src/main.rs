@@ -12,6 +12,7 @@
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::env;
 use std::process::Command;
 use walkdir::WalkDir;
@@ -20,6 +21,7 @@ use walkdir::WalkDir;
 
 #[derive(Debug, Serialize)]
 struct LogPayload {
+    agent_id: String,
     level: String,
     target: String,
     message: String,
@@ -30,6 +32,36 @@ struct AgentStatus {
     agent_id: String,
     status: String,
     message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    usage: Option<Usage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    report: Option<ReportHandoff>,
+}
+
+/// Where the final report is, so the dashboard can copy it out of the
+/// container instead of guessing a host path.
+#[derive(Debug, Clone, Serialize)]
+struct ReportHandoff {
+    path: String,
+    sha256: String,
+}
+
+#[derive(Debug, Serialize)]
+struct HitlRequest {
+    agent_id: String,
+    action_description: String,
+    parameters: serde_json::Value,
+    risk_level: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct HitlResponse {
+    approved: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct AskResponse {
+    answer: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -51,6 +83,17 @@ struct CompletionRequest {
 #[derive(Debug, Deserialize)]
 struct CompletionResponse {
     choices: Vec<Choice>,
+    #[serde(default)]
+    usage: Option<Usage>,
+}
+
+/// Token counts, in the OpenAI `usage` shape the dashboard also accepts.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+struct Usage {
+    #[serde(default)]
+    prompt_tokens: u64,
+    #[serde(default)]
+    completion_tokens: u64,
 }
 
 #[derive(Debug, Deserialize)]
@@ -73,6 +116,10 @@ struct OllamaRequest {
 #[derive(Debug, Deserialize)]
 struct OllamaResponse {
     message: OllamaMessage,
+    #[serde(default)]
+    prompt_eval_count: u64,
+    #[serde(default)]
+    eval_count: u64,
 }
 
 #[derive(Debug, Deserialize)]
@@ -86,43 +133,128 @@ struct HostCallback {
     client: reqwest::Client,
     callback_url: String,
     agent_id: String,
+    /// Per-agent secret issued by the dashboard; proves callbacks are ours.
+    secret: String,
 }
 
 impl HostCallback {
-    fn new(callback_url: String, agent_id: String) -> Self {
-        Self { client: reqwest::Client::new(), callback_url, agent_id }
+    fn new(callback_url: String, agent_id: String, secret: String) -> Self {
+        Self { client: reqwest::Client::new(), callback_url, agent_id, secret }
+    }
+
+    fn post(&self, route: &str) -> reqwest::RequestBuilder {
+        self.client.post(format!("{}{}", self.callback_url, route))
+            .header("x-sentinel-secret", &self.secret)
     }
 
     async fn log(&self, level: &str, target: &str, message: &str) {
         let payload = LogPayload {
+            agent_id: self.agent_id.clone(),
             level: level.to_string(),
             target: format!("{}::{}", self.agent_id, target),
             message: message.to_string(),
         };
-        let _ = self.client.post(format!("{}/log", self.callback_url))
-            .json(&payload).send().await;
+        let _ = self.post("/log").json(&payload).send().await;
         eprintln!("[{}] {} {}", level.to_uppercase(), target, message);
     }
 
     /// Send a thought that will display as a chat bubble in the UI.
-    /// The entire message is sent as ONE log entry so multi-line content stays together.
     async fn thought(&self, msg: &str) {
-        // Send as a single log entry — the frontend parses "THOUGHT:" prefix
-        self.log("info", "agent", &format!("THOUGHT: {}", msg)).await;
+        let _ = self.post("/thought")
+            .json(&serde_json::json!({ "agent_id": self.agent_id, "message": msg }))
+            .send().await;
+        eprintln!("[THOUGHT] {}", msg);
     }
 
-    async fn status(&self, status: &str, message: &str) {
+    /// Announce a tool call; `detail` is a short preview of its arguments.
+    async fn tool_use(&self, tool: &str, detail: &str) {
+        let _ = self.post("/thought")
+            .json(&serde_json::json!({ "agent_id": self.agent_id, "message": detail, "tool": tool }))
+            .send().await;
+        eprintln!("[TOOL] {} {}", tool, detail);
+    }
+
+    /// Send part of the report inline, for when no report file could be
+    /// written.
+    async fn report_section(&self, title: &str, markdown: &str) {
+        let _ = self.post("/report")
+            .json(&serde_json::json!({ "agent_id": self.agent_id, "title": title, "markdown": markdown }))
+            .send().await;
+    }
+
+    /// Ask the user a question and block until they answer. `None` if they
+    /// didn't answer in time or the host is unreachable.
+    async fn ask(&self, question: &str) -> Option<String> {
+        let resp = self.post("/ask")
+            .timeout(std::time::Duration::from_secs(ASK_TIMEOUT_SECS))
+            .json(&serde_json::json!({ "agent_id": self.agent_id, "question": question }))
+            .send().await;
+        match resp {
+            Ok(r) => r.json::<AskResponse>().await.ok().and_then(|r| r.answer),
+            Err(e) => {
+                eprintln!("[WARN] Question not delivered: {}", e);
+                None
+            }
+        }
+    }
+
+    /// `usage` is the token delta since the last report, if any. The host
+    /// copies `report` out before answering, so this must be sent while the
+    /// file still exists.
+    async fn status(&self, status: &str, message: &str, usage: Option<Usage>, report: Option<ReportHandoff>) {
         let payload = AgentStatus {
             agent_id: self.agent_id.clone(),
             status: status.to_string(),
             message: message.to_string(),
+            usage,
+            report,
+        };
+        let _ = self.post("/status").json(&payload).send().await;
+    }
+
+    /// Ask the user to approve an action and block until they decide.
+    ///
+    /// Any failure to reach the host — including the host-side timeout —
+    /// counts as a rejection.
+    async fn request_approval(&self, action_description: &str, params: serde_json::Value, risk: &str) -> bool {
+        let payload = HitlRequest {
+            agent_id: self.agent_id.clone(),
+            action_description: action_description.to_string(),
+            parameters: params,
+            risk_level: risk.to_string(),
         };
-        let _ = self.client.post(format!("{}/status", self.callback_url))
+        let resp = self.post("/hitl")
+            .timeout(std::time::Duration::from_secs(HITL_TIMEOUT_SECS))
             .json(&payload).send().await;
+        match resp {
+            Ok(r) => r.json::<HitlResponse>().await.map(|r| r.approved).unwrap_or(false),
+            Err(e) => {
+                eprintln!("[WARN] HITL request failed, treating as rejected: {}", e);
+                false
+            }
+        }
+    }
+
+    async fn heartbeat(&self) {
+        let _ = self.post("/heartbeat")
+            .json(&serde_json::json!({ "agent_id": self.agent_id }))
+            .send().await;
+    }
+
+    async fn progress(&self, phase: &str, current: u32, total: u32, detail: &str, usage: Option<Usage>) {
+        let _ = self.post("/progress")
+            .json(&serde_json::json!({
+                "agent_id": self.agent_id,
+                "phase": phase,
+                "current": current,
+                "total": total,
+                "detail": detail,
+                "usage": usage,
+            })).send().await;
     }
 
     async fn gui_active(&self, active: bool) {
-        let _ = self.client.post(format!("{}/gui", self.callback_url))
+        let _ = self.post("/gui")
             .json(&serde_json::json!({
                 "agent_id": self.agent_id,
                 "gui_active": active,
@@ -138,12 +270,30 @@ struct LlmClient {
     model: String,
     api_key: String,
     base_url: String,
+    max_tokens: u32,
+    /// Tokens used since the last `take_usage`.
+    unreported: std::sync::Mutex<Usage>,
+}
+
+/// Per-response token cap unless `SENTINEL_MAX_TOKENS` says otherwise.
+const DEFAULT_MAX_TOKENS: u32 = 4096;
+/// Tool-loop budget unless `SENTINEL_MAX_ITERATIONS` says otherwise.
+const DEFAULT_MAX_ITERATIONS: u32 = 20;
+
+/// Read a positive integer budget from the environment.
+fn env_budget(name: &str, default: u32) -> u32 {
+    env::var(name).ok()
+        .and_then(|v| v.trim().parse::<u32>().ok())
+        .filter(|n| *n > 0)
+        .unwrap_or(default)
 }
 
 impl LlmClient {
     fn new(provider: &str, model: &str, api_key: &str) -> Self {
         let base_url = match provider {
             "ollama" => "http://host.docker.internal:11434".to_string(),
+            // Ollama inside this container (GPU passthrough launches)
+            "ollama-local" => "http://localhost:11434".to_string(),
             "openai" => "https://api.openai.com/v1".to_string(),
             "anthropic" => "https://api.anthropic.com/v1".to_string(),
             "deepseek" => "https://api.deepseek.com/v1".to_string(),
@@ -157,11 +307,25 @@ impl LlmClient {
             model: model.to_string(),
             api_key: api_key.to_string(),
             base_url,
+            max_tokens: env_budget("SENTINEL_MAX_TOKENS", DEFAULT_MAX_TOKENS),
+            unreported: std::sync::Mutex::new(Usage::default()),
         }
     }
 
+    fn add_usage(&self, prompt_tokens: u64, completion_tokens: u64) {
+        let mut unreported = self.unreported.lock().unwrap();
+        unreported.prompt_tokens += prompt_tokens;
+        unreported.completion_tokens += completion_tokens;
+    }
+
+    /// Usage accumulated since the previous call, for the next callback.
+    fn take_usage(&self) -> Option<Usage> {
+        let usage = std::mem::take(&mut *self.unreported.lock().unwrap());
+        (usage.prompt_tokens + usage.completion_tokens > 0).then_some(usage)
+    }
+
     async fn chat(&self, messages: &[ChatMessage]) -> Result<String> {
-        if self.provider == "ollama" {
+        if self.provider.starts_with("ollama") {
             let req = OllamaRequest {
                 model: self.model.clone(),
                 messages: messages.to_vec(),
@@ -171,12 +335,13 @@ impl LlmClient {
                 .post(format!("{}/api/chat", self.base_url))
                 .json(&req).send().await.context("Ollama request failed")?
                 .json::<OllamaResponse>().await.context("Failed to parse Ollama response")?;
+            self.add_usage(resp.prompt_eval_count, resp.eval_count);
             Ok(resp.message.content)
         } else {
             let req = CompletionRequest {
                 model: self.model.clone(),
                 messages: messages.to_vec(),
-                max_tokens: Some(4096),
+                max_tokens: Some(self.max_tokens),
                 temperature: Some(0.2),
             };
             let mut http_req = self.client
@@ -190,7 +355,12 @@ impl LlmClient {
             
             // Try parsing as standard response
             match serde_json::from_str::<CompletionResponse>(&resp_text) {
-                Ok(parsed) => Ok(parsed.choices.first().map(|c| c.message.content.clone()).unwrap_or_default()),
+                Ok(parsed) => {
+                    if let Some(usage) = parsed.usage {
+                        self.add_usage(usage.prompt_tokens, usage.completion_tokens);
+                    }
+                    Ok(parsed.choices.first().map(|c| c.message.content.clone()).unwrap_or_default())
+                }
                 Err(_) => {
                     // Log raw response for debugging
                     eprintln!("[DEBUG] Raw LLM response: {}", &resp_text[..resp_text.len().min(500)]);
@@ -201,12 +371,122 @@ impl LlmClient {
     }
 }
 
+// ── Workspace Mounts ────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Deserialize)]
+struct Mount {
+    path: String,
+    #[serde(default = "default_mount_mode")]
+    mode: String,
+}
+
+fn default_mount_mode() -> String {
+    "rw".to_string()
+}
+
+impl Mount {
+    fn read_only(&self) -> bool {
+        self.mode == "ro"
+    }
+
+    fn contains(&self, path: &str) -> bool {
+        let root = self.path.trim_end_matches('/');
+        path == root || path.starts_with(&format!("{}/", root))
+    }
+
+    /// Exists and has at least one entry.
+    fn populated(&self) -> bool {
+        std::fs::read_dir(&self.path).map(|mut d| d.next().is_some()).unwrap_or(false)
+    }
+}
+
+/// The directories the host mounted into the container. The first mount is
+/// the primary root: relative tool paths and shell commands start there.
+#[derive(Debug, Clone)]
+struct Workspace {
+    mounts: Vec<Mount>,
+}
+
+impl Workspace {
+    /// Read `SENTINEL_MOUNTS`, falling back to the single `SENTINEL_TARGET_DIR`.
+    fn from_env() -> Self {
+        let mounts = env::var("SENTINEL_MOUNTS").ok()
+            .and_then(|raw| serde_json::from_str::<Vec<Mount>>(&raw).ok())
+            .filter(|m| !m.is_empty())
+            .unwrap_or_else(|| {
+                let dir = env::var("SENTINEL_TARGET_DIR").unwrap_or_else(|_| "/workspace".to_string());
+                vec![Mount { path: dir, mode: default_mount_mode() }]
+            });
+        Self { mounts }
+    }
+
+    fn primary(&self) -> &str {
+        &self.mounts[0].path
+    }
+
+    /// Relative paths resolve against the primary root; absolute paths are
+    /// taken as-is so any mount is reachable.
+    fn resolve(&self, path: &str) -> String {
+        let path = path.trim();
+        if path.starts_with('/') { path.to_string() } else { format!("{}/{}", self.primary(), path) }
+    }
+
+    /// The innermost mount holding `path`.
+    fn mount_for(&self, path: &str) -> Option<&Mount> {
+        self.mounts.iter().filter(|m| m.contains(path)).max_by_key(|m| m.path.len())
+    }
+
+    fn is_read_only(&self, path: &str) -> bool {
+        self.mount_for(path).is_some_and(Mount::read_only)
+    }
+
+    /// Show paths under the primary root as `./...`, others in full.
+    fn display(&self, path: &str) -> String {
+        match path.strip_prefix(self.primary().trim_end_matches('/')) {
+            Some(rest) if rest.is_empty() || rest.starts_with('/') => format!(".{}", rest),
+            _ => path.to_string(),
+        }
+    }
+
+    fn populated(&self) -> Vec<&Mount> {
+        self.mounts.iter().filter(|m| m.populated()).collect()
+    }
+
+    /// Where the report goes: the first writable mount that exists.
+    fn report_dir(&self) -> Option<&str> {
+        self.mounts.iter()
+            .find(|m| !m.read_only() && std::path::Path::new(&m.path).is_dir())
+            .map(|m| m.path.as_str())
+    }
+
+    fn describe(&self) -> String {
+        self.mounts.iter().enumerate()
+            .map(|(i, m)| format!(
+                "  {} ({}{})",
+                m.path,
+                if m.read_only() { "read-only" } else { "read-write" },
+                if i == 0 { ", primary" } else { "" },
+            ))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
 // ── Tool Execution ──────────────────────────────────────────────────────────
 
-fn execute_tool(tool_name: &str, args: &str, target_dir: &str) -> String {
+/// reqwest picks up `HTTP(S)_PROXY` on its own; Chromium needs it spelled out
+/// so the browser tools stay inside the host's network isolation.
+fn chromium_proxy_flag() -> String {
+    env::var("HTTPS_PROXY")
+        .or_else(|_| env::var("HTTP_PROXY"))
+        .map(|proxy| format!("--proxy-server={} ", proxy))
+        .unwrap_or_default()
+}
+
+fn execute_tool(tool_name: &str, args: &str, workspace: &Workspace) -> String {
     match tool_name {
         "read_file" => {
-            let path = if args.starts_with('/') { args.to_string() } else { format!("{}/{}", target_dir, args) };
+            let path = workspace.resolve(args);
             match std::fs::read_to_string(&path) {
                 Ok(content) => {
                     if content.len() > 15_000 {
@@ -221,8 +501,10 @@ fn execute_tool(tool_name: &str, args: &str, target_dir: &str) -> String {
         "write_file" => {
             let parts: Vec<&str> = args.splitn(2, "\n---CONTENT---\n").collect();
             if parts.len() < 2 { return "Error: write_file format must be 'path\\n---CONTENT---\\ncontent'".to_string(); }
-            let file_path = parts[0].trim();
-            let path = if file_path.starts_with('/') { file_path.to_string() } else { format!("{}/{}", target_dir, file_path) };
+            let path = workspace.resolve(parts[0]);
+            if workspace.is_read_only(&path) {
+                return format!("Error writing {}: the mount is read-only", path);
+            }
             // Create parent directories if needed
             if let Some(parent) = std::path::Path::new(&path).parent() {
                 let _ = std::fs::create_dir_all(parent);
@@ -233,18 +515,17 @@ fn execute_tool(tool_name: &str, args: &str, target_dir: &str) -> String {
             }
         }
         "list_files" => {
-            let dir = if args.trim().is_empty() { target_dir } else { args.trim() };
+            let dir = if args.trim().is_empty() { workspace.primary().to_string() } else { workspace.resolve(args) };
             let mut files = Vec::new();
-            for entry in WalkDir::new(dir).max_depth(3).into_iter()
+            for e in WalkDir::new(&dir).max_depth(3).into_iter()
                 .filter_entry(|e| {
                     let n = e.file_name().to_string_lossy();
                     !["target", "node_modules", ".git", "dist", "build", "__pycache__"].contains(&n.as_ref())
                 })
+                .flatten()
             {
-                if let Ok(e) = entry {
-                    if e.file_type().is_file() {
-                        files.push(e.path().to_string_lossy().replace(target_dir, "."));
-                    }
+                if e.file_type().is_file() {
+                    files.push(workspace.display(&e.path().to_string_lossy()));
                 }
             }
             if files.is_empty() { "No files found.".to_string() }
@@ -252,8 +533,9 @@ fn execute_tool(tool_name: &str, args: &str, target_dir: &str) -> String {
         }
         "shell" => {
             let cmd = args.trim();
+            let cwd = if std::path::Path::new(workspace.primary()).is_dir() { workspace.primary() } else { "/" };
             match Command::new("sh").arg("-c").arg(cmd)
-                .current_dir(target_dir)
+                .current_dir(cwd)
                 .output() {
                 Ok(output) => {
                     let stdout = String::from_utf8_lossy(&output.stdout);
@@ -272,15 +554,15 @@ fn execute_tool(tool_name: &str, args: &str, target_dir: &str) -> String {
             let screenshot_path = "/tmp/screenshot.png";
             let _ = Command::new("sh").arg("-c")
                 .arg(format!(
-                    "DISPLAY=:99 chromium --no-sandbox --disable-gpu --headless=new --screenshot={} --window-size=1280,720 '{}' 2>/dev/null",
-                    screenshot_path, url
+                    "DISPLAY=:99 chromium {}--no-sandbox --disable-gpu --headless=new --screenshot={} --window-size=1280,720 '{}' 2>/dev/null",
+                    chromium_proxy_flag(), screenshot_path, url
                 )).output();
             
             if std::path::Path::new(screenshot_path).exists() {
                 format!("Browser navigated to: {}\nScreenshot saved to {}\nNote: The live browser is visible in the noVNC stream.", url, screenshot_path)
             } else {
                 let _ = Command::new("sh").arg("-c")
-                    .arg(format!("DISPLAY=:99 chromium --no-sandbox --disable-gpu '{}' &", url))
+                    .arg(format!("DISPLAY=:99 chromium {}--no-sandbox --disable-gpu '{}' &", chromium_proxy_flag(), url))
                     .output();
                 format!("Opened {} in the browser. The user can see this in the live view.", url)
             }
@@ -289,7 +571,7 @@ fn execute_tool(tool_name: &str, args: &str, target_dir: &str) -> String {
             let query = args.trim().replace(' ', "+");
             let url = format!("https://www.google.com/search?q={}", query);
             let _ = Command::new("sh").arg("-c")
-                .arg(format!("DISPLAY=:99 chromium --no-sandbox --disable-gpu '{}' &", url))
+                .arg(format!("DISPLAY=:99 chromium {}--no-sandbox --disable-gpu '{}' &", chromium_proxy_flag(), url))
                 .output();
             format!("Searching the web for: {}\nOpened in browser. Results visible in live view.", args.trim())
         }
@@ -302,6 +584,310 @@ fn execute_tool(tool_name: &str, args: &str, target_dir: &str) -> String {
     }
 }
 
+// ── HITL Gate ───────────────────────────────────────────────────────────────
+
+/// Slightly longer than the host's approval timeout so the host decides.
+const HITL_TIMEOUT_SECS: u64 = 310;
+
+/// Slightly longer than the host's timeout for answering `/ask`.
+const ASK_TIMEOUT_SECS: u64 = 610;
+
+/// The only file the agent may write without asking.
+const REPORT_FILE: &str = "SENTINEL_REPORT.md";
+
+/// Report location when no writable workspace is mounted. Not under /tmp:
+/// that is a tmpfs, which Docker's archive API can't read.
+const REPORT_FALLBACK_DIR: &str = "/var/tmp/sentinel";
+
+/// Write the report and describe it for the final status callback.
+fn write_report(dir: &str, report: &str) -> std::io::Result<ReportHandoff> {
+    std::fs::create_dir_all(dir)?;
+    let path = format!("{}/{}", dir.trim_end_matches('/'), REPORT_FILE);
+    std::fs::write(&path, report)?;
+    let sha256 = Sha256::digest(report.as_bytes()).iter().map(|b| format!("{:02x}", b)).collect();
+    Ok(ReportHandoff { path, sha256 })
+}
+
+/// Programs a shell command may run without approval. They only read, and
+/// none of their options writes a file or runs another program, save the
+/// ones `is_read_only_command` turns away.
+const READ_ONLY_COMMANDS: &[&str] = &[
+    "cat", "head", "tail", "ls", "pwd", "echo", "wc", "grep", "find", "sort", "cut", "tr",
+    "diff", "stat", "du", "df", "basename", "dirname", "realpath", "which", "uname", "whoami",
+];
+
+/// `find` actions that delete, write a file or run a program.
+const FIND_WRITE_ACTIONS: &[&str] = &[
+    "-delete", "-exec", "-execdir", "-ok", "-okdir", "-fprint", "-fprint0", "-fprintf", "-fls",
+];
+
+/// `sort` long options that write a file or run a program. Any unambiguous
+/// prefix works too (`--out=file`).
+const SORT_WRITE_OPTIONS: &[&str] = &["--output", "--compress-program"];
+
+/// A shell command split into simple commands (on `;`, `&`, `|`, `&&`,
+/// `||`, newlines, subshells and substitutions), plus the files it
+/// redirects output to and which expansions it uses. Quotes and
+/// backslashes are honoured.
+#[derive(Default)]
+struct ShellCommand {
+    commands: Vec<Vec<String>>,
+    redirects: Vec<String>,
+    /// A `$` or backtick outside single quotes: the shell substitutes text
+    /// the parser never sees.
+    substitutes: bool,
+    /// An unquoted `*`, `?`, `[` or `{`: a word may expand into others.
+    globs: bool,
+}
+
+impl ShellCommand {
+    fn parse(cmd: &str) -> Self {
+        let mut parsed = Self::default();
+        let mut words: Vec<String> = Vec::new();
+        let mut word = String::new();
+        let mut in_word = false;
+        // Set after `>`: the next word is a redirect target, not an argument.
+        let mut redirect = false;
+        let mut chars = cmd.chars().peekable();
+
+        let end_word = |word: &mut String, in_word: &mut bool, redirect: &mut bool, words: &mut Vec<String>, redirects: &mut Vec<String>| {
+            if !*in_word {
+                return;
+            }
+            let w = std::mem::take(word);
+            *in_word = false;
+            if *redirect {
+                *redirect = false;
+                // `>&2` duplicates a descriptor; `/dev/null` discards.
+                if !w.starts_with('&') && w != "/dev/null" {
+                    redirects.push(w);
+                }
+            } else {
+                words.push(w);
+            }
+        };
+
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => {
+                    if let Some(next) = chars.next() {
+                        word.push(next);
+                        in_word = true;
+                    }
+                }
+                '\'' => {
+                    in_word = true;
+                    for q in chars.by_ref() {
+                        if q == '\'' {
+                            break;
+                        }
+                        word.push(q);
+                    }
+                }
+                '"' => {
+                    in_word = true;
+                    while let Some(q) = chars.next() {
+                        match q {
+                            '"' => break,
+                            '$' | '`' => {
+                                parsed.substitutes = true;
+                                word.push(q);
+                            }
+                            '\\' => {
+                                if let Some(next) = chars.next() {
+                                    word.push(next);
+                                }
+                            }
+                            q => word.push(q),
+                        }
+                    }
+                }
+                '>' => {
+                    // `2>file`: the digits name a descriptor, not an argument.
+                    if in_word && !redirect && word.chars().all(|d| d.is_ascii_digit()) {
+                        word.clear();
+                        in_word = false;
+                    }
+                    end_word(&mut word, &mut in_word, &mut redirect, &mut words, &mut parsed.redirects);
+                    if matches!(chars.peek(), Some('>') | Some('|')) {
+                        chars.next();
+                    }
+                    redirect = true;
+                }
+                '<' => {
+                    end_word(&mut word, &mut in_word, &mut redirect, &mut words, &mut parsed.redirects);
+                    // Input redirects read a file; skip the target.
+                    while chars.peek().is_some_and(|c| c.is_whitespace()) {
+                        chars.next();
+                    }
+                    while chars.peek().is_some_and(|c| !c.is_whitespace() && !";&|()<>".contains(*c)) {
+                        chars.next();
+                    }
+                }
+                '&' if chars.peek() == Some(&'>') => {
+                    // `&>file` redirects both streams.
+                    end_word(&mut word, &mut in_word, &mut redirect, &mut words, &mut parsed.redirects);
+                    chars.next();
+                    if chars.peek() == Some(&'>') {
+                        chars.next();
+                    }
+                    redirect = true;
+                }
+                '&' if redirect && !in_word => {
+                    // The `&1` of `>&1`.
+                    word.push(c);
+                    in_word = true;
+                }
+                ';' | '&' | '|' | '\n' | '(' | ')' | '`' => {
+                    if c == '`' {
+                        parsed.substitutes = true;
+                    }
+                    end_word(&mut word, &mut in_word, &mut redirect, &mut words, &mut parsed.redirects);
+                    if !words.is_empty() {
+                        parsed.commands.push(std::mem::take(&mut words));
+                    }
+                }
+                '$' if chars.peek() == Some(&'(') => {
+                    parsed.substitutes = true;
+                    end_word(&mut word, &mut in_word, &mut redirect, &mut words, &mut parsed.redirects);
+                    if !words.is_empty() {
+                        parsed.commands.push(std::mem::take(&mut words));
+                    }
+                }
+                '$' => {
+                    parsed.substitutes = true;
+                    word.push(c);
+                    in_word = true;
+                }
+                '*' | '?' | '[' | '{' => {
+                    parsed.globs = true;
+                    word.push(c);
+                    in_word = true;
+                }
+                c if c.is_whitespace() => {
+                    end_word(&mut word, &mut in_word, &mut redirect, &mut words, &mut parsed.redirects);
+                }
+                c => {
+                    word.push(c);
+                    in_word = true;
+                }
+            }
+        }
+        end_word(&mut word, &mut in_word, &mut redirect, &mut words, &mut parsed.redirects);
+        if !words.is_empty() {
+            parsed.commands.push(words);
+        }
+        parsed
+    }
+}
+
+/// Whether a simple command runs an allowlisted program with no option
+/// that writes or executes. Only the bare program name counts: a path
+/// could point at anything.
+fn is_read_only_command(words: &[String], globs: bool) -> bool {
+    let Some((program, args)) = words.split_first() else {
+        return true;
+    };
+    if !READ_ONLY_COMMANDS.contains(&program.as_str()) {
+        return false;
+    }
+    match program.as_str() {
+        // A glob could expand into one of the options checked below.
+        "find" | "sort" if globs => false,
+        "find" => !args.iter().any(|arg| FIND_WRITE_ACTIONS.contains(&arg.as_str())),
+        "sort" => !args.iter().any(|arg| {
+            let name = arg.split('=').next().unwrap_or(arg);
+            if name.starts_with("--") {
+                name.len() > 2 && SORT_WRITE_OPTIONS.iter().any(|o| o.starts_with(name))
+            } else {
+                // `-o file`, or `-o` inside a cluster such as `-ro`.
+                name.starts_with('-') && name.contains('o')
+            }
+        }),
+        _ => true,
+    }
+}
+
+/// Whether a shell command line may run without approval: every simple
+/// command in it is read-only, it redirects output into no file, and it
+/// substitutes nothing.
+fn is_read_only_shell(cmd: &str) -> bool {
+    let parsed = ShellCommand::parse(cmd);
+    parsed.redirects.is_empty()
+        && !parsed.substitutes
+        && parsed.commands.iter().all(|words| is_read_only_command(words, parsed.globs))
+}
+
+/// `path` with `.` and `..` components resolved lexically.
+fn normalize_path(path: &std::path::Path) -> std::path::PathBuf {
+    let mut normalized = std::path::PathBuf::new();
+    for component in path.components() {
+        match component {
+            std::path::Component::CurDir => {}
+            std::path::Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+/// Decide whether a tool call needs human approval.
+///
+/// Returns the manifest description, parameters, and risk level, or `None`
+/// when the call may run unattended.
+fn approval_needed(tool_name: &str, args: &str, autonomy: &str, workspace: &Workspace) -> Option<(String, serde_json::Value, &'static str)> {
+    if autonomy == "full" {
+        return None;
+    }
+    match tool_name {
+        "write_file" => {
+            let mut parts = args.splitn(2, "\n---CONTENT---\n");
+            let path = parts.next().unwrap_or("").trim();
+            let size = parts.next().map_or(0, str::len);
+            // Only the report itself, where `write_report` puts it, is exempt;
+            // a symlink planted there could redirect the write.
+            let report = std::path::Path::new(workspace.report_dir().unwrap_or(REPORT_FALLBACK_DIR)).join(REPORT_FILE);
+            let target = normalize_path(std::path::Path::new(&workspace.resolve(path)));
+            if target == normalize_path(&report) && !target.is_symlink() {
+                return None;
+            }
+            Some((format!("Write file: {}", path), serde_json::json!({ "path": path, "size_bytes": size }), "Medium"))
+        }
+        "shell" => {
+            let cmd = args.trim();
+            if is_read_only_shell(cmd) {
+                return None;
+            }
+            Some((format!("Run shell command: {}", cmd), serde_json::json!({ "command": cmd }), "High"))
+        }
+        _ => None,
+    }
+}
+
+/// Run a tool, asking the host for approval first when required.
+async fn execute_tool_gated(host: &HostCallback, tool_name: &str, args: &str, workspace: &Workspace, autonomy: &str) -> String {
+    if let Some((description, params, risk)) = approval_needed(tool_name, args, autonomy, workspace) {
+        host.log("info", "hitl", &format!("Requesting approval: {}", description)).await;
+        if !host.request_approval(&description, params, risk).await {
+            host.thought(&format!("🚫 Rejected: {}", description)).await;
+            return format!("Action rejected by the user: {}. Do not retry it; choose another approach or finish.", description);
+        }
+        host.log("info", "hitl", &format!("Approved: {}", description)).await;
+    }
+    execute_tool(tool_name, args, workspace)
+}
+
+/// First line of tool arguments, shortened for the chat.
+fn preview(args: &str) -> String {
+    let line = args.lines().next().unwrap_or("").trim();
+    match line.char_indices().nth(120) {
+        Some((i, _)) => format!("{}…", &line[..i]),
+        None => line.to_string(),
+    }
+}
+
 fn parse_tool_call(response: &str) -> Option<(String, String)> {
     // Look for tool calls in format: [TOOL:tool_name] args [/TOOL]
     let start = response.find("[TOOL:")?;
@@ -323,16 +909,15 @@ fn parse_tool_call(response: &str) -> Option<(String, String)> {
 fn discover_files(dir: &str) -> Vec<String> {
     let mut files = Vec::new();
     if !std::path::Path::new(dir).exists() { return files; }
-    for entry in WalkDir::new(dir).max_depth(4).into_iter()
+    for e in WalkDir::new(dir).max_depth(4).into_iter()
         .filter_entry(|e| {
             let n = e.file_name().to_string_lossy();
             !["target", "node_modules", ".git", "dist", "build", "__pycache__", ".next"].contains(&n.as_ref())
         })
+        .flatten()
     {
-        if let Ok(e) = entry {
-            if e.file_type().is_file() {
-                files.push(e.path().to_string_lossy().to_string());
-            }
+        if e.file_type().is_file() {
+            files.push(e.path().to_string_lossy().to_string());
         }
     }
     files
@@ -368,7 +953,8 @@ async fn run_subagent(
     llm: &LlmClient,
     host: &HostCallback,
     task: &str,
-    target_dir: &str,
+    workspace: &Workspace,
+    autonomy: &str,
     parent_context: &str,
 ) -> String {
     host.thought(&format!("🔀 Delegating sub-task: *{}*", task)).await;
@@ -406,8 +992,8 @@ async fn run_subagent(
         }
 
         if let Some((tool_name, tool_args)) = parse_tool_call(&response) {
-            host.log("info", "sub-agent", &format!("Using tool: {}", tool_name)).await;
-            let result = execute_tool(&tool_name, &tool_args, target_dir);
+            host.tool_use(&tool_name, &preview(&tool_args)).await;
+            let result = execute_tool_gated(host, &tool_name, &tool_args, workspace, autonomy).await;
             messages.push(ChatMessage { role: "assistant".into(), content: response });
             messages.push(ChatMessage { role: "user".into(), content: format!("[Tool Result for {}]\n{}", tool_name, result) });
         } else {
@@ -432,20 +1018,32 @@ async fn main() -> Result<()> {
 
     let callback_url = env::var("SENTINEL_CALLBACK_URL").unwrap_or_else(|_| "http://host.docker.internal:9876".to_string());
     let agent_id = env::var("SENTINEL_AGENT_ID").unwrap_or_else(|_| "agent-001".to_string());
+    let callback_secret = env::var("SENTINEL_CALLBACK_SECRET").unwrap_or_default();
     let provider = env::var("SENTINEL_PROVIDER").unwrap_or_else(|_| "ollama".to_string());
     let model = env::var("SENTINEL_MODEL").unwrap_or_else(|_| "llama3.1:8b".to_string());
-    let api_key = env::var("SENTINEL_API_KEY").unwrap_or_default();
-    let target_dir = env::var("SENTINEL_TARGET_DIR").unwrap_or_else(|_| "/workspace".to_string());
+    // The dashboard mounts the key as a file; env is the legacy fallback.
+    let api_key = env::var("SENTINEL_API_KEY_FILE").ok()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .map(|key| key.trim().to_string())
+        .or_else(|| env::var("SENTINEL_API_KEY").ok())
+        .unwrap_or_default();
+    let workspace = Workspace::from_env();
     let task = env::var("SENTINEL_TASK").unwrap_or_else(|_| "Help me with this project".to_string());
     let autonomy = env::var("SENTINEL_AUTONOMY").unwrap_or_else(|_| "read_report".to_string());
+    // Set by the dashboard when it re-creates this container after a crash.
+    let resuming = env::var("SENTINEL_RESUME").as_deref() == Ok("auto");
 
-    let host = HostCallback::new(callback_url, agent_id);
+    let host = HostCallback::new(callback_url, agent_id, callback_secret);
     let llm = LlmClient::new(&provider, &model, &api_key);
 
     host.log("info", "agent", "═══ SENTINEL Agent starting ═══").await;
-    host.thought(&format!("Task received: **{}**", task)).await;
+    if resuming {
+        host.thought(&format!("Restarted after a crash; resuming: **{}**", task)).await;
+    } else {
+        host.thought(&format!("Task received: **{}**", task)).await;
+    }
     host.log("info", "agent", &format!("Provider: {} ({})", provider, model)).await;
-    host.status("running", "Agent started").await;
+    host.status("running", "Agent started", None, None).await;
 
     // Determine if GUI is needed
     let use_gui = needs_gui(&task);
@@ -455,14 +1053,18 @@ async fn main() -> Result<()> {
     }
 
     // Build workspace context
-    let has_workspace = std::path::Path::new(&target_dir).exists() && 
-        std::fs::read_dir(&target_dir).map(|mut d| d.next().is_some()).unwrap_or(false);
+    let populated = workspace.populated();
+    let has_workspace = !populated.is_empty();
+    let files: Vec<String> = populated.iter().flat_map(|m| discover_files(&m.path)).collect();
 
     let workspace_overview = if has_workspace {
-        let files = discover_files(&target_dir);
-        let file_list: Vec<String> = files.iter().map(|f| f.replace(&target_dir, ".")).collect();
+        let file_list: Vec<String> = files.iter().map(|f| workspace.display(f)).collect();
         let preview: Vec<&String> = file_list.iter().take(40).collect();
-        format!("Files:\n{}", preview.iter().map(|f| format!("  {}", f)).collect::<Vec<_>>().join("\n"))
+        format!(
+            "Mounted directories:\n{}\n\nFiles:\n{}",
+            workspace.describe(),
+            preview.iter().map(|f| format!("  {}", f)).collect::<Vec<_>>().join("\n")
+        )
     } else {
         "No workspace mounted. You're running without a project folder.".to_string()
     };
@@ -470,13 +1072,12 @@ async fn main() -> Result<()> {
     // Read key files
     let mut file_contexts = Vec::new();
     if has_workspace {
-        let files = discover_files(&target_dir);
         let priority = ["README.md", "readme.md", "Cargo.toml", "package.json", "pyproject.toml", "go.mod"];
         for file in &files {
             let basename = std::path::Path::new(file).file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
             if priority.contains(&basename.as_str()) {
                 if let Some(content) = read_file_safe(file, 6_000) {
-                    file_contexts.push(format!("### {}\n```\n{}\n```", file.replace(&target_dir, "."), content));
+                    file_contexts.push(format!("### {}\n```\n{}\n```", workspace.display(file), content));
                 }
             }
         }
@@ -488,7 +1089,7 @@ async fn main() -> Result<()> {
 You can call tools by writing [TOOL:tool_name] followed by args and [/TOOL].
 
 ### read_file
-Read a file from the workspace. Args: relative file path.
+Read a file from the workspace. Args: path relative to the primary mount, or absolute under any mount.
 Example: [TOOL:read_file]src/main.rs[/TOOL]
 
 ### write_file
@@ -504,8 +1105,8 @@ Example: [TOOL:list_files][/TOOL]
 
 ### shell
 Run a shell command inside the container. Args: the command.
-IMPORTANT: Always use absolute paths or `cd /workspace && command`.
-Example: [TOOL:shell]cd /workspace && ls -la[/TOOL]
+Commands start in the primary mount; use absolute paths for the other mounts.
+Example: [TOOL:shell]ls -la[/TOOL]
 
 ### browse
 Open a URL in the browser (visible to the user in live view). Args: URL.
@@ -531,7 +1132,7 @@ Example: [TOOL:delegate]Analyze all Python files for security issues[/TOOL]
 ## IMPORTANT
 - If the user asks you a question, answer it directly — don't just use tools.
 - Talk to the user naturally. Your responses will appear as chat messages.
-- When using shell commands, always use absolute paths (prefix with /workspace/).
+- Read-only mounts cannot be written; put new files in a read-write mount.
 - If you need information from the user, ask clearly and wait for their response.
 "#;
 
@@ -550,14 +1151,26 @@ Example: [TOOL:delegate]Analyze all Python files for security issues[/TOOL]
     );
 
     // Tool-use conversation loop
+    let first_message = if resuming {
+        format!(
+            "{}\n\nYou were restarted after a crash partway through this task. \
+            Changes you already made in the workspace are still there; check them before redoing work.",
+            task
+        )
+    } else {
+        task.clone()
+    };
     let mut messages = vec![
         ChatMessage { role: "system".into(), content: system_prompt },
-        ChatMessage { role: "user".into(), content: task.clone() },
+        ChatMessage { role: "user".into(), content: first_message },
     ];
 
-    let max_iterations = 20;
+    let mut final_report = None;
+    let max_iterations = env_budget("SENTINEL_MAX_ITERATIONS", DEFAULT_MAX_ITERATIONS);
     for iteration in 0..max_iterations {
-        host.log("info", "agent", &format!("THOUGHT: Waiting for LLM response from {}...", provider)).await;
+        host.heartbeat().await;
+        host.progress("tool-loop", iteration + 1, max_iterations, "", llm.take_usage()).await;
+        host.log("info", "agent", &format!("Waiting for LLM response from {}...", provider)).await;
 
         let response = match llm.chat(&messages).await {
             Ok(r) => r,
@@ -584,32 +1197,28 @@ Example: [TOOL:delegate]Analyze all Python files for security issues[/TOOL]
 
             host.thought(&summary).await;
 
-            // Write report
-            if has_workspace {
-                let report = format!(
-                    "# Sentinel Agent Report\n\n**Task:** {}\n\n---\n\n## Summary\n\n{}\n\n---\n\n{}\n",
-                    task, summary, report_body
-                );
-                let report_path = format!("{}/SENTINEL_REPORT.md", target_dir);
-                match std::fs::write(&report_path, &report) {
-                    Ok(_) => {
-                        host.thought("✅ Full report written to `SENTINEL_REPORT.md`").await;
-                    }
-                    Err(e) => {
-                        host.log("warn", "agent", &format!("Could not write report: {}", e)).await;
-                        host.thought(&report_body).await;
-                    }
+            // Write report; the dashboard copies it out and shows it in chat.
+            let report = format!(
+                "# Sentinel Agent Report\n\n**Task:** {}\n\n---\n\n## Summary\n\n{}\n\n---\n\n{}\n",
+                task, summary, report_body
+            );
+            let report_dir = workspace.report_dir().filter(|_| has_workspace).unwrap_or(REPORT_FALLBACK_DIR);
+            match write_report(report_dir, &report) {
+                Ok(handoff) => {
+                    host.thought(&format!("✅ Full report written to `{}`", handoff.path)).await;
+                    final_report = Some(handoff);
+                }
+                Err(e) => {
+                    host.log("warn", "agent", &format!("Could not write report: {}", e)).await;
+                    host.report_section("Report", &report_body).await;
                 }
-            } else {
-                // No workspace — just send the full report in chat
-                host.thought(&report_body).await;
             }
             break;
         }
 
         // Check for tool call
         if let Some((tool_name, tool_args)) = parse_tool_call(&response) {
-            host.thought(&format!("Using tool: **{}**", tool_name)).await;
+            host.tool_use(&tool_name, &preview(&tool_args)).await;
 
             if tool_name == "browse" || tool_name == "search_web" {
                 host.gui_active(true).await;
@@ -618,9 +1227,9 @@ Example: [TOOL:delegate]Analyze all Python files for security issues[/TOOL]
             let result = if tool_name == "delegate" {
                 // Run a sub-agent
                 let parent_ctx = format!("Main task: {}", task);
-                run_subagent(&llm, &host, &tool_args, &target_dir, &parent_ctx).await
+                run_subagent(&llm, &host, &tool_args, &workspace, &autonomy, &parent_ctx).await
             } else {
-                execute_tool(&tool_name, &tool_args, &target_dir)
+                execute_tool_gated(&host, &tool_name, &tool_args, &workspace, &autonomy).await
             };
 
             host.log("info", "agent", &format!("Tool result ({}): {} chars", tool_name, result.len())).await;
@@ -630,17 +1239,23 @@ Example: [TOOL:delegate]Analyze all Python files for security issues[/TOOL]
             messages.push(ChatMessage { role: "user".into(), content: format!("[Tool Result for {}]\n{}", tool_name, result) });
         } else {
             // No tool call — this is natural language from the agent (question or statement)
-            let clean = response.trim();
-            if !clean.is_empty() {
-                host.thought(clean).await;
-            }
+            let clean = response.trim().to_string();
+            let answer = if clean.ends_with('?') {
+                host.ask(&clean).await
+            } else {
+                if !clean.is_empty() {
+                    host.thought(&clean).await;
+                }
+                None
+            };
             messages.push(ChatMessage { role: "assistant".into(), content: response });
-            // Give the agent a chance to continue or receive user input
-            messages.push(ChatMessage {
-                role: "user".into(),
-                content: "Continue with the task. If you need more information, ask clearly. \
-                         Use tools if needed, or respond with [DONE] and your final answer if finished.".to_string()
-            });
+            let content = match answer {
+                Some(answer) => answer,
+                // Give the agent a chance to continue or receive user input
+                None => "Continue with the task. If you need more information, ask clearly. \
+                         Use tools if needed, or respond with [DONE] and your final answer if finished.".to_string(),
+            };
+            messages.push(ChatMessage { role: "user".into(), content });
         }
 
         if iteration == max_iterations - 1 {
@@ -653,6 +1268,126 @@ Example: [TOOL:delegate]Analyze all Python files for security issues[/TOOL]
     }
 
     host.thought("Task complete. Send me a message if you need anything else!").await;
-    host.status("completed", "Task completed").await;
+    host.status("completed", "Task completed", llm.take_usage(), final_report).await;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commands(cmd: &str) -> Vec<Vec<String>> {
+        ShellCommand::parse(cmd).commands
+    }
+
+    #[test]
+    fn test_parse_quoting() {
+        assert_eq!(commands(r#"echo 'a b' "c d" e\ f"#), [["echo", "a b", "c d", "e f"]]);
+        assert_eq!(commands(r#"grep "x; y | z" 'a && b' file"#), [["grep", "x; y | z", "a && b", "file"]]);
+        assert_eq!(commands(r#"echo "say \"hi\"" it\'s"#), [["echo", "say \"hi\"", "it's"]]);
+        assert_eq!(commands("echo ''"), [["echo", ""]]);
+    }
+
+    #[test]
+    fn test_parse_pipes_and_lists() {
+        assert_eq!(
+            commands("cat a | grep b && echo ok; pwd || ls & wc -l\nuname"),
+            [vec!["cat", "a"], vec!["grep", "b"], vec!["echo", "ok"], vec!["pwd"], vec!["ls"], vec!["wc", "-l"], vec!["uname"]],
+        );
+        assert_eq!(commands("(cd src; ls)"), [vec!["cd", "src"], vec!["ls"]]);
+    }
+
+    #[test]
+    fn test_parse_redirects() {
+        let parsed = ShellCommand::parse("cat a > out 2>/dev/null >> log 2>&1 &> all < input");
+        assert_eq!(parsed.commands, [["cat", "a"]]);
+        assert_eq!(parsed.redirects, ["out", "log", "all"]);
+        assert!(ShellCommand::parse("ls 2>&1 | wc -l").redirects.is_empty());
+        assert!(ShellCommand::parse("echo '>' \">\"").redirects.is_empty());
+    }
+
+    #[test]
+    fn test_parse_substitution() {
+        let parsed = ShellCommand::parse("echo $(rm -rf /)");
+        assert!(parsed.substitutes);
+        assert_eq!(parsed.commands, [vec!["echo"], vec!["rm", "-rf", "/"]]);
+
+        let parsed = ShellCommand::parse("echo `id` done");
+        assert!(parsed.substitutes);
+        assert_eq!(parsed.commands, [vec!["echo"], vec!["id"], vec!["done"]]);
+
+        // Double quotes keep the text together but the shell still expands it.
+        let parsed = ShellCommand::parse(r#"echo "$(rm -rf /)""#);
+        assert!(parsed.substitutes);
+        assert_eq!(parsed.commands, [["echo", "$(rm -rf /)"]]);
+        assert!(ShellCommand::parse("x=rm; $x -rf /").substitutes);
+
+        for literal in ["echo '$(id)'", r"echo \$HOME", r#"echo "\$HOME""#] {
+            assert!(!ShellCommand::parse(literal).substitutes, "{}", literal);
+        }
+        assert!(ShellCommand::parse("ls *.rs").globs);
+        assert!(!ShellCommand::parse("find . -name '*.rs'").globs);
+    }
+
+    #[test]
+    fn test_read_only_commands_run_unattended() {
+        for cmd in [
+            "ls -la",
+            "cat README.md | grep -n TODO | wc -l",
+            "find . -name '*.rs' -type f",
+            "grep -rn 'fn main' src && echo found; pwd",
+            "sort -r names.txt | head -5",
+            "ls *.md 2>/dev/null",
+            "echo 'rm -rf /'",
+        ] {
+            assert!(is_read_only_shell(cmd), "{}", cmd);
+        }
+    }
+
+    #[test]
+    fn test_everything_else_needs_approval() {
+        for cmd in [
+            "cp a b",
+            "echo x | tee out",
+            "sed -i s/a/b/ file",
+            "python3 -c 'import os'",
+            "sh -c 'ls'",
+            "bash -c ls",
+            "find . -delete",
+            r"find . -name x -exec rm {} \;",
+            "find . -{delete,print}",
+            "find . *",
+            "x=rm; $x -rf /",
+            "echo `rm -rf /`",
+            r#"echo "$(rm -rf /)""#,
+            "ls > listing",
+            "cat a >> b",
+            "sort -o out in",
+            "sort -ro out in",
+            "sort --out=out in",
+            "sort --compress-prog=sh in",
+            "/tmp/cat file",
+            "./ls",
+            "PATH=/tmp ls",
+            "env ls",
+            "xargs rm < list",
+            "git status",
+            "cargo build",
+            "ls; rm -rf /",
+            "ls && curl example.com",
+        ] {
+            assert!(!is_read_only_shell(cmd), "{}", cmd);
+        }
+    }
+
+    #[test]
+    fn test_shell_approval_follows_autonomy() {
+        let workspace = Workspace { mounts: vec![Mount { path: "/workspace".into(), mode: default_mount_mode() }] };
+        assert!(approval_needed("shell", "ls -la", "read_report", &workspace).is_none());
+        let (description, params, risk) = approval_needed("shell", "cp a b", "read_report", &workspace).unwrap();
+        assert_eq!(description, "Run shell command: cp a b");
+        assert_eq!(params["command"], "cp a b");
+        assert_eq!(risk, "High");
+        assert!(approval_needed("shell", "cp a b", "full", &workspace).is_none());
+    }
+}
@@ -0,0 +1,12 @@
+//! Helpers over the `cancellation` interface.
+
+use crate::sentinel::agent::cancellation::{cancellation_reason, is_cancellation_requested};
+
+/// Exit code for a guest that stopped early because the host cancelled it.
+pub const EXIT_CANCELLED: i32 = 3;
+
+/// The reason, once the host has asked the guest to stop. Cheap enough to
+/// check between every unit of work.
+pub fn cancellation_requested() -> Option<String> {
+    is_cancellation_requested().then(|| cancellation_reason().unwrap_or_else(|| "Cancelled by host".to_string()))
+}
@@ -0,0 +1,104 @@
+//! Helpers over the `clock` interface.
+
+use crate::sentinel::agent::clock::{monotonic_ms, now_unix_ms};
+
+/// Measures elapsed time on the host's monotonic clock, like
+/// `std::time::Instant` (which isn't available to guests).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stopwatch {
+    started_ms: u64,
+}
+
+impl Stopwatch {
+    pub fn start() -> Self {
+        Self::starting_at(monotonic_ms())
+    }
+
+    /// A stopwatch started at the monotonic reading `ms`.
+    pub fn starting_at(ms: u64) -> Self {
+        Self { started_ms: ms }
+    }
+
+    pub fn elapsed_ms(&self) -> u64 {
+        self.elapsed_at(monotonic_ms())
+    }
+
+    /// Elapsed time at the monotonic reading `now_ms`.
+    pub fn elapsed_at(&self, now_ms: u64) -> u64 {
+        now_ms.saturating_sub(self.started_ms)
+    }
+
+    /// Elapsed time so far, restarting the stopwatch.
+    pub fn lap(&mut self) -> u64 {
+        let now = monotonic_ms();
+        let elapsed = self.elapsed_at(now);
+        self.started_ms = now;
+        elapsed
+    }
+}
+
+/// The current wall-clock time as RFC 3339, e.g. `2023-11-14T22:13:20Z`.
+pub fn now_rfc3339() -> String {
+    format_rfc3339(now_unix_ms())
+}
+
+/// Format Unix milliseconds as an RFC 3339 UTC timestamp, to the second.
+pub fn format_rfc3339(unix_ms: u64) -> String {
+    let secs = unix_ms / 1000;
+    let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+    let (h, m, s) = ((secs / 3600) % 24, (secs / 60) % 60, secs % 60);
+    format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z", year, month, day, h, m, s)
+}
+
+/// Days since 1970-01-01 to a proleptic Gregorian date (Howard Hinnant's
+/// `civil_from_days`).
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = (if mp < 10 { mp + 3 } else { mp - 9 }) as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// `1234` → `1.2s`, `95000` → `1m 35s`.
+pub fn format_duration_ms(ms: u64) -> String {
+    match ms {
+        0..=999 => format!("{}ms", ms),
+        1_000..=59_999 => format!("{:.1}s", ms as f64 / 1000.0),
+        _ => format!("{}m {:02}s", ms / 60_000, (ms / 1000) % 60),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stopwatch() {
+        let sw = Stopwatch::starting_at(1_500);
+        assert_eq!(sw.elapsed_at(1_500), 0);
+        assert_eq!(sw.elapsed_at(4_250), 2_750);
+        // A reading from before the start never underflows.
+        assert_eq!(sw.elapsed_at(1_000), 0);
+    }
+
+    #[test]
+    fn test_format_rfc3339() {
+        assert_eq!(format_rfc3339(0), "1970-01-01T00:00:00Z");
+        assert_eq!(format_rfc3339(1_700_000_000_999), "2023-11-14T22:13:20Z");
+        assert_eq!(format_rfc3339(951_782_400_000), "2000-02-29T00:00:00Z");
+        assert_eq!(format_rfc3339(4_102_444_799_000), "2099-12-31T23:59:59Z");
+    }
+
+    #[test]
+    fn test_format_duration_ms() {
+        assert_eq!(format_duration_ms(850), "850ms");
+        assert_eq!(format_duration_ms(1_234), "1.2s");
+        assert_eq!(format_duration_ms(95_000), "1m 35s");
+    }
+}
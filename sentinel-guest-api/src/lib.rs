@@ -11,11 +11,34 @@ wit_bindgen::generate!({
     world: "sentinel-guest",
 });
 
+pub mod cancellation;
+pub mod clock;
+pub mod metrics;
+pub mod random;
+pub mod state;
+pub mod tokens;
+pub mod version;
+pub mod watch;
+
 /// Convenience re-exports for guest authors.
 pub mod prelude {
     pub use super::sentinel::agent::capabilities::*;
+    pub use super::sentinel::agent::secrets::*;
     pub use super::sentinel::agent::hitl::*;
     pub use super::sentinel::agent::logging::*;
     pub use super::sentinel::agent::reasoning::*;
+    pub use super::sentinel::agent::clock::*;
+    pub use super::sentinel::agent::random::*;
+    pub use super::sentinel::agent::state::*;
+    pub use super::sentinel::agent::spawn::*;
+    pub use super::sentinel::agent::cancellation::*;
+    pub use super::sentinel::agent::meta::*;
+    pub use super::cancellation::{cancellation_requested, EXIT_CANCELLED};
+    pub use super::clock::{format_rfc3339, now_rfc3339, Stopwatch};
+    pub use super::metrics::metric;
+    pub use super::random::{new_manifest_id, random_nonce};
+    pub use super::state::Namespace;
+    pub use super::version::{host_supports, INTERFACE_VERSION};
+    pub use super::watch::wait_for_change;
     pub use super::Guest;
 }
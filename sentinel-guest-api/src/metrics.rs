@@ -0,0 +1,11 @@
+//! Helpers over `logging.record-metric`.
+
+use crate::sentinel::agent::logging::record_metric;
+
+/// Record one sample of `name`. The host sums, counts and takes the max per
+/// name and label set, so call it once per event (e.g. per file scanned)
+/// or once with a total.
+pub fn metric(name: &str, value: f64, labels: &[(&str, &str)]) {
+    let labels: Vec<(String, String)> = labels.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+    record_metric(name, value, &labels);
+}
@@ -0,0 +1,17 @@
+//! Helpers over the `random` interface.
+
+use crate::sentinel::agent::random::{random_bytes, uuid_v4};
+
+/// A unique HITL manifest id such as `audit-report-write-1b4e28ba-…`.
+/// `action` keeps ids readable in the approval log.
+pub fn new_manifest_id(action: &str) -> String {
+    format!("{}-{}", action, uuid_v4())
+}
+
+/// 32 random bytes for a manifest nonce.
+pub fn random_nonce() -> [u8; 32] {
+    let bytes = random_bytes(32).expect("32 bytes is within the host limit");
+    let mut nonce = [0u8; 32];
+    nonce.copy_from_slice(&bytes);
+    nonce
+}
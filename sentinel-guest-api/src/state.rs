@@ -0,0 +1,70 @@
+//! Typed access to the `state` interface.
+
+use crate::sentinel::agent::state::{kv_delete, kv_get, kv_list, kv_put};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// A namespace in the guest's persistent key-value store. Values written
+/// with [`Namespace::put`] are stored as JSON.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Namespace {
+    name: String,
+}
+
+impl Namespace {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self { name: name.into() }
+    }
+
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>, String> {
+        self.get_bytes(key)?.map(|bytes| decode(&bytes)).transpose()
+    }
+
+    pub fn put<T: Serialize>(&self, key: &str, value: &T) -> Result<(), String> {
+        self.put_bytes(key, &encode(value)?)
+    }
+
+    pub fn get_bytes(&self, key: &str) -> Result<Option<Vec<u8>>, String> {
+        kv_get(&self.name, key)
+    }
+
+    pub fn put_bytes(&self, key: &str, value: &[u8]) -> Result<(), String> {
+        kv_put(&self.name, key, value)
+    }
+
+    /// Returns whether the key existed.
+    pub fn delete(&self, key: &str) -> Result<bool, String> {
+        kv_delete(&self.name, key)
+    }
+
+    pub fn keys(&self) -> Result<Vec<String>, String> {
+        kv_list(&self.name)
+    }
+}
+
+pub fn encode<T: Serialize>(value: &T) -> Result<Vec<u8>, String> {
+    serde_json::to_vec(value).map_err(|e| e.to_string())
+}
+
+pub fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, String> {
+    serde_json::from_slice(bytes).map_err(|e| format!("Stored value is not valid: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Entry {
+        hash: u64,
+        clean: bool,
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let entry = Entry { hash: u64::MAX, clean: true };
+        assert_eq!(decode::<Entry>(&encode(&entry).unwrap()).unwrap(), entry);
+        assert!(decode::<Entry>(b"{\"hash\":1}").unwrap_err().contains("not valid"));
+    }
+}
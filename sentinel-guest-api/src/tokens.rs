@@ -0,0 +1,101 @@
+//! Token counting over `reasoning.count-tokens`, with a local estimate
+//! when the host can't count.
+
+use crate::sentinel::agent::reasoning::count_tokens;
+use core::sync::atomic::{AtomicU8, Ordering};
+
+const UNPROBED: u8 = 0;
+const HOST: u8 = 1;
+const LOCAL: u8 = 2;
+
+/// Where counts come from, decided by the first call.
+static SOURCE: AtomicU8 = AtomicU8::new(UNPROBED);
+
+/// Tokens `text` takes for `model_hint` (empty for the active model).
+/// Prefers the host's count; a host that answers 0 for non-empty text
+/// can't count, and from then on [`estimate`] is used instead.
+pub fn count(text: &str, model_hint: &str) -> u32 {
+    if text.is_empty() {
+        return 0;
+    }
+    match SOURCE.load(Ordering::Relaxed) {
+        HOST => count_tokens(text, model_hint),
+        LOCAL => estimate(text),
+        _ => {
+            let n = count_tokens(text, model_hint);
+            SOURCE.store(if n > 0 { HOST } else { LOCAL }, Ordering::Relaxed);
+            if n > 0 { n } else { estimate(text) }
+        }
+    }
+}
+
+/// Local estimate, the same rule as the host's fallback: word runs cost
+/// one token per 3.5 characters, other non-space characters and newlines
+/// one each.
+pub fn estimate(text: &str) -> u32 {
+    let mut tokens = 0u32;
+    let mut word = 0u32;
+    for c in text.chars() {
+        if c.is_alphanumeric() || c == '_' {
+            word += 1;
+            continue;
+        }
+        tokens += (word * 2).div_ceil(7);
+        word = 0;
+        if c == '\n' || !c.is_whitespace() {
+            tokens += 1;
+        }
+    }
+    tokens + (word * 2).div_ceil(7)
+}
+
+/// Split `text` at line boundaries into chunks of at most `max_tokens`
+/// each, as counted by [`count`]. A single line over the limit becomes a
+/// chunk of its own.
+pub fn split(text: &str, max_tokens: u32, model_hint: &str) -> Vec<String> {
+    split_with(text, max_tokens, |chunk| count(chunk, model_hint))
+}
+
+/// [`split`] with a caller-supplied counter.
+pub fn split_with(text: &str, max_tokens: u32, mut count: impl FnMut(&str) -> u32) -> Vec<String> {
+    if count(text) <= max_tokens {
+        return vec![text.to_string()];
+    }
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    let mut current_tokens = 0;
+    for line in text.split_inclusive('\n') {
+        let tokens = count(line);
+        if !current.is_empty() && current_tokens + tokens > max_tokens {
+            chunks.push(std::mem::take(&mut current));
+            current_tokens = 0;
+        }
+        current.push_str(line);
+        current_tokens += tokens;
+    }
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate() {
+        assert_eq!(estimate(""), 0);
+        assert_eq!(estimate("hello world"), 4);
+        // `fn` 1, `main` 2, then `(`, `)`, `{`, `}` and the newline
+        assert_eq!(estimate("fn main() {}\n"), 8);
+    }
+
+    #[test]
+    fn test_split_at_lines() {
+        let words = |s: &str| s.split_whitespace().count() as u32;
+        let text = "a b\nc d\ne f g h i\nj\n";
+        assert_eq!(split_with(text, 100, words), vec![text]);
+        assert_eq!(split_with(text, 4, words), vec!["a b\nc d\n", "e f g h i\n", "j\n"]);
+    }
+}
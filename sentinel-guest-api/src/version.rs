@@ -0,0 +1,33 @@
+//! Helpers over the `meta` interface.
+
+use crate::sentinel::agent::meta::interface_version;
+
+/// The `sentinel:agent` version these bindings were generated against.
+/// The host reads the same version off the guest's import names.
+pub const INTERFACE_VERSION: (u16, u16) = (0, 2);
+
+/// Whether the host implements `major.minor`: the same major, at least
+/// that minor. Check before calling functions added in a later minor.
+pub fn host_supports(major: u16, minor: u16) -> bool {
+    supports(interface_version(), major, minor)
+}
+
+fn supports(host: (u16, u16), major: u16, minor: u16) -> bool {
+    host.0 == major && host.1 >= minor
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_version_matches_wit() {
+        let wit = include_str!("../../wit/sentinel.wit");
+        let (major, minor) = INTERFACE_VERSION;
+        assert!(wit.contains(&format!("package sentinel:agent@{major}.{minor}.")));
+
+        assert!(supports((1, 3), 1, 2));
+        assert!(!supports((1, 1), 1, 2));
+        assert!(!supports((2, 0), 1, 0));
+    }
+}
@@ -0,0 +1,23 @@
+//! Helpers over `capabilities.watch-path` / `poll-watch`.
+
+use crate::cancellation::cancellation_requested;
+use crate::sentinel::agent::capabilities::{poll_watch, FsEvent, WatchId};
+
+/// How long each `poll-watch` waits; the host also returns early when the
+/// run is cancelled.
+const POLL_MS: u32 = 30_000;
+
+/// Block until a change `matches` accepts, e.g. any `.rs` file. Returns
+/// every change from that poll that matched, or `None` once the host
+/// asks the guest to stop.
+pub fn wait_for_change(watch: WatchId, matches: impl Fn(&FsEvent) -> bool) -> Result<Option<Vec<FsEvent>>, String> {
+    loop {
+        if cancellation_requested().is_some() {
+            return Ok(None);
+        }
+        let changes: Vec<FsEvent> = poll_watch(watch, POLL_MS)?.into_iter().filter(|e| matches(e)).collect();
+        if !changes.is_empty() {
+            return Ok(Some(changes));
+        }
+    }
+}
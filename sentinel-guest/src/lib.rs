@@ -3,7 +3,8 @@
 //! A multi-file security auditor that runs inside the SENTINEL sandbox.
 //! It discovers Rust source files, sends each to the LLM for security
 //! analysis, and writes an aggregate AUDIT_REPORT.md — but only after
-//! the user approves a HITL manifest.
+//! the user approves a HITL manifest. A cancelled audit writes what it has
+//! to AUDIT_REPORT.partial.md instead, without waiting for approval.
 
 wit_bindgen::generate!({
     path: "../wit/sentinel.wit",
@@ -14,12 +15,48 @@ use sentinel::agent::capabilities::*;
 use sentinel::agent::hitl::*;
 use sentinel::agent::logging::*;
 use sentinel::agent::reasoning::*;
+use exports::sentinel::agent::metadata::{CapabilityKind, CapabilityRequirement, GuestManifest};
+use sentinel_guest_api::cancellation::{cancellation_requested, EXIT_CANCELLED};
+use sentinel_guest_api::clock::{format_duration_ms, now_rfc3339, Stopwatch};
+use sentinel_guest_api::metrics::metric;
+use sentinel_guest_api::random::new_manifest_id;
+use sentinel_guest_api::state::Namespace;
+use sentinel_guest_api::tokens;
+use serde::{Deserialize, Serialize};
 
 struct Component;
 
+/// Overall phases reported through `report_progress`: discovery,
+/// analysis, reporting.
+const PHASES: u32 = 3;
+
+/// State namespace holding per-file results from earlier runs.
+const AUDIT_CACHE: &str = "audit-cache";
+
+/// The report, written after HITL approval.
+const REPORT_FILE: &str = "AUDIT_REPORT.md";
+
+/// Where a cancelled audit saves its partial report. The write grant is
+/// approved up front, since the grace period is too short to wait on the user;
+/// the audit still runs if it is denied.
+const PARTIAL_REPORT_FILE: &str = "AUDIT_REPORT.partial.md";
+
+/// Most source tokens sent in one request; larger files are audited in parts.
+const MAX_CHUNK_TOKENS: u32 = 6000;
+
+/// A file's last audit result, reused while its content and the prompt are
+/// unchanged.
+#[derive(Serialize, Deserialize)]
+struct CachedAudit {
+    hash: u64,
+    has_issues: bool,
+    finding: String,
+}
+
 impl Guest for Component {
     fn run(context_json: String) -> i32 {
         log(LogLevel::Info, "auditor", "═══ SENTINEL Security Auditor starting ═══");
+        let run_timer = Stopwatch::start();
         log(LogLevel::Info, "auditor", &format!("Received context JSON: {}", context_json));
 
         // ── Parse context JSON ──────────────────────────────────────────
@@ -31,19 +68,66 @@ impl Guest for Component {
         // PHASE 1: Discovery — list all files in the workspace
         // ──────────────────────────────────────────────────────────────────
         log(LogLevel::Info, "auditor", "[Phase 1] Discovering workspace files...");
-
-        let read_token = match request_fs_read(&target_dir, "List workspace files for security audit") {
-            CapabilityResult::Granted(t) => t,
-            CapabilityResult::Denied(reason) => {
+        report_progress("audit", 0, PHASES, "Discovering workspace files");
+
+        // Everything the audit touches, in one approval: the workspace
+        // (recursively, so files and sub-directories need no tokens of their
+        // own), the report, and the partial report kept if we're cancelled.
+        let grants = request_capabilities(&[
+            CapabilityRequest {
+                kind: RequestKind::FsRead,
+                target: target_dir.clone(),
+                method: None,
+                justification: "Read workspace source files for security audit".to_string(),
+            },
+            CapabilityRequest {
+                kind: RequestKind::FsWrite,
+                target: REPORT_FILE.to_string(),
+                method: None,
+                justification: "Write security audit report after HITL approval".to_string(),
+            },
+            CapabilityRequest {
+                kind: RequestKind::FsWrite,
+                target: PARTIAL_REPORT_FILE.to_string(),
+                method: None,
+                justification: "Save a partial report if the audit is cancelled".to_string(),
+            },
+        ]);
+        // Every path out of the audit gives back whatever was granted.
+        let release_all = || {
+            for grant in &grants {
+                if let CapabilityResult::Granted(token) = grant {
+                    release_capability(&token.id);
+                }
+            }
+        };
+        let (read_token, write_token) = match (&grants[0], &grants[1]) {
+            (CapabilityResult::Granted(read), CapabilityResult::Granted(write)) => (read.clone(), write.clone()),
+            (CapabilityResult::Denied(reason), _) => {
                 log(LogLevel::Error, "auditor", &format!("Cannot read workspace: {}", reason));
+                release_all();
+                return 1;
+            }
+            (_, CapabilityResult::Denied(reason)) => {
+                log(LogLevel::Error, "auditor", &format!("Cannot write report: {}", reason));
+                release_all();
                 return 1;
             }
         };
+        // Optional: without it a cancelled audit leaves its findings in the logs.
+        let partial_token = match &grants[2] {
+            CapabilityResult::Granted(partial) => Some(partial.clone()),
+            CapabilityResult::Denied(reason) => {
+                log(LogLevel::Warn, "auditor", &format!("No partial report if cancelled: {}", reason));
+                None
+            }
+        };
 
         let all_entries = match fs_list_dir(&read_token.id, &target_dir) {
             Ok(entries) => entries,
             Err(e) => {
                 log(LogLevel::Error, "auditor", &format!("Cannot list directory: {}", e));
+                release_all();
                 return 1;
             }
         };
@@ -72,13 +156,7 @@ impl Guest for Component {
         }
 
         for sub_dir in sub_dirs.iter() {
-            // Request a read token for the sub-directory
-            let sub_token = match request_fs_read(sub_dir, &format!("List {} for security audit", sub_dir)) {
-                CapabilityResult::Granted(t) => t,
-                CapabilityResult::Denied(_) => continue,
-            };
-
-            match fs_list_dir(&sub_token.id, sub_dir) {
+            match fs_list_dir(&read_token.id, sub_dir) {
                 Ok(entries) => {
                     for entry in entries {
                         if exts.iter().any(|ext| entry.ends_with(ext)) {
@@ -88,17 +166,17 @@ impl Guest for Component {
                 }
                 Err(_) => continue,
             }
-
-            release_capability(&sub_token.id);
         }
 
         log(LogLevel::Info, "auditor", &format!("[Phase 1] Found {} source files", target_files.len()));
+        metric("files_discovered", target_files.len() as f64, &[("phase", "discovery")]);
         for f in &target_files {
             log(LogLevel::Debug, "auditor", &format!("  → {}", f));
         }
 
         if target_files.is_empty() {
             log(LogLevel::Warn, "auditor", "No source files found — nothing to audit.");
+            release_all();
             return 0;
         }
 
@@ -106,6 +184,7 @@ impl Guest for Component {
         // PHASE 2 & 3: Analysis + Reasoning — read each file and audit it
         // ──────────────────────────────────────────────────────────────────
         log(LogLevel::Info, "auditor", "[Phase 2+3] Analyzing files with LLM...");
+        report_progress("audit", 1, PHASES, "Analyzing files");
 
         let provider = get_provider_name();
         log(LogLevel::Info, "auditor", &format!("Using LLM provider: {}", provider));
@@ -113,6 +192,8 @@ impl Guest for Component {
         let mut findings: Vec<String> = Vec::new();
         let mut files_audited: u32 = 0;
         let mut total_issues: u32 = 0;
+        let mut files_cached: u32 = 0;
+        let cache = Namespace::new(AUDIT_CACHE);
 
         let system_prompt = format!("\
 You are a senior security auditor. Your task: {}
@@ -126,91 +207,120 @@ Analyze the provided source code and report:
 Format your response as a concise bullet list. If the code is clean, say \"No issues found.\"
 Do NOT explain what the code does — only report problems.", task_prompt);
 
-        for file_path in &target_files {
+        let file_count = target_files.len() as u32;
+        // Files looked at before the host cancelled the run, if it did.
+        let mut cancelled_after: Option<u32> = None;
+        for (index, file_path) in target_files.iter().enumerate() {
+            // Stop between files so what's done so far can still be reported
+            if let Some(reason) = cancellation_requested() {
+                log(LogLevel::Warn, "auditor", &format!(
+                    "Cancellation requested ({}) — stopping after {} of {} files",
+                    reason, index, file_count
+                ));
+                cancelled_after = Some(index as u32);
+                break;
+            }
+            report_progress("analysis", index as u32, file_count, file_path);
             log(LogLevel::Info, "auditor", &format!("  Auditing: {}", file_path));
-
-            // Get a read token for this specific file
-            let file_token = match request_fs_read(file_path, &format!("Read {} for security audit", file_path)) {
-                CapabilityResult::Granted(t) => t,
-                CapabilityResult::Denied(reason) => {
-                    log(LogLevel::Warn, "auditor", &format!("  Skipped (denied): {} — {}", file_path, reason));
-                    findings.push(format!("### {}\n\n⚠️ Skipped: access denied — {}\n", file_path, reason));
-                    continue;
-                }
-            };
+            let file_timer = Stopwatch::start();
 
             // Read the file contents
-            let content = match fs_read(&file_token.id, file_path) {
+            let content = match fs_read(&read_token.id, file_path) {
                 Ok(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
                 Err(e) => {
                     log(LogLevel::Warn, "auditor", &format!("  Skipped (read error): {} — {}", file_path, e));
                     findings.push(format!("### {}\n\n⚠️ Skipped: read error — {}\n", file_path, e));
-                    release_capability(&file_token.id);
+                    metric("files_skipped", 1.0, &[("phase", "analysis"), ("reason", "read_error")]);
                     continue;
                 }
             };
 
-            release_capability(&file_token.id);
-
             // Skip very small files (< 50 bytes, likely empty or just re-exports)
             if content.len() < 50 {
                 log(LogLevel::Debug, "auditor", &format!("  Skipped (too small): {} ({} bytes)", file_path, content.len()));
+                metric("files_skipped", 1.0, &[("phase", "analysis"), ("reason", "too_small")]);
                 continue;
             }
 
+            // Unchanged since the last run — reuse the stored result
+            let hash = fnv1a(&[system_prompt.as_bytes(), content.as_bytes()]);
+            if let Ok(Some(cached)) = cache.get::<CachedAudit>(file_path) {
+                if cached.hash == hash {
+                    if cached.has_issues {
+                        total_issues += 1;
+                    }
+                    findings.push(cached.finding);
+                    files_audited += 1;
+                    files_cached += 1;
+                    log(LogLevel::Info, "auditor", &format!("  ✓ {} — unchanged, reusing previous result", file_path));
+                    continue;
+                }
+            }
+
             // Send to LLM for security analysis
-            let messages = vec![
-                ChatMessage {
-                    role: "system".to_string(),
-                    content: system_prompt.clone(),
-                },
-                ChatMessage {
-                    role: "user".to_string(),
-                    content: format!("Audit this file (`{}`):\n\n```\n{}\n```", file_path, content),
-                },
-            ];
-
-            match complete(&messages, Some(1024), Some(0.3), None) {
+            match audit_file(&system_prompt, file_path, &content) {
                 Ok(resp) => {
                     let has_issues = !resp.content.to_lowercase().contains("no issues found");
                     if has_issues {
                         total_issues += 1;
                     }
-                    findings.push(format!(
-                        "### {}\n\n{}\n\n*Model: {} | Tokens: {}*\n",
+                    let duration = format_duration_ms(file_timer.elapsed_ms());
+                    let finding = format!(
+                        "### {}\n\n{}\n\n*Model: {} | Tokens: {} | Analysis time: {}*\n",
                         file_path,
                         resp.content.trim(),
                         resp.model,
-                        resp.usage.total_tokens
-                    ));
+                        resp.usage.total_tokens,
+                        duration
+                    );
+                    let entry = CachedAudit { hash, has_issues, finding: finding.clone() };
+                    if let Err(e) = cache.put(file_path, &entry) {
+                        log(LogLevel::Warn, "auditor", &format!("  Could not cache result for {}: {}", file_path, e));
+                    }
+                    findings.push(finding);
                     files_audited += 1;
+                    metric("llm_tokens", resp.usage.total_tokens as f64, &[("phase", "analysis")]);
                     log(LogLevel::Info, "auditor", &format!(
-                        "  ✓ {} — {} (tokens: {})",
+                        "  ✓ {} — {} (tokens: {}, {})",
                         file_path,
                         if has_issues { "issues found" } else { "clean" },
-                        resp.usage.total_tokens
+                        resp.usage.total_tokens,
+                        duration
                     ));
                 }
                 Err(e) => {
                     log(LogLevel::Error, "auditor", &format!("  LLM error for {}: {}", file_path, e));
+                    metric("files_skipped", 1.0, &[("phase", "analysis"), ("reason", "llm_error")]);
                     findings.push(format!("### {}\n\n⚠️ LLM error: {}\n", file_path, e));
                 }
             }
         }
 
         log(LogLevel::Info, "auditor", &format!(
-            "[Phase 2+3] Complete — audited {} files, {} with potential issues",
-            files_audited, total_issues
+            "[Phase 2+3] Complete — audited {} files ({} unchanged since last run), {} with potential issues",
+            files_audited, files_cached, total_issues
         ));
+        metric("files_audited", files_audited as f64, &[("phase", "analysis")]);
+        metric("cache_hits", files_cached as f64, &[("phase", "analysis")]);
+        metric("files_with_issues", total_issues as f64, &[("phase", "analysis")]);
 
         // ──────────────────────────────────────────────────────────────────
         // PHASE 4: Reporting — build the Markdown report and write it
         // ──────────────────────────────────────────────────────────────────
+        report_progress("analysis", cancelled_after.unwrap_or(file_count), file_count, "");
         log(LogLevel::Info, "auditor", "[Phase 4] Building audit report...");
+        report_progress("audit", 2, PHASES, "Writing report");
 
+        let partial_notice = match cancelled_after {
+            Some(done) => format!("> ⚠️ **Partial report**: audit cancelled after {} of {} files.\n\n", done, file_count),
+            None => String::new(),
+        };
         let report = format!(
             "# 🔒 SENTINEL Security Audit Report\n\n\
+             {}\
              **Generated by**: SENTINEL Security Auditor Agent\n\
+             **Generated at**: {}\n\
+             **Duration**: {}\n\
              **LLM Provider**: {}\n\
              **Files Audited**: {}\n\
              **Files with Issues**: {}\n\n\
@@ -220,19 +330,39 @@ Do NOT explain what the code does — only report problems.", task_prompt);
              ---\n\n\
              *This report was generated autonomously by the SENTINEL agent framework.*\n\
              *All file access was capability-gated and write access was HITL-approved.*\n",
+            partial_notice,
+            now_rfc3339(),
+            format_duration_ms(run_timer.elapsed_ms()),
             provider,
             files_audited,
             total_issues,
             findings.join("\n---\n\n"),
         );
 
+        if let Some(done) = cancelled_after {
+            // The host stops us once the grace period is up, so there is no
+            // waiting on the user: save to the partial report, approved up front.
+            match &partial_token {
+                Some(partial) => {
+                    if write_report(&partial.id, PARTIAL_REPORT_FILE, &report) {
+                        log(LogLevel::Info, "auditor", "Review and rename it to keep it as the audit report.");
+                    }
+                }
+                None => log(LogLevel::Warn, "auditor", "Partial report NOT written. Audit findings are in the logs above."),
+            }
+            release_all();
+            report_progress("audit", PHASES, PHASES, "Cancelled");
+            log(LogLevel::Warn, "auditor", &format!("═══ SENTINEL Security Auditor cancelled after {} of {} files ═══", done, file_count));
+            return EXIT_CANCELLED;
+        }
+
         // ──────────────────────────────────────────────────────────────────
         // HITL GATE: Submit a manifest before writing the report
         // ──────────────────────────────────────────────────────────────────
         log(LogLevel::Info, "auditor", "Requesting HITL approval to write AUDIT_REPORT.md...");
 
         let manifest = ExecutionManifest {
-            id: "audit-report-write-001".to_string(),
+            id: new_manifest_id("audit-report-write"),
             action_description: format!(
                 "Write security audit report (AUDIT_REPORT.md) — {} files audited, {} potential issues found",
                 files_audited, total_issues
@@ -244,6 +374,8 @@ Do NOT explain what the code does — only report problems.", task_prompt);
             risk: RiskLevel::High,
         };
 
+        // Cancellation can also arrive while we wait on the user.
+        let failed = || if cancellation_requested().is_some() { EXIT_CANCELLED } else { 1 };
         match submit_manifest(&manifest) {
             ApprovalResult::Approved(_approval) => {
                 log(LogLevel::Info, "auditor", "✓ HITL approved — writing report");
@@ -251,37 +383,24 @@ Do NOT explain what the code does — only report problems.", task_prompt);
             ApprovalResult::Rejected(reason) => {
                 log(LogLevel::Error, "auditor", &format!("✗ HITL rejected: {}", reason));
                 log(LogLevel::Info, "auditor", "Report was NOT written. Audit findings are in the logs above.");
-                return 1;
+                release_all();
+                return failed();
             }
             ApprovalResult::TimedOut => {
                 log(LogLevel::Error, "auditor", "✗ HITL timed out — report was NOT written");
-                return 1;
+                release_all();
+                return failed();
             }
         }
 
         // ── Write the report ─────────────────────────────────────────────
-        let write_token = match request_fs_write("AUDIT_REPORT.md", "Write security audit report after HITL approval") {
-            CapabilityResult::Granted(t) => t,
-            CapabilityResult::Denied(reason) => {
-                log(LogLevel::Error, "auditor", &format!("Cannot write report: {}", reason));
-                return 1;
-            }
-        };
-
-        match fs_write(&write_token.id, "AUDIT_REPORT.md", report.as_bytes()) {
-            Ok(_) => {
-                log(LogLevel::Info, "auditor", "✓ AUDIT_REPORT.md written successfully");
-            }
-            Err(e) => {
-                log(LogLevel::Error, "auditor", &format!("Failed to write report: {}", e));
-                release_capability(&write_token.id);
-                return 1;
-            }
+        let written = write_report(&write_token.id, REPORT_FILE, &report);
+        release_all();
+        if !written {
+            return failed();
         }
 
-        release_capability(&write_token.id);
-        release_capability(&read_token.id);
-
+        report_progress("audit", PHASES, PHASES, "Complete");
         log(LogLevel::Info, "auditor", "═══ SENTINEL Security Auditor complete ═══");
         0
     }
@@ -292,6 +411,87 @@ Do NOT explain what the code does — only report problems.", task_prompt);
     }
 }
 
+impl exports::sentinel::agent::metadata::Guest for Component {
+    fn get_manifest() -> GuestManifest {
+        let requirement = |kind, pattern: &str, justification: &str| CapabilityRequirement {
+            kind,
+            pattern: pattern.to_string(),
+            justification: justification.to_string(),
+        };
+        GuestManifest {
+            name: "sentinel-auditor".to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            description: "Multi-file security auditor that writes AUDIT_REPORT.md".to_string(),
+            requirements: vec![
+                requirement(CapabilityKind::FsRead, ".", "List and read workspace source files to audit"),
+                requirement(CapabilityKind::FsWrite, REPORT_FILE, "Write the audit report, after HITL approval"),
+                requirement(CapabilityKind::FsWrite, PARTIAL_REPORT_FILE, "Save a partial report if the audit is cancelled"),
+            ],
+        }
+    }
+}
+
+/// Write `report` to `path`, logging the outcome.
+fn write_report(token_id: &str, path: &str, report: &str) -> bool {
+    match fs_write(token_id, path, report.as_bytes()) {
+        Ok(_) => {
+            log(LogLevel::Info, "auditor", &format!("✓ {} written successfully", path));
+            metric("report_bytes", report.len() as f64, &[("phase", "reporting")]);
+            true
+        }
+        Err(e) => {
+            log(LogLevel::Error, "auditor", &format!("Failed to write {}: {}", path, e));
+            false
+        }
+    }
+}
+
+/// Ask the LLM to audit `content`, in parts of at most [`MAX_CHUNK_TOKENS`]
+/// if needed. Answers for several parts are merged into one response;
+/// parts without issues are left out.
+fn audit_file(system_prompt: &str, file_path: &str, content: &str) -> Result<CompletionResponse, String> {
+    let chunks = tokens::split(content, MAX_CHUNK_TOKENS, "");
+    if chunks.len() > 1 {
+        log(LogLevel::Info, "auditor", &format!("  {} is large — auditing in {} parts", file_path, chunks.len()));
+    }
+
+    let mut merged = CompletionResponse {
+        content: String::new(),
+        model: String::new(),
+        usage: TokenUsage { prompt_tokens: 0, completion_tokens: 0, total_tokens: 0 },
+        finish_reason: None,
+    };
+    let mut parts_with_issues = Vec::new();
+    for (i, chunk) in chunks.iter().enumerate() {
+        let part = if chunks.len() > 1 { format!(" (part {} of {})", i + 1, chunks.len()) } else { String::new() };
+        let messages = vec![
+            ChatMessage {
+                role: "system".to_string(),
+                content: system_prompt.to_string(),
+            },
+            ChatMessage {
+                role: "user".to_string(),
+                content: format!("Audit this file (`{}`){}:\n\n```\n{}\n```", file_path, part, chunk),
+            },
+        ];
+        let resp = complete(&messages, Some(1024), Some(0.3), None)?;
+        merged.usage.prompt_tokens += resp.usage.prompt_tokens;
+        merged.usage.completion_tokens += resp.usage.completion_tokens;
+        merged.usage.total_tokens += resp.usage.total_tokens;
+        merged.model = resp.model;
+        merged.finish_reason = resp.finish_reason;
+        if chunks.len() == 1 {
+            merged.content = resp.content;
+        } else if !resp.content.to_lowercase().contains("no issues found") {
+            parts_with_issues.push(format!("**Part {} of {}**\n\n{}", i + 1, chunks.len(), resp.content.trim()));
+        }
+    }
+    if chunks.len() > 1 {
+        merged.content = if parts_with_issues.is_empty() { "No issues found.".to_string() } else { parts_with_issues.join("\n\n") };
+    }
+    Ok(merged)
+}
+
 /// Parse the context JSON received from the host using serde_json.
 /// Expected format: {"target_directory": "...", "task_prompt": "..."}
 fn parse_context(json: &str) -> (String, String) {
@@ -306,6 +506,16 @@ fn parse_context(json: &str) -> (String, String) {
     }
 }
 
+/// 64-bit FNV-1a over `parts`, used to detect unchanged files.
+fn fnv1a(parts: &[&[u8]]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in parts.iter().flat_map(|p| p.iter()) {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    hash
+}
+
 /// Minimal JSON string extractor (avoids pulling in full serde for guest size).
 fn extract_json_string(json: &str, key: &str) -> Option<String> {
     let pattern = format!("\"{}\"", key);
@@ -0,0 +1,373 @@
+//! # sentinel-host — Archive Extraction
+//!
+//! `fs-extract-archive` unpacks a zip or tar.gz host-side so a guest
+//! needn't carry a decompressor and issue thousands of `fs-write` calls.
+//! Every entry is checked before it is written:
+//!
+//! - **Zip-slip**: absolute paths and `..` components are skipped, as are
+//!   links and special files. Files are created fresh (never through an
+//!   existing file or symlink) and their directory must resolve inside
+//!   the destination.
+//! - **Bombs**: the entry count, total uncompressed size and overall
+//!   compression ratio are capped. Breaching a cap aborts the extraction
+//!   and removes everything it wrote.
+
+use crate::config::ArchiveConfig;
+use sentinel_shared::RiskLevel;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+use tracing::{info, warn};
+
+/// Ratios only count once this much has been extracted, so a tiny archive
+/// of very compressible text isn't mistaken for a bomb.
+const RATIO_GRACE_BYTES: u64 = 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    Zip,
+    TarGz,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkippedEntry {
+    pub path: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExtractReport {
+    pub files: u32,
+    pub directories: u32,
+    pub total_bytes: u64,
+    /// Entries left out as unsafe.
+    pub skipped: Vec<SkippedEntry>,
+}
+
+/// An extraction awaiting human approval; it creates many files at once.
+#[derive(Debug, Clone)]
+pub struct ExtractRequest {
+    pub request_id: String,
+    pub archive: PathBuf,
+    pub dest: PathBuf,
+    pub format: ArchiveFormat,
+    pub archive_bytes: u64,
+    pub risk_level: RiskLevel,
+}
+
+impl ExtractRequest {
+    pub fn new(archive: PathBuf, dest: PathBuf, format: ArchiveFormat, archive_bytes: u64) -> Self {
+        Self {
+            request_id: format!("extract-{}", uuid::Uuid::new_v4()),
+            archive,
+            dest,
+            format,
+            archive_bytes,
+            risk_level: RiskLevel::Medium,
+        }
+    }
+}
+
+/// Asks a human about an extraction; implemented by the HITL bridge.
+#[async_trait::async_trait]
+pub trait ExtractApprover: Send + Sync {
+    async fn approve(&self, request: &ExtractRequest) -> bool;
+}
+
+enum EntryKind {
+    File,
+    Directory,
+    /// Symlinks, hard links, devices: never extracted.
+    Other(&'static str),
+}
+
+/// The relative path an entry may be written to, or why it may not.
+fn entry_path(name: &str) -> Result<PathBuf, &'static str> {
+    // Zips written on Windows may use backslashes.
+    let name = name.replace('\\', "/");
+    let mut path = PathBuf::new();
+    for component in Path::new(&name).components() {
+        match component {
+            Component::Normal(part) => path.push(part),
+            Component::CurDir => {}
+            Component::ParentDir => return Err("path contains '..'"),
+            Component::RootDir | Component::Prefix(_) => return Err("absolute path"),
+        }
+    }
+    if path.as_os_str().is_empty() {
+        return Err("empty path");
+    }
+    Ok(path)
+}
+
+/// Writes entries under `dest`, enforcing the caps as it goes.
+struct Extractor<'a> {
+    dest: &'a Path,
+    limits: &'a ArchiveConfig,
+    archive_bytes: u64,
+    entries: u64,
+    report: ExtractReport,
+    /// Everything created, in order, for cleanup on abort.
+    created: Vec<PathBuf>,
+}
+
+impl<'a> Extractor<'a> {
+    fn new(dest: &'a Path, limits: &'a ArchiveConfig, archive_bytes: u64) -> Self {
+        Self { dest, limits, archive_bytes, entries: 0, report: ExtractReport::default(), created: Vec::new() }
+    }
+
+    fn skip(&mut self, name: &str, reason: &str) {
+        warn!(entry = %name, reason = %reason, "Skipping unsafe archive entry");
+        self.report.skipped.push(SkippedEntry { path: name.to_string(), reason: reason.to_string() });
+    }
+
+    fn entry(&mut self, name: &str, kind: EntryKind, reader: &mut dyn Read) -> Result<(), String> {
+        self.entries += 1;
+        if self.entries > self.limits.max_entries {
+            return Err(format!("Archive has more than {} entries", self.limits.max_entries));
+        }
+        let relative = match entry_path(name) {
+            Ok(relative) => relative,
+            Err(reason) => {
+                self.skip(name, reason);
+                return Ok(());
+            }
+        };
+        match kind {
+            EntryKind::Other(what) => self.skip(name, what),
+            EntryKind::Directory => {
+                let dir = self.dest.join(&relative);
+                match self.create_dirs(&dir) {
+                    Ok(()) => self.report.directories += 1,
+                    Err(reason) => self.skip(name, &reason),
+                }
+            }
+            EntryKind::File => {
+                let target = self.dest.join(&relative);
+                if let Err(reason) = self.create_dirs(target.parent().unwrap_or(self.dest)) {
+                    self.skip(name, &reason);
+                    return Ok(());
+                }
+                // `create_new` refuses existing files and symlinks alike.
+                let mut file = match OpenOptions::new().write(true).create_new(true).open(&target) {
+                    Ok(file) => file,
+                    Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                        self.skip(name, "already exists");
+                        return Ok(());
+                    }
+                    Err(e) => return Err(format!("Cannot create {}: {e}", relative.display())),
+                };
+                self.created.push(target);
+                self.copy(reader, &mut file)?;
+                self.report.files += 1;
+            }
+        }
+        Ok(())
+    }
+
+    /// Create `dir` and its parents, all of which must resolve inside
+    /// `dest` (a symlinked directory in the way would not).
+    fn create_dirs(&mut self, dir: &Path) -> Result<(), String> {
+        let mut missing = Vec::new();
+        let mut current = dir;
+        while !current.exists() {
+            missing.push(current.to_path_buf());
+            current = current.parent().unwrap_or(self.dest);
+        }
+        let resolved = current.canonicalize().map_err(|e| e.to_string())?;
+        if !resolved.starts_with(self.dest) {
+            return Err("resolves outside the destination".to_string());
+        }
+        for dir in missing.into_iter().rev() {
+            std::fs::create_dir(&dir).map_err(|e| format!("Cannot create directory: {e}"))?;
+            self.created.push(dir);
+        }
+        Ok(())
+    }
+
+    fn copy(&mut self, reader: &mut dyn Read, file: &mut File) -> Result<(), String> {
+        let mut buf = [0u8; 64 * 1024];
+        loop {
+            let n = reader.read(&mut buf).map_err(|e| format!("Corrupt archive: {e}"))?;
+            if n == 0 {
+                return Ok(());
+            }
+            self.report.total_bytes += n as u64;
+            if self.report.total_bytes > self.limits.max_total_bytes {
+                return Err(format!("Archive expands past {} bytes", self.limits.max_total_bytes));
+            }
+            if self.report.total_bytes > RATIO_GRACE_BYTES
+                && self.report.total_bytes > self.archive_bytes.saturating_mul(self.limits.max_compression_ratio)
+            {
+                return Err(format!(
+                    "Archive expands more than {}x; refusing a likely zip bomb",
+                    self.limits.max_compression_ratio
+                ));
+            }
+            file.write_all(&buf[..n]).map_err(|e| format!("Cannot write file: {e}"))?;
+        }
+    }
+
+    /// Remove everything written so far, newest first.
+    fn roll_back(&mut self) {
+        for path in self.created.drain(..).rev() {
+            let _ = if path.is_dir() { std::fs::remove_dir(&path) } else { std::fs::remove_file(&path) };
+        }
+    }
+}
+
+/// Unpack `archive` into the existing directory `dest`. Both must already
+/// be resolved and checked against policy. Blocking.
+pub fn extract(archive: &Path, dest: &Path, format: ArchiveFormat, limits: &ArchiveConfig) -> Result<ExtractReport, String> {
+    let dest = dest.canonicalize().map_err(|e| format!("Cannot resolve destination: {e}"))?;
+    let file = File::open(archive).map_err(|e| format!("Cannot open archive: {e}"))?;
+    let archive_bytes = file.metadata().map_err(|e| e.to_string())?.len();
+
+    let mut extractor = Extractor::new(&dest, limits, archive_bytes);
+    let result = match format {
+        ArchiveFormat::Zip => extract_zip(file, &mut extractor),
+        ArchiveFormat::TarGz => extract_tar_gz(file, &mut extractor),
+    };
+    if let Err(e) = result {
+        extractor.roll_back();
+        return Err(e);
+    }
+    let report = extractor.report;
+    info!(
+        archive = %archive.display(),
+        files = report.files,
+        bytes = report.total_bytes,
+        skipped = report.skipped.len(),
+        "Archive extracted"
+    );
+    Ok(report)
+}
+
+fn extract_zip(file: File, extractor: &mut Extractor) -> Result<(), String> {
+    let mut zip = zip::ZipArchive::new(file).map_err(|e| format!("Not a zip archive: {e}"))?;
+    for i in 0..zip.len() {
+        let mut entry = zip.by_index(i).map_err(|e| format!("Corrupt archive: {e}"))?;
+        let name = entry.name().to_string();
+        let kind = if entry.unix_mode().is_some_and(|mode| mode & 0o170000 == 0o120000) {
+            EntryKind::Other("symlinks are not extracted")
+        } else if entry.is_dir() {
+            EntryKind::Directory
+        } else {
+            EntryKind::File
+        };
+        extractor.entry(&name, kind, &mut entry)?;
+    }
+    Ok(())
+}
+
+fn extract_tar_gz(file: File, extractor: &mut Extractor) -> Result<(), String> {
+    let mut tar = tar::Archive::new(flate2::read::GzDecoder::new(file));
+    for entry in tar.entries().map_err(|e| format!("Not a tar.gz archive: {e}"))? {
+        let mut entry = entry.map_err(|e| format!("Corrupt archive: {e}"))?;
+        let name = entry.path().map_err(|e| format!("Corrupt archive: {e}"))?.to_string_lossy().into_owned();
+        let kind = match entry.header().entry_type() {
+            tar::EntryType::Regular | tar::EntryType::Continuous => EntryKind::File,
+            tar::EntryType::Directory => EntryKind::Directory,
+            tar::EntryType::Symlink | tar::EntryType::Link => EntryKind::Other("links are not extracted"),
+            // PAX and GNU long-name headers are folded into the next entry.
+            _ => EntryKind::Other("unsupported entry type"),
+        };
+        extractor.entry(&name, kind, &mut entry)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use zip::write::SimpleFileOptions;
+
+    fn zip_fixture(path: &Path, entries: &[(&str, &[u8])]) {
+        let mut zip = zip::ZipWriter::new(File::create(path).unwrap());
+        let options = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+        for (name, data) in entries {
+            zip.start_file(*name, options).unwrap();
+            zip.write_all(data).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    fn workspace() -> (tempfile::TempDir, PathBuf) {
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("out");
+        std::fs::create_dir(&dest).unwrap();
+        (dir, dest)
+    }
+
+    #[test]
+    fn test_zip_slip_entries_skipped() {
+        let (dir, dest) = workspace();
+        let archive = dir.path().join("slip.zip");
+        zip_fixture(&archive, &[
+            ("pkg/src/lib.rs", b"pub fn ok() {}"),
+            ("../evil.txt", b"escaped"),
+            ("/tmp/abs.txt", b"absolute"),
+            ("pkg/../../evil2.txt", b"escaped"),
+            ("..\\evil3.txt", b"escaped"),
+        ]);
+
+        let report = extract(&archive, &dest, ArchiveFormat::Zip, &ArchiveConfig::default()).unwrap();
+        assert_eq!(report.files, 1);
+        assert_eq!(std::fs::read_to_string(dest.join("pkg/src/lib.rs")).unwrap(), "pub fn ok() {}");
+        let skipped: Vec<(&str, &str)> = report.skipped.iter().map(|s| (s.path.as_str(), s.reason.as_str())).collect();
+        assert_eq!(skipped, vec![
+            ("../evil.txt", "path contains '..'"),
+            ("/tmp/abs.txt", "absolute path"),
+            ("pkg/../../evil2.txt", "path contains '..'"),
+            ("..\\evil3.txt", "path contains '..'"),
+        ]);
+        assert!(!dir.path().join("evil.txt").exists());
+        assert!(!dir.path().join("evil2.txt").exists());
+    }
+
+    #[test]
+    fn test_bomb_aborts_and_rolls_back() {
+        let (dir, dest) = workspace();
+        let archive = dir.path().join("bomb.zip");
+        let zeros = vec![0u8; 16 * 1024 * 1024];
+        zip_fixture(&archive, &[("readme.txt", b"hello"), ("nested/zeros.bin", &zeros)]);
+
+        let err = extract(&archive, &dest, ArchiveFormat::Zip, &ArchiveConfig::default()).unwrap_err();
+        assert!(err.contains("zip bomb"), "{err}");
+        assert_eq!(std::fs::read_dir(&dest).unwrap().count(), 0);
+
+        let limits = ArchiveConfig { max_entries: 1, ..ArchiveConfig::default() };
+        let err = extract(&archive, &dest, ArchiveFormat::Zip, &limits).unwrap_err();
+        assert!(err.contains("more than 1 entries"), "{err}");
+        assert_eq!(std::fs::read_dir(&dest).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_tar_gz_skips_links() {
+        let (dir, dest) = workspace();
+        let mut builder = tar::Builder::new(flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default()));
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Directory);
+        header.set_mode(0o755);
+        header.set_size(0);
+        builder.append_data(&mut header, "crate/", std::io::empty()).unwrap();
+        let mut header = tar::Header::new_gnu();
+        header.set_mode(0o644);
+        header.set_size(11);
+        builder.append_data(&mut header, "crate/a.rs", Cursor::new(b"fn a() {}\n\n")).unwrap();
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Symlink);
+        header.set_size(0);
+        builder.append_link(&mut header, "crate/passwd", "/etc/passwd").unwrap();
+        let bytes = builder.into_inner().unwrap().finish().unwrap();
+        let archive = dir.path().join("crate.tar.gz");
+        std::fs::write(&archive, bytes).unwrap();
+
+        let report = extract(&archive, &dest, ArchiveFormat::TarGz, &ArchiveConfig::default()).unwrap();
+        assert_eq!((report.files, report.directories, report.total_bytes), (1, 1, 11));
+        assert_eq!(report.skipped, vec![SkippedEntry { path: "crate/passwd".into(), reason: "links are not extracted".into() }]);
+        assert!(dest.join("crate/a.rs").is_file());
+        assert!(!dest.join("crate/passwd").exists());
+    }
+}
@@ -0,0 +1,229 @@
+//! # sentinel-host — Batch Capability Requests
+//!
+//! Lets a guest ask for everything it needs up front instead of one token
+//! (and one audit entry) per file. Requests that policy denies are answered
+//! straight away; the rest go to the user as a single manifest — "This
+//! agent wants: read ./src recursively, write AUDIT_REPORT.md, call
+//! api.openai.com" — and are all granted on approval or all denied on
+//! rejection.
+
+use sentinel_shared::RiskLevel;
+use std::path::Path;
+use tracing::{info, warn};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequestKind {
+    FsRead,
+    FsWrite,
+    NetOutbound,
+}
+
+/// One item of a `request-capabilities` call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapabilityRequest {
+    pub kind: RequestKind,
+    /// A path, or a URL for `NetOutbound`.
+    pub target: String,
+    /// HTTP method; `NetOutbound` only.
+    pub method: Option<String>,
+    pub justification: String,
+}
+
+impl CapabilityRequest {
+    /// Short phrase for the approval prompt, e.g. `read ./src recursively`.
+    pub fn describe(&self) -> String {
+        match self.kind {
+            RequestKind::FsRead if Path::new(&self.target).is_dir() => format!("read {} recursively", self.target),
+            RequestKind::FsRead => format!("read {}", self.target),
+            RequestKind::FsWrite => format!("write {}", self.target),
+            RequestKind::NetOutbound => format!("call {}", url_host(&self.target)),
+        }
+    }
+}
+
+/// `api.openai.com` for `https://api.openai.com/v1/chat`.
+fn url_host(url: &str) -> &str {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    rest.split(['/', '?', '#']).next().unwrap_or(rest)
+}
+
+/// The requests from one call that policy allows, for a human to approve.
+#[derive(Debug, Clone)]
+pub struct CapabilityBatch {
+    pub request_id: String,
+    pub requests: Vec<CapabilityRequest>,
+    pub risk_level: RiskLevel,
+}
+
+impl CapabilityBatch {
+    pub fn new(requests: Vec<CapabilityRequest>) -> Self {
+        let risk_level = if requests.iter().any(|r| r.kind == RequestKind::FsWrite) {
+            RiskLevel::High
+        } else if requests.iter().any(|r| r.kind == RequestKind::NetOutbound) {
+            RiskLevel::Medium
+        } else {
+            RiskLevel::Low
+        };
+        Self { request_id: format!("batch-{}", uuid::Uuid::new_v4()), requests, risk_level }
+    }
+
+    /// The one-line summary shown for approval. Repeated phrases (e.g. two
+    /// calls to the same host) appear once.
+    pub fn describe(&self) -> String {
+        let mut phrases: Vec<String> = Vec::new();
+        for phrase in self.requests.iter().map(CapabilityRequest::describe) {
+            if !phrases.contains(&phrase) {
+                phrases.push(phrase);
+            }
+        }
+        format!("This agent wants: {}", phrases.join(", "))
+    }
+}
+
+/// Asks a human about a whole batch; implemented by the HITL bridge.
+#[async_trait::async_trait]
+pub trait BatchApprover: Send + Sync {
+    async fn approve(&self, batch: &CapabilityBatch) -> bool;
+}
+
+/// Policy checks and token minting behind a batch; implemented by
+/// `HostCallHandler`.
+#[async_trait::async_trait]
+pub trait CapabilityIssuer: Send + Sync {
+    /// Why policy denies `request`, if it does. Mints nothing.
+    fn check(&self, request: &CapabilityRequest) -> Result<(), String>;
+    /// A token id for `request`, which has been approved.
+    async fn mint(&self, request: &CapabilityRequest) -> Result<String, String>;
+}
+
+/// Answer `requests` in order with a token id or the reason for denial.
+/// At most one approval is asked for, covering every request policy allows.
+pub async fn request_batch(
+    issuer: &dyn CapabilityIssuer,
+    approver: &dyn BatchApprover,
+    requests: &[CapabilityRequest],
+) -> Vec<Result<String, String>> {
+    let mut results: Vec<Option<Result<String, String>>> = vec![None; requests.len()];
+    let mut allowed = Vec::new();
+    for (i, request) in requests.iter().enumerate() {
+        match issuer.check(request) {
+            Ok(()) => allowed.push(i),
+            Err(reason) => {
+                warn!(target = %request.target, reason = %reason, "Batch item denied by policy");
+                results[i] = Some(Err(format!("Denied by policy: {}", reason)));
+            }
+        }
+    }
+
+    if !allowed.is_empty() {
+        let batch = CapabilityBatch::new(allowed.iter().map(|&i| requests[i].clone()).collect());
+        info!(request_id = %batch.request_id, items = allowed.len(), "Guest requesting capability batch");
+        if approver.approve(&batch).await {
+            for &i in &allowed {
+                results[i] = Some(issuer.mint(&requests[i]).await);
+            }
+        } else {
+            warn!(request_id = %batch.request_id, "Capability batch rejected");
+            for &i in &allowed {
+                results[i] = Some(Err("The capability batch was not approved".to_string()));
+            }
+        }
+    }
+    results.into_iter().map(|result| result.expect("every request is answered")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Denies anything under /etc; mints `tok:<target>`.
+    struct Issuer;
+
+    #[async_trait::async_trait]
+    impl CapabilityIssuer for Issuer {
+        fn check(&self, request: &CapabilityRequest) -> Result<(), String> {
+            if request.target.starts_with("/etc") { Err("outside the allowed directories".into()) } else { Ok(()) }
+        }
+
+        async fn mint(&self, request: &CapabilityRequest) -> Result<String, String> {
+            Ok(format!("tok:{}", request.target))
+        }
+    }
+
+    struct Approver {
+        approve: bool,
+        seen: Mutex<Vec<(String, RiskLevel)>>,
+    }
+
+    #[async_trait::async_trait]
+    impl BatchApprover for Approver {
+        async fn approve(&self, batch: &CapabilityBatch) -> bool {
+            self.seen.lock().unwrap().push((batch.describe(), batch.risk_level));
+            self.approve
+        }
+    }
+
+    fn approver(approve: bool) -> Approver {
+        Approver { approve, seen: Mutex::new(Vec::new()) }
+    }
+
+    fn request(kind: RequestKind, target: &str) -> CapabilityRequest {
+        CapabilityRequest { kind, target: target.into(), method: None, justification: "audit".into() }
+    }
+
+    fn requests(src: &str) -> Vec<CapabilityRequest> {
+        vec![
+            request(RequestKind::FsRead, src),
+            request(RequestKind::FsRead, "/etc/shadow"),
+            request(RequestKind::FsWrite, "AUDIT_REPORT.md"),
+            request(RequestKind::NetOutbound, "https://api.openai.com/v1/chat/completions"),
+            request(RequestKind::NetOutbound, "https://api.openai.com/v1/models"),
+        ]
+    }
+
+    #[tokio::test]
+    async fn test_mixed_grant_and_policy_deny() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().display().to_string();
+        let approver = approver(true);
+
+        let results = request_batch(&Issuer, &approver, &requests(&src)).await;
+        assert_eq!(results[0], Ok(format!("tok:{}", src)));
+        assert_eq!(results[1], Err("Denied by policy: outside the allowed directories".into()));
+        assert_eq!(results[2], Ok("tok:AUDIT_REPORT.md".into()));
+        assert_eq!(results[4], Ok("tok:https://api.openai.com/v1/models".into()));
+
+        // One manifest for the four allowed requests.
+        let seen = approver.seen.lock().unwrap();
+        assert_eq!(*seen, vec![(
+            format!("This agent wants: read {} recursively, write AUDIT_REPORT.md, call api.openai.com", src),
+            RiskLevel::High,
+        )]);
+    }
+
+    #[tokio::test]
+    async fn test_rejection_denies_whole_batch() {
+        let approver = approver(false);
+        let results = request_batch(&Issuer, &approver, &requests("src/main.rs")).await;
+        assert!(results.iter().all(Result::is_err));
+        assert!(results[1].as_ref().unwrap_err().starts_with("Denied by policy"));
+        assert!(results[0].as_ref().unwrap_err().contains("not approved"));
+
+        // Nothing left to approve once policy has denied everything.
+        let approver = self::approver(true);
+        let results = request_batch(&Issuer, &approver, &[request(RequestKind::FsRead, "/etc")]).await;
+        assert!(results[0].is_err());
+        assert!(approver.seen.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_describe_and_risk() {
+        let batch = CapabilityBatch::new(vec![request(RequestKind::FsRead, "src/main.rs")]);
+        assert_eq!(batch.describe(), "This agent wants: read src/main.rs");
+        assert_eq!(batch.risk_level, RiskLevel::Low);
+        let batch = CapabilityBatch::new(vec![request(RequestKind::NetOutbound, "http://localhost:8080?q=1")]);
+        assert_eq!(batch.describe(), "This agent wants: call localhost:8080");
+        assert_eq!(batch.risk_level, RiskLevel::Medium);
+    }
+}
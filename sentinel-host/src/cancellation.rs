@@ -0,0 +1,103 @@
+//! # sentinel-host — Cancellation
+//!
+//! Stops a running agent without throwing its work away.
+//! [`CancellationHandle::cancel`] raises a flag the guest polls through the
+//! `cancellation` WIT interface; a well-behaved guest notices between units
+//! of work, saves what it has and exits with [`EXIT_CANCELLED`]. A guest
+//! still running when the grace period is over gets an epoch interrupt,
+//! which traps it wherever it is.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Exit code of a guest that stopped early because it was cancelled.
+/// Mirrors `sentinel_guest_api::cancellation::EXIT_CANCELLED`.
+pub const EXIT_CANCELLED: i32 = 3;
+
+#[derive(Default)]
+struct Signal {
+    requested: AtomicBool,
+    forced: AtomicBool,
+    reason: Mutex<Option<String>>,
+}
+
+/// Cancels one agent run, child guests included. Cheap to clone.
+#[derive(Clone)]
+pub struct CancellationHandle {
+    signal: Arc<Signal>,
+    engine: wasmtime::Engine,
+    grace_period: Duration,
+}
+
+impl CancellationHandle {
+    /// `engine` must have epoch interruption enabled.
+    pub fn new(engine: wasmtime::Engine, grace_period: Duration) -> Self {
+        Self { signal: Arc::default(), engine, grace_period }
+    }
+
+    /// Ask the guest to stop, and interrupt it once the grace period is
+    /// over. Only the first reason is kept. Must be called on a Tokio
+    /// runtime.
+    pub fn cancel(&self, reason: impl Into<String>) {
+        {
+            let mut current = self.signal.reason.lock().unwrap_or_else(|e| e.into_inner());
+            if current.is_some() {
+                return;
+            }
+            let reason = reason.into();
+            info!(reason = %reason, grace_ms = self.grace_period.as_millis() as u64, "Cancellation requested");
+            *current = Some(reason);
+        }
+        // After the reason, so a guest that sees the flag also sees why.
+        self.signal.requested.store(true, Ordering::SeqCst);
+
+        let handle = self.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(handle.grace_period).await;
+            handle.force();
+        });
+    }
+
+    /// Interrupt the guest now.
+    pub fn force(&self) {
+        if !self.signal.forced.swap(true, Ordering::SeqCst) {
+            warn!("Cancellation grace period over; interrupting guest");
+        }
+        self.engine.increment_epoch();
+    }
+
+    pub fn is_requested(&self) -> bool {
+        self.signal.requested.load(Ordering::SeqCst)
+    }
+
+    pub fn is_forced(&self) -> bool {
+        self.signal.forced.load(Ordering::SeqCst)
+    }
+
+    pub fn reason(&self) -> Option<String> {
+        self.signal.reason.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_first_reason_kept_and_forced_after_grace() {
+        let handle = CancellationHandle::new(wasmtime::Engine::default(), Duration::from_millis(20));
+        assert!(!handle.is_requested());
+        assert_eq!(handle.reason(), None);
+
+        handle.cancel("Interrupted by user");
+        handle.clone().cancel("Timed out");
+        assert!(handle.is_requested());
+        assert!(!handle.is_forced());
+        assert_eq!(handle.reason().as_deref(), Some("Interrupted by user"));
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(handle.is_forced());
+    }
+}
@@ -4,7 +4,7 @@
 //! ephemeral tokens from this manager before accessing any host resource.
 //! Tokens are scoped, time-limited, and revocable.
 
-use sentinel_shared::{CapabilityScope, CapabilityToken, SentinelError};
+use sentinel_shared::{paths, CapabilityScope, CapabilityToken, SentinelError};
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::{Duration, SystemTime};
@@ -12,6 +12,7 @@ use tokio::sync::RwLock;
 use tracing::{info, warn};
 
 use crate::config::SentinelConfig;
+use crate::guest_manifest::{GuestManifest, PreauthorizedToken, RequirementKind};
 
 /// The capability manager — mints, validates, and revokes tokens.
 pub struct CapabilityManager {
@@ -66,10 +67,20 @@ impl CapabilityManager {
         token_id: &str,
         requested_resource: &str,
     ) -> Result<CapabilityToken, SentinelError> {
+        let token = self.active_token(token_id).await?;
+
+        // Validate the requested resource against the token scope
+        self.check_resource_against_scope(&token.scope, requested_resource)?;
+
+        Ok(token)
+    }
+
+    /// Look up a token that is neither revoked nor expired.
+    async fn active_token(&self, token_id: &str) -> Result<CapabilityToken, SentinelError> {
         let tokens = self.tokens.read().await;
-        let token = tokens.get(token_id).ok_or_else(|| SentinelError::CapabilityDenied {
-            reason: format!("Unknown token: {token_id}"),
-        })?;
+        let token = tokens
+            .get(token_id)
+            .ok_or_else(|| SentinelError::CapabilityDenied(format!("Unknown token: {token_id}")))?;
 
         if token.revoked {
             return Err(SentinelError::TokenRevoked {
@@ -83,12 +94,96 @@ impl CapabilityManager {
             });
         }
 
-        // Validate the requested resource against the token scope
-        self.check_resource_against_scope(&token.scope, requested_resource)?;
-
         Ok(token.clone())
     }
 
+    /// Mint a token for a child guest from one its parent holds. The child's
+    /// scope is the parent's, narrowed to `resource` if given, and it expires
+    /// no later than the parent's.
+    pub async fn derive_token(
+        &self,
+        parent_id: &str,
+        resource: Option<&str>,
+    ) -> Result<CapabilityToken, SentinelError> {
+        let parent = match resource {
+            Some(resource) => self.validate_token(parent_id, resource).await?,
+            None => self.active_token(parent_id).await?,
+        };
+
+        let mut scope = parent.scope.clone();
+        if let Some(resource) = resource {
+            match &mut scope {
+                CapabilityScope::FsPath { allowed_pattern, read_only } => {
+                    // Already checked to lie within the parent's path.
+                    let path = resolve_fs_resource(resource, *read_only).map_err(|_| SentinelError::PathEscapeAttempt {
+                        path: resource.to_string(),
+                    })?;
+                    *allowed_pattern = path.to_string_lossy().to_string();
+                }
+                CapabilityScope::NetUrl { allowed_url_pattern, .. } => *allowed_url_pattern = resource.to_string(),
+                CapabilityScope::UiObserve | CapabilityScope::UiDispatch { .. } => {
+                    return Err(SentinelError::CapabilityDenied(format!(
+                        "Token {parent_id} cannot be narrowed to a resource"
+                    )))
+                }
+            }
+        }
+
+        let now = SystemTime::now();
+        let token = CapabilityToken {
+            id: generate_token_id(),
+            scope,
+            issued_at: now,
+            ttl: parent.expires_at().duration_since(now).unwrap_or_default(),
+            revoked: false,
+        };
+
+        info!(token_id = %token.id, parent_id = %parent_id, "Capability token derived");
+        self.tokens.write().await.insert(token.id.clone(), token.clone());
+
+        Ok(token)
+    }
+
+    /// Mint a token for each requirement in a guest's manifest, before it
+    /// runs. All or nothing: tokens already minted are revoked on failure.
+    pub async fn preauthorize(
+        &self,
+        manifest: &GuestManifest,
+    ) -> Result<Vec<PreauthorizedToken>, SentinelError> {
+        let mut minted = Vec::with_capacity(manifest.requirements.len());
+        for requirement in &manifest.requirements {
+            let scope = match requirement.kind {
+                RequirementKind::FsRead | RequirementKind::FsWrite => {
+                    let path = requirement.resolve_path().map_err(SentinelError::CapabilityDenied)?;
+                    CapabilityScope::FsPath {
+                        allowed_pattern: path.to_string_lossy().to_string(),
+                        read_only: requirement.kind == RequirementKind::FsRead,
+                    }
+                }
+                // The manifest doesn't say which methods, so any will do.
+                RequirementKind::NetOutbound => CapabilityScope::NetUrl {
+                    allowed_url_pattern: requirement.pattern.clone(),
+                    methods: Vec::new(),
+                },
+            };
+            match self.mint_token(scope).await {
+                Ok(token) => minted.push(PreauthorizedToken {
+                    kind: requirement.kind,
+                    pattern: requirement.pattern.clone(),
+                    token_id: token.id,
+                }),
+                Err(e) => {
+                    for token in &minted {
+                        self.revoke_token(&token.token_id).await;
+                    }
+                    return Err(e);
+                }
+            }
+        }
+        info!(guest = %manifest.name, count = minted.len(), "Capability tokens preauthorized");
+        Ok(minted)
+    }
+
     /// Revoke a token immediately.
     pub async fn revoke_token(&self, token_id: &str) -> bool {
         let mut tokens = self.tokens.write().await;
@@ -125,15 +220,18 @@ impl CapabilityManager {
     // ── Internal helpers ────────────────────────────────────────────────
 
     /// Check that a requested scope is allowed by policy.
-    fn validate_scope(&self, scope: &CapabilityScope) -> Result<(), SentinelError> {
+    pub(crate) fn validate_scope(&self, scope: &CapabilityScope) -> Result<(), SentinelError> {
         match scope {
-            CapabilityScope::FsPath { allowed_pattern, .. } => {
-                // Ensure the requested path pattern falls within allowed directories
+            CapabilityScope::FsPath { allowed_pattern, read_only } => {
+                // Ensure the requested path pattern falls within allowed directories.
+                // Like the write check at runtime, a written file's directory must be writable.
                 let requested = std::path::Path::new(allowed_pattern);
-                let is_allowed = self.config.filesystem.allowed_read_dirs.iter().any(|dir| {
-                    let dir_canon = dir.canonicalize().unwrap_or_else(|_| dir.clone());
-                    requested.starts_with(&dir_canon)
-                });
+                let is_allowed = if *read_only {
+                    self.config.filesystem.allowed_read_dirs.iter().any(|dir| paths::is_within(requested, dir))
+                } else {
+                    let parent = requested.parent().unwrap_or(requested);
+                    self.config.filesystem.allowed_write_dirs.iter().any(|dir| paths::is_within(parent, dir))
+                };
                 if !is_allowed {
                     return Err(SentinelError::PathEscapeAttempt {
                         path: allowed_pattern.clone(),
@@ -168,15 +266,13 @@ impl CapabilityManager {
         resource: &str,
     ) -> Result<(), SentinelError> {
         match scope {
-            CapabilityScope::FsPath { allowed_pattern, .. } => {
-                // Canonicalize and check path containment
-                let resource_path = std::path::Path::new(resource).canonicalize().map_err(|_| {
-                    SentinelError::PathEscapeAttempt {
-                        path: resource.to_string(),
-                    }
+            CapabilityScope::FsPath { allowed_pattern, read_only } => {
+                // Canonicalize and check path containment.
+                let resource_path = resolve_fs_resource(resource, *read_only).map_err(|_| SentinelError::PathEscapeAttempt {
+                    path: resource.to_string(),
                 })?;
                 let scope_path = std::path::Path::new(allowed_pattern);
-                if !resource_path.starts_with(&scope_path) {
+                if !resource_path.starts_with(scope_path) {
                     return Err(SentinelError::PathEscapeAttempt {
                         path: resource.to_string(),
                     });
@@ -189,7 +285,19 @@ impl CapabilityManager {
                     });
                 }
             }
-            _ => {}
+            CapabilityScope::UiObserve => {
+                if resource != "ui:observe" {
+                    return Err(SentinelError::CapabilityDenied(format!("{resource} is not covered by ui.observe")));
+                }
+            }
+            CapabilityScope::UiDispatch { allowed_event_types } => {
+                let covered = resource
+                    .strip_prefix("ui:dispatch:")
+                    .is_some_and(|event_type| allowed_event_types.iter().any(|allowed| allowed == event_type));
+                if !covered {
+                    return Err(SentinelError::CapabilityDenied(format!("{resource} is not covered by ui.dispatch")));
+                }
+            }
         }
         Ok(())
     }
@@ -197,6 +305,16 @@ impl CapabilityManager {
 
 // ─── Utility Functions ──────────────────────────────────────────────────────
 
+/// Canonicalize a path a token is checked against. A file about to be
+/// written may not exist yet, so resolve its directory.
+fn resolve_fs_resource(resource: &str, read_only: bool) -> std::io::Result<std::path::PathBuf> {
+    let requested = std::path::Path::new(resource);
+    requested.canonicalize().or_else(|e| match (read_only, requested.parent(), requested.file_name()) {
+        (false, Some(parent), Some(name)) => parent.canonicalize().map(|p| p.join(name)),
+        _ => Err(e),
+    })
+}
+
 /// Generate a cryptographically random token ID.
 fn generate_token_id() -> String {
     use rand::Rng;
@@ -212,11 +330,9 @@ fn hex_encode(bytes: &[u8]) -> String {
 
 /// Simple URL pattern matching (supports trailing `*` wildcard).
 fn url_matches_pattern(url: &str, pattern: &str) -> bool {
-    if pattern.ends_with('*') {
-        let prefix = &pattern[..pattern.len() - 1];
-        url.starts_with(prefix)
-    } else {
-        url == pattern
+    match pattern.strip_suffix('*') {
+        Some(prefix) => url.starts_with(prefix),
+        None => url == pattern,
     }
 }
 
@@ -245,4 +361,71 @@ mod tests {
         let bytes = [0xDE, 0xAD, 0xBE, 0xEF];
         assert_eq!(hex_encode(&bytes), "deadbeef");
     }
+
+    #[tokio::test]
+    async fn test_preauthorized_tokens_are_scoped() {
+        use crate::guest_manifest::Requirement;
+
+        let dir = tempfile::tempdir().unwrap();
+        let readable = dir.path().canonicalize().unwrap().join("src");
+        std::fs::create_dir(&readable).unwrap();
+        std::fs::write(readable.join("main.rs"), "").unwrap();
+        std::fs::write(dir.path().join("secret.txt"), "").unwrap();
+
+        let mut config = SentinelConfig::default();
+        config.filesystem.allowed_read_dirs = vec![dir.path().to_path_buf()];
+        config.network.url_whitelist = vec!["https://api.example.com/*".into()];
+        let manager = CapabilityManager::new(config);
+        let requirement = |kind, pattern: &str| Requirement { kind, pattern: pattern.into(), justification: String::new() };
+        let manifest = GuestManifest {
+            name: "auditor".into(),
+            version: "1.0.0".into(),
+            description: String::new(),
+            requirements: vec![
+                requirement(RequirementKind::FsRead, &readable.to_string_lossy()),
+                requirement(RequirementKind::NetOutbound, "https://api.example.com/v1/*"),
+            ],
+        };
+
+        let tokens = manager.preauthorize(&manifest).await.unwrap();
+        let (read, net) = (&tokens[0].token_id, &tokens[1].token_id);
+        assert!(manager.validate_token(read, &readable.join("main.rs").to_string_lossy()).await.is_ok());
+        assert!(manager.validate_token(read, &dir.path().join("secret.txt").to_string_lossy()).await.is_err());
+        assert!(manager.validate_token(net, "https://api.example.com/v1/chat").await.is_ok());
+        assert!(manager.validate_token(net, "https://api.example.com/admin").await.is_err());
+        assert!(manager.validate_token(net, "ui:observe").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_derived_tokens_are_narrower() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        let (src, docs) = (root.join("src"), root.join("docs"));
+        std::fs::create_dir(&src).unwrap();
+        std::fs::create_dir(&docs).unwrap();
+        std::fs::write(src.join("main.rs"), "").unwrap();
+        std::fs::write(docs.join("README.md"), "").unwrap();
+
+        let mut config = SentinelConfig::default();
+        config.filesystem.allowed_read_dirs = vec![root.clone()];
+        let manager = CapabilityManager::new(config);
+        let parent = manager
+            .mint_token(CapabilityScope::FsPath { allowed_pattern: root.to_string_lossy().to_string(), read_only: true })
+            .await
+            .unwrap();
+
+        let child = manager.derive_token(&parent.id, Some(&src.to_string_lossy())).await.unwrap();
+        assert!(child.expires_at() <= parent.expires_at());
+        assert!(manager.validate_token(&child.id, &src.join("main.rs").to_string_lossy()).await.is_ok());
+        assert!(manager.validate_token(&child.id, &docs.join("README.md").to_string_lossy()).await.is_err());
+        // A child can't be narrowed to anything its parent doesn't cover.
+        assert!(manager.derive_token(&parent.id, Some("/etc")).await.is_err());
+
+        let ui = manager.mint_token(CapabilityScope::UiObserve).await.unwrap();
+        assert!(manager.derive_token(&ui.id, Some("ui:observe")).await.is_err());
+        assert!(manager.derive_token(&ui.id, None).await.is_ok());
+
+        manager.revoke_token(&parent.id).await;
+        assert!(matches!(manager.derive_token(&parent.id, None).await, Err(SentinelError::TokenRevoked { .. })));
+    }
 }
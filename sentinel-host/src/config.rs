@@ -4,6 +4,7 @@
 //! limits, capability scopes, and security policy thresholds.
 
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::time::Duration;
 
@@ -14,6 +15,15 @@ pub struct SentinelConfig {
     pub network: NetConfig,
     pub hitl: HitlConfig,
     pub llm: crate::llm::LlmConfig,
+    #[serde(default)]
+    pub state: StateConfig,
+    /// Secrets guests may request by name (`crate::secrets`).
+    #[serde(default)]
+    pub secrets: HashMap<String, SecretConfig>,
+    #[serde(default)]
+    pub spawn: SpawnConfig,
+    #[serde(default)]
+    pub archive: ArchiveConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -23,6 +33,14 @@ pub struct EngineConfig {
     pub max_table_elements: u32,
     pub fuel_limit: Option<u64>,
     pub guest_module_path: PathBuf,
+    /// Longest single `clock.sleep` a guest may request.
+    pub max_sleep: Duration,
+    /// How long a cancelled guest may keep running to save partial work
+    /// before it is interrupted.
+    pub cancel_grace_period: Duration,
+    /// Run guests built against another `sentinel:agent` major version.
+    #[serde(default)]
+    pub allow_version_mismatch: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -37,6 +55,8 @@ pub struct NetConfig {
     pub url_whitelist: Vec<String>,
     pub allowed_methods: Vec<String>,
     pub request_timeout: Duration,
+    /// Largest body `net-download` will save.
+    pub max_download_size: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -45,6 +65,68 @@ pub struct HitlConfig {
     pub approval_timeout: Duration,
 }
 
+/// Guest key-value state (`crate::state`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateConfig {
+    /// One subdirectory per guest module lives here.
+    pub root: PathBuf,
+    pub max_namespace_bytes: u64,
+    pub max_total_bytes: u64,
+}
+
+impl Default for StateConfig {
+    fn default() -> Self {
+        let home = std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE")).unwrap_or_default();
+        Self {
+            root: PathBuf::from(home).join(".sentinel").join("state"),
+            max_namespace_bytes: 1024 * 1024,
+            max_total_bytes: 8 * 1024 * 1024,
+        }
+    }
+}
+
+/// How a named secret is found and sent.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SecretConfig {
+    /// Request header carrying the secret, e.g. `Authorization`.
+    pub header: String,
+    /// Header value with a `{value}` placeholder, e.g. `Bearer {value}`.
+    pub template: String,
+    /// Environment variable holding the plaintext.
+    pub env: String,
+}
+
+/// Child guests (`crate::engine::spawn`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpawnConfig {
+    /// The only modules a guest may spawn, by name.
+    pub modules: HashMap<String, PathBuf>,
+    /// Children running at once, across the whole tree of guests.
+    pub max_concurrent_children: usize,
+}
+
+impl Default for SpawnConfig {
+    fn default() -> Self {
+        Self { modules: HashMap::new(), max_concurrent_children: 4 }
+    }
+}
+
+/// Limits on `fs-extract-archive` (`crate::archive`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveConfig {
+    pub max_entries: u64,
+    /// Total uncompressed bytes one extraction may write.
+    pub max_total_bytes: u64,
+    /// Uncompressed bytes allowed per archive byte.
+    pub max_compression_ratio: u64,
+}
+
+impl Default for ArchiveConfig {
+    fn default() -> Self {
+        Self { max_entries: 10_000, max_total_bytes: 1024 * 1024 * 1024, max_compression_ratio: 100 }
+    }
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub enum ApprovalThreshold {
     None,
@@ -62,6 +144,9 @@ impl Default for SentinelConfig {
                 max_table_elements: 10_000,
                 fuel_limit: Some(1_000_000_000),
                 guest_module_path: PathBuf::from("guest.wasm"),
+                max_sleep: Duration::from_secs(30),
+                cancel_grace_period: Duration::from_secs(10),
+                allow_version_mismatch: false,
             },
             filesystem: FsConfig {
                 allowed_read_dirs: vec![std::env::current_dir().unwrap_or_default()],
@@ -72,12 +157,17 @@ impl Default for SentinelConfig {
                 url_whitelist: vec![],
                 allowed_methods: vec!["GET".into(), "POST".into(), "PUT".into(), "DELETE".into()],
                 request_timeout: Duration::from_secs(30),
+                max_download_size: 512 * 1024 * 1024,
             },
             hitl: HitlConfig {
                 approval_threshold: ApprovalThreshold::High,
                 approval_timeout: Duration::from_secs(300),
             },
             llm: crate::llm::LlmConfig::default(),
+            state: StateConfig::default(),
+            secrets: HashMap::new(),
+            spawn: SpawnConfig::default(),
+            archive: ArchiveConfig::default(),
         }
     }
 }
@@ -0,0 +1,173 @@
+//! # sentinel-host — Streaming Downloads
+//!
+//! `net-download` saves an HTTP response straight to a file the guest may
+//! write, so large artifacts never pass through Wasm memory. The body is
+//! streamed to `<dest>.part`, hashed on the way, and renamed into place
+//! once complete; an oversized or failed download leaves nothing behind.
+
+use reqwest::header::CONTENT_TYPE;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use tokio::io::AsyncWriteExt;
+
+/// Progress is reported each time this many more bytes have arrived.
+const PROGRESS_STEP: u64 = 1024 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DownloadResult {
+    pub size: u64,
+    /// Hex-encoded SHA-256 of the body.
+    pub sha256: String,
+    pub content_type: Option<String>,
+}
+
+/// GET `url` into `dest`, giving up once the body exceeds `max_bytes`.
+/// `progress` gets the bytes received so far and the total, if the server
+/// said.
+pub async fn download(
+    client: &reqwest::Client,
+    url: &str,
+    dest: &Path,
+    max_bytes: u64,
+    progress: impl FnMut(u64, Option<u64>),
+) -> Result<DownloadResult, String> {
+    let part = partial_path(dest);
+    let result = match stream_to(client, url, &part, max_bytes, progress).await {
+        Ok(result) => tokio::fs::rename(&part, dest)
+            .await
+            .map(|_| result)
+            .map_err(|e| format!("Cannot move download into place: {e}")),
+        Err(e) => Err(e),
+    };
+    if result.is_err() {
+        let _ = tokio::fs::remove_file(&part).await;
+    }
+    result
+}
+
+fn partial_path(dest: &Path) -> PathBuf {
+    let mut name = dest.file_name().unwrap_or_default().to_os_string();
+    name.push(".part");
+    dest.with_file_name(name)
+}
+
+async fn stream_to(
+    client: &reqwest::Client,
+    url: &str,
+    part: &Path,
+    max_bytes: u64,
+    mut progress: impl FnMut(u64, Option<u64>),
+) -> Result<DownloadResult, String> {
+    let too_large = || format!("Download exceeds the {max_bytes} byte limit");
+    let mut response = client.get(url).send().await.map_err(|e| format!("Request failed: {e}"))?;
+    if !response.status().is_success() {
+        return Err(format!("Server answered {}", response.status()));
+    }
+    let total = response.content_length();
+    if total.is_some_and(|total| total > max_bytes) {
+        return Err(too_large());
+    }
+    let content_type = response.headers().get(CONTENT_TYPE).and_then(|v| v.to_str().ok()).map(str::to_string);
+
+    let mut file = tokio::fs::File::create(part).await.map_err(|e| format!("Cannot create file: {e}"))?;
+    let mut hasher = Sha256::new();
+    let mut size = 0u64;
+    let mut reported = 0u64;
+    while let Some(chunk) = response.chunk().await.map_err(|e| format!("Download interrupted: {e}"))? {
+        size += chunk.len() as u64;
+        if size > max_bytes {
+            return Err(too_large());
+        }
+        hasher.update(&chunk);
+        file.write_all(&chunk).await.map_err(|e| format!("Cannot write file: {e}"))?;
+        if size - reported >= PROGRESS_STEP {
+            reported = size;
+            progress(size, total);
+        }
+    }
+    file.flush().await.map_err(|e| format!("Cannot write file: {e}"))?;
+    progress(size, total);
+
+    let sha256 = hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect();
+    Ok(DownloadResult { size, sha256, content_type })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncReadExt;
+    use tokio::net::TcpListener;
+
+    /// Serves `body` once per connection. Without a Content-Length the
+    /// body simply ends when the connection closes.
+    async fn serve(body: Vec<u8>, content_length: bool) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let body = body.clone();
+                tokio::spawn(async move {
+                    let mut request = [0u8; 1024];
+                    let _ = stream.read(&mut request).await;
+                    let mut head = "HTTP/1.1 200 OK\r\nContent-Type: application/octet-stream\r\nConnection: close\r\n".to_string();
+                    if content_length {
+                        head.push_str(&format!("Content-Length: {}\r\n", body.len()));
+                    }
+                    head.push_str("\r\n");
+                    let _ = stream.write_all(head.as_bytes()).await;
+                    for chunk in body.chunks(64 * 1024) {
+                        if stream.write_all(chunk).await.is_err() {
+                            return;
+                        }
+                    }
+                });
+            }
+        });
+        format!("http://{addr}/artifact.bin")
+    }
+
+    fn fixture() -> Vec<u8> {
+        (0..3 * 1024 * 1024 + 17).map(|i: u32| (i % 251) as u8).collect()
+    }
+
+    #[tokio::test]
+    async fn test_streams_to_file_with_checksum() {
+        let body = fixture();
+        let url = serve(body.clone(), true).await;
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("artifact.bin");
+
+        let mut reports = Vec::new();
+        let result = download(&reqwest::Client::new(), &url, &dest, 10 * 1024 * 1024, |n, total| reports.push((n, total)))
+            .await
+            .unwrap();
+
+        let expected: String = Sha256::digest(&body).iter().map(|b| format!("{:02x}", b)).collect();
+        assert_eq!(result.size, body.len() as u64);
+        assert_eq!(result.sha256, expected);
+        assert_eq!(result.content_type.as_deref(), Some("application/octet-stream"));
+        assert_eq!(std::fs::read(&dest).unwrap(), body);
+        assert!(!partial_path(&dest).exists());
+        assert!(reports.len() >= 3);
+        assert_eq!(*reports.last().unwrap(), (body.len() as u64, Some(body.len() as u64)));
+    }
+
+    #[tokio::test]
+    async fn test_size_limit_removes_partial_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("artifact.bin");
+        let client = reqwest::Client::new();
+
+        // No Content-Length: the limit trips mid-stream.
+        let url = serve(fixture(), false).await;
+        let err = download(&client, &url, &dest, 1024 * 1024, |_, _| {}).await.unwrap_err();
+        assert!(err.contains("limit"), "{err}");
+        assert!(!dest.exists());
+        assert!(!partial_path(&dest).exists());
+
+        // With one, it's refused before anything is written.
+        let url = serve(fixture(), true).await;
+        assert!(download(&client, &url, &dest, 1024, |_, _| {}).await.is_err());
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+}
@@ -4,16 +4,38 @@
 //! Implements the security boundary and HITL hooks.
 
 use wasmtime::*;
-use wasmtime_wasi::preview1::{WasiP1Ctx, add_to_linker_async};
+use wasmtime_wasi::preview1::WasiP1Ctx;
 use wasmtime_wasi::WasiCtxBuilder;
 use anyhow::{Result, Context};
-use serde::{Deserialize, Serialize};
+use rand::RngCore;
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use crate::cancellation::CancellationHandle;
+use crate::config::SentinelConfig;
+use crate::metrics::Metrics;
+use crate::guest_manifest::{GuestManifest, PreauthorizedToken, Requirement, RequirementKind};
+use crate::progress::{GuestEvent, GuestEventSender, Progress};
+use crate::state::{self, StateStore};
+
+pub mod host_calls;
+pub mod spawn;
+pub mod version;
+
+use host_calls::HeldTokens;
+use spawn::{Budget, Children, Spawner};
+
+wasmtime::component::bindgen!({
+    path: "../wit/sentinel.wit",
+    world: "sentinel-guest",
+    async: true,
+});
 
 pub struct Engine {
     engine: wasmtime::Engine,
-    linker: Linker<HostState>,
+    component_linker: Arc<component::Linker<HostState>>,
+    config: SentinelConfig,
+    capabilities: Arc<crate::capabilities::CapabilityManager>,
+    spawner: Arc<Spawner>,
 }
 
 pub struct HostState {
@@ -22,6 +44,44 @@ pub struct HostState {
     pub target_directory: String,
     pub hitl_bridge: Arc<HitlBridge>,
     pub capability_manager: Arc<CapabilityManager>,
+    /// Origin of `clock.monotonic-ms`.
+    pub started: Instant,
+    pub max_sleep: Duration,
+    /// This module's persistent key-value state.
+    pub kv: StateStore,
+    /// Logs and progress reported by the guest, for the embedder.
+    pub events: GuestEventSender,
+    pub spawner: Arc<Spawner>,
+    pub children: Children,
+    pub budget: Budget,
+    /// Set by `spawn.set-output`; handed to the parent of a child guest.
+    pub output: Option<String>,
+    pub cancellation: CancellationHandle,
+    /// Aggregated `logging.record-metric` samples.
+    pub metrics: Metrics,
+    /// Backs `capabilities`, `secrets`, `hitl` and `reasoning`; without it
+    /// those calls are denied.
+    pub host_calls: Option<Arc<crate::host_calls::HostCallHandler>>,
+    /// The tokens this guest may use.
+    pub tokens: HeldTokens,
+    /// Children can't mint tokens of their own.
+    pub is_child: bool,
+}
+
+/// What a finished `run_agent` call hands back to the embedder.
+#[derive(Debug, Clone, Default)]
+pub struct RunReport {
+    /// What the guest's `run` returned.
+    pub exit_code: i32,
+    pub metrics: Metrics,
+}
+
+/// A guest that passed its boot checks.
+pub struct Boot {
+    pub manifest: Option<GuestManifest>,
+    /// The context to pass to `run_agent`.
+    pub context_json: String,
+    pub preauthorized: Vec<PreauthorizedToken>,
 }
 
 #[derive(Clone)]
@@ -35,33 +95,195 @@ pub struct CapabilityManager {
 
 impl Engine {
     pub fn new() -> Result<Self> {
+        Self::with_config(SentinelConfig::default())
+    }
+
+    pub fn with_config(sentinel_config: SentinelConfig) -> Result<Self> {
         let mut config = Config::new();
         config.async_support(true);
         config.wasm_component_model(true);
+        config.consume_fuel(sentinel_config.engine.fuel_limit.is_some());
+        // Only used to force out a cancelled guest; see `configure_store`.
+        config.epoch_interruption(true);
         
         let engine = wasmtime::Engine::new(&config)?;
-        let mut linker = Linker::new(&engine);
-        
-        // Add WASI support
-        add_to_linker_async(&mut linker)?;
+
+        let mut component_linker = component::Linker::new(&engine);
+        sentinel::agent::clock::add_to_linker(&mut component_linker, |state: &mut HostState| state)?;
+        sentinel::agent::random::add_to_linker(&mut component_linker, |state: &mut HostState| state)?;
+        sentinel::agent::state::add_to_linker(&mut component_linker, |state: &mut HostState| state)?;
+        sentinel::agent::logging::add_to_linker(&mut component_linker, |state: &mut HostState| state)?;
+        sentinel::agent::spawn::add_to_linker(&mut component_linker, |state: &mut HostState| state)?;
+        sentinel::agent::cancellation::add_to_linker(&mut component_linker, |state: &mut HostState| state)?;
+        sentinel::agent::meta::add_to_linker(&mut component_linker, |state: &mut HostState| state)?;
+        sentinel::agent::capabilities::add_to_linker(&mut component_linker, |state: &mut HostState| state)?;
+        sentinel::agent::secrets::add_to_linker(&mut component_linker, |state: &mut HostState| state)?;
+        sentinel::agent::hitl::add_to_linker(&mut component_linker, |state: &mut HostState| state)?;
+        sentinel::agent::reasoning::add_to_linker(&mut component_linker, |state: &mut HostState| state)?;
+        let component_linker = Arc::new(component_linker);
+
+        let capabilities = Arc::new(crate::capabilities::CapabilityManager::new(sentinel_config.clone()));
+        let spawner = Arc::new(Spawner::new(engine.clone(), component_linker.clone(), sentinel_config.clone()));
         
-        Ok(Self { engine, linker })
+        Ok(Self { engine, component_linker, config: sentinel_config, capabilities, spawner })
+    }
+
+    /// Mints the tokens `boot` preauthorizes; share it with the
+    /// `HostCallHandler`.
+    pub fn capabilities(&self) -> Arc<crate::capabilities::CapabilityManager> {
+        self.capabilities.clone()
+    }
+
+    /// The guest's manifest, if it exports `metadata.get-manifest`. The
+    /// guest is instantiated in a throwaway store with a linker of its own,
+    /// where every import but `meta` traps, so it can't do anything but
+    /// answer.
+    pub async fn read_manifest(&self, wasm_bytes: &[u8]) -> Result<Option<GuestManifest>> {
+        let component = component::Component::from_binary(&self.engine, wasm_bytes)?;
+        self.manifest_of(&component, wasm_bytes).await
+    }
+
+    async fn manifest_of(&self, component: &component::Component, wasm_bytes: &[u8]) -> Result<Option<GuestManifest>> {
+        use exports::sentinel::agent::metadata::{CapabilityKind, GuestManifest as RawManifest};
+
+        let mut linker = component::Linker::new(&self.engine);
+        sentinel::agent::meta::add_to_linker(&mut linker, |state: &mut HostState| state)?;
+        linker.define_unknown_imports_as_traps(component)?;
+
+        let (events, _) = tokio::sync::mpsc::unbounded_channel();
+        let state = HostState {
+            wasi: WasiCtxBuilder::new().build_p1(),
+            agent_id: "manifest-probe".into(),
+            target_directory: String::new(),
+            hitl_bridge: Arc::new(HitlBridge { callback_url: String::new() }),
+            capability_manager: Arc::new(CapabilityManager { autonomy: String::new() }),
+            started: Instant::now(),
+            max_sleep: Duration::ZERO,
+            kv: StateStore::open(&self.config.state, &state::module_hash(wasm_bytes))?,
+            events,
+            spawner: self.spawner.clone(),
+            children: Children::default(),
+            budget: self.root_budget(),
+            output: None,
+            metrics: Metrics::default(),
+            cancellation: self.cancellation_handle(),
+            host_calls: None,
+            tokens: HeldTokens::default(),
+            is_child: false,
+        };
+        let mut store = Store::new(&self.engine, state);
+        configure_store(&mut store, self.config.engine.fuel_limit)?;
+
+        let instance = linker.instantiate_async(&mut store, component).await?;
+        let Some(interface) = instance.get_export(&mut store, None, "sentinel:agent/metadata@0.2.0") else {
+            return Ok(None);
+        };
+        let func = instance
+            .get_export(&mut store, Some(&interface), "get-manifest")
+            .context("metadata interface has no get-manifest")?;
+        let get_manifest = instance.get_typed_func::<(), (RawManifest,)>(&mut store, &func)?;
+        let (raw,) = get_manifest.call_async(&mut store, ()).await?;
+        get_manifest.post_return_async(&mut store).await?;
+
+        Ok(Some(GuestManifest {
+            name: raw.name,
+            version: raw.version,
+            description: raw.description,
+            requirements: raw
+                .requirements
+                .into_iter()
+                .map(|req| Requirement {
+                    kind: match req.kind {
+                        CapabilityKind::FsRead => RequirementKind::FsRead,
+                        CapabilityKind::FsWrite => RequirementKind::FsWrite,
+                        CapabilityKind::NetOutbound => RequirementKind::NetOutbound,
+                    },
+                    pattern: req.pattern,
+                    justification: req.justification,
+                })
+                .collect(),
+        }))
+    }
+
+    /// Get a guest ready to run: refuse it if its imports don't link
+    /// against this host's interfaces (unless `allow_version_mismatch`), read its
+    /// manifest, refuse it if policy denies any requirement, pass the
+    /// manifest on to the embedder, and with `preauthorize` mint its
+    /// tokens, adding them to the context as `preauthorized_tokens`.
+    pub async fn boot(
+        &self,
+        wasm_bytes: &[u8],
+        context_json: String,
+        preauthorize: bool,
+        events: &GuestEventSender,
+    ) -> Result<Boot> {
+        let component = component::Component::from_binary(&self.engine, wasm_bytes)?;
+        let guest_version = version::guest_version(&self.engine, &component);
+        tracing::info!(
+            host = %version::HOST_VERSION,
+            guest = %guest_version.map_or_else(|| "unversioned".to_string(), |v| v.to_string()),
+            "Interface versions"
+        );
+        version::check_version(guest_version, self.config.engine.allow_version_mismatch)?;
+
+        let Some(manifest) = self.manifest_of(&component, wasm_bytes).await.context("Failed to read guest manifest")? else {
+            return Ok(Boot { manifest: None, context_json, preauthorized: Vec::new() });
+        };
+
+        let violations = manifest.check_policy(&self.config);
+        if !violations.is_empty() {
+            let list: Vec<String> = violations.iter().map(|v| format!("  {}", v)).collect();
+            anyhow::bail!("Guest {} requires capabilities policy denies:\n{}", manifest.name, list.join("\n"));
+        }
+        let _ = events.send(GuestEvent::Manifest(manifest.clone()));
+
+        let mut preauthorized = Vec::new();
+        let context_json = if preauthorize {
+            preauthorized = self.capabilities.preauthorize(&manifest).await?;
+            let mut context: serde_json::Map<String, serde_json::Value> =
+                serde_json::from_str(&context_json).context("Context must be a JSON object to carry preauthorized tokens")?;
+            context.insert("preauthorized_tokens".into(), serde_json::to_value(&preauthorized)?);
+            serde_json::Value::Object(context).to_string()
+        } else {
+            context_json
+        };
+        Ok(Boot { manifest: Some(manifest), context_json, preauthorized })
+    }
+
+    fn root_budget(&self) -> Budget {
+        Budget::new(
+            self.config.engine.max_memory_bytes,
+            self.config.engine.max_tables as usize,
+            self.config.engine.max_table_elements as usize,
+        )
+    }
+
+    /// A handle for cancelling one `run_agent` call.
+    pub fn cancellation_handle(&self) -> CancellationHandle {
+        CancellationHandle::new(self.engine.clone(), self.config.engine.cancel_grace_period)
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub async fn run_agent(
         &self,
         wasm_bytes: &[u8],
         agent_id: String,
         target_dir: String,
-        context_json: String,
+        boot: Boot,
         hitl_bridge: Arc<HitlBridge>,
         capability_manager: Arc<CapabilityManager>,
-    ) -> Result<()> {
+        host_calls: Arc<crate::host_calls::HostCallHandler>,
+        events: GuestEventSender,
+        cancellation: CancellationHandle,
+    ) -> Result<RunReport> {
+        let context_json = boot.context_json;
         let wasi = WasiCtxBuilder::new()
             .inherit_stdout()
             .inherit_stderr()
             .env("SENTINEL_CONTEXT", &context_json)
             .build_p1();
+        let kv = StateStore::open(&self.config.state, &state::module_hash(wasm_bytes))
+            .context("Failed to open guest state store")?;
 
         let state = HostState {
             wasi,
@@ -69,14 +291,333 @@ impl Engine {
             target_directory: target_dir,
             hitl_bridge,
             capability_manager,
+            started: Instant::now(),
+            max_sleep: self.config.engine.max_sleep,
+            kv,
+            events,
+            spawner: self.spawner.clone(),
+            children: Children::default(),
+            budget: self.root_budget(),
+            output: None,
+            metrics: Metrics::default(),
+            cancellation,
+            host_calls: Some(host_calls),
+            tokens: HeldTokens::new(boot.preauthorized.into_iter().map(|token| token.token_id)),
+            is_child: false,
         };
 
         let mut store = Store::new(&self.engine, state);
-        let component = Component::from_binary(&self.engine, wasm_bytes)?;
-        
-        // Note: This is an abstraction, actual instantiation depends on the component's exports
-        // let (instance, _) = linker.instantiate_async(&mut store, &component).await?;
-        
-        Ok(())
+        configure_store(&mut store, self.config.engine.fuel_limit)?;
+        let component = component::Component::from_binary(&self.engine, wasm_bytes)?;
+
+        let instance = self
+            .component_linker
+            .instantiate_async(&mut store, &component)
+            .await
+            .map_err(|e| version::explain_link_error(&self.engine, &component, e))?;
+        let run = instance.get_typed_func::<(&str,), (i32,)>(&mut store, "run")?;
+        let (exit_code,) = run.call_async(&mut store, (&context_json,)).await?;
+        run.post_return_async(&mut store).await?;
+
+        Ok(RunReport { exit_code, metrics: store.into_data().metrics })
+    }
+}
+
+/// Apply the store's [`Budget`], give it `fuel`, and trap it once its
+/// cancellation is forced.
+pub(crate) fn configure_store(store: &mut Store<HostState>, fuel: Option<u64>) -> Result<()> {
+    store.limiter(|state| &mut state.budget);
+    store.call_hook(spawn::track_fuel);
+    if let Some(fuel) = fuel {
+        store.set_fuel(fuel)?;
+    }
+    // Every engine epoch tick lands here; only a forced cancellation stops
+    // the guest.
+    store.set_epoch_deadline(1);
+    store.epoch_deadline_callback(|store| {
+        if store.data().cancellation.is_forced() {
+            return Err(Trap::Interrupt.into());
+        }
+        Ok(UpdateDeadline::Continue(1))
+    });
+    Ok(())
+}
+
+#[async_trait::async_trait]
+impl sentinel::agent::cancellation::Host for HostState {
+    async fn is_cancellation_requested(&mut self) -> bool {
+        self.cancellation.is_requested()
+    }
+
+    async fn cancellation_reason(&mut self) -> Option<String> {
+        self.cancellation.reason()
+    }
+}
+
+#[async_trait::async_trait]
+impl sentinel::agent::logging::Host for HostState {
+    async fn log(&mut self, level: sentinel::agent::logging::LogLevel, target: String, message: String) {
+        use sentinel::agent::logging::LogLevel;
+        let level = match level {
+            LogLevel::Trace => tracing::Level::TRACE,
+            LogLevel::Debug => tracing::Level::DEBUG,
+            LogLevel::Info => tracing::Level::INFO,
+            LogLevel::Warn => tracing::Level::WARN,
+            LogLevel::Error => tracing::Level::ERROR,
+        };
+        match level {
+            tracing::Level::ERROR => tracing::error!(agent_id = %self.agent_id, guest_target = %target, "{}", message),
+            tracing::Level::WARN => tracing::warn!(agent_id = %self.agent_id, guest_target = %target, "{}", message),
+            tracing::Level::INFO => tracing::info!(agent_id = %self.agent_id, guest_target = %target, "{}", message),
+            _ => tracing::debug!(agent_id = %self.agent_id, guest_target = %target, "{}", message),
+        }
+        // The embedder may have stopped listening; the guest carries on.
+        let _ = self.events.send(GuestEvent::Log { level, target, message });
+    }
+
+    async fn report_progress(&mut self, phase: String, current: u32, total: u32, detail: String) {
+        let _ = self.events.send(GuestEvent::Progress(Progress { phase, current, total, detail }));
+    }
+
+    async fn record_metric(&mut self, name: String, value: f64, labels: Vec<(String, String)>) {
+        self.metrics.record(&name, value, labels);
+    }
+}
+
+#[async_trait::async_trait]
+impl sentinel::agent::meta::Host for HostState {
+    async fn interface_version(&mut self) -> (u16, u16) {
+        (version::HOST_VERSION.major, version::HOST_VERSION.minor)
+    }
+}
+
+/// How long a `clock.sleep(ms)` call actually waits.
+fn sleep_duration(ms: u64, max: Duration) -> Duration {
+    Duration::from_millis(ms).min(max)
+}
+
+#[async_trait::async_trait]
+impl sentinel::agent::clock::Host for HostState {
+    async fn now_unix_ms(&mut self) -> u64 {
+        SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64
+    }
+
+    async fn monotonic_ms(&mut self) -> u64 {
+        self.started.elapsed().as_millis() as u64
+    }
+
+    async fn sleep(&mut self, ms: u64) {
+        let duration = sleep_duration(ms, self.max_sleep);
+        if duration.as_millis() < ms as u128 {
+            tracing::warn!(agent_id = %self.agent_id, requested_ms = ms, max_ms = self.max_sleep.as_millis() as u64, "Guest sleep clamped");
+        }
+        tokio::time::sleep(duration).await;
+    }
+}
+
+/// Most bytes one `random.random-bytes` call may return.
+pub const MAX_RANDOM_BYTES: u32 = 4096;
+
+fn random_bytes(len: u32) -> Result<Vec<u8>, String> {
+    if len > MAX_RANDOM_BYTES {
+        return Err(format!("Requested {} random bytes; the limit is {}", len, MAX_RANDOM_BYTES));
+    }
+    let mut bytes = vec![0u8; len as usize];
+    rand::rngs::OsRng.fill_bytes(&mut bytes);
+    Ok(bytes)
+}
+
+fn uuid_v4() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+#[async_trait::async_trait]
+impl sentinel::agent::random::Host for HostState {
+    async fn random_bytes(&mut self, len: u32) -> Result<Vec<u8>, String> {
+        random_bytes(len)
+    }
+
+    async fn uuid_v4(&mut self) -> String {
+        uuid_v4()
+    }
+}
+
+#[async_trait::async_trait]
+impl sentinel::agent::state::Host for HostState {
+    async fn kv_get(&mut self, namespace: String, key: String) -> Result<Option<Vec<u8>>, String> {
+        self.kv.get(&namespace, &key).map_err(|e| e.to_string())
+    }
+
+    async fn kv_put(&mut self, namespace: String, key: String, value: Vec<u8>) -> Result<(), String> {
+        self.kv.put(&namespace, &key, value).map_err(|e| {
+            tracing::warn!(agent_id = %self.agent_id, namespace = %namespace, "kv-put refused: {}", e);
+            e.to_string()
+        })
+    }
+
+    async fn kv_delete(&mut self, namespace: String, key: String) -> Result<bool, String> {
+        self.kv.delete(&namespace, &key).map_err(|e| e.to_string())
+    }
+
+    async fn kv_list(&mut self, namespace: String) -> Result<Vec<String>, String> {
+        self.kv.list(&namespace).map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use sentinel::agent::logging::{Host as _, LogLevel};
+
+    pub(crate) fn host_state(dir: &std::path::Path) -> (HostState, tokio::sync::mpsc::UnboundedReceiver<GuestEvent>) {
+        let config = crate::config::StateConfig { root: dir.to_path_buf(), ..Default::default() };
+        let (events, rx) = tokio::sync::mpsc::unbounded_channel();
+        let state = HostState {
+            wasi: WasiCtxBuilder::new().build_p1(),
+            agent_id: "agent-test".into(),
+            target_directory: dir.display().to_string(),
+            hitl_bridge: Arc::new(HitlBridge { callback_url: "http://127.0.0.1:9".into() }),
+            capability_manager: Arc::new(CapabilityManager { autonomy: "read_report".into() }),
+            started: Instant::now(),
+            max_sleep: Duration::from_secs(1),
+            kv: StateStore::open(&config, "test").unwrap(),
+            events,
+            spawner: Engine::new().unwrap().spawner,
+            children: Children::default(),
+            budget: Budget::new(1024 * 1024, 1, 1000),
+            output: None,
+            metrics: Metrics::default(),
+            cancellation: CancellationHandle::new(wasmtime::Engine::default(), Duration::from_secs(1)),
+            host_calls: None,
+            tokens: HeldTokens::default(),
+            is_child: false,
+        };
+        (state, rx)
+    }
+
+    /// Run the fixture component's `run: func() -> s32` with `state`.
+    async fn run_fixture(engine: &Engine, state: HostState, fixture: &str) -> Result<i32> {
+        let path = format!("{}/testdata/{}", env!("CARGO_MANIFEST_DIR"), fixture);
+        let component = component::Component::from_file(&engine.engine, path)?;
+        let mut store = Store::new(&engine.engine, state);
+        configure_store(&mut store, engine.config.engine.fuel_limit)?;
+        let instance = engine.component_linker.instantiate_async(&mut store, &component).await?;
+        let run = instance.get_typed_func::<(), (i32,)>(&mut store, "run")?;
+        let (exit_code,) = run.call_async(&mut store, ()).await?;
+        Ok(exit_code)
+    }
+
+    #[tokio::test]
+    async fn test_guest_stops_cooperatively_when_cancelled() {
+        let dir = tempfile::tempdir().unwrap();
+        let engine = Engine::new().unwrap();
+        let (mut state, _events) = host_state(dir.path());
+        state.cancellation = engine.cancellation_handle();
+
+        // Stop is clicked while the guest is part-way through its files.
+        let cancellation = state.cancellation.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            cancellation.cancel("Stop clicked");
+        });
+        let exit_code = run_fixture(&engine, state, "cancellable_loop.wat").await.unwrap();
+        assert_eq!(exit_code, crate::cancellation::EXIT_CANCELLED);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_guest_interrupted_after_grace_period() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = SentinelConfig::default();
+        config.engine.fuel_limit = None;
+        config.engine.cancel_grace_period = Duration::from_millis(20);
+        let engine = Engine::with_config(config).unwrap();
+        let (mut state, _events) = host_state(dir.path());
+        state.cancellation = engine.cancellation_handle();
+
+        // This guest never looks at the flag, so only the interrupt stops it.
+        state.cancellation.cancel("Timed out");
+        let error = run_fixture(&engine, state, "busy_loop.wat").await.unwrap_err();
+        assert_eq!(error.downcast_ref::<Trap>(), Some(&Trap::Interrupt));
+    }
+
+    #[tokio::test]
+    async fn test_guest_events_forwarded_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let (mut state, mut rx) = host_state(dir.path());
+
+        // What the auditor sends for a two-file workspace.
+        state.log(LogLevel::Info, "auditor".into(), "starting".into()).await;
+        state.report_progress("audit".into(), 1, 4, "Discovering workspace files".into()).await;
+        state.report_progress("analysis".into(), 1, 2, "src/main.rs".into()).await;
+        state.log(LogLevel::Warn, "auditor".into(), "skipped src/big.rs".into()).await;
+        state.report_progress("analysis".into(), 2, 2, "src/lib.rs".into()).await;
+        drop(state);
+
+        let mut events = Vec::new();
+        while let Some(event) = rx.recv().await {
+            events.push(event);
+        }
+        let progress = |phase: &str, current, total, detail: &str| GuestEvent::Progress(Progress {
+            phase: phase.into(), current, total, detail: detail.into(),
+        });
+        assert_eq!(events, vec![
+            GuestEvent::Log { level: tracing::Level::INFO, target: "auditor".into(), message: "starting".into() },
+            progress("audit", 1, 4, "Discovering workspace files"),
+            progress("analysis", 1, 2, "src/main.rs"),
+            GuestEvent::Log { level: tracing::Level::WARN, target: "auditor".into(), message: "skipped src/big.rs".into() },
+            progress("analysis", 2, 2, "src/lib.rs"),
+        ]);
+    }
+
+    #[tokio::test]
+    async fn test_manifest_probe_cannot_call_host() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = SentinelConfig::default();
+        config.state.root = dir.path().to_path_buf();
+        let engine = Engine::with_config(config.clone()).unwrap();
+        let path = format!("{}/testdata/meddling_manifest.wat", env!("CARGO_MANIFEST_DIR"));
+        let component = component::Component::from_file(&engine.engine, &path).unwrap();
+        let wasm_bytes = std::fs::read(&path).unwrap();
+
+        let error = engine.manifest_of(&component, &wasm_bytes).await.unwrap_err();
+        assert!(format!("{:?}", error).contains("kv-put"), "{:?}", error);
+        let kv = StateStore::open(&config.state, &state::module_hash(&wasm_bytes)).unwrap();
+        assert!(kv.list("probe").unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_closed_event_channel_is_ignored() {
+        let dir = tempfile::tempdir().unwrap();
+        let (mut state, rx) = host_state(dir.path());
+        drop(rx);
+        state.report_progress("audit".into(), 1, 1, String::new()).await;
+    }
+
+    #[test]
+    fn test_sleep_clamped_to_max() {
+        let max = Duration::from_secs(30);
+        assert_eq!(sleep_duration(250, max), Duration::from_millis(250));
+        assert_eq!(sleep_duration(30_000, max), max);
+        assert_eq!(sleep_duration(u64::MAX, max), max);
+        assert_eq!(sleep_duration(0, max), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_random_bytes_unique_and_capped() {
+        let a = random_bytes(32).unwrap();
+        let b = random_bytes(32).unwrap();
+        assert_eq!(a.len(), 32);
+        assert_ne!(a, b);
+        assert_eq!(random_bytes(MAX_RANDOM_BYTES).unwrap().len(), MAX_RANDOM_BYTES as usize);
+        assert!(random_bytes(0).unwrap().is_empty());
+        assert!(random_bytes(MAX_RANDOM_BYTES + 1).unwrap_err().contains("limit is 4096"));
+    }
+
+    #[test]
+    fn test_uuid_v4_unique() {
+        let ids: std::collections::HashSet<String> = (0..100).map(|_| uuid_v4()).collect();
+        assert_eq!(ids.len(), 100);
+        assert!(ids.iter().all(|id| id.len() == 36 && id.as_bytes()[14] == b'4'));
     }
 }
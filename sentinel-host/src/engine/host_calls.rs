@@ -0,0 +1,521 @@
+//! # sentinel-host — Guest-Facing Host Calls
+//!
+//! Binds the `capabilities`, `secrets`, `hitl` and `reasoning` interfaces
+//! to the run's [`HostCallHandler`]. Tokens are bearer strings inside the
+//! handler, so each guest also keeps the ids it was given: a token minted
+//! for one guest (or preauthorized for the root) can't be used by another
+//! guest that learns its id.
+
+use super::{sentinel, HostState};
+use crate::archive::{self, ArchiveFormat};
+use crate::batch::{CapabilityRequest, RequestKind};
+use crate::hitl::ApprovalStatus;
+use crate::host_calls::HostCallHandler;
+use crate::llm::{ChatMessage, CompletionRequest, Role};
+use crate::watch::{FsEvent, FsEventKind};
+use sentinel_shared::{ExecutionManifest, RiskLevel, SentinelError};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use sentinel::agent::capabilities::{self as wit_caps, CapabilityResult, CapabilityToken};
+use sentinel::agent::hitl::{self as wit_hitl, ApprovalResult, ManifestApproval};
+use sentinel::agent::reasoning as wit_reasoning;
+
+/// The token, secret and watch ids one guest holds.
+#[derive(Debug, Default)]
+pub struct HeldTokens {
+    tokens: HashSet<String>,
+    watches: HashSet<u32>,
+}
+
+impl HeldTokens {
+    pub fn new(token_ids: impl IntoIterator<Item = String>) -> Self {
+        Self { tokens: token_ids.into_iter().collect(), watches: HashSet::new() }
+    }
+
+    pub fn holds(&self, token_id: &str) -> bool {
+        self.tokens.contains(token_id)
+    }
+
+    pub fn insert(&mut self, token_id: String) {
+        self.tokens.insert(token_id);
+    }
+}
+
+fn not_held(token_id: &str) -> String {
+    SentinelError::CapabilityDenied(format!("Unknown token: {token_id}")).to_string()
+}
+
+fn granted(result: Result<String, SentinelError>) -> CapabilityResult {
+    match result {
+        Ok(id) => CapabilityResult::Granted(CapabilityToken { id, is_valid: true }),
+        Err(e) => CapabilityResult::Denied(e.to_string()),
+    }
+}
+
+impl HostState {
+    fn host_calls(&self) -> Result<Arc<HostCallHandler>, SentinelError> {
+        self.host_calls
+            .clone()
+            .ok_or_else(|| SentinelError::CapabilityDenied("Capabilities are not available to this guest".to_string()))
+    }
+
+    /// The handler, if this guest may mint tokens of its own. A child only
+    /// holds what its parent derived for it.
+    fn minting_host_calls(&self) -> Result<Arc<HostCallHandler>, SentinelError> {
+        if self.is_child {
+            return Err(SentinelError::CapabilityDenied(
+                "Child guests only hold the grants their parent passes down".to_string(),
+            ));
+        }
+        self.host_calls()
+    }
+
+    /// The handler, once `token_id` is known to be this guest's.
+    pub(super) fn gated_host_calls(&self, token_id: &str) -> Result<Arc<HostCallHandler>, String> {
+        if !self.tokens.holds(token_id) {
+            return Err(not_held(token_id));
+        }
+        self.host_calls().map_err(|e| e.to_string())
+    }
+
+    fn keep(&mut self, result: Result<String, SentinelError>) -> CapabilityResult {
+        if let Ok(id) = &result {
+            self.tokens.insert(id.clone());
+        }
+        granted(result)
+    }
+}
+
+#[async_trait::async_trait]
+impl wit_caps::Host for HostState {
+    async fn request_fs_read(&mut self, path: String, justification: String) -> CapabilityResult {
+        let result = match self.minting_host_calls() {
+            Ok(calls) => calls.request_fs_read(path, justification).await,
+            Err(e) => Err(e),
+        };
+        self.keep(result)
+    }
+
+    async fn request_fs_write(&mut self, path: String, justification: String) -> CapabilityResult {
+        let result = match self.minting_host_calls() {
+            Ok(calls) => calls.request_fs_write(path, justification).await,
+            Err(e) => Err(e),
+        };
+        self.keep(result)
+    }
+
+    async fn request_net_outbound(&mut self, url: String, method: String, justification: String) -> CapabilityResult {
+        let result = match self.minting_host_calls() {
+            Ok(calls) => calls.request_net_outbound(url, method, justification).await,
+            Err(e) => Err(e),
+        };
+        self.keep(result)
+    }
+
+    async fn request_ui_observe(&mut self) -> CapabilityResult {
+        let result = match self.minting_host_calls() {
+            Ok(calls) => calls.request_ui_observe().await,
+            Err(e) => Err(e),
+        };
+        self.keep(result)
+    }
+
+    async fn request_ui_dispatch(&mut self, event_type: String) -> CapabilityResult {
+        let result = match self.minting_host_calls() {
+            Ok(calls) => calls.request_ui_dispatch(event_type).await,
+            Err(e) => Err(e),
+        };
+        self.keep(result)
+    }
+
+    async fn request_capabilities(&mut self, requests: Vec<wit_caps::CapabilityRequest>) -> Vec<CapabilityResult> {
+        let calls = match self.minting_host_calls() {
+            Ok(calls) => calls,
+            Err(e) => return requests.iter().map(|_| CapabilityResult::Denied(e.to_string())).collect(),
+        };
+        let requests = requests
+            .into_iter()
+            .map(|request| CapabilityRequest {
+                kind: match request.kind {
+                    wit_caps::RequestKind::FsRead => RequestKind::FsRead,
+                    wit_caps::RequestKind::FsWrite => RequestKind::FsWrite,
+                    wit_caps::RequestKind::NetOutbound => RequestKind::NetOutbound,
+                },
+                target: request.target,
+                method: request.method,
+                justification: request.justification,
+            })
+            .collect();
+        calls.request_capabilities(requests).await.into_iter().map(|result| self.keep(result)).collect()
+    }
+
+    async fn release_capability(&mut self, token_id: String) -> bool {
+        if !self.tokens.tokens.remove(&token_id) {
+            return false;
+        }
+        match self.host_calls() {
+            Ok(calls) => calls.release_capability(token_id).await,
+            Err(_) => false,
+        }
+    }
+
+    async fn fs_read(&mut self, token_id: String, path: String) -> Result<Vec<u8>, String> {
+        let calls = self.gated_host_calls(&token_id)?;
+        calls.fs_read(token_id, path).await.map_err(|e| e.to_string())
+    }
+
+    async fn fs_write(&mut self, token_id: String, path: String, data: Vec<u8>) -> Result<bool, String> {
+        let calls = self.gated_host_calls(&token_id)?;
+        calls.fs_write(token_id, path, data).await.map_err(|e| e.to_string())
+    }
+
+    async fn fs_list_dir(&mut self, token_id: String, path: String) -> Result<Vec<String>, String> {
+        let calls = self.gated_host_calls(&token_id)?;
+        calls.fs_list_dir(token_id, path).await.map_err(|e| e.to_string())
+    }
+
+    async fn net_request(
+        &mut self,
+        token_id: String,
+        url: String,
+        method: String,
+        headers: Vec<(String, String)>,
+        body: Option<Vec<u8>>,
+        secret_token_id: Option<String>,
+    ) -> Result<wit_caps::NetResponse, String> {
+        let calls = self.gated_host_calls(&token_id)?;
+        if let Some(secret_token_id) = &secret_token_id {
+            if !self.tokens.holds(secret_token_id) {
+                return Err(not_held(secret_token_id));
+            }
+        }
+        let response = calls.net_request(token_id, url, method, headers, body, secret_token_id).await.map_err(|e| e.to_string())?;
+        Ok(wit_caps::NetResponse { status: response.status, headers: response.headers, body: response.body })
+    }
+
+    async fn watch_path(&mut self, token_id: String, path: String, recursive: bool) -> Result<wit_caps::WatchId, String> {
+        let calls = self.gated_host_calls(&token_id)?;
+        let watch_id = calls.watch_path(token_id, path, recursive).await.map_err(|e| e.to_string())?;
+        self.tokens.watches.insert(watch_id);
+        Ok(watch_id)
+    }
+
+    async fn poll_watch(&mut self, watch_id: wit_caps::WatchId, timeout_ms: u32) -> Result<Vec<wit_caps::FsEvent>, String> {
+        if !self.tokens.watches.contains(&watch_id) {
+            return Err(SentinelError::NotFound(format!("Unknown watch: {watch_id}")).to_string());
+        }
+        let calls = self.host_calls().map_err(|e| e.to_string())?;
+        let events = calls.poll_watch(watch_id, timeout_ms).await.map_err(|e| e.to_string())?;
+        Ok(events.into_iter().map(fs_event).collect())
+    }
+
+    async fn unwatch(&mut self, watch_id: wit_caps::WatchId) -> bool {
+        if !self.tokens.watches.remove(&watch_id) {
+            return false;
+        }
+        self.host_calls().is_ok_and(|calls| calls.unwatch(watch_id))
+    }
+
+    async fn net_download(
+        &mut self,
+        net_token_id: String,
+        url: String,
+        fs_write_token_id: String,
+        dest_path: String,
+    ) -> Result<wit_caps::DownloadResult, String> {
+        let calls = self.gated_host_calls(&net_token_id)?;
+        if !self.tokens.holds(&fs_write_token_id) {
+            return Err(not_held(&fs_write_token_id));
+        }
+        let result = calls.net_download(net_token_id, url, fs_write_token_id, dest_path).await.map_err(|e| e.to_string())?;
+        Ok(wit_caps::DownloadResult { size: result.size, sha256: result.sha256, content_type: result.content_type })
+    }
+
+    async fn fs_extract_archive(
+        &mut self,
+        write_token_id: String,
+        archive_path: String,
+        dest_dir: String,
+        format: wit_caps::ArchiveFormat,
+    ) -> Result<wit_caps::ExtractReport, String> {
+        let calls = self.gated_host_calls(&write_token_id)?;
+        let format = match format {
+            wit_caps::ArchiveFormat::Zip => ArchiveFormat::Zip,
+            wit_caps::ArchiveFormat::TarGz => ArchiveFormat::TarGz,
+        };
+        let report: archive::ExtractReport =
+            calls.fs_extract_archive(write_token_id, archive_path, dest_dir, format).await.map_err(|e| e.to_string())?;
+        Ok(wit_caps::ExtractReport {
+            files: report.files,
+            directories: report.directories,
+            total_bytes: report.total_bytes,
+            skipped: report
+                .skipped
+                .into_iter()
+                .map(|entry| wit_caps::SkippedEntry { path: entry.path, reason: entry.reason })
+                .collect(),
+        })
+    }
+
+    async fn ui_get_state(&mut self, token_id: String) -> Result<String, String> {
+        let calls = self.gated_host_calls(&token_id)?;
+        calls.ui_get_state(token_id).await.map_err(|e| e.to_string())
+    }
+
+    async fn ui_send_event(&mut self, token_id: String, event_type: String, payload: String) -> Result<bool, String> {
+        let calls = self.gated_host_calls(&token_id)?;
+        calls.ui_send_event(token_id, event_type, payload).await.map_err(|e| e.to_string())
+    }
+}
+
+fn fs_event(event: FsEvent) -> wit_caps::FsEvent {
+    wit_caps::FsEvent {
+        kind: match event.kind {
+            FsEventKind::Created => wit_caps::FsEventKind::Created,
+            FsEventKind::Modified => wit_caps::FsEventKind::Modified,
+            FsEventKind::Removed => wit_caps::FsEventKind::Removed,
+        },
+        path: event.path,
+    }
+}
+
+#[async_trait::async_trait]
+impl sentinel::agent::secrets::Host for HostState {
+    async fn request_secret(&mut self, name: String, justification: String) -> CapabilityResult {
+        let result = match self.minting_host_calls() {
+            Ok(calls) => calls.request_secret(name, justification).await,
+            Err(e) => Err(e),
+        };
+        self.keep(result)
+    }
+}
+
+/// `parameters-json` as manifest parameters: an object's values, or the
+/// raw text under `parameters` if it isn't one.
+fn manifest_parameters(parameters_json: &str) -> HashMap<String, String> {
+    match serde_json::from_str::<serde_json::Map<String, serde_json::Value>>(parameters_json) {
+        Ok(object) => object
+            .into_iter()
+            .map(|(key, value)| match value {
+                serde_json::Value::String(text) => (key, text),
+                other => (key, other.to_string()),
+            })
+            .collect(),
+        Err(_) => HashMap::from([("parameters".to_string(), parameters_json.to_string())]),
+    }
+}
+
+fn approval_result(status: Result<ApprovalStatus, SentinelError>, approver_key: Vec<u8>) -> ApprovalResult {
+    match status {
+        Ok(ApprovalStatus::Approved(signature)) => ApprovalResult::Approved(ManifestApproval {
+            manifest_id: signature.manifest_id,
+            signature: signature.signature_bytes,
+            approver_key,
+        }),
+        Ok(ApprovalStatus::Pending) => ApprovalResult::Rejected("Awaiting approval".to_string()),
+        Ok(ApprovalStatus::Rejected(reason)) => ApprovalResult::Rejected(reason),
+        Ok(ApprovalStatus::TimedOut) => ApprovalResult::TimedOut,
+        Err(e) => ApprovalResult::Rejected(e.to_string()),
+    }
+}
+
+#[async_trait::async_trait]
+impl wit_hitl::Host for HostState {
+    async fn submit_manifest(&mut self, manifest: wit_hitl::ExecutionManifest) -> ApprovalResult {
+        let calls = match self.host_calls() {
+            Ok(calls) => calls,
+            Err(e) => return ApprovalResult::Rejected(e.to_string()),
+        };
+        let manifest = ExecutionManifest {
+            id: manifest.id,
+            action_description: manifest.action_description,
+            risk_level: match manifest.risk {
+                wit_hitl::RiskLevel::Low => RiskLevel::Low,
+                wit_hitl::RiskLevel::Medium => RiskLevel::Medium,
+                wit_hitl::RiskLevel::High => RiskLevel::High,
+                wit_hitl::RiskLevel::Critical => RiskLevel::Critical,
+            },
+            parameters: manifest_parameters(&manifest.parameters_json),
+            capability_token_id: None,
+            created_at: std::time::SystemTime::now(),
+            nonce: rand::random(),
+        };
+        approval_result(calls.submit_manifest(manifest).await, calls.approver_key())
+    }
+
+    async fn check_approval(&mut self, manifest_id: String) -> ApprovalResult {
+        match self.host_calls() {
+            Ok(calls) => approval_result(calls.check_approval(&manifest_id).await, calls.approver_key()),
+            Err(e) => ApprovalResult::Rejected(e.to_string()),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl wit_reasoning::Host for HostState {
+    async fn complete(
+        &mut self,
+        messages: Vec<wit_reasoning::ChatMessage>,
+        max_tokens: Option<u32>,
+        temperature: Option<f32>,
+        response_format_json: Option<String>,
+    ) -> Result<wit_reasoning::CompletionResponse, String> {
+        let calls = self.host_calls().map_err(|e| e.to_string())?;
+        let response_format = match response_format_json {
+            Some(json) => Some(serde_json::from_str(&json).map_err(|e| format!("Invalid response format: {e}"))?),
+            None => None,
+        };
+        let request = CompletionRequest {
+            messages: messages
+                .into_iter()
+                .map(|message| ChatMessage {
+                    role: match message.role.as_str() {
+                        "system" => Role::System,
+                        "assistant" => Role::Assistant,
+                        _ => Role::User,
+                    },
+                    content: message.content,
+                })
+                .collect(),
+            max_tokens,
+            temperature,
+            response_format,
+        };
+        let response = calls.complete(request).await.map_err(|e| e.to_string())?;
+        Ok(wit_reasoning::CompletionResponse {
+            content: response.content,
+            model: response.model,
+            usage: wit_reasoning::TokenUsage {
+                prompt_tokens: response.usage.prompt_tokens,
+                completion_tokens: response.usage.completion_tokens,
+                total_tokens: response.usage.total_tokens,
+            },
+            finish_reason: response.finish_reason,
+        })
+    }
+
+    async fn get_provider_name(&mut self) -> String {
+        self.host_calls().map_or_else(|_| "none".to_string(), |calls| calls.provider_name())
+    }
+
+    async fn count_tokens(&mut self, text: String, model_hint: String) -> u32 {
+        match self.host_calls() {
+            Ok(calls) => calls.count_tokens(&text, &model_hint),
+            Err(_) => crate::tokens::shared().count(&text, &model_hint),
+        }
+    }
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use super::*;
+    use crate::batch;
+    use crate::capabilities::CapabilityManager;
+    use crate::config::SentinelConfig;
+    use crate::engine::tests::host_state;
+    use crate::secrets::{SecretApprover, SecretRequest, SecretVault};
+    use wit_caps::Host as _;
+
+    struct Approve;
+
+    #[async_trait::async_trait]
+    impl SecretApprover for Approve {
+        async fn approve(&self, _request: &SecretRequest) -> bool {
+            true
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl batch::BatchApprover for Approve {
+        async fn approve(&self, _batch: &batch::CapabilityBatch) -> bool {
+            true
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl archive::ExtractApprover for Approve {
+        async fn approve(&self, _request: &archive::ExtractRequest) -> bool {
+            true
+        }
+    }
+
+    pub(crate) fn handler(config: SentinelConfig) -> Arc<HostCallHandler> {
+        let capabilities = Arc::new(CapabilityManager::new(config.clone()));
+        let secrets = Arc::new(SecretVault::new(config.secrets.clone(), Arc::new(Approve)));
+        let cancellation = crate::cancellation::CancellationHandle::new(wasmtime::Engine::default(), std::time::Duration::from_secs(1));
+        Arc::new(HostCallHandler::new(capabilities, config, secrets, Arc::new(Approve), Arc::new(Approve), cancellation))
+    }
+
+    #[tokio::test]
+    async fn test_tokens_only_work_for_their_guest() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "hello").unwrap();
+        let mut config = SentinelConfig::default();
+        config.filesystem.allowed_read_dirs = vec![dir.path().to_path_buf()];
+        let calls = handler(config);
+        let path = dir.path().join("notes.txt").to_string_lossy().to_string();
+
+        let (mut owner, _events) = host_state(dir.path());
+        owner.host_calls = Some(calls.clone());
+        let CapabilityResult::Granted(token) = owner.request_fs_read(path.clone(), "read notes".into()).await else {
+            panic!("fs-read was denied");
+        };
+        assert_eq!(owner.fs_read(token.id.clone(), path.clone()).await.unwrap(), b"hello");
+
+        // Another guest on the same handler that learns the id gets nothing.
+        let (mut other, _events) = host_state(dir.path());
+        other.host_calls = Some(calls);
+        assert!(other.fs_read(token.id.clone(), path.clone()).await.unwrap_err().contains("Unknown token"));
+        assert!(!other.release_capability(token.id.clone()).await);
+
+        assert!(owner.release_capability(token.id.clone()).await);
+        assert!(owner.fs_read(token.id, path).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_guest_without_handler_is_denied() {
+        let dir = tempfile::tempdir().unwrap();
+        let (mut state, _events) = host_state(dir.path());
+        let CapabilityResult::Denied(reason) = state.request_fs_read(".".into(), "look".into()).await else {
+            panic!("granted without a handler");
+        };
+        assert!(reason.contains("not available"), "{reason}");
+    }
+
+    #[tokio::test]
+    async fn test_watch_through_capabilities_interface() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        let mut config = SentinelConfig::default();
+        config.filesystem.allowed_read_dirs = vec![root.clone()];
+        let calls = handler(config);
+        let path = root.to_string_lossy().to_string();
+
+        let (mut owner, _events) = host_state(dir.path());
+        owner.host_calls = Some(calls.clone());
+        let CapabilityResult::Granted(token) = owner.request_fs_read(path.clone(), "watch the workspace".into()).await else {
+            panic!("fs-read was denied");
+        };
+        let watch_id = owner.watch_path(token.id.clone(), path, true).await.unwrap();
+
+        std::fs::write(root.join("notes.md"), "todo").unwrap();
+        let events = owner.poll_watch(watch_id, 5000).await.unwrap();
+        assert!(matches!(events.as_slice(), [wit_caps::FsEvent { kind: wit_caps::FsEventKind::Created, path }] if path.ends_with("notes.md")));
+
+        // Watch ids are as private as tokens.
+        let (mut other, _events) = host_state(dir.path());
+        other.host_calls = Some(calls);
+        assert!(other.poll_watch(watch_id, 0).await.unwrap_err().contains("Unknown watch"));
+        assert!(!other.unwatch(watch_id).await);
+        assert!(owner.unwatch(watch_id).await);
+    }
+
+    #[test]
+    fn test_manifest_parameters() {
+        let parameters = manifest_parameters(r#"{"file": "AUDIT_REPORT.md", "size_bytes": 120}"#);
+        assert_eq!(parameters["file"], "AUDIT_REPORT.md");
+        assert_eq!(parameters["size_bytes"], "120");
+        assert_eq!(manifest_parameters("not json")["parameters"], "not json");
+    }
+}
@@ -0,0 +1,515 @@
+//! # sentinel-host — Child Guests
+//!
+//! Backs the `spawn` WIT interface. A guest may start another guest, but
+//! only one named in `[spawn.modules]`, so it can never load arbitrary code.
+//! Each child runs in a fresh [`Store`] on its own task:
+//!
+//! - **Fuel**: half of the fuel the parent has left moves to the child;
+//!   whatever the child doesn't burn is refunded on `await-guest`.
+//! - **Memory**: half of the parent's unused memory budget is reserved for
+//!   the child until it is awaited.
+//! - **Capabilities**: nothing is inherited, and a child can't mint tokens
+//!   of its own. Each grant the parent passes is derived from a token the
+//!   parent holds into a new, possibly narrower one that expires no later,
+//!   and is revoked when the child exits.
+//!
+//! `max_concurrent_children` is shared by the whole tree of guests, so a
+//! guest can't get around it by having its children spawn more.
+
+use super::{sentinel, HostState};
+use crate::config::SentinelConfig;
+use crate::host_calls::HostCallHandler;
+use crate::state::{self, StateStore};
+use anyhow::Result;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tokio::task::JoinHandle;
+use wasmtime::component::{Component, Linker};
+use wasmtime::{CallHook, ResourceLimiter, Store, StoreContextMut};
+use wasmtime_wasi::WasiCtxBuilder;
+
+use sentinel::agent::spawn::{CapabilityGrant, RunHandle, RunResult};
+
+/// What one store may use, and how much of it is lent to children.
+/// Installed as the store's [`ResourceLimiter`].
+#[derive(Debug, Clone)]
+pub struct Budget {
+    memory_bytes: usize,
+    memory_in_use: usize,
+    memory_lent: usize,
+    max_tables: usize,
+    max_table_elements: usize,
+    /// Fuel left when the guest last called into the host.
+    fuel_left: Option<u64>,
+    /// Fuel to add to (or take from) the store as the host call returns.
+    fuel_adjustment: i64,
+}
+
+impl Budget {
+    pub fn new(memory_bytes: usize, max_tables: usize, max_table_elements: usize) -> Self {
+        Self {
+            memory_bytes,
+            memory_in_use: 0,
+            memory_lent: 0,
+            max_tables,
+            max_table_elements,
+            fuel_left: None,
+            fuel_adjustment: 0,
+        }
+    }
+
+    /// Fuel and memory for a new child: half of what this store has left.
+    fn lend(&mut self) -> Result<(Option<u64>, usize), String> {
+        let fuel = self.fuel_left.map(|left| left.saturating_add_signed(self.fuel_adjustment) / 2);
+        if fuel == Some(0) {
+            return Err("Not enough fuel left to spawn a child".to_string());
+        }
+        let memory = self.memory_bytes.saturating_sub(self.memory_in_use + self.memory_lent) / 2;
+        self.fuel_adjustment -= fuel.unwrap_or(0) as i64;
+        self.memory_lent += memory;
+        Ok((fuel, memory))
+    }
+
+    /// Return what a finished child didn't use.
+    fn repay(&mut self, fuel_unused: Option<u64>, memory: usize) {
+        self.fuel_adjustment += fuel_unused.unwrap_or(0) as i64;
+        self.memory_lent -= memory;
+    }
+
+    fn for_child(&self, memory_bytes: usize) -> Self {
+        Self::new(memory_bytes, self.max_tables, self.max_table_elements)
+    }
+}
+
+impl ResourceLimiter for Budget {
+    fn memory_growing(&mut self, current: usize, desired: usize, _maximum: Option<usize>) -> Result<bool> {
+        let growth = desired.saturating_sub(current);
+        if self.memory_in_use + self.memory_lent + growth > self.memory_bytes {
+            return Ok(false);
+        }
+        self.memory_in_use += growth;
+        Ok(true)
+    }
+
+    fn table_growing(&mut self, _current: usize, desired: usize, _maximum: Option<usize>) -> Result<bool> {
+        Ok(desired <= self.max_table_elements)
+    }
+
+    fn tables(&self) -> usize {
+        self.max_tables
+    }
+}
+
+/// Keeps [`Budget`]'s view of the store's fuel current, and moves fuel lent
+/// to or repaid by children in and out of the store.
+pub(super) fn track_fuel(mut store: StoreContextMut<'_, HostState>, hook: CallHook) -> Result<()> {
+    // Fuel isn't enabled; nothing to track.
+    let Ok(fuel) = store.get_fuel() else {
+        return Ok(());
+    };
+    match hook {
+        CallHook::CallingHost => store.data_mut().budget.fuel_left = Some(fuel),
+        CallHook::ReturningFromHost => {
+            let adjustment = std::mem::take(&mut store.data_mut().budget.fuel_adjustment);
+            if adjustment != 0 {
+                store.set_fuel(fuel.saturating_add_signed(adjustment))?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Loads registered modules and runs them as children.
+pub struct Spawner {
+    engine: wasmtime::Engine,
+    linker: Arc<Linker<HostState>>,
+    config: SentinelConfig,
+    /// Compiled modules and their hashes, by name.
+    modules: Mutex<HashMap<String, (Component, String)>>,
+    running: AtomicUsize,
+}
+
+/// One of the `max_concurrent_children` slots, freed on drop.
+struct Slot(Arc<Spawner>);
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        self.0.running.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl Spawner {
+    pub fn new(engine: wasmtime::Engine, linker: Arc<Linker<HostState>>, config: SentinelConfig) -> Self {
+        Self { engine, linker, config, modules: Mutex::new(HashMap::new()), running: AtomicUsize::new(0) }
+    }
+
+    /// Children spawned and not yet awaited, across all guests.
+    pub fn running(&self) -> usize {
+        self.running.load(Ordering::SeqCst)
+    }
+
+    fn claim_slot(self: &Arc<Self>) -> Result<Slot, String> {
+        let max = self.config.spawn.max_concurrent_children;
+        let running = self.running.fetch_add(1, Ordering::SeqCst);
+        let slot = Slot(self.clone());
+        if running >= max {
+            return Err(format!("Too many child guests running (limit {})", max));
+        }
+        Ok(slot)
+    }
+
+    /// The compiled module registered as `module_ref`, and its hash.
+    fn load(&self, module_ref: &str) -> Result<(Component, String), String> {
+        let mut modules = self.modules.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(loaded) = modules.get(module_ref) {
+            return Ok(loaded.clone());
+        }
+        let path = self.config.spawn.modules.get(module_ref).ok_or_else(|| format!("Unknown module '{}'", module_ref))?;
+        let bytes = std::fs::read(path).map_err(|e| format!("Cannot read module '{}': {}", module_ref, e))?;
+        let component = Component::new(&self.engine, &bytes).map_err(|e| format!("Invalid module '{}': {}", module_ref, e))?;
+        let loaded = (component, state::module_hash(&bytes));
+        modules.insert(module_ref.to_string(), loaded.clone());
+        Ok(loaded)
+    }
+}
+
+/// A guest's unawaited children, aborted if the guest goes away first.
+/// Each holds its concurrency slot until awaited.
+#[derive(Default)]
+pub struct Children {
+    next_handle: RunHandle,
+    running: HashMap<RunHandle, Child>,
+}
+
+struct Child {
+    task: JoinHandle<ChildExit>,
+    fuel: Option<u64>,
+    memory: usize,
+    _slot: Slot,
+}
+
+impl Drop for Children {
+    fn drop(&mut self) {
+        for child in self.running.values() {
+            child.task.abort();
+        }
+    }
+}
+
+struct ChildExit {
+    result: Result<i32, String>,
+    output: Option<String>,
+    fuel_left: Option<u64>,
+}
+
+/// Tokens derived for one child. Revoked when it exits, or, should the
+/// child be aborted, once this is dropped.
+struct Grants {
+    host_calls: Option<Arc<HostCallHandler>>,
+    token_ids: Vec<String>,
+}
+
+impl Grants {
+    /// Derive a token for each grant; all or nothing. The caller has
+    /// checked that it holds every grant's token.
+    async fn derive(host_calls: Option<Arc<HostCallHandler>>, grants: &[CapabilityGrant]) -> Result<Self, String> {
+        let mut derived = Grants { host_calls, token_ids: Vec::with_capacity(grants.len()) };
+        for grant in grants {
+            let token_id = match &derived.host_calls {
+                Some(host_calls) => host_calls
+                    .derive_capability(&grant.token_id, grant.narrow_to.as_deref())
+                    .await
+                    .map_err(|e| e.to_string()),
+                None => Err("Capabilities are not available to this guest".to_string()),
+            };
+            match token_id {
+                Ok(token_id) => derived.token_ids.push(token_id),
+                Err(e) => {
+                    derived.revoke().await;
+                    return Err(e);
+                }
+            }
+        }
+        Ok(derived)
+    }
+
+    async fn revoke(&mut self) {
+        let Some(host_calls) = &self.host_calls else {
+            return;
+        };
+        for token_id in self.token_ids.drain(..) {
+            host_calls.release_capability(token_id).await;
+        }
+    }
+}
+
+impl Drop for Grants {
+    fn drop(&mut self) {
+        if self.token_ids.is_empty() {
+            return;
+        }
+        let mut grants = Grants { host_calls: self.host_calls.take(), token_ids: std::mem::take(&mut self.token_ids) };
+        if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+            runtime.spawn(async move { grants.revoke().await });
+        }
+    }
+}
+
+/// `context_json` parsed as an object, to carry capability grants.
+fn context_object(context_json: &str) -> Result<serde_json::Map<String, serde_json::Value>, String> {
+    serde_json::from_str(context_json).map_err(|_| "context-json must be a JSON object to pass capability grants".to_string())
+}
+
+impl HostState {
+    /// Check that this guest holds each grant's token, before any is
+    /// derived.
+    fn check_grants(&self, grants: &[CapabilityGrant]) -> Result<(), String> {
+        grants.iter().try_for_each(|grant| self.gated_host_calls(&grant.token_id).map(drop))
+    }
+
+    fn child(&self, agent_id: String, kv: StateStore, budget: Budget, tokens: super::HeldTokens) -> HostState {
+        HostState {
+            wasi: WasiCtxBuilder::new().inherit_stderr().build_p1(),
+            agent_id,
+            target_directory: self.target_directory.clone(),
+            hitl_bridge: self.hitl_bridge.clone(),
+            capability_manager: self.capability_manager.clone(),
+            started: Instant::now(),
+            max_sleep: self.max_sleep,
+            kv,
+            events: self.events.clone(),
+            spawner: self.spawner.clone(),
+            children: Children::default(),
+            budget,
+            output: None,
+            metrics: crate::metrics::Metrics::default(),
+            cancellation: self.cancellation.clone(),
+            host_calls: self.host_calls.clone(),
+            tokens,
+            is_child: true,
+        }
+    }
+}
+
+async fn run_child(
+    spawner: Arc<Spawner>,
+    component: Component,
+    state: HostState,
+    fuel: Option<u64>,
+    context_json: String,
+    mut grants: Grants,
+) -> ChildExit {
+    let agent_id = state.agent_id.clone();
+    let mut store = Store::new(&spawner.engine, state);
+    let result = async {
+        super::configure_store(&mut store, fuel)?;
+        let instance = spawner
+            .linker
+            .instantiate_async(&mut store, &component)
+            .await
+            .map_err(|e| super::version::explain_link_error(&spawner.engine, &component, e))?;
+        let run = instance.get_typed_func::<(&str,), (i32,)>(&mut store, "run")?;
+        let (exit_code,) = run.call_async(&mut store, (&context_json,)).await?;
+        run.post_return_async(&mut store).await?;
+        Ok(exit_code)
+    }
+    .await
+    .map_err(|e: anyhow::Error| format!("Child guest {} failed: {:#}", agent_id, e));
+    grants.revoke().await;
+
+    tracing::info!(agent_id = %agent_id, ok = result.is_ok(), "Child guest finished");
+    ChildExit {
+        result,
+        output: store.data_mut().output.take(),
+        fuel_left: fuel.and_then(|_| store.get_fuel().ok()),
+    }
+}
+
+#[async_trait::async_trait]
+impl sentinel::agent::spawn::Host for HostState {
+    async fn spawn_guest(
+        &mut self,
+        module_ref: String,
+        context_json: String,
+        capability_grants: Vec<CapabilityGrant>,
+    ) -> Result<RunHandle, String> {
+        let spawner = self.spawner.clone();
+        let slot = spawner.claim_slot()?;
+        let (component, hash) = spawner.load(&module_ref)?;
+        let kv = StateStore::open(&spawner.config.state, &hash).map_err(|e| e.to_string())?;
+        let context = if capability_grants.is_empty() { None } else { Some(context_object(&context_json)?) };
+        self.check_grants(&capability_grants)?;
+
+        let (fuel, memory) = self.budget.lend()?;
+        let grants = match Grants::derive(self.host_calls.clone(), &capability_grants).await {
+            Ok(grants) => grants,
+            Err(e) => {
+                self.budget.repay(fuel, memory);
+                return Err(e);
+            }
+        };
+        let context_json = match context {
+            Some(mut context) => {
+                context.insert("capability_grants".into(), grants.token_ids.clone().into());
+                serde_json::Value::Object(context).to_string()
+            }
+            None => context_json,
+        };
+
+        let handle = self.children.next_handle;
+        self.children.next_handle += 1;
+        let agent_id = format!("{}/{}#{}", self.agent_id, module_ref, handle);
+        tracing::info!(agent_id = %agent_id, fuel = ?fuel, memory_bytes = memory, grants = grants.token_ids.len(), "Spawning child guest");
+        let tokens = super::HeldTokens::new(grants.token_ids.clone());
+        let state = self.child(agent_id, kv, self.budget.for_child(memory), tokens);
+        let task = tokio::spawn(run_child(spawner, component, state, fuel, context_json, grants));
+        self.children.running.insert(handle, Child { task, fuel, memory, _slot: slot });
+        Ok(handle)
+    }
+
+    async fn await_guest(&mut self, handle: RunHandle) -> Result<RunResult, String> {
+        let child = self.children.running.remove(&handle).ok_or_else(|| format!("Unknown run handle {}", handle))?;
+        let exit = child.task.await;
+        let fuel_left = exit.as_ref().ok().and_then(|exit| exit.fuel_left);
+        self.budget.repay(fuel_left, child.memory);
+
+        let exit = exit.map_err(|e| format!("Child guest aborted: {}", e))?;
+        Ok(RunResult {
+            exit_code: exit.result?,
+            output: exit.output,
+            fuel_used: child.fuel.zip(exit.fuel_left).map(|(given, left)| given - left).unwrap_or(0),
+        })
+    }
+
+    async fn set_output(&mut self, output: String) {
+        self.output = Some(output);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::tests::host_state;
+
+    const ECHO_CHILD: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/testdata/echo_child.wat");
+
+    fn config(max_concurrent_children: usize) -> SentinelConfig {
+        let mut config = SentinelConfig::default();
+        config.spawn.modules.insert("echo".into(), ECHO_CHILD.into());
+        config.spawn.max_concurrent_children = max_concurrent_children;
+        config
+    }
+
+    fn spawner(config: SentinelConfig) -> Arc<Spawner> {
+        let engine = crate::engine::Engine::with_config(config.clone()).unwrap();
+        Arc::new(Spawner::new(engine.engine.clone(), engine.component_linker.clone(), config))
+    }
+
+    #[test]
+    fn test_lend_and_repay() {
+        let mut budget = Budget::new(1000, 1, 10);
+        budget.fuel_left = Some(800);
+        assert!(budget.memory_growing(0, 200, None).unwrap());
+
+        assert_eq!(budget.lend().unwrap(), (Some(400), 400));
+        // The first loan hasn't left the store yet, but still counts.
+        assert_eq!(budget.lend().unwrap(), (Some(200), 200));
+        assert!(!budget.memory_growing(200, 500, None).unwrap());
+
+        budget.repay(Some(150), 400);
+        assert_eq!(budget.fuel_adjustment, -450);
+        assert!(budget.memory_growing(200, 500, None).unwrap());
+
+        budget.fuel_left = Some(1);
+        budget.fuel_adjustment = 0;
+        assert!(budget.lend().unwrap_err().contains("fuel"));
+    }
+
+    #[tokio::test]
+    async fn test_echo_child_returns_context() {
+        use sentinel::agent::spawn::Host as _;
+
+        let dir = tempfile::tempdir().unwrap();
+        let spawner = spawner(config(1));
+        let (mut state, _events) = host_state(dir.path());
+        state.spawner = spawner.clone();
+        state.budget.fuel_left = Some(1_000_000);
+
+        let context = r#"{"task":"echo","n":1}"#;
+        let handle = state.spawn_guest("echo".into(), context.into(), vec![]).await.unwrap();
+        assert_eq!(state.budget.fuel_adjustment, -500_000);
+        // Until the first child is awaited it holds the only slot.
+        assert!(state.spawn_guest("echo".into(), context.into(), vec![]).await.unwrap_err().contains("limit 1"));
+
+        let result = state.await_guest(handle).await.unwrap();
+        assert_eq!(result.exit_code, 0);
+        assert_eq!(result.output.as_deref(), Some(context));
+        assert!(result.fuel_used > 0);
+        assert_eq!(state.budget.fuel_adjustment, -(result.fuel_used as i64));
+        assert_eq!(spawner.running(), 0);
+        assert!(state.await_guest(handle).await.unwrap_err().contains("Unknown run handle"));
+    }
+
+    #[tokio::test]
+    async fn test_spawn_refusals() {
+        use sentinel::agent::spawn::Host as _;
+
+        let dir = tempfile::tempdir().unwrap();
+        let (mut state, _events) = host_state(dir.path());
+        state.spawner = spawner(config(4));
+
+        assert!(state.spawn_guest("/tmp/evil.wasm".into(), "{}".into(), vec![]).await.unwrap_err().contains("Unknown module"));
+        let grant = CapabilityGrant { token_id: "forged".into(), narrow_to: None };
+        assert!(state.spawn_guest("echo".into(), "[]".into(), vec![grant.clone()]).await.unwrap_err().contains("JSON object"));
+        assert!(state.spawn_guest("echo".into(), "{}".into(), vec![grant]).await.unwrap_err().contains("Unknown token"));
+        // Nothing was lent for the refused children.
+        assert_eq!((state.budget.memory_lent, state.budget.fuel_adjustment), (0, 0));
+        assert_eq!(state.spawner.running(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_child_gets_narrowed_grants() {
+        use crate::engine::host_calls::tests::handler;
+        use sentinel::agent::capabilities::{CapabilityResult, Host as _};
+        use sentinel::agent::spawn::Host as _;
+
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        let src = root.join("src");
+        std::fs::create_dir(&src).unwrap();
+        let mut config = config(1);
+        config.filesystem.allowed_read_dirs = vec![root.clone()];
+        let calls = handler(config.clone());
+        let (mut state, _events) = host_state(dir.path());
+        state.spawner = spawner(config);
+        state.host_calls = Some(calls.clone());
+        state.budget.fuel_left = Some(1_000_000);
+
+        let CapabilityResult::Granted(token) = state.request_fs_read(root.to_string_lossy().to_string(), "audit".into()).await else {
+            panic!("fs-read was denied");
+        };
+        let grant = CapabilityGrant { token_id: token.id.clone(), narrow_to: Some(src.to_string_lossy().to_string()) };
+        let handle = state.spawn_guest("echo".into(), r#"{"task":"audit"}"#.into(), vec![grant]).await.unwrap();
+        let output = state.await_guest(handle).await.unwrap().output.unwrap();
+        let context: serde_json::Value = serde_json::from_str(&output).unwrap();
+        let derived = context["capability_grants"][0].as_str().unwrap().to_string();
+        assert_ne!(derived, token.id);
+
+        // Revoked once the child exited; the parent's token is untouched.
+        let manager = &calls.capability_manager;
+        assert!(manager.validate_token(&derived, &src.to_string_lossy()).await.is_err());
+        assert!(manager.validate_token(&token.id, &root.to_string_lossy()).await.is_ok());
+
+        // A child can only pass on what it was given, and can't mint.
+        let (mut child, _events) = host_state(dir.path());
+        child.spawner = state.spawner.clone();
+        child.host_calls = Some(calls.clone());
+        child.is_child = true;
+        assert!(matches!(child.request_fs_read(src.to_string_lossy().to_string(), "more".into()).await, CapabilityResult::Denied(_)));
+        let grant = CapabilityGrant { token_id: token.id, narrow_to: None };
+        assert!(child.spawn_guest("echo".into(), "{}".into(), vec![grant]).await.unwrap_err().contains("Unknown token"));
+    }
+}
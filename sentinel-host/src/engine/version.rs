@@ -0,0 +1,188 @@
+//! # sentinel-host — Interface Versions
+//!
+//! The `sentinel:agent` package version is the interface version. The
+//! host's is [`HOST_VERSION`], which guests can ask for through
+//! `meta.interface-version`. A guest's is read off its import names
+//! (`sentinel:agent/clock@0.2.0`), which the bindings stamp with the
+//! version they were generated against. A guest runs only if its imports
+//! link against the host's, which before 1.0 means the same minor too.
+
+use super::Engine;
+use anyhow::Result;
+use std::fmt;
+use wasmtime::component::Component;
+
+pub const PACKAGE: &str = "sentinel:agent";
+
+/// Keep in step with the `package` line of `wit/sentinel.wit`.
+pub const HOST_VERSION: InterfaceVersion = InterfaceVersion { major: 0, minor: 2 };
+
+/// Interfaces the component linker provides; keep in step with
+/// `Engine::with_config`.
+pub const HOST_INTERFACES: &[&str] = &[
+    "capabilities",
+    "secrets",
+    "hitl",
+    "reasoning",
+    "clock",
+    "random",
+    "state",
+    "logging",
+    "spawn",
+    "cancellation",
+    "meta",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct InterfaceVersion {
+    pub major: u16,
+    pub minor: u16,
+}
+
+impl InterfaceVersion {
+    /// The major and minor of a semver string such as `0.1.0`.
+    fn parse(semver: &str) -> Option<Self> {
+        let mut parts = semver.split('.');
+        let major = parts.next()?.parse().ok()?;
+        let minor = parts.next()?.parse().ok()?;
+        Some(Self { major, minor })
+    }
+
+    /// Whether imports at this version link against `host`. Before 1.0 a
+    /// minor bump is breaking, as in semver.
+    fn links_against(self, host: InterfaceVersion) -> bool {
+        self.major == host.major && if self.major == 0 { self.minor == host.minor } else { self.minor <= host.minor }
+    }
+}
+
+impl fmt::Display for InterfaceVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+/// Split `sentinel:agent/clock@0.2.0` into `clock` and its version.
+fn parse_name(name: &str) -> Option<(&str, Option<InterfaceVersion>)> {
+    let rest = name.strip_prefix(PACKAGE)?.strip_prefix('/')?;
+    Some(match rest.split_once('@') {
+        Some((interface, version)) => (interface, InterfaceVersion::parse(version)),
+        None => (rest, None),
+    })
+}
+
+/// The newest `sentinel:agent` version among the guest's imports and
+/// exports, or `None` if it names no versioned interface.
+pub fn guest_version(engine: &wasmtime::Engine, component: &Component) -> Option<InterfaceVersion> {
+    let ty = component.component_type();
+    let imports = ty.imports(engine).map(|(name, _)| name);
+    let exports = ty.exports(engine).map(|(name, _)| name);
+    imports.chain(exports).filter_map(|name| parse_name(name)?.1).max()
+}
+
+/// Refuse a guest whose imports don't link against this host, unless
+/// `allow_mismatch`.
+pub fn check_version(guest: Option<InterfaceVersion>, allow_mismatch: bool) -> Result<()> {
+    let Some(guest) = guest else {
+        return Ok(());
+    };
+    if !guest.links_against(HOST_VERSION) {
+        if !allow_mismatch {
+            anyhow::bail!(
+                "Guest was built against {PACKAGE} {guest}, but this host provides {PACKAGE} {HOST_VERSION}. \
+                 Rebuild the guest against this host's WIT, or pass --allow-version-mismatch to try anyway."
+            );
+        }
+        tracing::warn!(guest = %guest, host = %HOST_VERSION, "Running a guest built against an incompatible interface");
+    }
+    Ok(())
+}
+
+/// The guest's imports this host has no definition for.
+pub fn missing_imports(engine: &wasmtime::Engine, component: &Component) -> Vec<String> {
+    component
+        .component_type()
+        .imports(engine)
+        .map(|(name, _)| name)
+        .filter(|name| match parse_name(name) {
+            Some((interface, version)) => {
+                !HOST_INTERFACES.contains(&interface) || version.is_some_and(|v| !v.links_against(HOST_VERSION))
+            }
+            // The component linker provides nothing outside the package.
+            None => true,
+        })
+        .map(str::to_string)
+        .collect()
+}
+
+/// Name the missing interfaces when instantiation fails for want of them;
+/// other failures pass through unchanged.
+pub fn explain_link_error(engine: &wasmtime::Engine, component: &Component, error: anyhow::Error) -> anyhow::Error {
+    let missing = missing_imports(engine, component);
+    match missing.as_slice() {
+        [] => error,
+        [one] => anyhow::anyhow!("Guest requires interface {one} not provided by this host ({PACKAGE} {HOST_VERSION})"),
+        many => anyhow::anyhow!(
+            "Guest requires interfaces {} not provided by this host ({PACKAGE} {HOST_VERSION})",
+            many.join(", ")
+        ),
+    }
+}
+
+impl Engine {
+    /// The `sentinel:agent` version a guest was built against.
+    pub fn guest_interface_version(&self, wasm_bytes: &[u8]) -> Result<Option<InterfaceVersion>> {
+        let component = Component::from_binary(&self.engine, wasm_bytes)?;
+        Ok(guest_version(&self.engine, &component))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn component(engine: &Engine, imports: &[&str]) -> Component {
+        let imports: String = imports.iter().map(|name| format!("(import \"{name}\" (instance (export \"f\" (func))))\n")).collect();
+        Component::new(&engine.engine, format!("(component {imports})")).unwrap()
+    }
+
+    #[test]
+    fn test_host_version_matches_wit() {
+        let wit = include_str!("../../../wit/sentinel.wit");
+        assert!(wit.contains(&format!("package {PACKAGE}@{}.{}.", HOST_VERSION.major, HOST_VERSION.minor)));
+    }
+
+    #[test]
+    fn test_mismatch_refused() {
+        let engine = Engine::new().unwrap();
+        let guest = component(&engine, &["sentinel:agent/clock@1.0.0", "sentinel:agent/logging@1.2.0"]);
+        let version = guest_version(&engine.engine, &guest);
+        assert_eq!(version, Some(InterfaceVersion { major: 1, minor: 2 }));
+
+        let err = check_version(version, false).unwrap_err().to_string();
+        assert!(err.contains("sentinel:agent 1.2") && err.contains("sentinel:agent 0.2"), "{err}");
+        assert!(check_version(version, true).is_ok());
+        // Before 1.0 a minor is breaking either way.
+        assert!(check_version(Some(InterfaceVersion { major: 0, minor: 1 }), false).is_err());
+        assert!(check_version(Some(InterfaceVersion { major: 0, minor: 3 }), false).is_err());
+        assert!(check_version(Some(HOST_VERSION), false).is_ok());
+        assert!(check_version(None, false).is_ok());
+    }
+
+    #[test]
+    fn test_missing_imports_named() {
+        let engine = Engine::new().unwrap();
+        let guest = component(&engine, &["sentinel:agent/clock@0.2.0", "sentinel:agent/telepathy@0.2.0"]);
+        let error = engine.component_linker.instantiate_pre(&guest).err().unwrap();
+        assert_eq!(
+            explain_link_error(&engine.engine, &guest, error).to_string(),
+            "Guest requires interface sentinel:agent/telepathy@0.2.0 not provided by this host (sentinel:agent 0.2)"
+        );
+
+        let older = component(&engine, &["sentinel:agent/clock@0.1.0", "wasi:cli/environment@0.2.0"]);
+        assert_eq!(missing_imports(&engine.engine, &older), vec!["sentinel:agent/clock@0.1.0", "wasi:cli/environment@0.2.0"]);
+        for interface in HOST_INTERFACES {
+            let guest = component(&engine, &[&format!("{PACKAGE}/{interface}@0.2.0")]);
+            assert!(missing_imports(&engine.engine, &guest).is_empty(), "{interface}");
+        }
+    }
+}
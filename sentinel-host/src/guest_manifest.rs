@@ -0,0 +1,232 @@
+//! # sentinel-host — Guest Manifests
+//!
+//! A guest may export `metadata.get-manifest` to say what it is and which
+//! capabilities it will ask for. The host reads it before `run` (see
+//! `Engine::boot`): a guest that needs more than policy allows is refused
+//! before it starts, the operator sees up front what it will touch, and
+//! with `--preauthorize` the tokens are minted ahead of time.
+
+use crate::config::SentinelConfig;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+use thiserror::Error;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RequirementKind {
+    FsRead,
+    FsWrite,
+    NetOutbound,
+}
+
+impl fmt::Display for RequirementKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::FsRead => "fs-read",
+            Self::FsWrite => "fs-write",
+            Self::NetOutbound => "net-outbound",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Requirement {
+    pub kind: RequirementKind,
+    pub pattern: String,
+    pub justification: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GuestManifest {
+    pub name: String,
+    pub version: String,
+    pub description: String,
+    pub requirements: Vec<Requirement>,
+}
+
+/// A requirement the host's policy doesn't allow.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("{kind} {pattern}: {reason}")]
+pub struct PolicyViolation {
+    pub kind: RequirementKind,
+    pub pattern: String,
+    pub reason: String,
+}
+
+/// A token minted for a requirement by `--preauthorize`, as passed to the
+/// guest under `preauthorized_tokens` in its context.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PreauthorizedToken {
+    pub kind: RequirementKind,
+    pub pattern: String,
+    pub token_id: String,
+}
+
+impl Requirement {
+    /// The absolute path a filesystem requirement covers. Resolved like
+    /// the guest's own requests: relative to the host's working directory,
+    /// through symlinks where the path exists.
+    pub fn resolve_path(&self) -> Result<PathBuf, String> {
+        let path = Path::new(&self.pattern);
+        if self.pattern.is_empty() || self.pattern.contains('*') {
+            return Err("path requirements must be a plain file or directory".to_string());
+        }
+        if path.components().any(|c| c == Component::ParentDir) {
+            return Err("path requirements must not contain '..'".to_string());
+        }
+        let absolute = std::env::current_dir().map_err(|e| e.to_string())?.join(path);
+        Ok(absolute.canonicalize().unwrap_or(absolute))
+    }
+
+    fn check(&self, config: &SentinelConfig) -> Result<(), String> {
+        match self.kind {
+            RequirementKind::FsRead => {
+                let path = self.resolve_path()?;
+                let dirs = &config.filesystem.allowed_read_dirs;
+                if !dirs.iter().any(|dir| sentinel_shared::paths::is_within(&path, dir)) {
+                    return Err(format!("outside the readable directories ({})", list(dirs)));
+                }
+            }
+            RequirementKind::FsWrite => {
+                let path = self.resolve_path()?;
+                let dirs = &config.filesystem.allowed_write_dirs;
+                if dirs.is_empty() {
+                    return Err("no directories are writable under this policy".to_string());
+                }
+                // Like the write check at runtime, the file's directory must be writable.
+                let parent = path.parent().unwrap_or(&path);
+                if !dirs.iter().any(|dir| sentinel_shared::paths::is_within(parent, dir)) {
+                    return Err(format!("outside the writable directories ({})", list(dirs)));
+                }
+            }
+            RequirementKind::NetOutbound => {
+                let whitelist = &config.network.url_whitelist;
+                if !whitelist.iter().any(|allowed| url_pattern_within(&self.pattern, allowed)) {
+                    return Err("not covered by the URL whitelist".to_string());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn list(dirs: &[PathBuf]) -> String {
+    if dirs.is_empty() {
+        return "none".to_string();
+    }
+    dirs.iter().map(|d| d.display().to_string()).collect::<Vec<_>>().join(", ")
+}
+
+/// Whether every URL matching `pattern` also matches `allowed`. Both may
+/// end in a `*` wildcard.
+fn url_pattern_within(pattern: &str, allowed: &str) -> bool {
+    match allowed.strip_suffix('*') {
+        Some(prefix) => pattern.starts_with(prefix),
+        None => pattern == allowed,
+    }
+}
+
+impl GuestManifest {
+    /// Every requirement `config` would deny; empty if the guest may run.
+    pub fn check_policy(&self, config: &SentinelConfig) -> Vec<PolicyViolation> {
+        self.requirements
+            .iter()
+            .filter_map(|req| {
+                req.check(config).err().map(|reason| PolicyViolation {
+                    kind: req.kind,
+                    pattern: req.pattern.clone(),
+                    reason,
+                })
+            })
+            .collect()
+    }
+
+    /// Multi-line description for the boot banner and `sentinel validate`.
+    pub fn summary(&self) -> String {
+        let mut out = format!("Guest: {} v{}", self.name, self.version);
+        if !self.description.is_empty() {
+            out.push_str(&format!(" — {}", self.description));
+        }
+        if self.requirements.is_empty() {
+            out.push_str("\nRequires: nothing");
+        } else {
+            out.push_str("\nRequires:");
+            for req in &self.requirements {
+                out.push_str(&format!("\n  {:<12} {:<24} {}", req.kind.to_string(), req.pattern, req.justification));
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn requirement(kind: RequirementKind, pattern: &str) -> Requirement {
+        Requirement { kind, pattern: pattern.into(), justification: "audit".into() }
+    }
+
+    fn manifest(requirements: Vec<Requirement>) -> GuestManifest {
+        GuestManifest { name: "auditor".into(), version: "0.1.0".into(), description: String::new(), requirements }
+    }
+
+    fn config(workspace: &Path) -> SentinelConfig {
+        let mut config = SentinelConfig::default();
+        config.filesystem.allowed_read_dirs = vec![workspace.to_path_buf()];
+        config.filesystem.allowed_write_dirs = vec![workspace.join("out")];
+        config.network.url_whitelist = vec!["https://api.example.com/*".into()];
+        config
+    }
+
+    #[test]
+    fn test_scoped_requirements_allowed() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("src")).unwrap();
+        std::fs::create_dir_all(dir.path().join("out")).unwrap();
+        let src = dir.path().join("src").display().to_string();
+        let report = dir.path().join("out/AUDIT_REPORT.md").display().to_string();
+
+        let manifest = manifest(vec![
+            requirement(RequirementKind::FsRead, &src),
+            requirement(RequirementKind::FsWrite, &report),
+            requirement(RequirementKind::NetOutbound, "https://api.example.com/v1/*"),
+        ]);
+        assert_eq!(manifest.check_policy(&config(dir.path())), vec![]);
+    }
+
+    #[test]
+    fn test_over_broad_requirements_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let workspace = dir.path().join("workspace");
+        std::fs::create_dir_all(&workspace).unwrap();
+        let escape = format!("{}/../secrets", workspace.display());
+
+        let manifest = manifest(vec![
+            requirement(RequirementKind::FsRead, "/"),
+            requirement(RequirementKind::FsRead, &escape),
+            requirement(RequirementKind::FsRead, &format!("{}/**", workspace.display())),
+            requirement(RequirementKind::FsWrite, &workspace.join("AUDIT_REPORT.md").display().to_string()),
+            requirement(RequirementKind::NetOutbound, "https://*"),
+        ]);
+        let violations = manifest.check_policy(&config(&workspace));
+        let reasons: Vec<(&str, &str)> = violations.iter().map(|v| (v.pattern.as_str(), v.reason.as_str())).collect();
+        assert_eq!(violations.len(), 5, "{reasons:?}");
+        assert!(violations[0].reason.starts_with("outside the readable directories"));
+        assert!(violations[1].reason.contains("'..'"));
+        assert!(violations[2].reason.contains("plain file or directory"));
+        assert!(violations[3].reason.starts_with("outside the writable directories"));
+        assert_eq!(violations[4].to_string(), "net-outbound https://*: not covered by the URL whitelist");
+    }
+
+    #[test]
+    fn test_summary() {
+        let mut manifest = manifest(vec![requirement(RequirementKind::FsRead, "src")]);
+        manifest.description = "Security auditor".into();
+        assert_eq!(
+            manifest.summary(),
+            "Guest: auditor v0.1.0 — Security auditor\nRequires:\n  fs-read      src                      audit"
+        );
+    }
+}
@@ -6,7 +6,7 @@
 
 use ed25519_dalek::{Signer, SigningKey, VerifyingKey, Signature, Verifier};
 use rand::rngs::OsRng;
-use sentinel_shared::{ExecutionManifest, ManifestSignature, RiskLevel, SentinelError};
+use sentinel_shared::{ExecutionManifest, ManifestSignature, SentinelError};
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::{RwLock, Mutex};
@@ -50,6 +50,12 @@ pub struct HitlBridge {
     approval_callback: Arc<Mutex<Option<ApprovalCallback>>>,
 }
 
+impl Default for HitlBridge {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl HitlBridge {
     pub fn new() -> Self {
         let signing_key = SigningKey::generate(&mut OsRng);
@@ -62,6 +68,12 @@ impl HitlBridge {
         }
     }
 
+    async fn set_status(&self, manifest_id: &str, status: ApprovalStatus) {
+        if let Some((_, s)) = self.manifests.write().await.get_mut(manifest_id) {
+            *s = status;
+        }
+    }
+
     pub async fn set_approval_callback(&self, callback: ApprovalCallback) {
         *self.approval_callback.lock().await = Some(callback);
         info!("HITL: External approval callback set (UI mode)");
@@ -81,12 +93,12 @@ impl HitlBridge {
         if approved {
             let signature = self.sign_manifest(&manifest)?;
             let status = ApprovalStatus::Approved(signature);
-            self.manifests.write().await.get_mut(manifest_id).map(|(_, s)| *s = status.clone());
+            self.set_status(manifest_id, status.clone()).await;
             info!(manifest_id = %manifest_id, "HITL: Manifest APPROVED (external)");
             Ok(status)
         } else {
             let status = ApprovalStatus::Rejected("User rejected via UI".into());
-            self.manifests.write().await.get_mut(manifest_id).map(|(_, s)| *s = status.clone());
+            self.set_status(manifest_id, status.clone()).await;
             warn!(manifest_id = %manifest_id, "HITL: Manifest REJECTED (external)");
             Ok(status)
         }
@@ -109,7 +121,7 @@ impl HitlBridge {
                     Ok(Err(_)) => false,
                     Err(_) => {
                         let status = ApprovalStatus::TimedOut;
-                        self.manifests.write().await.get_mut(&manifest_id).map(|(_, s)| *s = status.clone());
+                        self.set_status(&manifest_id, status.clone()).await;
                         return Ok(status);
                     }
                 }
@@ -122,12 +134,12 @@ impl HitlBridge {
         if approved {
             let signature = self.sign_manifest(&manifest)?;
             let status = ApprovalStatus::Approved(signature);
-            self.manifests.write().await.get_mut(&manifest_id).map(|(_, s)| *s = status.clone());
+            self.set_status(&manifest_id, status.clone()).await;
             info!(manifest_id = %manifest_id, "HITL: Manifest APPROVED");
             Ok(status)
         } else {
             let status = ApprovalStatus::Rejected("User rejected the action".into());
-            self.manifests.write().await.get_mut(&manifest_id).map(|(_, s)| *s = status.clone());
+            self.set_status(&manifest_id, status.clone()).await;
             warn!(manifest_id = %manifest_id, "HITL: Manifest REJECTED");
             Ok(status)
         }
@@ -164,7 +176,7 @@ impl HitlBridge {
     async fn prompt_terminal(&self, manifest: &ExecutionManifest) -> bool {
         let risk = format!("{:?}", manifest.risk_level);
         println!("\n========================================================");
-        println!("       SENTINEL \u2014 Pre-flight Verification");
+        println!("       SENTINEL \u{2014} Pre-flight Verification");
         println!("========================================================");
         println!(" Manifest ID: {}", manifest.id);
         println!(" Risk Level:  {}", risk);
@@ -183,3 +195,64 @@ impl HitlBridge {
         input.trim().eq_ignore_ascii_case("y")
     }
 }
+
+/// Secret grants go through the same approval flow as any other manifest.
+#[async_trait::async_trait]
+impl crate::secrets::SecretApprover for HitlBridge {
+    async fn approve(&self, request: &crate::secrets::SecretRequest) -> bool {
+        let manifest = ExecutionManifest {
+            id: request.request_id.clone(),
+            action_description: format!("Let the agent use secret '{}' (sent as the {} header)", request.name, request.header),
+            risk_level: request.risk_level,
+            parameters: HashMap::from([
+                ("secret".to_string(), request.name.clone()),
+                ("header".to_string(), request.header.clone()),
+                ("justification".to_string(), request.justification.clone()),
+            ]),
+            capability_token_id: None,
+            created_at: std::time::SystemTime::now(),
+            nonce: rand::random(),
+        };
+        matches!(self.submit_manifest(manifest).await, Ok(ApprovalStatus::Approved(_)))
+    }
+}
+
+/// A capability batch is one manifest listing every item.
+#[async_trait::async_trait]
+impl crate::batch::BatchApprover for HitlBridge {
+    async fn approve(&self, batch: &crate::batch::CapabilityBatch) -> bool {
+        let mut parameters = HashMap::new();
+        for (i, request) in batch.requests.iter().enumerate() {
+            parameters.insert(format!("request.{}", i + 1), format!("{} — {}", request.describe(), request.justification));
+        }
+        let manifest = ExecutionManifest {
+            id: batch.request_id.clone(),
+            action_description: batch.describe(),
+            risk_level: batch.risk_level,
+            parameters,
+            capability_token_id: None,
+            created_at: std::time::SystemTime::now(),
+            nonce: rand::random(),
+        };
+        matches!(self.submit_manifest(manifest).await, Ok(ApprovalStatus::Approved(_)))
+    }
+}
+
+#[async_trait::async_trait]
+impl crate::archive::ExtractApprover for HitlBridge {
+    async fn approve(&self, request: &crate::archive::ExtractRequest) -> bool {
+        let manifest = ExecutionManifest {
+            id: request.request_id.clone(),
+            action_description: format!("Extract {} into {}", request.archive.display(), request.dest.display()),
+            risk_level: request.risk_level,
+            parameters: HashMap::from([
+                ("format".to_string(), format!("{:?}", request.format)),
+                ("archive_bytes".to_string(), request.archive_bytes.to_string()),
+            ]),
+            capability_token_id: None,
+            created_at: std::time::SystemTime::now(),
+            nonce: rand::random(),
+        };
+        matches!(self.submit_manifest(manifest).await, Ok(ApprovalStatus::Approved(_)))
+    }
+}
@@ -4,21 +4,74 @@
 //! Guest invokes through the WIT interface. Every call goes through
 //! capability validation before touching any host resource.
 
+use crate::archive::{self, ArchiveFormat, ExtractApprover, ExtractReport, ExtractRequest};
+use crate::batch::{self, BatchApprover, CapabilityIssuer, CapabilityRequest, RequestKind};
+use crate::cancellation::CancellationHandle;
 use crate::capabilities::CapabilityManager;
 use crate::config::SentinelConfig;
-use sentinel_shared::{CapabilityScope, SentinelError};
+use crate::download::{self, DownloadResult};
+use crate::hitl::{ApprovalStatus, HitlBridge};
+use crate::llm::{CompletionRequest, CompletionResponse, LlmBackend};
+use crate::progress::{GuestEvent, GuestEventSender, Progress};
+use crate::secrets::SecretVault;
+use crate::watch::{FsEvent, WatchRegistry};
+use sentinel_shared::{paths, CapabilityScope, ExecutionManifest, SentinelError};
 use std::path::Path;
 use std::sync::Arc;
-use tracing::{info, warn, error};
+use std::time::Duration;
+use tracing::{info, warn};
 
 pub struct HostCallHandler {
     pub capability_manager: Arc<CapabilityManager>,
     pub config: SentinelConfig,
+    pub secrets: Arc<SecretVault>,
+    pub batch_approver: Arc<dyn BatchApprover>,
+    pub extract_approver: Arc<dyn ExtractApprover>,
+    /// Stopped when the handler, and with it the run, goes away.
+    pub watches: WatchRegistry,
+    /// Where download progress goes, if anyone is listening.
+    pub events: Option<GuestEventSender>,
+    /// Answers the guest's own `hitl.submit-manifest` calls.
+    pub hitl: Option<Arc<HitlBridge>>,
+    /// Serves `reasoning.complete`.
+    pub llm: Option<Arc<dyn LlmBackend>>,
 }
 
 impl HostCallHandler {
-    pub fn new(capability_manager: Arc<CapabilityManager>, config: SentinelConfig) -> Self {
-        Self { capability_manager, config }
+    pub fn new(
+        capability_manager: Arc<CapabilityManager>,
+        config: SentinelConfig,
+        secrets: Arc<SecretVault>,
+        batch_approver: Arc<dyn BatchApprover>,
+        extract_approver: Arc<dyn ExtractApprover>,
+        cancellation: CancellationHandle,
+    ) -> Self {
+        Self {
+            capability_manager,
+            config,
+            secrets,
+            batch_approver,
+            extract_approver,
+            watches: WatchRegistry::new(cancellation),
+            events: None,
+            hitl: None,
+            llm: None,
+        }
+    }
+
+    pub fn with_events(mut self, events: GuestEventSender) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    pub fn with_hitl(mut self, hitl: Arc<HitlBridge>) -> Self {
+        self.hitl = Some(hitl);
+        self
+    }
+
+    pub fn with_llm(mut self, llm: Arc<dyn LlmBackend>) -> Self {
+        self.llm = Some(llm);
+        self
     }
 
     pub async fn request_fs_read(&self, path: String, justification: String) -> Result<String, SentinelError> {
@@ -39,7 +92,7 @@ impl HostCallHandler {
 
     pub async fn request_net_outbound(&self, url: String, method: String, justification: String) -> Result<String, SentinelError> {
         info!(url = %url, method = %method, justification = %justification, "Guest requesting net.outbound capability");
-        let scope = CapabilityScope::NetUrl { allowed_url_pattern: url.clone(), methods: vec![method] };
+        let scope = net_scope(url, method);
         let token = self.capability_manager.mint_token(scope).await?;
         Ok(token.id)
     }
@@ -58,11 +111,34 @@ impl HostCallHandler {
         Ok(token.id)
     }
 
+    /// Every request in one approval; see [`batch::request_batch`].
+    pub async fn request_capabilities(&self, requests: Vec<CapabilityRequest>) -> Vec<Result<String, SentinelError>> {
+        batch::request_batch(self, self.batch_approver.as_ref(), &requests)
+            .await
+            .into_iter()
+            .map(|result| result.map_err(SentinelError::CapabilityDenied))
+            .collect()
+    }
+
+    pub async fn request_secret(&self, name: String, justification: String) -> Result<String, SentinelError> {
+        self.secrets.request(&name, &justification).await.map_err(SentinelError::CapabilityDenied)
+    }
+
     pub async fn release_capability(&self, token_id: String) -> bool {
         info!(token_id = %token_id, "Guest releasing capability");
+        if self.secrets.release(&token_id).await {
+            return true;
+        }
+        self.watches.release_token(&token_id).await;
         self.capability_manager.revoke_token(&token_id).await
     }
 
+    /// A token for a child guest, no broader and no longer-lived than
+    /// `parent_token_id`, narrowed to `narrow_to` if given.
+    pub async fn derive_capability(&self, parent_token_id: &str, narrow_to: Option<&str>) -> Result<String, SentinelError> {
+        self.capability_manager.derive_token(parent_token_id, narrow_to).await.map(|token| token.id)
+    }
+
     // ── Token-Gated Operations ──────────────────────────────────────────
 
     pub async fn fs_read(&self, token_id: String, path: String) -> Result<Vec<u8>, SentinelError> {
@@ -86,10 +162,7 @@ impl HostCallHandler {
         let parent = target.parent().unwrap_or(Path::new("."));
         let parent_canon = parent.canonicalize().map_err(|e| SentinelError::GuestError { message: format!("Cannot resolve write directory: {e}") })?;
 
-        let is_allowed = self.config.filesystem.allowed_write_dirs.iter().any(|dir| {
-            let d = dir.canonicalize().unwrap_or_else(|_| dir.clone());
-            parent_canon.starts_with(&d)
-        });
+        let is_allowed = self.config.filesystem.allowed_write_dirs.iter().any(|dir| paths::is_within(&parent_canon, dir));
 
         if !is_allowed {
             warn!(path = %path, "Write denied — directory not in allowed_write_dirs");
@@ -119,10 +192,113 @@ impl HostCallHandler {
         Ok(entries)
     }
 
-    pub async fn net_request(&self, token_id: String, url: String, method: String, _headers: Vec<(String, String)>, _body: Option<Vec<u8>>) -> Result<NetResponse, SentinelError> {
+    pub async fn watch_path(&self, token_id: String, path: String, recursive: bool) -> Result<u32, SentinelError> {
+        self.capability_manager.validate_token(&token_id, &path).await?;
+        let canonical = self.canonicalize_and_validate_read_path(&path)?;
+        self.watches.watch(&token_id, &canonical, recursive).map_err(SentinelError::Internal)
+    }
+
+    pub async fn poll_watch(&self, watch_id: u32, timeout_ms: u32) -> Result<Vec<FsEvent>, SentinelError> {
+        let (token_id, root) = self.watches.scope(watch_id).await
+            .ok_or_else(|| SentinelError::NotFound(format!("Unknown watch: {watch_id}")))?;
+        // The token may have expired or been revoked since the watch started.
+        if let Err(e) = self.capability_manager.validate_token(&token_id, &root.to_string_lossy()).await {
+            self.watches.unwatch(watch_id);
+            return Err(e);
+        }
+        self.watches.poll(watch_id, Duration::from_millis(timeout_ms.into())).await.map_err(SentinelError::Internal)
+    }
+
+    pub fn unwatch(&self, watch_id: u32) -> bool {
+        self.watches.unwatch(watch_id)
+    }
+
+    pub async fn net_request(&self, token_id: String, url: String, method: String, mut headers: Vec<(String, String)>, _body: Option<Vec<u8>>, secret_token_id: Option<String>) -> Result<NetResponse, SentinelError> {
         self.capability_manager.validate_token(&token_id, &url).await?;
-        info!(url = %url, method = %method, "net.request — validated (stub response)");
-        Ok(NetResponse { status: 200, headers: vec![("content-type".into(), "application/json".into())], body: b"{}".to_vec() })
+        if let Some(secret_token_id) = secret_token_id {
+            // Added host-side; the guest only ever holds the token id.
+            let header = self.secrets.header(&secret_token_id).await.map_err(SentinelError::CapabilityDenied)?;
+            headers.retain(|(name, _)| !name.eq_ignore_ascii_case(&header.0));
+            headers.push(header);
+        }
+        let header_names: Vec<&str> = headers.iter().map(|(name, _)| name.as_str()).collect();
+        info!(url = %url, method = %method, headers = ?header_names, "net.request — validated (stub response)");
+        let response = NetResponse { status: 200, headers: vec![("content-type".into(), "application/json".into())], body: b"{}".to_vec() };
+        Ok(self.secrets.scrub(response))
+    }
+
+    pub async fn net_download(&self, net_token_id: String, url: String, fs_token_id: String, dest_path: String) -> Result<DownloadResult, SentinelError> {
+        let net_token = self.capability_manager.validate_token(&net_token_id, &url).await?;
+        let fs_token = self.capability_manager.validate_token(&fs_token_id, &dest_path).await?;
+        if !matches!(net_token.scope, CapabilityScope::NetUrl { .. }) {
+            return Err(SentinelError::CapabilityDenied(format!("{net_token_id} is not a network token")));
+        }
+        if !is_write_scope(&fs_token.scope) {
+            return Err(SentinelError::CapabilityDenied(format!("{fs_token_id} is not a write token")));
+        }
+        self.capability_manager.validate_scope(&net_scope(url.clone(), "GET".to_string()))?;
+        let dest = self.canonicalize_and_validate_write_path(&dest_path)?;
+
+        // Redirects could lead off the whitelist.
+        let client = reqwest::Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .connect_timeout(self.config.network.request_timeout)
+            .read_timeout(self.config.network.request_timeout)
+            .build()
+            .map_err(|e| SentinelError::Internal(format!("Cannot build HTTP client: {e}")))?;
+        // Progress in KiB, which fits a u32 for any sane limit.
+        let report = |received: u64, total: Option<u64>| {
+            if let Some(events) = &self.events {
+                let _ = events.send(GuestEvent::Progress(Progress {
+                    phase: "download".to_string(),
+                    current: (received / 1024) as u32,
+                    total: total.map_or(0, |t| (t / 1024) as u32),
+                    detail: url.clone(),
+                }));
+            }
+        };
+        let result = download::download(&client, &url, &dest, self.config.network.max_download_size, report)
+            .await
+            .map_err(SentinelError::Internal)?;
+        info!(url = %url, path = %dest.display(), size = result.size, sha256 = %result.sha256, "net.download completed");
+        Ok(result)
+    }
+
+    pub async fn fs_extract_archive(&self, token_id: String, archive_path: String, dest_dir: String, format: ArchiveFormat) -> Result<ExtractReport, SentinelError> {
+        let token = self.capability_manager.validate_token(&token_id, &dest_dir).await?;
+        if !is_write_scope(&token.scope) {
+            return Err(SentinelError::CapabilityDenied(format!("{token_id} is not a write token")));
+        }
+        let dest = self.canonicalize_and_validate_write_path(&dest_dir)?;
+        // The archive is usually something just downloaded into the write
+        // scope, so either scope will do for reading it.
+        let archive = match self.canonicalize_and_validate_read_path(&archive_path) {
+            Ok(archive) => archive,
+            Err(e) => {
+                if self.capability_manager.validate_token(&token_id, &archive_path).await.is_err() {
+                    return Err(e);
+                }
+                self.canonicalize_and_validate_write_path(&archive_path)?
+            }
+        };
+        let archive_bytes = tokio::fs::metadata(&archive)
+            .await
+            .map_err(|e| SentinelError::Internal(format!("Cannot read archive: {e}")))?
+            .len();
+
+        let request = ExtractRequest::new(archive.clone(), dest.clone(), format, archive_bytes);
+        if !self.extract_approver.approve(&request).await {
+            return Err(SentinelError::CapabilityDenied(format!("Extraction of {} was rejected", archive.display())));
+        }
+        tokio::fs::create_dir_all(&dest)
+            .await
+            .map_err(|e| SentinelError::Internal(format!("Cannot create {}: {e}", dest.display())))?;
+
+        let limits = self.config.archive.clone();
+        tokio::task::spawn_blocking(move || archive::extract(&archive, &dest, format, &limits))
+            .await
+            .map_err(|e| SentinelError::Internal(format!("Extraction failed: {e}")))?
+            .map_err(SentinelError::Internal)
     }
 
     pub async fn ui_get_state(&self, token_id: String) -> Result<String, SentinelError> {
@@ -137,16 +313,49 @@ impl HostCallHandler {
         Ok(true)
     }
 
+    // ── HITL and Reasoning ──────────────────────────────────────────────
+
+    pub async fn submit_manifest(&self, manifest: ExecutionManifest) -> Result<ApprovalStatus, SentinelError> {
+        self.hitl()?.submit_manifest(manifest).await
+    }
+
+    pub async fn check_approval(&self, manifest_id: &str) -> Result<ApprovalStatus, SentinelError> {
+        self.hitl()?
+            .check_status(manifest_id)
+            .await
+            .ok_or_else(|| SentinelError::NotFound(format!("Unknown manifest: {manifest_id}")))
+    }
+
+    pub fn approver_key(&self) -> Vec<u8> {
+        self.hitl.as_ref().map(|hitl| hitl.public_key()).unwrap_or_default()
+    }
+
+    pub async fn complete(&self, request: CompletionRequest) -> Result<CompletionResponse, SentinelError> {
+        let llm = self.llm.as_ref().ok_or_else(|| SentinelError::LlmError("No LLM backend configured".to_string()))?;
+        llm.complete(request).await.map_err(|e| SentinelError::LlmError(format!("{e:#}")))
+    }
+
+    pub fn provider_name(&self) -> String {
+        self.llm.as_ref().map_or_else(|| "none".to_string(), |llm| llm.provider_name().to_string())
+    }
+
+    /// Tokens `text` takes for `model_hint`, or the configured model if empty.
+    pub fn count_tokens(&self, text: &str, model_hint: &str) -> u32 {
+        let model = if model_hint.is_empty() { &self.config.llm.model } else { model_hint };
+        crate::tokens::shared().count(text, model)
+    }
+
     // ── Internal Helpers ────────────────────────────────────────────────
 
+    fn hitl(&self) -> Result<&HitlBridge, SentinelError> {
+        self.hitl.as_deref().ok_or_else(|| SentinelError::Internal("No HITL bridge configured".to_string()))
+    }
+
     fn canonicalize_and_validate_read_path(&self, path: &str) -> Result<std::path::PathBuf, SentinelError> {
         let requested = Path::new(path);
         let canonical = requested.canonicalize().map_err(|_| SentinelError::PathEscapeAttempt { path: path.to_string() })?;
 
-        let is_allowed = self.config.filesystem.allowed_read_dirs.iter().any(|dir| {
-            let d = dir.canonicalize().unwrap_or_else(|_| dir.clone());
-            canonical.starts_with(&d)
-        });
+        let is_allowed = self.config.filesystem.allowed_read_dirs.iter().any(|dir| paths::is_within(&canonical, dir));
 
         if !is_allowed {
             warn!(path = %path, canonical = %canonical.display(), "Path escape attempt blocked (read)");
@@ -160,10 +369,7 @@ impl HostCallHandler {
         let parent = requested.parent().unwrap_or(Path::new("."));
         let parent_canon = parent.canonicalize().map_err(|_| SentinelError::PathEscapeAttempt { path: path.to_string() })?;
 
-        let is_allowed = self.config.filesystem.allowed_write_dirs.iter().any(|dir| {
-            let d = dir.canonicalize().unwrap_or_else(|_| dir.clone());
-            parent_canon.starts_with(&d)
-        });
+        let is_allowed = self.config.filesystem.allowed_write_dirs.iter().any(|dir| paths::is_within(&parent_canon, dir));
 
         if !is_allowed {
             warn!(path = %path, canonical = %parent_canon.display(), "Path escape attempt blocked (write)");
@@ -173,6 +379,44 @@ impl HostCallHandler {
     }
 }
 
+#[async_trait::async_trait]
+impl CapabilityIssuer for HostCallHandler {
+    fn check(&self, request: &CapabilityRequest) -> Result<(), String> {
+        let checked = match request.kind {
+            RequestKind::FsRead => self.canonicalize_and_validate_read_path(&request.target).map(drop),
+            RequestKind::FsWrite => self.canonicalize_and_validate_write_path(&request.target).map(drop),
+            RequestKind::NetOutbound => {
+                let method = request.method.clone().unwrap_or_else(|| "GET".to_string());
+                self.capability_manager.validate_scope(&net_scope(request.target.clone(), method))
+            }
+        };
+        checked.map_err(|e| e.to_string())
+    }
+
+    async fn mint(&self, request: &CapabilityRequest) -> Result<String, String> {
+        let (target, justification) = (request.target.clone(), request.justification.clone());
+        let minted = match request.kind {
+            RequestKind::FsRead => self.request_fs_read(target, justification).await,
+            RequestKind::FsWrite => self.request_fs_write(target, justification).await,
+            RequestKind::NetOutbound => {
+                let method = request.method.clone().unwrap_or_else(|| "GET".to_string());
+                self.request_net_outbound(target, method, justification).await
+            }
+        };
+        minted.map_err(|e| e.to_string())
+    }
+}
+
+/// The scope `request-net-outbound` mints, and batches are checked against.
+fn net_scope(url: String, method: String) -> CapabilityScope {
+    CapabilityScope::NetUrl { allowed_url_pattern: url, methods: vec![method] }
+}
+
+/// Whether `scope` is one `request-fs-write` mints.
+fn is_write_scope(scope: &CapabilityScope) -> bool {
+    matches!(scope, CapabilityScope::FsPath { read_only: false, .. })
+}
+
 #[derive(Debug, Clone)]
 pub struct NetResponse {
     pub status: u16,
@@ -2,9 +2,22 @@
 //!
 //! Re-exports host modules for use by external crates (e.g. sentinel-ui).
 
+pub mod archive;
+pub mod batch;
+pub mod cancellation;
 pub mod capabilities;
 pub mod config;
+pub mod download;
 pub mod engine;
+pub mod guest_manifest;
 pub mod hitl;
 pub mod host_calls;
 pub mod llm;
+pub mod metrics;
+pub mod progress;
+pub mod secrets;
+pub mod state;
+pub mod tokens;
+pub mod watch;
+
+pub use engine::{CapabilityManager, Engine, HitlBridge};
@@ -10,7 +10,8 @@
 //! The Guest never knows which backend is active — it just sees the
 //! `reasoning` WIT interface. Backend selection is a Host-side config concern.
 
-use anyhow::{Context, Result};
+use anyhow::Result;
+use sentinel_shared::pricing::{self, TokenCounts};
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
 use tracing::{info, warn, debug};
@@ -32,6 +33,10 @@ pub struct LlmConfig {
     pub timeout: Duration,
     /// System prompt prepended to every request.
     pub system_prompt: Option<String>,
+    /// The model's context window in tokens. When set, the oldest messages
+    /// are dropped from requests that wouldn't fit.
+    #[serde(default)]
+    pub context_window: Option<u32>,
 }
 
 /// Supported LLM providers.
@@ -73,6 +78,21 @@ pub enum LlmProvider {
     },
 }
 
+impl LlmProvider {
+    /// Provider id as used by the dashboard and the shared pricing table.
+    pub fn id(&self) -> &'static str {
+        match self {
+            LlmProvider::Ollama { .. } => "ollama",
+            LlmProvider::OpenAi { .. } => "openai",
+            LlmProvider::Anthropic { .. } => "anthropic",
+            LlmProvider::Deepseek { .. } => "deepseek",
+            LlmProvider::Grok { .. } => "grok",
+            LlmProvider::Google { .. } => "google",
+            LlmProvider::OpenAiCompatible { .. } => "custom",
+        }
+    }
+}
+
 impl Default for LlmConfig {
     fn default() -> Self {
         Self {
@@ -89,10 +109,24 @@ impl Default for LlmConfig {
                  before accessing any resources."
                     .into(),
             ),
+            context_window: None,
         }
     }
 }
 
+impl LlmConfig {
+    /// `request`, trimmed to the context window if one is set.
+    fn fit_to_context(&self, mut request: CompletionRequest) -> CompletionRequest {
+        if let Some(window) = self.context_window {
+            let dropped = request.trim_to_context(&self.model, window, self.max_tokens);
+            if dropped > 0 {
+                warn!(model = %self.model, dropped, "Dropped oldest messages to fit the context window");
+            }
+        }
+        request
+    }
+}
+
 // ─── Message Types ──────────────────────────────────────────────────────────
 
 /// A message in a conversation with the LLM.
@@ -121,6 +155,28 @@ pub struct CompletionRequest {
     pub response_format: Option<serde_json::Value>,
 }
 
+impl CompletionRequest {
+    /// Drop the oldest non-system messages until the prompt plus the
+    /// response budget fits in `context_window` tokens, as counted by
+    /// [`tokens::shared`](crate::tokens::shared). The last message is always
+    /// kept. Returns how many were dropped.
+    pub fn trim_to_context(&mut self, model: &str, context_window: u32, default_max_tokens: u32) -> usize {
+        let counter = crate::tokens::shared();
+        let reserve = self.max_tokens.unwrap_or(default_max_tokens);
+        let mut total: u32 = self.messages.iter().map(|m| counter.count(&m.content, model)).sum();
+        let mut dropped = 0;
+        while total.saturating_add(reserve) > context_window {
+            let Some(i) = self.messages.iter().position(|m| !matches!(m.role, Role::System)) else { break };
+            if i + 1 == self.messages.len() {
+                break;
+            }
+            total -= counter.count(&self.messages.remove(i).content, model);
+            dropped += 1;
+        }
+        dropped
+    }
+}
+
 /// The LLM's response.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CompletionResponse {
@@ -142,6 +198,52 @@ pub struct TokenUsage {
     pub total_tokens: u32,
 }
 
+// ─── Cost Tracking ──────────────────────────────────────────────────────────
+
+/// Cumulative token usage and estimated spend for one model.
+///
+/// Pricing comes from `sentinel_shared::pricing`, the same table the
+/// dashboard uses for its live counters.
+#[derive(Debug, Clone)]
+pub struct CostTracker {
+    provider: String,
+    model: String,
+    totals: TokenCounts,
+}
+
+impl CostTracker {
+    pub fn new(config: &LlmConfig) -> Self {
+        Self { provider: config.provider.id().to_string(), model: config.model.clone(), totals: TokenCounts::default() }
+    }
+
+    /// Add one response's usage to the running totals.
+    pub fn record(&mut self, usage: &TokenUsage) {
+        self.totals.add(TokenCounts::new(usage.prompt_tokens.into(), usage.completion_tokens.into()));
+    }
+
+    pub fn totals(&self) -> TokenCounts {
+        self.totals
+    }
+
+    /// Estimated spend in USD so far, `None` if the model is unpriced.
+    pub fn estimated_cost_usd(&self) -> Option<f64> {
+        pricing::estimate_cost(&self.provider, &self.model, &self.totals)
+    }
+}
+
+/// Record a response's usage in a backend's tracker and log the running spend.
+fn record_usage(cost: &std::sync::Mutex<CostTracker>, usage: &TokenUsage) {
+    let mut cost = cost.lock().unwrap_or_else(|e| e.into_inner());
+    cost.record(usage);
+    let totals = cost.totals();
+    debug!(
+        prompt_tokens = totals.prompt_tokens,
+        completion_tokens = totals.completion_tokens,
+        estimated_cost_usd = ?cost.estimated_cost_usd(),
+        "LLM usage so far"
+    );
+}
+
 // ─── Provider Trait ─────────────────────────────────────────────────────────
 
 /// Trait that all LLM providers implement.
@@ -158,6 +260,12 @@ pub trait LlmBackend: Send + Sync {
 
     /// Human-readable name for logging.
     fn provider_name(&self) -> &str;
+
+    /// The model's tokenizer family (e.g. `llama`, `qwen2`), when the
+    /// provider reports it.
+    async fn tokenizer_family(&self) -> Option<String> {
+        None
+    }
 }
 
 // ─── Ollama Backend ─────────────────────────────────────────────────────────
@@ -167,12 +275,24 @@ pub struct OllamaBackend {
     pub base_url: String,
     pub model: String,
     pub config: LlmConfig,
+    /// Set once the model's tokenizer family has been looked up.
+    pub family_checked: tokio::sync::OnceCell<()>,
+    /// Usage and estimated spend across all completions.
+    pub cost: std::sync::Mutex<CostTracker>,
 }
 
 #[async_trait::async_trait]
 impl LlmBackend for OllamaBackend {
     async fn complete(&self, request: CompletionRequest) -> Result<CompletionResponse> {
         debug!(model = %self.model, "Ollama: sending completion request");
+        self.family_checked
+            .get_or_init(|| async {
+                if let Some(family) = self.tokenizer_family().await {
+                    crate::tokens::shared().set_family(&self.model, &family);
+                }
+            })
+            .await;
+        let request = self.config.fit_to_context(request);
 
         // Build Ollama-native request payload
         let payload = serde_json::json!({
@@ -203,7 +323,7 @@ impl LlmBackend for OllamaBackend {
             .ok_or_else(|| anyhow::anyhow!("Ollama error: {}", data))?
             .to_string();
 
-        Ok(CompletionResponse {
+        let response = CompletionResponse {
             content,
             usage: TokenUsage {
                 prompt_tokens: data["prompt_eval_count"].as_u64().unwrap_or(0) as u32,
@@ -212,7 +332,9 @@ impl LlmBackend for OllamaBackend {
             },
             model: self.model.clone(),
             finish_reason: Some(data["done_reason"].as_str().unwrap_or("stop").to_string()),
-        })
+        };
+        record_usage(&self.cost, &response.usage);
+        Ok(response)
     }
 
     async fn health_check(&self) -> Result<bool> {
@@ -224,6 +346,18 @@ impl LlmBackend for OllamaBackend {
     fn provider_name(&self) -> &str {
         "Ollama (Local)"
     }
+
+    async fn tokenizer_family(&self) -> Option<String> {
+        let client = reqwest::Client::builder().timeout(self.config.timeout).build().ok()?;
+        let res = client
+            .post(format!("{}/api/show", self.base_url))
+            .json(&serde_json::json!({ "model": self.model }))
+            .send()
+            .await
+            .ok()?;
+        let data: serde_json::Value = res.error_for_status().ok()?.json().await.ok()?;
+        data["details"]["family"].as_str().map(str::to_string)
+    }
 }
 
 // ─── OpenAI-Compatible Backend ──────────────────────────────────────────────
@@ -240,6 +374,8 @@ pub struct OpenAiCompatibleBackend {
     pub model: String,
     pub config: LlmConfig,
     pub display_name: String,
+    /// Usage and estimated spend across all completions.
+    pub cost: std::sync::Mutex<CostTracker>,
 }
 
 #[async_trait::async_trait]
@@ -247,6 +383,7 @@ impl LlmBackend for OpenAiCompatibleBackend {
     async fn complete(&self, request: CompletionRequest) -> Result<CompletionResponse> {
         debug!(model = %self.model, provider = %self.display_name,
                "Sending completion request");
+        let request = self.config.fit_to_context(request);
 
         let mut payload = serde_json::json!({
             "model": self.model,
@@ -284,7 +421,7 @@ impl LlmBackend for OpenAiCompatibleBackend {
 
         let usage = &data["usage"];
 
-        Ok(CompletionResponse {
+        let response = CompletionResponse {
             content,
             usage: TokenUsage {
                 prompt_tokens: usage["prompt_tokens"].as_u64().unwrap_or(0) as u32,
@@ -293,7 +430,9 @@ impl LlmBackend for OpenAiCompatibleBackend {
             },
             model: self.model.clone(),
             finish_reason: Some(choice["finish_reason"].as_str().unwrap_or("stop").to_string()),
-        })
+        };
+        record_usage(&self.cost, &response.usage);
+        Ok(response)
     }
 
     async fn health_check(&self) -> Result<bool> {
@@ -313,12 +452,15 @@ pub struct AnthropicBackend {
     pub api_key: String,
     pub model: String,
     pub config: LlmConfig,
+    /// Usage and estimated spend across all completions.
+    pub cost: std::sync::Mutex<CostTracker>,
 }
 
 #[async_trait::async_trait]
 impl LlmBackend for AnthropicBackend {
     async fn complete(&self, request: CompletionRequest) -> Result<CompletionResponse> {
         debug!(model = %self.model, "Anthropic: sending completion request");
+        let request = self.config.fit_to_context(request);
 
         // Anthropic uses a different message format:
         // - System prompt is a top-level field, not a message
@@ -367,7 +509,7 @@ impl LlmBackend for AnthropicBackend {
 
         let usage = &data["usage"];
 
-        Ok(CompletionResponse {
+        let response = CompletionResponse {
             content,
             usage: TokenUsage {
                 prompt_tokens: usage["input_tokens"].as_u64().unwrap_or(0) as u32,
@@ -376,7 +518,9 @@ impl LlmBackend for AnthropicBackend {
             },
             model: self.model.clone(),
             finish_reason: Some(data["stop_reason"].as_str().unwrap_or("end_turn").to_string()),
-        })
+        };
+        record_usage(&self.cost, &response.usage);
+        Ok(response)
     }
 
     async fn health_check(&self) -> Result<bool> {
@@ -400,6 +544,8 @@ pub fn create_backend(config: &LlmConfig) -> Result<Box<dyn LlmBackend>> {
                 base_url: base_url.clone(),
                 model: config.model.clone(),
                 config: config.clone(),
+                family_checked: tokio::sync::OnceCell::new(),
+                cost: std::sync::Mutex::new(CostTracker::new(config)),
             })
         }
         LlmProvider::OpenAi { api_key, .. } => {
@@ -410,6 +556,7 @@ pub fn create_backend(config: &LlmConfig) -> Result<Box<dyn LlmBackend>> {
                 model: config.model.clone(),
                 config: config.clone(),
                 display_name: "OpenAI (ChatGPT)".into(),
+                cost: std::sync::Mutex::new(CostTracker::new(config)),
             })
         }
         LlmProvider::Anthropic { api_key } => {
@@ -418,6 +565,7 @@ pub fn create_backend(config: &LlmConfig) -> Result<Box<dyn LlmBackend>> {
                 api_key: api_key.clone(),
                 model: config.model.clone(),
                 config: config.clone(),
+                cost: std::sync::Mutex::new(CostTracker::new(config)),
             })
         }
         LlmProvider::Deepseek { api_key, base_url } => {
@@ -431,6 +579,7 @@ pub fn create_backend(config: &LlmConfig) -> Result<Box<dyn LlmBackend>> {
                 model: config.model.clone(),
                 config: config.clone(),
                 display_name: "Deepseek".into(),
+                cost: std::sync::Mutex::new(CostTracker::new(config)),
             })
         }
         LlmProvider::Grok { api_key } => {
@@ -441,6 +590,7 @@ pub fn create_backend(config: &LlmConfig) -> Result<Box<dyn LlmBackend>> {
                 model: config.model.clone(),
                 config: config.clone(),
                 display_name: "xAI (Grok)".into(),
+                cost: std::sync::Mutex::new(CostTracker::new(config)),
             })
         }
         LlmProvider::Google { api_key } => {
@@ -451,6 +601,7 @@ pub fn create_backend(config: &LlmConfig) -> Result<Box<dyn LlmBackend>> {
                 model: config.model.clone(),
                 config: config.clone(),
                 display_name: "Google (Gemini)".into(),
+                cost: std::sync::Mutex::new(CostTracker::new(config)),
             })
         }
         LlmProvider::OpenAiCompatible { api_key, base_url } => {
@@ -461,9 +612,63 @@ pub fn create_backend(config: &LlmConfig) -> Result<Box<dyn LlmBackend>> {
                 model: config.model.clone(),
                 config: config.clone(),
                 display_name: format!("Custom ({})", base_url),
+                cost: std::sync::Mutex::new(CostTracker::new(config)),
             })
         }
     };
 
     Ok(backend)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(role: Role, content: &str) -> ChatMessage {
+        ChatMessage { role, content: content.to_string() }
+    }
+
+    #[test]
+    fn test_trim_to_context_keeps_system_and_last() {
+        let turn = "word ".repeat(100);
+        let mut request = CompletionRequest {
+            messages: vec![
+                message(Role::System, "You are an auditor."),
+                message(Role::User, &turn),
+                message(Role::Assistant, &turn),
+                message(Role::User, &turn),
+            ],
+            max_tokens: Some(50),
+            temperature: None,
+            response_format: None,
+        };
+        let counter = crate::tokens::shared();
+        let needed = counter.count("You are an auditor.", "mystery-model") + counter.count(&turn, "mystery-model") + 50;
+
+        assert_eq!(request.trim_to_context("mystery-model", needed, 0), 2);
+        assert!(matches!(request.messages[0].role, Role::System));
+        assert_eq!(request.messages.len(), 2);
+
+        // Never drops the final message, even if it alone doesn't fit.
+        assert_eq!(request.trim_to_context("mystery-model", 10, 0), 0);
+        assert_eq!(request.messages.len(), 2);
+    }
+
+    #[test]
+    fn test_cost_tracker_accumulates() {
+        let config = LlmConfig {
+            provider: LlmProvider::OpenAi { api_key: String::new(), org_id: None },
+            model: "gpt-4o-2024-08-06".into(),
+            ..LlmConfig::default()
+        };
+        let cost = std::sync::Mutex::new(CostTracker::new(&config));
+        let usage = TokenUsage { prompt_tokens: 400_000, completion_tokens: 50_000, total_tokens: 450_000 };
+        record_usage(&cost, &usage);
+        record_usage(&cost, &usage);
+
+        let cost = cost.into_inner().unwrap();
+        assert_eq!(cost.totals(), TokenCounts::new(800_000, 100_000));
+        assert_eq!(cost.estimated_cost_usd(), Some(3.0));
+        assert_eq!(CostTracker::new(&LlmConfig::default()).estimated_cost_usd(), Some(0.0));
+    }
+}
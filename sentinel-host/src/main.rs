@@ -2,31 +2,93 @@
 //!
 //! Boots the engine and starts the task execution.
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
+use std::path::PathBuf;
 use std::sync::Arc;
 use anyhow::Result;
+use sentinel_host::progress::ProgressRenderer;
+use sentinel_host::secrets::RedactingMakeWriter;
 
 #[derive(Parser)]
-#[command(author, version, about, long_about = None)]
+#[command(author, version, about, long_about = None, args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Args {
-    #[arg(short, long)]
-    task: String,
-    #[arg(short, long)]
-    target: String,
+    #[command(subcommand)]
+    command: Option<Command>,
+    #[arg(short, long, required = true)]
+    task: Option<String>,
+    #[arg(short, long, required = true)]
+    target: Option<String>,
     #[arg(short, long, default_value = "read_report")]
     autonomy: String,
+    /// Guest component to run.
+    #[arg(short, long, default_value = "guest.wasm")]
+    module: PathBuf,
+    /// Mint the tokens the guest's manifest asks for before it starts.
+    #[arg(long)]
+    preauthorize: bool,
+    /// Also write the guest's metrics here, in the Prometheus text format.
+    #[arg(long)]
+    metrics_file: Option<PathBuf>,
+    /// Run a guest built against a WIT interface version this host can't link.
+    #[arg(long)]
+    allow_version_mismatch: bool,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Print a guest's manifest and check it against policy.
+    Validate { module: PathBuf },
+}
+
+/// `sentinel validate`: fails if policy would refuse the guest.
+async fn validate(module: PathBuf) -> Result<()> {
+    let engine = sentinel_host::Engine::new()?;
+    let wasm_bytes = std::fs::read(&module)?;
+    let host = sentinel_host::engine::version::HOST_VERSION;
+    match engine.guest_interface_version(&wasm_bytes)? {
+        Some(guest) => println!("Requires interface: sentinel:agent {} (this host provides {})", guest, host),
+        None => println!("Requires interface: none versioned (this host provides sentinel:agent {})", host),
+    }
+    let Some(manifest) = engine.read_manifest(&wasm_bytes).await? else {
+        println!("{} exports no manifest; its capabilities are only known at runtime.", module.display());
+        return Ok(());
+    };
+    println!("{}", manifest.summary());
+
+    let violations = manifest.check_policy(&sentinel_host::config::SentinelConfig::default());
+    if violations.is_empty() {
+        println!("✓ All requirements are allowed by policy");
+        return Ok(());
+    }
+    println!("✗ Denied by policy:");
+    for violation in &violations {
+        println!("  {}", violation);
+    }
+    anyhow::bail!("{} requirement(s) denied by policy", violations.len())
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let args = Args::parse();
+    tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .with_writer(RedactingMakeWriter)
+        .init();
+
+    if let Some(Command::Validate { module }) = args.command {
+        return validate(module).await;
+    }
+    // Both required unless a subcommand is given.
+    let (task, target) = (args.task.unwrap_or_default(), args.target.unwrap_or_default());
     
     println!("🛡️ SENTINEL Host starting...");
-    println!("Task: {}", args.task);
-    println!("Target: {}", args.target);
+    println!("Task: {}", task);
+    println!("Target: {}", target);
     println!("Autonomy: {}", args.autonomy);
 
-    let engine = sentinel_host::Engine::new()?;
+    let mut config = sentinel_host::config::SentinelConfig::default();
+    config.engine.allow_version_mismatch = args.allow_version_mismatch;
+    let engine = sentinel_host::Engine::with_config(config.clone())?;
     let hitl_bridge = Arc::new(sentinel_host::HitlBridge {
         callback_url: "http://localhost:9876".to_string(),
     });
@@ -34,19 +96,77 @@ async fn main() -> Result<()> {
         autonomy: args.autonomy,
     });
 
-    // Mock WASM for demonstration
-    let wasm_bytes = vec![]; 
+    let wasm_bytes = std::fs::read(&args.module)?;
     let agent_id = "agent-123".to_string();
-    let context_json = format!(r#"{{"task": "{}", "target": "{}"}}"#, args.task, args.target);
+    let context_json = serde_json::json!({ "task": task, "target": target }).to_string();
 
-    engine.run_agent(
+    let (events_tx, mut events_rx) = tokio::sync::mpsc::unbounded_channel();
+    let boot = engine.boot(&wasm_bytes, context_json, args.preauthorize, &events_tx).await?;
+    if let Some(manifest) = &boot.manifest {
+        println!("{}", manifest.summary());
+    }
+    if !boot.preauthorized.is_empty() {
+        println!("Preauthorized {} capability token(s)", boot.preauthorized.len());
+    }
+
+    let renderer = tokio::spawn(async move {
+        let mut renderer = ProgressRenderer::new();
+        while let Some(event) = events_rx.recv().await {
+            renderer.handle(&event);
+        }
+        renderer.finish();
+    });
+
+    // Ctrl-C asks the guest to stop; it gets the grace period to save a
+    // partial report before being interrupted.
+    let cancellation = engine.cancellation_handle();
+    let on_interrupt = cancellation.clone();
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            on_interrupt.cancel("Interrupted by user");
+        }
+    });
+
+    // The guest's own capability, secret, HITL and LLM calls. Tokens come
+    // from the engine's manager, so preauthorized ones are honoured.
+    let hitl = Arc::new(sentinel_host::hitl::HitlBridge::new());
+    let secrets = Arc::new(sentinel_host::secrets::SecretVault::new(config.secrets.clone(), hitl.clone()));
+    let llm: Arc<dyn sentinel_host::llm::LlmBackend> = sentinel_host::llm::create_backend(&config.llm)?.into();
+    let host_calls = sentinel_host::host_calls::HostCallHandler::new(
+        engine.capabilities(),
+        config,
+        secrets,
+        hitl.clone(),
+        hitl.clone(),
+        cancellation.clone(),
+    )
+    .with_events(events_tx.clone())
+    .with_hitl(hitl)
+    .with_llm(llm);
+
+    let report = engine.run_agent(
         &wasm_bytes,
         agent_id,
-        args.target,
-        context_json,
+        target,
+        boot,
         hitl_bridge,
         capability_manager,
+        Arc::new(host_calls),
+        events_tx,
+        cancellation,
     ).await?;
+    renderer.await?;
+
+    if !report.metrics.is_empty() {
+        println!("\n{}", report.metrics.table());
+    }
+    if let Some(path) = &args.metrics_file {
+        std::fs::write(path, report.metrics.to_prometheus())?;
+        println!("Metrics written to {}", path.display());
+    }
+    if report.exit_code != 0 {
+        std::process::exit(report.exit_code);
+    }
 
     Ok(())
 }
@@ -0,0 +1,221 @@
+//! # sentinel-host — Guest Metrics
+//!
+//! Numbers a guest reports through `logging.record-metric` (files scanned,
+//! findings per severity, cache hits, ...). Samples are aggregated as they
+//! arrive — sum, count and max per name and label set — and handed back in
+//! the [`RunReport`](crate::engine::RunReport). The CLI prints them as a
+//! table and can export them in the Prometheus text format.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use tracing::warn;
+
+/// Distinct name + label sets kept per run; later new series are dropped
+/// so a guest can't grow host memory without bound.
+pub const MAX_SERIES: usize = 1024;
+
+/// A metric name and its labels, sorted by label name.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SeriesKey {
+    pub name: String,
+    pub labels: Vec<(String, String)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aggregate {
+    pub sum: f64,
+    pub count: u64,
+    pub max: f64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Metrics {
+    series: BTreeMap<SeriesKey, Aggregate>,
+    dropped: u64,
+}
+
+impl Metrics {
+    /// Fold one sample in. Non-finite values are ignored.
+    pub fn record(&mut self, name: &str, value: f64, mut labels: Vec<(String, String)>) {
+        if !value.is_finite() {
+            warn!(metric = %name, "Ignoring non-finite metric value");
+            return;
+        }
+        labels.sort();
+        let key = SeriesKey { name: name.to_string(), labels };
+        if let Some(agg) = self.series.get_mut(&key) {
+            agg.sum += value;
+            agg.count += 1;
+            agg.max = agg.max.max(value);
+        } else if self.series.len() < MAX_SERIES {
+            self.series.insert(key, Aggregate { sum: value, count: 1, max: value });
+        } else {
+            if self.dropped == 0 {
+                warn!(max = MAX_SERIES, "Guest exceeded the metric series limit; dropping new series");
+            }
+            self.dropped += 1;
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.series.is_empty()
+    }
+
+    /// Series in name, then label, order.
+    pub fn iter(&self) -> impl Iterator<Item = (&SeriesKey, &Aggregate)> {
+        self.series.iter()
+    }
+
+    pub fn get(&self, name: &str, labels: &[(&str, &str)]) -> Option<&Aggregate> {
+        let mut labels: Vec<(String, String)> = labels.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        labels.sort();
+        self.series.get(&SeriesKey { name: name.to_string(), labels })
+    }
+
+    /// Samples dropped for exceeding [`MAX_SERIES`].
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    /// Plain-text table for the end of a CLI run.
+    pub fn table(&self) -> String {
+        let rows: Vec<(String, &Aggregate)> = self
+            .series
+            .iter()
+            .map(|(key, agg)| {
+                let labels = key.labels.iter().map(|(k, v)| format!("{}={}", k, v)).collect::<Vec<_>>().join(",");
+                let name = if labels.is_empty() { key.name.clone() } else { format!("{}{{{}}}", key.name, labels) };
+                (name, agg)
+            })
+            .collect();
+        let width = rows.iter().map(|(name, _)| name.chars().count()).max().unwrap_or(0).max("METRIC".len());
+        let mut out = format!("{:<width$}  {:>12}  {:>8}  {:>12}", "METRIC", "SUM", "COUNT", "MAX");
+        for (name, agg) in rows {
+            let _ = write!(out, "\n{:<width$}  {:>12}  {:>8}  {:>12}", name, agg.sum, agg.count, agg.max);
+        }
+        out
+    }
+
+    /// The Prometheus text exposition format: each name becomes a summary
+    /// (`_sum`, `_count`) plus a `_max` gauge.
+    pub fn to_prometheus(&self) -> String {
+        let mut out = String::new();
+        let mut current: Option<&str> = None;
+        let mut maxes = String::new();
+        for (key, agg) in &self.series {
+            let name = metric_name(&key.name);
+            if current != Some(key.name.as_str()) {
+                out.push_str(&maxes);
+                maxes.clear();
+                let _ = writeln!(out, "# TYPE {} summary", name);
+                let _ = writeln!(maxes, "# TYPE {}_max gauge", name);
+                current = Some(&key.name);
+            }
+            let labels = label_set(&key.labels);
+            let _ = writeln!(out, "{}_sum{} {}", name, labels, agg.sum);
+            let _ = writeln!(out, "{}_count{} {}", name, labels, agg.count);
+            let _ = writeln!(maxes, "{}_max{} {}", name, labels, agg.max);
+        }
+        out.push_str(&maxes);
+        out
+    }
+}
+
+/// `name` with anything Prometheus doesn't allow replaced by `_`.
+fn metric_name(name: &str) -> String {
+    let mut out: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '_' || c == ':' { c } else { '_' })
+        .collect();
+    if out.is_empty() || out.starts_with(|c: char| c.is_ascii_digit()) {
+        out.insert(0, '_');
+    }
+    out
+}
+
+fn label_set(labels: &[(String, String)]) -> String {
+    if labels.is_empty() {
+        return String::new();
+    }
+    let pairs: Vec<String> = labels
+        .iter()
+        .map(|(k, v)| format!("{}=\"{}\"", metric_name(k).replace(':', "_"), escape_label_value(v)))
+        .collect();
+    format!("{{{}}}", pairs.join(","))
+}
+
+fn escape_label_value(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn labels(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn test_aggregates_per_name_and_labels() {
+        let mut metrics = Metrics::default();
+        metrics.record("findings", 2.0, labels(&[("severity", "high"), ("phase", "analysis")]));
+        metrics.record("findings", 5.0, labels(&[("phase", "analysis"), ("severity", "high")]));
+        metrics.record("findings", 1.0, labels(&[("severity", "low"), ("phase", "analysis")]));
+        metrics.record("files_scanned", 3.0, vec![]);
+        metrics.record("files_scanned", f64::NAN, vec![]);
+
+        // Label order doesn't matter; label values do.
+        let high = metrics.get("findings", &[("severity", "high"), ("phase", "analysis")]).unwrap();
+        assert_eq!(*high, Aggregate { sum: 7.0, count: 2, max: 5.0 });
+        let low = metrics.get("findings", &[("phase", "analysis"), ("severity", "low")]).unwrap();
+        assert_eq!(*low, Aggregate { sum: 1.0, count: 1, max: 1.0 });
+        assert_eq!(*metrics.get("files_scanned", &[]).unwrap(), Aggregate { sum: 3.0, count: 1, max: 3.0 });
+        assert_eq!(metrics.iter().count(), 3);
+    }
+
+    #[test]
+    fn test_series_limit() {
+        let mut metrics = Metrics::default();
+        for i in 0..MAX_SERIES + 2 {
+            metrics.record("file_bytes", 1.0, labels(&[("file", &i.to_string())]));
+        }
+        metrics.record("file_bytes", 1.0, labels(&[("file", "0")]));
+        assert_eq!(metrics.iter().count(), MAX_SERIES);
+        assert_eq!(metrics.dropped(), 2);
+        assert_eq!(metrics.get("file_bytes", &[("file", "0")]).unwrap().count, 2);
+    }
+
+    #[test]
+    fn test_prometheus_rendering() {
+        let mut metrics = Metrics::default();
+        metrics.record("cache.hits", 4.0, vec![]);
+        metrics.record("findings", 2.0, labels(&[("file", "C:\\src\\\"main\".rs\nx")]));
+
+        assert_eq!(
+            metrics.to_prometheus(),
+            "# TYPE cache_hits summary\n\
+             cache_hits_sum 4\n\
+             cache_hits_count 1\n\
+             # TYPE cache_hits_max gauge\n\
+             cache_hits_max 4\n\
+             # TYPE findings summary\n\
+             findings_sum{file=\"C:\\\\src\\\\\\\"main\\\".rs\\nx\"} 2\n\
+             findings_count{file=\"C:\\\\src\\\\\\\"main\\\".rs\\nx\"} 1\n\
+             # TYPE findings_max gauge\n\
+             findings_max{file=\"C:\\\\src\\\\\\\"main\\\".rs\\nx\"} 2\n"
+        );
+    }
+
+    #[test]
+    fn test_table() {
+        let mut metrics = Metrics::default();
+        metrics.record("files_audited", 12.0, labels(&[("phase", "analysis")]));
+        let table = metrics.table();
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("METRIC"));
+        assert!(lines[1].starts_with("files_audited{phase=analysis}"));
+        assert!(lines[1].ends_with("12"));
+    }
+}
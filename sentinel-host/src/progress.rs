@@ -0,0 +1,142 @@
+//! # sentinel-host — Guest Events & Progress
+//!
+//! Everything a guest reports through the `logging` interface is forwarded
+//! to the embedder as a [`GuestEvent`] over an unbounded channel. The CLI
+//! feeds them to a [`ProgressRenderer`], which keeps a single updating
+//! progress line and prints log lines above it; embedders such as the
+//! dashboard consume the raw events instead.
+
+use crate::guest_manifest::GuestManifest;
+use indicatif::{ProgressBar, ProgressStyle};
+use tokio::sync::mpsc;
+use tracing::Level;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GuestEvent {
+    Log { level: Level, target: String, message: String },
+    Progress(Progress),
+    /// The guest's manifest, read at boot before it runs.
+    Manifest(GuestManifest),
+}
+
+pub type GuestEventSender = mpsc::UnboundedSender<GuestEvent>;
+
+/// One `report-progress` call. `total` is 0 when the guest can't tell how
+/// much work is left.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Progress {
+    pub phase: String,
+    pub current: u32,
+    pub total: u32,
+    pub detail: String,
+}
+
+impl Progress {
+    /// Completed fraction in `0.0..=1.0`, or `None` without a total.
+    pub fn fraction(&self) -> Option<f64> {
+        (self.total > 0).then(|| f64::from(self.current.min(self.total)) / f64::from(self.total))
+    }
+
+    /// Text shown next to the bar, e.g. `analysis · src/main.rs`.
+    pub fn message(&self) -> String {
+        match (self.phase.is_empty(), self.detail.is_empty()) {
+            (false, false) => format!("{} · {}", self.phase, self.detail),
+            (false, true) => self.phase.clone(),
+            (true, _) => self.detail.clone(),
+        }
+    }
+}
+
+const BAR_TEMPLATE: &str = "{bar:30.cyan/blue} {pos}/{len} {msg}";
+const SPINNER_TEMPLATE: &str = "{spinner:.cyan} {msg}";
+
+/// Renders guest events for the CLI.
+pub struct ProgressRenderer {
+    bar: ProgressBar,
+    determinate: Option<bool>,
+}
+
+impl Default for ProgressRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProgressRenderer {
+    pub fn new() -> Self {
+        Self::with_bar(ProgressBar::new(0))
+    }
+
+    pub fn with_bar(bar: ProgressBar) -> Self {
+        Self { bar, determinate: None }
+    }
+
+    pub fn bar(&self) -> &ProgressBar {
+        &self.bar
+    }
+
+    pub fn handle(&mut self, event: &GuestEvent) {
+        match event {
+            GuestEvent::Log { level, target, message } => {
+                self.bar.println(format!("{:>5} {}: {}", level, target, message));
+            }
+            GuestEvent::Progress(progress) => self.update(progress),
+            // The CLI prints it in the boot banner.
+            GuestEvent::Manifest(_) => {}
+        }
+    }
+
+    fn update(&mut self, progress: &Progress) {
+        // A total of 0 has no meaningful bar; show a spinner instead.
+        let determinate = progress.total > 0;
+        if self.determinate != Some(determinate) {
+            let template = if determinate { BAR_TEMPLATE } else { SPINNER_TEMPLATE };
+            if let Ok(style) = ProgressStyle::with_template(template) {
+                self.bar.set_style(style);
+            }
+            self.determinate = Some(determinate);
+        }
+        self.bar.set_length(u64::from(progress.total));
+        self.bar.set_position(u64::from(progress.current.min(progress.total)));
+        self.bar.set_message(progress.message());
+        if !determinate {
+            self.bar.tick();
+        }
+    }
+
+    pub fn finish(&self) {
+        self.bar.finish_and_clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn progress(phase: &str, current: u32, total: u32, detail: &str) -> Progress {
+        Progress { phase: phase.into(), current, total, detail: detail.into() }
+    }
+
+    #[test]
+    fn test_fraction_and_message() {
+        assert_eq!(progress("analysis", 3, 4, "src/lib.rs").fraction(), Some(0.75));
+        assert_eq!(progress("analysis", 9, 4, "").fraction(), Some(1.0));
+        assert_eq!(progress("discovery", 0, 0, "").fraction(), None);
+        assert_eq!(progress("analysis", 3, 4, "src/lib.rs").message(), "analysis · src/lib.rs");
+        assert_eq!(progress("", 0, 0, "waiting").message(), "waiting");
+    }
+
+    #[test]
+    fn test_renderer_handles_zero_total() {
+        let mut renderer = ProgressRenderer::with_bar(ProgressBar::hidden());
+        renderer.handle(&GuestEvent::Progress(progress("discovery", 5, 0, "scanning")));
+        assert_eq!(renderer.bar().length(), Some(0));
+        assert_eq!(renderer.bar().position(), 0);
+        assert_eq!(renderer.bar().message(), "discovery · scanning");
+
+        renderer.handle(&GuestEvent::Progress(progress("analysis", 2, 8, "src/main.rs")));
+        assert_eq!((renderer.bar().length(), renderer.bar().position()), (Some(8), 2));
+        renderer.handle(&GuestEvent::Log { level: Level::INFO, target: "auditor".into(), message: "hi".into() });
+        renderer.finish();
+    }
+}
@@ -0,0 +1,259 @@
+//! # sentinel-host — Named Secrets
+//!
+//! Lets a guest use a credential without ever holding it. The guest asks
+//! for a secret by name and, once a human approves (always at High risk),
+//! gets an opaque token. Passing that token to `net-request` makes the host
+//! add the secret as a header rendered from the configured template, e.g.
+//! `Authorization: Bearer {value}`. The plaintext never enters Wasm memory:
+//! responses are scrubbed of it before the guest sees them, and host logs
+//! written through [`RedactingMakeWriter`] mask every granted value.
+
+use crate::config::SecretConfig;
+use crate::host_calls::NetResponse;
+use sentinel_shared::RiskLevel;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, Write};
+use std::sync::{Arc, RwLock};
+use tokio::sync::Mutex;
+use tracing::{info, warn};
+
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Placeholder in a secret's header template.
+const VALUE_PLACEHOLDER: &str = "{value}";
+
+/// Every secret value granted in this process, masked by [`redact`].
+static GRANTED_VALUES: RwLock<Vec<String>> = RwLock::new(Vec::new());
+
+/// A secret's plaintext. Never printed by `Debug`.
+#[derive(Clone, PartialEq, Eq)]
+pub struct SecretValue(String);
+
+impl SecretValue {
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for SecretValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+/// What a human is asked to approve.
+#[derive(Debug, Clone)]
+pub struct SecretRequest {
+    pub request_id: String,
+    pub name: String,
+    pub justification: String,
+    /// Header the value will be sent in.
+    pub header: String,
+    pub risk_level: RiskLevel,
+}
+
+/// Decides whether a guest may use a secret; implemented by the HITL bridge.
+#[async_trait::async_trait]
+pub trait SecretApprover: Send + Sync {
+    async fn approve(&self, request: &SecretRequest) -> bool;
+}
+
+struct Grant {
+    name: String,
+    value: SecretValue,
+}
+
+pub struct SecretVault {
+    configs: HashMap<String, SecretConfig>,
+    approver: Arc<dyn SecretApprover>,
+    grants: Mutex<HashMap<String, Grant>>,
+}
+
+/// Mask every granted secret value in `text`.
+pub fn redact(text: &str) -> String {
+    let values = GRANTED_VALUES.read().unwrap_or_else(|e| e.into_inner());
+    values.iter().fold(text.to_string(), |text, value| text.replace(value.as_str(), REDACTED))
+}
+
+fn register_for_redaction(value: &SecretValue) {
+    let mut values = GRANTED_VALUES.write().unwrap_or_else(|e| e.into_inner());
+    if !values.iter().any(|v| v == value.expose()) {
+        values.push(value.expose().to_string());
+        // Longest first, so a value containing another is masked whole.
+        values.sort_by_key(|v| std::cmp::Reverse(v.len()));
+    }
+}
+
+impl SecretVault {
+    pub fn new(configs: HashMap<String, SecretConfig>, approver: Arc<dyn SecretApprover>) -> Self {
+        Self { configs, approver, grants: Mutex::new(HashMap::new()) }
+    }
+
+    /// Ask for access to secret `name`. Returns a token id once approved,
+    /// or the reason for denial.
+    pub async fn request(&self, name: &str, justification: &str) -> Result<String, String> {
+        let config = self.configs.get(name).ok_or_else(|| format!("Unknown secret '{}'", name))?;
+        let value = std::env::var(&config.env)
+            .ok()
+            .filter(|v| !v.is_empty())
+            .map(SecretValue)
+            .ok_or_else(|| format!("Secret '{}' is not available on this host", name))?;
+
+        let request = SecretRequest {
+            request_id: format!("secret-{}", uuid::Uuid::new_v4()),
+            name: name.to_string(),
+            justification: justification.to_string(),
+            header: config.header.clone(),
+            risk_level: RiskLevel::High,
+        };
+        info!(secret = %name, request_id = %request.request_id, justification = %justification, "Guest requesting secret");
+        if !self.approver.approve(&request).await {
+            warn!(secret = %name, "Secret request rejected");
+            return Err(format!("Access to secret '{}' was not approved", name));
+        }
+
+        register_for_redaction(&value);
+        let token_id = format!("sec-{}", uuid::Uuid::new_v4());
+        self.grants.lock().await.insert(token_id.clone(), Grant { name: name.to_string(), value });
+        info!(secret = %name, token_id = %token_id, "Secret granted");
+        Ok(token_id)
+    }
+
+    /// The header to add for `token_id`, with the secret filled in.
+    pub async fn header(&self, token_id: &str) -> Result<(String, String), String> {
+        let grants = self.grants.lock().await;
+        let grant = grants.get(token_id).ok_or_else(|| format!("Invalid secret token: {}", token_id))?;
+        let config = self.configs.get(&grant.name).ok_or_else(|| format!("Unknown secret '{}'", grant.name))?;
+        Ok((config.header.clone(), config.template.replace(VALUE_PLACEHOLDER, grant.value.expose())))
+    }
+
+    /// Revoke `token_id`; returns whether it was live.
+    pub async fn release(&self, token_id: &str) -> bool {
+        self.grants.lock().await.remove(token_id).is_some()
+    }
+
+    /// `response` with every granted value masked, so servers that echo
+    /// request headers can't leak a secret back to the guest.
+    pub fn scrub(&self, response: NetResponse) -> NetResponse {
+        let body = match std::str::from_utf8(&response.body) {
+            Ok(text) => redact(text).into_bytes(),
+            Err(_) => scrub_bytes(response.body),
+        };
+        NetResponse {
+            status: response.status,
+            headers: response.headers.into_iter().map(|(k, v)| (k, redact(&v))).collect(),
+            body,
+        }
+    }
+}
+
+/// Byte-level masking for bodies that aren't UTF-8.
+fn scrub_bytes(mut body: Vec<u8>) -> Vec<u8> {
+    let values = GRANTED_VALUES.read().unwrap_or_else(|e| e.into_inner());
+    for value in values.iter().map(|v| v.as_bytes()) {
+        let mut out = Vec::with_capacity(body.len());
+        let mut i = 0;
+        while i < body.len() {
+            if body[i..].starts_with(value) {
+                out.extend_from_slice(REDACTED.as_bytes());
+                i += value.len();
+            } else {
+                out.push(body[i]);
+                i += 1;
+            }
+        }
+        body = out;
+    }
+    body
+}
+
+/// `tracing_subscriber` writer that masks granted secrets in every line.
+#[derive(Clone, Copy, Default)]
+pub struct RedactingMakeWriter;
+
+pub struct RedactingWriter(io::Stderr);
+
+impl Write for RedactingWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // The fmt layer writes each event in one call, so values can't
+        // straddle two writes.
+        self.0.write_all(redact(&String::from_utf8_lossy(buf)).as_bytes())?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+impl<'a> tracing_subscriber::fmt::MakeWriter<'a> for RedactingMakeWriter {
+    type Writer = RedactingWriter;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        RedactingWriter(io::stderr())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct FixedApprover(bool);
+
+    #[async_trait::async_trait]
+    impl SecretApprover for FixedApprover {
+        async fn approve(&self, request: &SecretRequest) -> bool {
+            assert_eq!(request.risk_level, RiskLevel::High);
+            self.0
+        }
+    }
+
+    fn vault(env: &str, value: &str, approve: bool) -> SecretVault {
+        std::env::set_var(env, value);
+        let config = SecretConfig { header: "Authorization".into(), template: "Bearer {value}".into(), env: env.into() };
+        SecretVault::new(HashMap::from([("tracker".to_string(), config)]), Arc::new(FixedApprover(approve)))
+    }
+
+    #[tokio::test]
+    async fn test_header_injected_after_approval() {
+        let vault = vault("SENTINEL_TEST_SECRET_INJECT", "tok-inject-8f3a", true);
+        let token = vault.request("tracker", "Post audit results").await.unwrap();
+        assert_eq!(vault.header(&token).await.unwrap(), ("Authorization".into(), "Bearer tok-inject-8f3a".into()));
+        assert!(!token.contains("tok-inject"));
+
+        assert!(vault.release(&token).await);
+        assert!(vault.header(&token).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_denied_requests() {
+        let vault = vault("SENTINEL_TEST_SECRET_DENY", "tok-deny-51c0", false);
+        assert!(vault.request("tracker", "Post results").await.unwrap_err().contains("not approved"));
+        assert!(vault.request("github", "Push").await.unwrap_err().contains("Unknown secret"));
+        assert!(vault.header("sec-forged").await.is_err());
+        // Never granted, so never registered for redaction either.
+        assert_eq!(redact("tok-deny-51c0"), "tok-deny-51c0");
+    }
+
+    #[tokio::test]
+    async fn test_response_never_echoes_secret() {
+        let vault = vault("SENTINEL_TEST_SECRET_ECHO", "tok-echo-77de", true);
+        vault.request("tracker", "Post results").await.unwrap();
+
+        // An httpbin-style server reflecting the request headers.
+        let response = NetResponse {
+            status: 200,
+            headers: vec![("x-echo-authorization".into(), "Bearer tok-echo-77de".into())],
+            body: br#"{"headers":{"Authorization":"Bearer tok-echo-77de"}}"#.to_vec(),
+        };
+        let scrubbed = vault.scrub(response);
+        assert_eq!(scrubbed.headers[0].1, "Bearer [REDACTED]");
+        assert_eq!(scrubbed.body, br#"{"headers":{"Authorization":"Bearer [REDACTED]"}}"#.to_vec());
+
+        let binary = vault.scrub(NetResponse { status: 200, headers: vec![], body: [&[0xff, 0xfe][..], b"tok-echo-77de"].concat() });
+        assert_eq!(binary.body, [&[0xff, 0xfe][..], b"[REDACTED]"].concat());
+        assert_eq!(redact("log: sent tok-echo-77de"), "log: sent [REDACTED]");
+        assert_eq!(format!("{:?}", SecretValue("tok-echo-77de".into())), "[REDACTED]");
+    }
+}
@@ -0,0 +1,284 @@
+//! # sentinel-host — Guest State Store
+//!
+//! Backs the `state` WIT interface: a small key-value store that lets a
+//! guest keep data between runs (e.g. a hash cache for incremental audits).
+//! It never touches user files, so no capability token or HITL approval is
+//! needed; quotas bound how much a guest can store instead.
+//!
+//! Each guest module gets its own directory, `<root>/<sha256 of the wasm>/`,
+//! so modules cannot read each other's state. Every namespace is one JSON
+//! file in that directory, rewritten atomically on each change.
+
+use crate::config::StateConfig;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use tracing::warn;
+
+pub const MAX_NAMESPACE_LEN: usize = 64;
+pub const MAX_KEY_LEN: usize = 256;
+
+#[derive(Debug, Error)]
+pub enum StateError {
+    #[error("Invalid namespace '{0}': use 1-64 letters, digits, '-', '_' or '.'")]
+    InvalidNamespace(String),
+
+    #[error("Invalid key: must be 1-{MAX_KEY_LEN} bytes")]
+    InvalidKey,
+
+    #[error("Namespace '{namespace}' would use {needed} bytes; the quota is {quota}")]
+    NamespaceQuota { namespace: String, needed: u64, quota: u64 },
+
+    #[error("State would use {needed} bytes in total; the quota is {quota}")]
+    TotalQuota { needed: u64, quota: u64 },
+
+    #[error("State storage error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Corrupt state file {0}")]
+    Corrupt(String),
+}
+
+type Entries = BTreeMap<String, Vec<u8>>;
+
+#[derive(Serialize, Deserialize)]
+struct NamespaceFile {
+    entries: Entries,
+}
+
+/// One guest module's state. All namespaces are loaded when opened.
+pub struct StateStore {
+    dir: PathBuf,
+    namespaces: HashMap<String, Entries>,
+    max_namespace_bytes: u64,
+    max_total_bytes: u64,
+}
+
+/// Hex SHA-256 of a guest module, naming its state directory.
+pub fn module_hash(wasm_bytes: &[u8]) -> String {
+    Sha256::digest(wasm_bytes).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Bytes counted against quotas: keys plus values.
+fn usage(entries: &Entries) -> u64 {
+    entries.iter().map(|(k, v)| (k.len() + v.len()) as u64).sum()
+}
+
+fn validate_namespace(namespace: &str) -> Result<(), StateError> {
+    let valid = !namespace.is_empty()
+        && namespace.len() <= MAX_NAMESPACE_LEN
+        && !namespace.starts_with('.')
+        && namespace.chars().all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c));
+    if valid { Ok(()) } else { Err(StateError::InvalidNamespace(namespace.to_string())) }
+}
+
+fn validate_key(key: &str) -> Result<(), StateError> {
+    if key.is_empty() || key.len() > MAX_KEY_LEN { Err(StateError::InvalidKey) } else { Ok(()) }
+}
+
+/// Move an unreadable namespace file out of the way as `<name>.json.corrupt`.
+fn quarantine(path: &Path, namespace: &str, error: &str) {
+    let mut aside = path.as_os_str().to_owned();
+    aside.push(".corrupt");
+    match fs::rename(path, &aside) {
+        Ok(()) => {
+            warn!(namespace = %namespace, error = %error, moved_to = %Path::new(&aside).display(), "Quarantined corrupt state namespace")
+        }
+        Err(e) => warn!(namespace = %namespace, error = %error, rename_error = %e, "Skipping corrupt state namespace"),
+    }
+}
+
+impl StateStore {
+    /// Open the store for the module with hash `module_hash`.
+    pub fn open(config: &StateConfig, module_hash: &str) -> Result<Self, StateError> {
+        let dir = config.root.join(module_hash);
+        let mut namespaces = HashMap::new();
+        if dir.is_dir() {
+            for entry in fs::read_dir(&dir)? {
+                let path = entry?.path();
+                let Some(name) = path.file_name().and_then(|n| n.to_str()).and_then(|n| n.strip_suffix(".json")) else {
+                    continue;
+                };
+                if validate_namespace(name).is_err() {
+                    continue;
+                }
+                match fs::read(&path).map_err(|e| e.to_string()).and_then(|bytes| {
+                    serde_json::from_slice::<NamespaceFile>(&bytes).map_err(|e| e.to_string())
+                }) {
+                    Ok(file) => {
+                        namespaces.insert(name.to_string(), file.entries);
+                    }
+                    // One bad file shouldn't keep the guest from starting;
+                    // set it aside and start that namespace empty.
+                    Err(e) => quarantine(&path, name, &e),
+                }
+            }
+        }
+        Ok(Self {
+            dir,
+            namespaces,
+            max_namespace_bytes: config.max_namespace_bytes,
+            max_total_bytes: config.max_total_bytes,
+        })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn total_bytes(&self) -> u64 {
+        self.namespaces.values().map(usage).sum()
+    }
+
+    pub fn get(&self, namespace: &str, key: &str) -> Result<Option<Vec<u8>>, StateError> {
+        validate_namespace(namespace)?;
+        Ok(self.namespaces.get(namespace).and_then(|entries| entries.get(key)).cloned())
+    }
+
+    pub fn put(&mut self, namespace: &str, key: &str, value: Vec<u8>) -> Result<(), StateError> {
+        validate_namespace(namespace)?;
+        validate_key(key)?;
+        let mut entries = self.namespaces.get(namespace).cloned().unwrap_or_default();
+        let before = usage(&entries);
+        entries.insert(key.to_string(), value);
+        let after = usage(&entries);
+
+        if after > self.max_namespace_bytes {
+            return Err(StateError::NamespaceQuota { namespace: namespace.to_string(), needed: after, quota: self.max_namespace_bytes });
+        }
+        let total = self.total_bytes() - before + after;
+        if total > self.max_total_bytes {
+            return Err(StateError::TotalQuota { needed: total, quota: self.max_total_bytes });
+        }
+        self.save(namespace, entries)
+    }
+
+    /// Remove `key`; returns whether it existed.
+    pub fn delete(&mut self, namespace: &str, key: &str) -> Result<bool, StateError> {
+        validate_namespace(namespace)?;
+        let Some(mut entries) = self.namespaces.get(namespace).cloned() else {
+            return Ok(false);
+        };
+        if entries.remove(key).is_none() {
+            return Ok(false);
+        }
+        self.save(namespace, entries)?;
+        Ok(true)
+    }
+
+    /// Keys in `namespace`, sorted.
+    pub fn list(&self, namespace: &str) -> Result<Vec<String>, StateError> {
+        validate_namespace(namespace)?;
+        Ok(self.namespaces.get(namespace).map(|entries| entries.keys().cloned().collect()).unwrap_or_default())
+    }
+
+    /// Persist `entries` as `namespace`, then adopt them in memory.
+    fn save(&mut self, namespace: &str, entries: Entries) -> Result<(), StateError> {
+        fs::create_dir_all(&self.dir)?;
+        let path = self.dir.join(format!("{}.json", namespace));
+        if entries.is_empty() {
+            match fs::remove_file(&path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+                _ => {}
+            }
+            self.namespaces.remove(namespace);
+            return Ok(());
+        }
+        let file = NamespaceFile { entries };
+        let tmp = self.dir.join(format!("{}.json.tmp", namespace));
+        fs::write(&tmp, serde_json::to_vec(&file).map_err(|e| StateError::Corrupt(e.to_string()))?)?;
+        fs::rename(&tmp, &path)?;
+        self.namespaces.insert(namespace.to_string(), file.entries);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(root: &Path) -> StateConfig {
+        StateConfig { root: root.to_path_buf(), max_namespace_bytes: 64, max_total_bytes: 100 }
+    }
+
+    #[test]
+    fn test_persists_across_boots() {
+        let dir = tempfile::tempdir().unwrap();
+        let hash = module_hash(b"\0asm guest");
+        {
+            let mut store = StateStore::open(&config(dir.path()), &hash).unwrap();
+            store.put("audit-cache", "src/main.rs", b"abc".to_vec()).unwrap();
+            store.put("audit-cache", "src/lib.rs", b"def".to_vec()).unwrap();
+            assert!(store.delete("audit-cache", "src/lib.rs").unwrap());
+            assert!(!store.delete("audit-cache", "src/lib.rs").unwrap());
+        }
+        let store = StateStore::open(&config(dir.path()), &hash).unwrap();
+        assert_eq!(store.get("audit-cache", "src/main.rs").unwrap(), Some(b"abc".to_vec()));
+        assert_eq!(store.list("audit-cache").unwrap(), vec!["src/main.rs"]);
+        assert_eq!(store.get("other", "src/main.rs").unwrap(), None);
+        assert!(store.dir().ends_with(&hash));
+    }
+
+    #[test]
+    fn test_modules_are_isolated() {
+        let dir = tempfile::tempdir().unwrap();
+        let (a, b) = (module_hash(b"module a"), module_hash(b"module b"));
+        assert_ne!(a, b);
+        StateStore::open(&config(dir.path()), &a).unwrap().put("ns", "key", b"secret".to_vec()).unwrap();
+
+        let other = StateStore::open(&config(dir.path()), &b).unwrap();
+        assert_eq!(other.get("ns", "key").unwrap(), None);
+        assert!(other.list("ns").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_quotas_enforced() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = StateStore::open(&config(dir.path()), &module_hash(b"m")).unwrap();
+
+        // 1-byte key + 60-byte value fits the 64-byte namespace quota...
+        store.put("a", "k", vec![0; 60]).unwrap();
+        // ...a second entry does not, and the first is untouched.
+        assert!(matches!(store.put("a", "j", vec![0; 10]), Err(StateError::NamespaceQuota { needed: 72, .. })));
+        assert_eq!(store.list("a").unwrap(), vec!["k"]);
+        // Overwriting counts the new value only.
+        store.put("a", "k", vec![0; 63]).unwrap();
+
+        // 64 + 41 exceeds the 100-byte total across namespaces.
+        assert!(matches!(store.put("b", "k", vec![0; 40]), Err(StateError::TotalQuota { needed: 105, .. })));
+        store.delete("a", "k").unwrap();
+        store.put("b", "k", vec![0; 40]).unwrap();
+        assert_eq!(store.total_bytes(), 41);
+    }
+
+    #[test]
+    fn test_corrupt_namespace_quarantined() {
+        let dir = tempfile::tempdir().unwrap();
+        let hash = module_hash(b"m");
+        StateStore::open(&config(dir.path()), &hash).unwrap().put("good", "k", b"v".to_vec()).unwrap();
+        let bad = dir.path().join(&hash).join("bad.json");
+        fs::write(&bad, b"{ not json").unwrap();
+
+        let mut store = StateStore::open(&config(dir.path()), &hash).unwrap();
+        assert_eq!(store.get("good", "k").unwrap(), Some(b"v".to_vec()));
+        assert!(store.list("bad").unwrap().is_empty());
+        assert!(!bad.exists());
+        assert_eq!(fs::read(dir.path().join(&hash).join("bad.json.corrupt")).unwrap(), b"{ not json");
+        // The namespace is usable again.
+        store.put("bad", "k", b"fresh".to_vec()).unwrap();
+    }
+
+    #[test]
+    fn test_invalid_names_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = StateStore::open(&config(dir.path()), &module_hash(b"m")).unwrap();
+        for namespace in ["", "../escape", ".hidden", "a/b", &"x".repeat(65)] {
+            assert!(matches!(store.put(namespace, "k", vec![]), Err(StateError::InvalidNamespace(_))), "{namespace}");
+        }
+        assert!(matches!(store.put("ns", "", vec![]), Err(StateError::InvalidKey)));
+        assert!(matches!(store.put("ns", &"k".repeat(257), vec![]), Err(StateError::InvalidKey)));
+    }
+}
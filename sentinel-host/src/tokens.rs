@@ -0,0 +1,176 @@
+//! # sentinel-host — Token Counting
+//!
+//! Counts tokens the way the model will. OpenAI-family models use their
+//! real BPE through tiktoken; everything else falls back to a per-family
+//! heuristic that, unlike chars/4, charges code punctuation a token
+//! apiece. Guests reach this through `reasoning.count-tokens` and the
+//! backends use it to trim requests to the context window, so both see
+//! the same numbers.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+use tiktoken_rs::CoreBPE;
+use tracing::debug;
+
+/// Average characters per token inside words, by tokenizer family. Matched
+/// against the family (if known) or the model name, first hit wins.
+const HEURISTICS: &[(&str, f64)] = &[
+    ("llama3", 4.2),
+    ("llama", 3.2),
+    ("mistral", 3.3),
+    ("mixtral", 3.3),
+    ("qwen", 3.9),
+    ("gemma", 4.0),
+    ("gemini", 4.0),
+    ("phi", 3.8),
+    ("deepseek", 3.8),
+    ("claude", 3.5),
+    ("grok", 3.8),
+];
+
+const DEFAULT_CHARS_PER_TOKEN: f64 = 3.5;
+
+enum Encoding {
+    Tiktoken(CoreBPE),
+    Heuristic(f64),
+}
+
+impl Encoding {
+    fn count(&self, text: &str) -> u32 {
+        match self {
+            Self::Tiktoken(bpe) => bpe.encode_ordinary(text).len() as u32,
+            Self::Heuristic(chars_per_token) => heuristic_count(text, *chars_per_token),
+        }
+    }
+}
+
+/// Per-model tokenizers, built on first use.
+#[derive(Default)]
+pub struct TokenCounter {
+    encodings: Mutex<HashMap<String, Arc<Encoding>>>,
+    /// Tokenizer families reported by backends (e.g. Ollama's `/api/show`).
+    families: Mutex<HashMap<String, String>>,
+}
+
+impl TokenCounter {
+    /// Tokens `text` takes for `model`.
+    pub fn count(&self, text: &str, model: &str) -> u32 {
+        if text.is_empty() {
+            return 0;
+        }
+        self.encoding(model).count(text)
+    }
+
+    /// Whether counts for `model` are exact rather than estimated.
+    pub fn is_exact(&self, model: &str) -> bool {
+        matches!(*self.encoding(model), Encoding::Tiktoken(_))
+    }
+
+    /// Record `model`'s tokenizer family, as reported by its backend.
+    pub fn set_family(&self, model: &str, family: &str) {
+        let family = family.to_ascii_lowercase();
+        let mut families = self.families.lock().unwrap_or_else(|e| e.into_inner());
+        if families.get(model) != Some(&family) {
+            families.insert(model.to_string(), family);
+            self.encodings.lock().unwrap_or_else(|e| e.into_inner()).remove(model);
+        }
+    }
+
+    fn encoding(&self, model: &str) -> Arc<Encoding> {
+        if let Some(encoding) = self.encodings.lock().unwrap_or_else(|e| e.into_inner()).get(model) {
+            return encoding.clone();
+        }
+        let family = self.families.lock().unwrap_or_else(|e| e.into_inner()).get(model).cloned();
+        // Loading a BPE takes a while; do it outside the lock.
+        let encoding = Arc::new(build_encoding(model, family.as_deref()));
+        self.encodings
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(model.to_string())
+            .or_insert(encoding)
+            .clone()
+    }
+}
+
+fn build_encoding(model: &str, family: Option<&str>) -> Encoding {
+    if let Ok(bpe) = tiktoken_rs::get_bpe_from_model(model) {
+        debug!(model = %model, "Counting tokens with tiktoken");
+        return Encoding::Tiktoken(bpe);
+    }
+    let name = family.unwrap_or(model).to_ascii_lowercase();
+    let chars_per_token = HEURISTICS
+        .iter()
+        .find(|(prefix, _)| name.contains(prefix))
+        .map_or(DEFAULT_CHARS_PER_TOKEN, |(_, ratio)| *ratio);
+    debug!(model = %model, family = ?family, chars_per_token, "Estimating token counts");
+    Encoding::Heuristic(chars_per_token)
+}
+
+/// Word runs cost `len / chars_per_token` (at least 1); every other
+/// non-space character, and every newline, costs one.
+fn heuristic_count(text: &str, chars_per_token: f64) -> u32 {
+    let mut tokens = 0.0;
+    let mut word = 0usize;
+    let flush = |word: &mut usize, tokens: &mut f64| {
+        if *word > 0 {
+            *tokens += (*word as f64 / chars_per_token).ceil();
+            *word = 0;
+        }
+    };
+    for c in text.chars() {
+        if c.is_alphanumeric() || c == '_' {
+            word += 1;
+            continue;
+        }
+        flush(&mut word, &mut tokens);
+        if c == '\n' || !c.is_whitespace() {
+            tokens += 1.0;
+        }
+    }
+    flush(&mut word, &mut tokens);
+    tokens as u32
+}
+
+/// The counter shared by `reasoning.count-tokens` and the backends.
+pub fn shared() -> &'static TokenCounter {
+    static COUNTER: OnceLock<TokenCounter> = OnceLock::new();
+    COUNTER.get_or_init(TokenCounter::default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tiktoken_fixtures() {
+        let counter = TokenCounter::default();
+        // cl100k_base: t|ik|token| is| great|!
+        assert_eq!(counter.count("tiktoken is great!", "gpt-4"), 6);
+        assert_eq!(counter.count("Hello, world!", "gpt-3.5-turbo"), 4);
+        // o200k_base
+        assert_eq!(counter.count("Hello, world!", "gpt-4o"), 4);
+        assert!(counter.is_exact("gpt-4o-mini"));
+        assert_eq!(counter.count("", "gpt-4"), 0);
+    }
+
+    #[test]
+    fn test_heuristic_fallback() {
+        let counter = TokenCounter::default();
+        assert!(!counter.is_exact("llama3.1:8b"));
+        assert_eq!(counter.count("hello world", "mystery-model"), 4);
+
+        // Code is dense in punctuation; chars/4 undercounts it badly.
+        let code = "fn main() {\n    let x: Vec<u8> = vec![1, 2, 3];\n}\n";
+        let count = counter.count(code, "mystery-model");
+        assert!(count as usize > code.len() / 4 * 13 / 10, "{count}");
+    }
+
+    #[test]
+    fn test_reported_family_replaces_cached_encoding() {
+        let counter = TokenCounter::default();
+        let text = "a".repeat(42);
+        assert_eq!(counter.count(&text, "custom:latest"), 12);
+        counter.set_family("custom:latest", "Llama");
+        assert_eq!(counter.count(&text, "custom:latest"), 14);
+    }
+}
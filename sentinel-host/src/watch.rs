@@ -0,0 +1,318 @@
+//! # sentinel-host — File Watches
+//!
+//! Lets a guest wait for workspace changes instead of polling
+//! `fs-list-dir` and burning fuel. `watch-path` starts a `notify` watcher
+//! on a path the guest's read token covers; `poll-watch` waits for
+//! changes, coalesces bursts (an editor's create + write + close is one
+//! `Created`) and returns at most [`MAX_EVENTS_PER_POLL`] of them, leaving
+//! the rest for the next poll. Waiting wakes up regularly so a cancelled
+//! run isn't kept hanging, and watches go away with their token or the run.
+
+use crate::cancellation::CancellationHandle;
+use notify::event::{ModifyKind, RenameMode};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+use tracing::{info, warn};
+
+/// Active watches per run.
+pub const MAX_WATCHES: usize = 16;
+/// Events returned by one `poll-watch`; the rest wait for the next poll.
+pub const MAX_EVENTS_PER_POLL: usize = 256;
+/// Longest a single `poll-watch` may block.
+pub const MAX_POLL_TIMEOUT: Duration = Duration::from_secs(60);
+/// How often a waiting poll checks for cancellation.
+const CANCEL_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+/// After the first event, keep collecting until nothing has arrived for
+/// this long, so a burst of changes arrives as one batch.
+const SETTLE: Duration = Duration::from_millis(50);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FsEventKind {
+    Created,
+    Modified,
+    Removed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FsEvent {
+    pub kind: FsEventKind,
+    pub path: String,
+}
+
+/// What `prev` then `next` on one path amount to; `None` if nothing (a
+/// file created and removed between polls).
+fn coalesce(prev: FsEventKind, next: FsEventKind) -> Option<FsEventKind> {
+    use FsEventKind::*;
+    match (prev, next) {
+        (Created, Removed) => None,
+        (Created, _) => Some(Created),
+        (Removed, Removed) => Some(Removed),
+        (Removed, _) => Some(Modified),
+        (Modified, Removed) => Some(Removed),
+        (Modified, _) => Some(Modified),
+    }
+}
+
+/// The guest-visible changes in a `notify` event. Access events are dropped.
+fn classify(event: Event) -> Vec<(FsEventKind, PathBuf)> {
+    let kinds: Vec<FsEventKind> = match event.kind {
+        EventKind::Create(_) => vec![FsEventKind::Created],
+        EventKind::Remove(_) => vec![FsEventKind::Removed],
+        EventKind::Modify(ModifyKind::Name(RenameMode::From)) => vec![FsEventKind::Removed],
+        EventKind::Modify(ModifyKind::Name(RenameMode::To)) => vec![FsEventKind::Created],
+        EventKind::Modify(ModifyKind::Name(RenameMode::Both)) => vec![FsEventKind::Removed, FsEventKind::Created],
+        EventKind::Modify(_) | EventKind::Any => vec![FsEventKind::Modified],
+        EventKind::Access(_) | EventKind::Other => vec![],
+    };
+    // `Both` carries the old and new path; everything else applies to all paths.
+    let last = kinds.len().saturating_sub(1);
+    event
+        .paths
+        .into_iter()
+        .enumerate()
+        .filter_map(|(i, path)| kinds.get(i.min(last)).map(|kind| (*kind, path)))
+        .collect()
+}
+
+struct Watch {
+    token_id: String,
+    root: PathBuf,
+    _watcher: RecommendedWatcher,
+    events: mpsc::UnboundedReceiver<notify::Result<Event>>,
+    /// Coalesced changes not yet returned, oldest first.
+    pending: Vec<FsEvent>,
+}
+
+impl Watch {
+    fn absorb(&mut self, event: notify::Result<Event>) {
+        let event = match event {
+            Ok(event) => event,
+            Err(e) => {
+                warn!(root = %self.root.display(), error = %e, "File watch error");
+                return;
+            }
+        };
+        for (kind, path) in classify(event) {
+            // Symlinks and renames can report paths outside the watched
+            // tree; `root` is already canonical.
+            if !path.starts_with(&self.root) {
+                continue;
+            }
+            // A directory's own mtime changes with every entry; the entry's
+            // event is the one that matters.
+            if kind == FsEventKind::Modified && path.is_dir() {
+                continue;
+            }
+            let path = path.to_string_lossy().into_owned();
+            match self.pending.iter().position(|e| e.path == path) {
+                Some(i) => match coalesce(self.pending[i].kind, kind) {
+                    Some(kind) => self.pending[i].kind = kind,
+                    None => {
+                        self.pending.remove(i);
+                    }
+                },
+                None => self.pending.push(FsEvent { kind, path }),
+            }
+        }
+    }
+
+    /// Absorb whatever has arrived; whether there was anything.
+    fn drain(&mut self) -> Result<bool, String> {
+        let mut any = false;
+        loop {
+            match self.events.try_recv() {
+                Ok(event) => {
+                    self.absorb(event);
+                    any = true;
+                }
+                Err(mpsc::error::TryRecvError::Empty) => return Ok(any),
+                Err(mpsc::error::TryRecvError::Disconnected) => return Err("The watch has stopped".to_string()),
+            }
+        }
+    }
+}
+
+/// The watches of one run. Dropping it stops them all.
+pub struct WatchRegistry {
+    watches: Mutex<HashMap<u32, Arc<tokio::sync::Mutex<Watch>>>>,
+    next_id: AtomicU32,
+    cancellation: CancellationHandle,
+}
+
+impl WatchRegistry {
+    pub fn new(cancellation: CancellationHandle) -> Self {
+        Self { watches: Mutex::default(), next_id: AtomicU32::new(1), cancellation }
+    }
+
+    /// Watch `root`, which the caller has already resolved and checked
+    /// against `token_id`'s scope.
+    pub fn watch(&self, token_id: &str, root: &Path, recursive: bool) -> Result<u32, String> {
+        let mut watches = self.watches.lock().unwrap_or_else(|e| e.into_inner());
+        if watches.len() >= MAX_WATCHES {
+            return Err(format!("At most {} watches may be active", MAX_WATCHES));
+        }
+        let (tx, events) = mpsc::unbounded_channel();
+        let mut watcher = notify::recommended_watcher(move |event| {
+            // The watch may already be gone.
+            let _ = tx.send(event);
+        })
+        .map_err(|e| format!("Cannot start watcher: {}", e))?;
+        let mode = if recursive { RecursiveMode::Recursive } else { RecursiveMode::NonRecursive };
+        watcher.watch(root, mode).map_err(|e| format!("Cannot watch {}: {}", root.display(), e))?;
+
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let watch = Watch { token_id: token_id.to_string(), root: root.to_path_buf(), _watcher: watcher, events, pending: Vec::new() };
+        watches.insert(id, Arc::new(tokio::sync::Mutex::new(watch)));
+        info!(watch_id = id, root = %root.display(), recursive, "File watch started");
+        Ok(id)
+    }
+
+    /// The token a watch was started with, and what it watches.
+    pub async fn scope(&self, watch_id: u32) -> Option<(String, PathBuf)> {
+        let watch = self.get(watch_id)?;
+        let watch = watch.lock().await;
+        Some((watch.token_id.clone(), watch.root.clone()))
+    }
+
+    /// Changes since the last poll, waiting up to `timeout` for the first.
+    /// Returns early, possibly empty, once the run is cancelled.
+    pub async fn poll(&self, watch_id: u32, timeout: Duration) -> Result<Vec<FsEvent>, String> {
+        let watch = self.get(watch_id).ok_or_else(|| format!("Unknown watch: {}", watch_id))?;
+        let mut watch = watch.lock().await;
+        watch.drain()?;
+
+        let deadline = Instant::now() + timeout.min(MAX_POLL_TIMEOUT);
+        while watch.pending.is_empty() && !self.cancellation.is_requested() {
+            let now = Instant::now();
+            if now >= deadline {
+                break;
+            }
+            match tokio::time::timeout((deadline - now).min(CANCEL_CHECK_INTERVAL), watch.events.recv()).await {
+                Ok(Some(event)) => watch.absorb(event),
+                Ok(None) => return Err("The watch has stopped".to_string()),
+                Err(_) => {}
+            }
+        }
+
+        // The rest of a burst may still be on its way, however the first of
+        // it arrived.
+        if !watch.pending.is_empty() {
+            tokio::time::sleep(SETTLE).await;
+            while watch.drain()? {
+                tokio::time::sleep(SETTLE).await;
+            }
+        }
+
+        let take = watch.pending.len().min(MAX_EVENTS_PER_POLL);
+        Ok(watch.pending.drain(..take).collect())
+    }
+
+    pub fn unwatch(&self, watch_id: u32) -> bool {
+        self.watches.lock().unwrap_or_else(|e| e.into_inner()).remove(&watch_id).is_some()
+    }
+
+    /// Stop every watch started with `token_id`.
+    pub async fn release_token(&self, token_id: &str) -> usize {
+        let watches: Vec<(u32, Arc<tokio::sync::Mutex<Watch>>)> =
+            self.watches.lock().unwrap_or_else(|e| e.into_inner()).iter().map(|(id, w)| (*id, w.clone())).collect();
+        let mut released = 0;
+        for (id, watch) in watches {
+            if watch.lock().await.token_id == token_id && self.unwatch(id) {
+                released += 1;
+            }
+        }
+        released
+    }
+
+    fn get(&self, watch_id: u32) -> Option<Arc<tokio::sync::Mutex<Watch>>> {
+        self.watches.lock().unwrap_or_else(|e| e.into_inner()).get(&watch_id).cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry() -> WatchRegistry {
+        WatchRegistry::new(CancellationHandle::new(wasmtime::Engine::default(), Duration::from_secs(10)))
+    }
+
+    fn events(list: &[FsEvent]) -> Vec<(FsEventKind, String)> {
+        let mut list: Vec<(FsEventKind, String)> = list
+            .iter()
+            .map(|e| (e.kind, Path::new(&e.path).file_name().unwrap().to_string_lossy().into_owned()))
+            .collect();
+        list.sort_by(|a, b| a.1.cmp(&b.1));
+        list
+    }
+
+    #[tokio::test]
+    async fn test_event_stream() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        std::fs::create_dir(root.join("src")).unwrap();
+        let registry = registry();
+        let id = registry.watch("tok", &root, true).unwrap();
+        let poll = || registry.poll(id, Duration::from_secs(5));
+
+        std::fs::write(root.join("src/lib.rs"), "fn main() {}").unwrap();
+        std::fs::write(root.join("notes.md"), "todo").unwrap();
+        assert_eq!(
+            events(&poll().await.unwrap()),
+            vec![(FsEventKind::Created, "lib.rs".into()), (FsEventKind::Created, "notes.md".into())]
+        );
+
+        std::fs::write(root.join("src/lib.rs"), "fn main() { run() }").unwrap();
+        assert_eq!(events(&poll().await.unwrap()), vec![(FsEventKind::Modified, "lib.rs".into())]);
+
+        std::fs::remove_file(root.join("notes.md")).unwrap();
+        assert_eq!(events(&poll().await.unwrap()), vec![(FsEventKind::Removed, "notes.md".into())]);
+
+        // Nothing happening: an empty list once the timeout is up.
+        assert_eq!(registry.poll(id, Duration::from_millis(200)).await.unwrap(), vec![]);
+        assert_eq!(registry.release_token("tok").await, 1);
+        assert!(registry.poll(id, Duration::ZERO).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_coalesced_and_capped() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        let registry = registry();
+        let id = registry.watch("tok", &root, false).unwrap();
+
+        // Created and removed between polls: no event at all.
+        std::fs::write(root.join("scratch.tmp"), "x").unwrap();
+        std::fs::remove_file(root.join("scratch.tmp")).unwrap();
+        let count = MAX_EVENTS_PER_POLL + 10;
+        for i in 0..count {
+            std::fs::write(root.join(format!("f{i}.rs")), "a").unwrap();
+            std::fs::write(root.join(format!("f{i}.rs")), "ab").unwrap();
+        }
+        tokio::time::sleep(Duration::from_millis(300)).await;
+
+        let first = registry.poll(id, Duration::from_secs(5)).await.unwrap();
+        assert_eq!(first.len(), MAX_EVENTS_PER_POLL);
+        let rest = registry.poll(id, Duration::from_secs(5)).await.unwrap();
+        assert_eq!(rest.len(), 10);
+        assert!(first.iter().chain(&rest).all(|e| e.kind == FsEventKind::Created && e.path.ends_with(".rs")));
+    }
+
+    #[tokio::test]
+    async fn test_cancellation_ends_wait() {
+        let dir = tempfile::tempdir().unwrap();
+        let registry = registry();
+        let id = registry.watch("tok", &dir.path().canonicalize().unwrap(), true).unwrap();
+        registry.cancellation.cancel("Interrupted by user");
+
+        let started = std::time::Instant::now();
+        assert_eq!(registry.poll(id, Duration::from_secs(30)).await.unwrap(), vec![]);
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+}
@@ -0,0 +1,13 @@
+;; A guest that never checks for cancellation.
+(component
+  (core module $spin
+    (func (export "run") (result i32)
+      (loop $forever (br $forever))
+      (unreachable))
+  )
+  (core instance $spin (instantiate $spin))
+
+  (func (export "run") (result s32)
+    (canon lift (core func $spin "run"))
+  )
+)
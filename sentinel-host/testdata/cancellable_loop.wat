@@ -0,0 +1,37 @@
+;; A scripted guest for the cancellation tests: "audits" up to 1000 files,
+;; 1ms each, checking for cancellation between files. Exits 3 (cancelled)
+;; if asked to stop, 0 otherwise.
+(component
+  (import "sentinel:agent/cancellation@0.2.0" (instance $cancellation
+    (export "is-cancellation-requested" (func (result bool)))
+  ))
+  (import "sentinel:agent/clock@0.2.0" (instance $clock
+    (export "sleep" (func (param "ms" u64)))
+  ))
+
+  (core func $requested (canon lower (func $cancellation "is-cancellation-requested")))
+  (core func $sleep (canon lower (func $clock "sleep")))
+
+  (core module $auditor
+    (import "host" "requested" (func $requested (result i32)))
+    (import "host" "sleep" (func $sleep (param i64)))
+    (func (export "run") (result i32)
+      (local $file i32)
+      (loop $files
+        (if (call $requested) (then (return (i32.const 3))))
+        (call $sleep (i64.const 1))
+        (local.set $file (i32.add (local.get $file) (i32.const 1)))
+        (br_if $files (i32.lt_u (local.get $file) (i32.const 1000))))
+      (i32.const 0))
+  )
+  (core instance $auditor (instantiate $auditor
+    (with "host" (instance
+      (export "requested" (func $requested))
+      (export "sleep" (func $sleep))
+    ))
+  ))
+
+  (func (export "run") (result s32)
+    (canon lift (core func $auditor "run"))
+  )
+)
@@ -0,0 +1,35 @@
+;; A minimal child guest for the spawn tests: `run` passes its context
+;; straight to `spawn.set-output` and exits 0.
+(component
+  (import "sentinel:agent/spawn@0.2.0" (instance $spawn
+    (export "set-output" (func (param "output" string)))
+  ))
+
+  ;; Linear memory and a bump allocator for the canonical ABI.
+  (core module $libc
+    (memory (export "memory") 1)
+    (global $heap (mut i32) (i32.const 1024))
+    (func (export "realloc") (param i32 i32 i32 i32) (result i32)
+      (local $ptr i32)
+      (local.set $ptr (global.get $heap))
+      (global.set $heap (i32.add (global.get $heap) (local.get 3)))
+      (local.get $ptr))
+  )
+  (core instance $libc (instantiate $libc))
+
+  (core func $set-output (canon lower (func $spawn "set-output") (memory (core memory $libc "memory"))))
+
+  (core module $echo
+    (import "host" "set-output" (func $set-output (param i32 i32)))
+    (func (export "run") (param i32 i32) (result i32)
+      (call $set-output (local.get 0) (local.get 1))
+      (i32.const 0))
+  )
+  (core instance $echo (instantiate $echo
+    (with "host" (instance (export "set-output" (func $set-output))))
+  ))
+
+  (func (export "run") (param "context-json" string) (result s32)
+    (canon lift (core func $echo "run") (memory (core memory $libc "memory")) (realloc (core func $libc "realloc")))
+  )
+)
@@ -0,0 +1,57 @@
+;; A guest whose `metadata.get-manifest` tries to write to its persistent
+;; state instead of just answering. Reading the manifest must trap on the
+;; `kv-put` call and leave the state untouched.
+(component
+  (import "sentinel:agent/state@0.2.0" (instance $state
+    (export "kv-put" (func
+      (param "namespace" string) (param "key" string) (param "value" (list u8))
+      (result (result (error string)))))
+  ))
+
+  ;; Linear memory and a bump allocator for the canonical ABI, with the
+  ;; namespace, key and value at 0.
+  (core module $libc
+    (memory (export "memory") 1)
+    (data (i32.const 0) "probekv")
+    (global $heap (mut i32) (i32.const 1024))
+    (func (export "realloc") (param i32 i32 i32 i32) (result i32)
+      (local $ptr i32)
+      (local.set $ptr (global.get $heap))
+      (global.set $heap (i32.add (global.get $heap) (local.get 3)))
+      (local.get $ptr))
+  )
+  (core instance $libc (instantiate $libc))
+
+  (core func $kv-put (canon lower (func $state "kv-put")
+    (memory (core memory $libc "memory")) (realloc (core func $libc "realloc"))))
+
+  (core module $probe
+    (import "host" "kv-put" (func $kv-put (param i32 i32 i32 i32 i32 i32 i32)))
+    ;; Puts "probe"/"k" = "v", then returns an empty manifest at 64.
+    (func (export "get-manifest") (result i32)
+      (call $kv-put (i32.const 0) (i32.const 5) (i32.const 5) (i32.const 1) (i32.const 6) (i32.const 1) (i32.const 128))
+      (i32.const 64))
+  )
+  (core instance $probe (instantiate $probe
+    (with "host" (instance (export "kv-put" (func $kv-put))))
+  ))
+
+  (type $capability-kind (enum "fs-read" "fs-write" "net-outbound"))
+  (type $capability-requirement (record
+    (field "kind" $capability-kind) (field "pattern" string) (field "justification" string)))
+  (type $guest-manifest (record
+    (field "name" string) (field "version" string) (field "description" string)
+    (field "requirements" (list $capability-requirement))))
+
+  (func $get-manifest (result $guest-manifest)
+    (canon lift (core func $probe "get-manifest") (memory (core memory $libc "memory")))
+  )
+
+  (instance $metadata
+    (export "capability-kind" (type $capability-kind))
+    (export "capability-requirement" (type $capability-requirement))
+    (export "guest-manifest" (type $guest-manifest))
+    (export "get-manifest" (func $get-manifest))
+  )
+  (export "sentinel:agent/metadata@0.2.0" (instance $metadata))
+)
@@ -1,7 +1,10 @@
+pub mod paths;
+pub mod pricing;
+
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use std::collections::HashMap;
-use std::time::SystemTime;
+use std::time::{Duration, SystemTime};
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 pub enum RiskLevel {
@@ -11,22 +14,38 @@ pub enum RiskLevel {
     Critical,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum CapabilityScope {
-    Read(String),        // Path pattern, e.g., "/workspace/src/**"
-    Write(String),       // Path pattern
-    Network(String),     // URL pattern, e.g., "https://api.github.com/**"
-    Shell(String),       // Command pattern
+    /// A canonical path and everything beneath it.
+    FsPath { allowed_pattern: String, read_only: bool },
+    /// A URL, or a prefix of one ending in `*`, and the methods allowed on
+    /// it; none listed allows any.
+    NetUrl { allowed_url_pattern: String, methods: Vec<String> },
+    UiObserve,
+    UiDispatch { allowed_event_types: Vec<String> },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CapabilityToken {
     pub id: String,
     pub scope: CapabilityScope,
-    pub expires_at: SystemTime,
+    pub issued_at: SystemTime,
+    pub ttl: Duration,
+    pub revoked: bool,
+}
+
+impl CapabilityToken {
+    pub fn expires_at(&self) -> SystemTime {
+        self.issued_at + self.ttl
+    }
+
+    /// Not revoked and not yet expired.
+    pub fn is_valid(&self) -> bool {
+        !self.revoked && SystemTime::now() < self.expires_at()
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExecutionManifest {
     pub id: String,
     pub action_description: String,
@@ -37,22 +56,60 @@ pub struct ExecutionManifest {
     pub nonce: [u8; 32],
 }
 
+/// The host's Ed25519 signature over an approved manifest.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestSignature {
+    pub manifest_id: String,
+    pub signature_bytes: Vec<u8>,
+    pub signer_public_key: Vec<u8>,
+}
+
 #[derive(Error, Debug, Serialize, Deserialize)]
 pub enum SentinelError {
     #[error("Capability denied: {0}")]
     CapabilityDenied(String),
-    
+
+    #[error("Capability token revoked: {token_id}")]
+    TokenRevoked { token_id: String },
+
+    #[error("Capability token expired: {token_id}")]
+    TokenExpired { token_id: String },
+
+    #[error("Path escape attempt blocked: {path}")]
+    PathEscapeAttempt { path: String },
+
+    #[error("URL not whitelisted: {url}")]
+    UrlNotWhitelisted { url: String },
+
+    #[error("Resource limit exceeded: {resource}")]
+    ResourceExhausted { resource: String },
+
+    #[error("Nonce reuse detected")]
+    NonceReuse,
+
+    #[error("Invalid manifest signature")]
+    InvalidSignature,
+
+    #[error("Guest error: {message}")]
+    GuestError { message: String },
+
     #[error("Resource not found: {0}")]
     NotFound(String),
-    
+
     #[error("LLM error: {0}")]
     LlmError(String),
-    
+
     #[error("HITL approval required")]
     ApprovalRequired,
-    
+
     #[error("Internal error: {0}")]
     Internal(String),
 }
 
+impl From<serde_json::Error> for SentinelError {
+    fn from(e: serde_json::Error) -> Self {
+        SentinelError::Internal(e.to_string())
+    }
+}
+
 pub type Result<T> = std::result::Result<T, SentinelError>;
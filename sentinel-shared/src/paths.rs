@@ -0,0 +1,66 @@
+//! Path containment checks shared by the host runtime and the dashboard.
+
+use std::path::{Component, Path, PathBuf};
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum PathError {
+    #[error("Path escapes the allowed directory: {0}")]
+    Escape(String),
+
+    #[error("Path not found: {0}")]
+    NotFound(String),
+}
+
+/// Whether the canonical `path` lies inside `dir`. `dir` is canonicalized
+/// when it exists so symlinked roots compare correctly.
+pub fn is_within(path: &Path, dir: &Path) -> bool {
+    let dir = dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf());
+    path.starts_with(dir)
+}
+
+/// Resolve `relative` under `root` to an existing canonical path inside it.
+/// Absolute paths, `..` escapes and symlinks leading out of `root` are
+/// rejected.
+pub fn resolve_within(root: &Path, relative: &str) -> Result<PathBuf, PathError> {
+    let requested = Path::new(relative);
+    if requested.components().any(|c| matches!(c, Component::RootDir | Component::Prefix(_))) {
+        return Err(PathError::Escape(relative.to_string()));
+    }
+    let root = root.canonicalize().map_err(|_| PathError::NotFound(root.display().to_string()))?;
+    let canonical = root.join(requested).canonicalize().map_err(|_| PathError::NotFound(relative.to_string()))?;
+    if !canonical.starts_with(&root) {
+        return Err(PathError::Escape(relative.to_string()));
+    }
+    Ok(canonical)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_within() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("workspace");
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(root.join("src/main.rs"), "fn main() {}").unwrap();
+        std::fs::write(dir.path().join("secret.txt"), "nope").unwrap();
+
+        let resolved = resolve_within(&root, "src/../src/main.rs").unwrap();
+        assert!(is_within(&resolved, &root));
+        assert_eq!(resolved, root.canonicalize().unwrap().join("src/main.rs"));
+        assert_eq!(resolve_within(&root, "").unwrap(), root.canonicalize().unwrap());
+
+        assert_eq!(resolve_within(&root, "../secret.txt"), Err(PathError::Escape("../secret.txt".into())));
+        assert!(matches!(resolve_within(&root, "/etc/passwd"), Err(PathError::Escape(_))));
+        assert!(matches!(resolve_within(&root, "src/missing.rs"), Err(PathError::NotFound(_))));
+        assert!(!is_within(&dir.path().join("secret.txt").canonicalize().unwrap(), &root));
+
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(dir.path().join("secret.txt"), root.join("link")).unwrap();
+            assert!(matches!(resolve_within(&root, "link"), Err(PathError::Escape(_))));
+        }
+    }
+}
@@ -0,0 +1,105 @@
+//! Token usage and cost estimation.
+//!
+//! Prices are list prices in USD per million tokens. They drift, so every
+//! figure derived from this table is an *estimate*. Local providers cost
+//! nothing; unknown models have no price rather than a guessed one.
+
+use serde::{Deserialize, Serialize};
+
+/// Prompt and completion token counts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenCounts {
+    #[serde(default)]
+    pub prompt_tokens: u64,
+    #[serde(default)]
+    pub completion_tokens: u64,
+}
+
+impl TokenCounts {
+    pub fn new(prompt_tokens: u64, completion_tokens: u64) -> Self {
+        Self { prompt_tokens, completion_tokens }
+    }
+
+    pub fn total(&self) -> u64 {
+        self.prompt_tokens + self.completion_tokens
+    }
+
+    pub fn add(&mut self, other: TokenCounts) {
+        self.prompt_tokens += other.prompt_tokens;
+        self.completion_tokens += other.completion_tokens;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.total() == 0
+    }
+}
+
+/// USD per million tokens.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct ModelPrice {
+    pub input_per_mtok: f64,
+    pub output_per_mtok: f64,
+}
+
+impl ModelPrice {
+    pub fn cost(&self, tokens: &TokenCounts) -> f64 {
+        (tokens.prompt_tokens as f64 * self.input_per_mtok + tokens.completion_tokens as f64 * self.output_per_mtok) / 1_000_000.0
+    }
+}
+
+/// `(provider, model prefix, input, output)`. The longest matching prefix
+/// wins, so dated snapshots (`gpt-4o-2024-08-06`) price like their family
+/// and `gpt-4o-mini` isn't priced as `gpt-4o`.
+const PRICES: &[(&str, &str, f64, f64)] = &[
+    ("openai", "gpt-4o", 2.50, 10.00),
+    ("openai", "gpt-4o-mini", 0.15, 0.60),
+    ("openai", "o3-mini", 1.10, 4.40),
+    ("anthropic", "claude-3-5-sonnet", 3.00, 15.00),
+    ("anthropic", "claude-3-5-haiku", 0.80, 4.00),
+    ("google", "gemini-1.5-pro", 1.25, 5.00),
+    ("google", "gemini-1.5-flash", 0.075, 0.30),
+    ("deepseek", "deepseek-chat", 0.27, 1.10),
+    ("deepseek", "deepseek-reasoner", 0.55, 2.19),
+    ("grok", "grok-beta", 5.00, 15.00),
+];
+
+/// Providers that run on the user's machine and are always free.
+const LOCAL_PROVIDERS: &[&str] = &["ollama", "ollama-local"];
+
+/// Price for `model` on `provider` (dashboard provider ids: `openai`,
+/// `anthropic`, ...), or `None` if the model isn't in the table.
+pub fn price_for(provider: &str, model: &str) -> Option<ModelPrice> {
+    if LOCAL_PROVIDERS.contains(&provider) {
+        return Some(ModelPrice { input_per_mtok: 0.0, output_per_mtok: 0.0 });
+    }
+    PRICES.iter()
+        .filter(|(p, prefix, _, _)| *p == provider && model.starts_with(prefix))
+        .max_by_key(|(_, prefix, _, _)| prefix.len())
+        .map(|&(_, _, input_per_mtok, output_per_mtok)| ModelPrice { input_per_mtok, output_per_mtok })
+}
+
+/// Estimated cost in USD, or `None` for unpriced models.
+pub fn estimate_cost(provider: &str, model: &str, tokens: &TokenCounts) -> Option<f64> {
+    price_for(provider, model).map(|price| price.cost(tokens))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_longest_prefix_wins() {
+        assert_eq!(price_for("openai", "gpt-4o-2024-08-06").unwrap().input_per_mtok, 2.50);
+        assert_eq!(price_for("openai", "gpt-4o-mini").unwrap().input_per_mtok, 0.15);
+        assert!(price_for("anthropic", "gpt-4o").is_none());
+        assert!(price_for("openai", "gpt-5-preview").is_none());
+    }
+
+    #[test]
+    fn test_estimate_cost() {
+        let tokens = TokenCounts::new(1_000_000, 500_000);
+        assert_eq!(estimate_cost("anthropic", "claude-3-5-sonnet-20241022", &tokens), Some(3.0 + 7.5));
+        assert_eq!(estimate_cost("ollama", "llama3.3:latest", &tokens), Some(0.0));
+        assert_eq!(estimate_cost("custom", "whatever", &tokens), None);
+    }
+}
@@ -0,0 +1,280 @@
+//! Re-attaching to agent containers that outlived the dashboard.
+//!
+//! Everything needed to resume an agent is recoverable from `docker
+//! inspect`: the env `start_agent` passed (provider, model, callback secret,
+//! mounts, proxy URL), the published noVNC port, and the network. The
+//! session log on disk supplies the chat history.
+
+use crate::callback_server::CallbackConfig;
+use crate::commands::{self, LogEntry, SharedAgentState};
+use crate::mounts::MOUNTS_ENV;
+use crate::network::{self, NETWORK_PREFIX};
+use crate::ports::CONTAINER_NOVNC_PORT;
+use crate::reaper::CONTAINER_PREFIX;
+use crate::session::{self, SessionRecord, SharedSessions};
+use crate::usage::AgentUsage;
+use bollard::container::{InspectContainerOptions, ListContainersOptions};
+use bollard::models::ContainerInspectResponse;
+use bollard::Docker;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::time::SystemTime;
+use tauri::{Manager, State};
+use tracing::{info, warn};
+
+/// What the dashboard knows about a running agent.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AgentInfo {
+    pub agent_id: String,
+    pub container_id: String,
+    pub provider: String,
+    pub model: String,
+    pub autonomy: String,
+    pub task: String,
+    /// Host directory behind the agent's primary mount, if any.
+    pub target_dir: Option<String>,
+    pub novnc_port: Option<u16>,
+    pub running: bool,
+}
+
+/// An inspected container, split into what the UI sees and what only the
+/// backend needs to resume it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reconstructed {
+    pub info: AgentInfo,
+    pub callback_secret: String,
+    /// `(network, proxy address)` for llm-only agents.
+    pub egress: Option<(String, SocketAddr)>,
+}
+
+/// Returned by `attach_agent`.
+#[derive(Debug, Clone, Serialize)]
+pub struct AttachedAgent {
+    pub info: AgentInfo,
+    pub history: Vec<SessionRecord>,
+}
+
+/// Returned by `list_agents`.
+#[derive(Debug, Clone, Serialize)]
+pub struct AgentListing {
+    #[serde(flatten)]
+    pub info: AgentInfo,
+    /// Whether this dashboard is already tracking the agent.
+    pub attached: bool,
+}
+
+/// Rebuild an agent's launch parameters from its inspect response.
+pub fn reconstruct(inspect: &ContainerInspectResponse) -> Result<Reconstructed, String> {
+    let agent_id = inspect.name.as_deref().unwrap_or_default().trim_start_matches('/').to_string();
+    if !agent_id.starts_with(CONTAINER_PREFIX) {
+        return Err(format!("Not a SENTINEL agent container: {}", agent_id));
+    }
+    let env: HashMap<&str, &str> = inspect.config.as_ref()
+        .and_then(|c| c.env.as_ref())
+        .into_iter()
+        .flatten()
+        .filter_map(|kv| kv.split_once('='))
+        .collect();
+    let var = |name: &str| env.get(name).map(|v| v.to_string());
+    let callback_secret = var("SENTINEL_CALLBACK_SECRET")
+        .ok_or_else(|| format!("{} has no callback secret; it was not started by this dashboard", agent_id))?;
+
+    // The primary mount is the first `SENTINEL_MOUNTS` entry, or the legacy
+    // target dir; map it back to its host source.
+    let primary = var(MOUNTS_ENV)
+        .and_then(|raw| serde_json::from_str::<Vec<serde_json::Value>>(&raw).ok())
+        .and_then(|list| list.first()?.get("path")?.as_str().map(String::from))
+        .or_else(|| var("SENTINEL_TARGET_DIR"));
+    let target_dir = primary.and_then(|path| {
+        inspect.mounts.as_ref()?.iter()
+            .find(|m| m.destination.as_deref() == Some(path.as_str()))
+            .and_then(|m| m.source.clone())
+    });
+
+    let novnc_port = inspect.network_settings.as_ref()
+        .and_then(|n| n.ports.as_ref())
+        .and_then(|ports| ports.get(&format!("{}/tcp", CONTAINER_NOVNC_PORT)))
+        .and_then(|bindings| bindings.as_ref()?.iter().find_map(|b| b.host_port.as_ref()?.parse().ok()));
+
+    let network_mode = inspect.host_config.as_ref().and_then(|h| h.network_mode.clone());
+    let egress = match (network_mode, var("HTTP_PROXY")) {
+        (Some(network), Some(proxy)) if network.starts_with(NETWORK_PREFIX) => {
+            let addr = proxy.trim_start_matches("http://").trim_end_matches('/').parse()
+                .map_err(|_| format!("{} has an unparseable proxy address: {}", agent_id, proxy))?;
+            Some((network, addr))
+        }
+        _ => None,
+    };
+
+    Ok(Reconstructed {
+        info: AgentInfo {
+            container_id: inspect.id.clone().unwrap_or_else(|| agent_id.clone()),
+            provider: var("SENTINEL_PROVIDER").unwrap_or_default(),
+            model: var("SENTINEL_MODEL").unwrap_or_default(),
+            autonomy: var("SENTINEL_AUTONOMY").unwrap_or_default(),
+            task: var("SENTINEL_TASK").unwrap_or_default(),
+            target_dir,
+            novnc_port,
+            running: inspect.state.as_ref().and_then(|s| s.running).unwrap_or(false),
+            agent_id,
+        },
+        callback_secret,
+        egress,
+    })
+}
+
+/// Resume tracking a running agent container: accept its callbacks again,
+/// restore its port and egress proxy, and follow new log output.
+#[tauri::command]
+pub async fn attach_agent(
+    app: tauri::AppHandle,
+    state: State<'_, SharedAgentState>,
+    sessions: State<'_, SharedSessions>,
+    container_id: String,
+) -> Result<AttachedAgent, String> {
+    let docker = Docker::connect_with_local_defaults().map_err(|e| e.to_string())?;
+    let inspect = docker.inspect_container(&container_id, None::<InspectContainerOptions>).await
+        .map_err(|e| format!("Failed to inspect {}: {}", container_id, e))?;
+    let Reconstructed { info, callback_secret, egress } = reconstruct(&inspect)?;
+    if !info.running {
+        return Err(format!("{} is not running", info.agent_id));
+    }
+    let agent_id = info.agent_id.clone();
+    if state.lock().await.active_agents.contains_key(&agent_id) {
+        return Err(format!("{} is already attached", agent_id));
+    }
+
+    // A proxy that fails to come back leaves the agent without LLM access,
+    // but its logs and HITL requests are still worth reconnecting to.
+    let callback_port = app.state::<CallbackConfig>().addr.port();
+    let egress = match egress {
+        Some((net, addr)) => match network::resume(&agent_id, net, addr, &info.provider, callback_port).await {
+            Ok(resources) => Some(resources),
+            Err(e) => {
+                warn!(agent_id = %agent_id, error = %e, "Agent re-attached without egress proxy");
+                None
+            }
+        },
+        None => None,
+    };
+
+    let notice = LogEntry {
+        level: "info".to_string(),
+        target: "system".to_string(),
+        message: "Re-attached to running container after dashboard restart".to_string(),
+    };
+    sessions.record(&agent_id, session::event_from_log(&notice.level, &notice.target, &notice.message));
+    {
+        let mut s = state.lock().await;
+        s.active_agents.insert(agent_id.clone(), agent_id.clone());
+        s.callback_secrets.insert(agent_id.clone(), callback_secret);
+        s.last_heartbeat.insert(agent_id.clone(), SystemTime::now());
+        s.usage.insert(agent_id.clone(), AgentUsage::new(&info.provider, &info.model));
+        s.agent_logs.insert(agent_id.clone(), vec![notice]);
+        if let Some(dir) = &info.target_dir {
+            s.workspaces.insert(agent_id.clone(), dir.into());
+        }
+        if let Some(port) = info.novnc_port {
+            s.ports.adopt(&agent_id, port);
+        }
+        if let Some(egress) = egress {
+            s.networks.register(&agent_id, egress);
+        }
+    }
+    commands::follow_logs(commands::LogFollower::new(&app, docker), agent_id.clone(), "0");
+    info!(agent_id = %agent_id, "Re-attached to agent container");
+
+    let history = sessions.history(&agent_id).unwrap_or_default();
+    Ok(AttachedAgent { info, history })
+}
+
+/// Running `sentinel-*` containers, attached or not. The frontend calls this
+/// on startup and attaches to any that aren't.
+#[tauri::command]
+pub async fn list_agents(state: State<'_, SharedAgentState>) -> Result<Vec<AgentListing>, String> {
+    let docker = Docker::connect_with_local_defaults().map_err(|e| e.to_string())?;
+    let options = ListContainersOptions::<String> {
+        filters: HashMap::from([("name".to_string(), vec![CONTAINER_PREFIX.to_string()])]),
+        ..Default::default()
+    };
+    let containers = docker.list_containers(Some(options)).await.map_err(|e| e.to_string())?;
+    let mut listings = Vec::new();
+    for id in containers.into_iter().filter_map(|c| c.id) {
+        let Ok(inspect) = docker.inspect_container(&id, None::<InspectContainerOptions>).await else { continue };
+        if let Ok(r) = reconstruct(&inspect) {
+            let attached = state.lock().await.active_agents.contains_key(&r.info.agent_id);
+            listings.push(AgentListing { info: r.info, attached });
+        }
+    }
+    Ok(listings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(extra_env: &[&str], network_mode: &str) -> ContainerInspectResponse {
+        let mut env = vec![
+            "SENTINEL_AGENT_ID=sentinel-1a2b3c4d",
+            "SENTINEL_CALLBACK_SECRET=6f1e-secret",
+            "SENTINEL_TASK=Audit the parser",
+            "SENTINEL_PROVIDER=anthropic",
+            "SENTINEL_MODEL=claude-3-5-sonnet-20241022",
+            "SENTINEL_AUTONOMY=read_report",
+            r#"SENTINEL_MOUNTS=[{"path":"/workspace","mode":"rw"},{"path":"/notes","mode":"ro"}]"#,
+            "SENTINEL_TARGET_DIR=/workspace",
+            "PATH=/usr/local/bin:/usr/bin",
+        ];
+        env.extend_from_slice(extra_env);
+        serde_json::from_value(serde_json::json!({
+            "Id": "9c0ffee",
+            "Name": "/sentinel-1a2b3c4d",
+            "State": { "Status": "running", "Running": true },
+            "Config": { "Image": "sentinel-agent:latest", "Env": env },
+            "HostConfig": { "NetworkMode": network_mode },
+            "Mounts": [
+                { "Type": "bind", "Source": "/home/me/notes", "Destination": "/notes", "RW": false },
+                { "Type": "bind", "Source": "/home/me/project", "Destination": "/workspace", "RW": true },
+            ],
+            "NetworkSettings": {
+                "Ports": { "6080/tcp": [{ "HostIp": "127.0.0.1", "HostPort": "6083" }] },
+            },
+        })).unwrap()
+    }
+
+    #[test]
+    fn test_reconstruct_from_inspect() {
+        let r = reconstruct(&fixture(&[], "default")).unwrap();
+        assert_eq!(r.info, AgentInfo {
+            agent_id: "sentinel-1a2b3c4d".into(),
+            container_id: "9c0ffee".into(),
+            provider: "anthropic".into(),
+            model: "claude-3-5-sonnet-20241022".into(),
+            autonomy: "read_report".into(),
+            task: "Audit the parser".into(),
+            target_dir: Some("/home/me/project".into()),
+            novnc_port: Some(6083),
+            running: true,
+        });
+        assert_eq!(r.callback_secret, "6f1e-secret");
+        assert_eq!(r.egress, None);
+    }
+
+    #[test]
+    fn test_reconstruct_llm_only_egress() {
+        let r = reconstruct(&fixture(&["HTTP_PROXY=http://172.30.0.1:41234"], "sentinel-net-sentinel-1a2b3c4d")).unwrap();
+        assert_eq!(r.egress, Some(("sentinel-net-sentinel-1a2b3c4d".into(), "172.30.0.1:41234".parse().unwrap())));
+    }
+
+    #[test]
+    fn test_reconstruct_rejects_foreign_containers() {
+        let mut inspect = fixture(&[], "default");
+        inspect.name = Some("/postgres".into());
+        assert!(reconstruct(&inspect).unwrap_err().contains("Not a SENTINEL"));
+
+        let mut inspect = fixture(&[], "default");
+        inspect.config.as_mut().unwrap().env.as_mut().unwrap().retain(|kv| !kv.starts_with("SENTINEL_CALLBACK_SECRET"));
+        assert!(reconstruct(&inspect).unwrap_err().contains("callback secret"));
+    }
+}
@@ -0,0 +1,997 @@
+//! Host-side HTTP callback server.
+//!
+//! Agent containers reach the dashboard through `SENTINEL_CALLBACK_URL`
+//! (`http://host.docker.internal:<port>`, the port the server is bound to).
+//! Each route translates the agent's
+//! payload into a `sentinel://*` event for the frontend and updates the
+//! shared backend state.
+//!
+//! Every request must carry the agent's ID and the per-agent secret that
+//! `start_agent` passed in `SENTINEL_CALLBACK_SECRET`, so a stray container
+//! cannot post as another agent.
+
+use crate::commands::{self, AgentState, HitlPendingSenders, HitlResolution, LogEntry, PendingManifest};
+use crate::messages::{self, AgentMessage, MessageKind};
+use crate::notifications::{Notifier, NotifyEvent, NotifyKind};
+use crate::report::{self, ReportHandoff};
+use crate::session::{self, SessionEvent, SharedSessions};
+use crate::usage;
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode};
+use axum::routing::post;
+use axum::{Json, Router};
+use bollard::Docker;
+use sentinel_shared::pricing::TokenCounts;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use subtle::ConstantTimeEq;
+use tokio::sync::{oneshot, Mutex};
+use tracing::{info, warn};
+
+/// Port the callback server binds unless `SENTINEL_CALLBACK_ADDR` says otherwise.
+pub const DEFAULT_PORT: u16 = 9876;
+
+/// How long an agent blocks on a HITL decision before it is rejected.
+pub const DEFAULT_HITL_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// How long an agent blocks on `/ask` before continuing without an answer.
+pub const DEFAULT_ASK_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// An agent with no heartbeat for this long is reported as stalled.
+pub const DEFAULT_STALL_AFTER: Duration = Duration::from_secs(180);
+
+/// How long the final status callback may spend copying the report out.
+pub const REPORT_FETCH_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Header carrying the per-agent callback secret.
+pub const SECRET_HEADER: &str = "x-sentinel-secret";
+
+/// Destination for frontend events.
+///
+/// The Tauri `AppHandle` is the production sink; tests inject a recorder.
+pub trait EventSink: Send + Sync {
+    fn emit(&self, event: &str, payload: serde_json::Value);
+}
+
+impl EventSink for tauri::AppHandle {
+    fn emit(&self, event: &str, payload: serde_json::Value) {
+        if let Err(e) = tauri::Emitter::emit(self, event, payload) {
+            warn!(event = %event, error = %e, "Failed to emit frontend event");
+        }
+    }
+}
+
+/// Callback server settings.
+#[derive(Debug, Clone)]
+pub struct CallbackConfig {
+    pub addr: SocketAddr,
+    pub hitl_timeout: Duration,
+    pub ask_timeout: Duration,
+    pub stall_after: Duration,
+}
+
+impl Default for CallbackConfig {
+    fn default() -> Self {
+        Self {
+            addr: SocketAddr::from(([127, 0, 0, 1], DEFAULT_PORT)),
+            hitl_timeout: DEFAULT_HITL_TIMEOUT,
+            ask_timeout: DEFAULT_ASK_TIMEOUT,
+            stall_after: DEFAULT_STALL_AFTER,
+        }
+    }
+}
+
+impl CallbackConfig {
+    /// Defaults, with the bind address overridable via `SENTINEL_CALLBACK_ADDR`.
+    pub fn from_env() -> Self {
+        let mut config = Self::default();
+        if let Ok(addr) = std::env::var("SENTINEL_CALLBACK_ADDR") {
+            match addr.parse() {
+                Ok(addr) => config.addr = addr,
+                Err(e) => warn!(addr = %addr, error = %e, "Ignoring invalid SENTINEL_CALLBACK_ADDR"),
+            }
+        }
+        config
+    }
+
+    /// `SENTINEL_CALLBACK_URL` for agent containers: the bound port on the
+    /// host gateway.
+    pub fn agent_url(&self) -> String {
+        format!("http://host.docker.internal:{}", self.addr.port())
+    }
+}
+
+#[derive(Clone)]
+pub struct CallbackState {
+    pub agents: Arc<Mutex<AgentState>>,
+    pub hitl: Arc<HitlPendingSenders>,
+    pub sink: Arc<dyn EventSink>,
+    pub sessions: SharedSessions,
+    pub notifier: Arc<Notifier>,
+    pub config: CallbackConfig,
+}
+
+// ── Payloads ────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Deserialize)]
+pub struct LogRequest {
+    pub agent_id: String,
+    pub level: String,
+    pub target: String,
+    pub message: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StatusRequest {
+    pub agent_id: String,
+    pub status: String,
+    #[serde(default)]
+    pub message: String,
+    /// Tokens used since the agent's last usage report.
+    #[serde(default)]
+    pub usage: Option<TokenCounts>,
+    /// The final report, sent with the last status update.
+    #[serde(default)]
+    pub report: Option<ReportHandoff>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GuiRequest {
+    pub agent_id: String,
+    pub gui_active: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HeartbeatRequest {
+    pub agent_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ProgressRequest {
+    pub agent_id: String,
+    pub phase: String,
+    pub current: u32,
+    pub total: u32,
+    #[serde(default)]
+    pub detail: String,
+    /// Tokens used since the agent's last usage report.
+    #[serde(default)]
+    pub usage: Option<TokenCounts>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ThoughtRequest {
+    pub agent_id: String,
+    pub message: String,
+    /// Set when the thought announces a tool call.
+    #[serde(default)]
+    pub tool: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReportRequest {
+    pub agent_id: String,
+    #[serde(default)]
+    pub title: Option<String>,
+    pub markdown: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AskRequest {
+    pub agent_id: String,
+    pub question: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AskResponse {
+    pub answer: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StreamRequest {
+    pub agent_id: String,
+    pub delta: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HitlRequest {
+    pub agent_id: String,
+    pub action_description: String,
+    #[serde(default)]
+    pub parameters: serde_json::Value,
+    pub risk_level: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HitlResponse {
+    pub manifest_id: String,
+    pub approved: bool,
+}
+
+// ── Server ──────────────────────────────────────────────────────────────────
+
+pub fn router(state: CallbackState) -> Router {
+    Router::new()
+        .route("/log", post(log))
+        .route("/status", post(status))
+        .route("/gui", post(gui))
+        .route("/heartbeat", post(heartbeat))
+        .route("/progress", post(progress))
+        .route("/thought", post(thought))
+        .route("/ask", post(ask))
+        .route("/report", post(report_section))
+        .route("/hitl", post(hitl))
+        .route("/stream", post(stream))
+        .with_state(state)
+}
+
+/// Set once `serve` holds the callback port, so diagnostics don't report
+/// our own listener as a conflict.
+static LISTENING: AtomicBool = AtomicBool::new(false);
+
+pub fn is_listening() -> bool {
+    LISTENING.load(Ordering::SeqCst)
+}
+
+/// Bind the callback port synchronously, so it is held before any agent
+/// can be launched; hand the listener to [`serve`].
+pub fn bind(addr: SocketAddr) -> std::io::Result<std::net::TcpListener> {
+    let listener = std::net::TcpListener::bind(addr)?;
+    listener.set_nonblocking(true)?;
+    LISTENING.store(true, Ordering::SeqCst);
+    info!(addr = %addr, "Callback server listening");
+    Ok(listener)
+}
+
+/// Serve callbacks on `listener` until `shutdown` resolves.
+pub async fn serve(
+    listener: std::net::TcpListener,
+    state: CallbackState,
+    shutdown: impl Future<Output = ()> + Send + 'static,
+) -> std::io::Result<()> {
+    let listener = tokio::net::TcpListener::from_std(listener)?;
+    axum::serve(listener, router(state)).with_graceful_shutdown(shutdown).await
+}
+
+/// Check the caller's secret against the one issued to `agent_id`.
+async fn authorize(cb: &CallbackState, headers: &HeaderMap, agent_id: &str) -> Result<(), StatusCode> {
+    let presented = headers.get(SECRET_HEADER).and_then(|v| v.to_str().ok());
+    let mut agents = cb.agents.lock().await;
+    match (agents.callback_secrets.get(agent_id), presented) {
+        (Some(expected), Some(presented)) if bool::from(expected.as_bytes().ct_eq(presented.as_bytes())) => {
+            // The agent is up and has read its key; the host copy can go.
+            agents.key_files.remove(agent_id);
+            Ok(())
+        }
+        (None, _) => {
+            warn!(agent_id = %agent_id, "Callback from unknown agent rejected");
+            Err(StatusCode::UNAUTHORIZED)
+        }
+        _ => {
+            warn!(agent_id = %agent_id, "Callback with bad secret rejected");
+            Err(StatusCode::UNAUTHORIZED)
+        }
+    }
+}
+
+async fn log(State(cb): State<CallbackState>, headers: HeaderMap, Json(req): Json<LogRequest>) -> StatusCode {
+    if let Err(code) = authorize(&cb, &headers, &req.agent_id).await {
+        return code;
+    }
+    if let Some(message) = AgentMessage::from_legacy_log(&req.agent_id, &req.message) {
+        messages::send(&cb.sessions, cb.sink.as_ref(), &message);
+        return StatusCode::NO_CONTENT;
+    }
+    let entry = LogEntry { level: req.level, target: req.target, message: req.message };
+    cb.sessions.record(&req.agent_id, session::event_from_log(&entry.level, &entry.target, &entry.message));
+    cb.agents.lock().await.agent_logs.entry(req.agent_id.clone()).or_default().push(entry.clone());
+    cb.sink.emit("sentinel://log", serde_json::json!({
+        "agent_id": req.agent_id,
+        "level": entry.level,
+        "target": entry.target,
+        "message": entry.message,
+    }));
+    StatusCode::NO_CONTENT
+}
+
+async fn status(State(cb): State<CallbackState>, headers: HeaderMap, Json(req): Json<StatusRequest>) -> StatusCode {
+    if let Err(code) = authorize(&cb, &headers, &req.agent_id).await {
+        return code;
+    }
+    info!(agent_id = %req.agent_id, status = %req.status, "Agent status update");
+    cb.agents.lock().await.agent_status.insert(req.agent_id.clone(), req.status.clone());
+    record_usage(&cb, &req.agent_id, req.usage).await;
+    cb.sessions.record(&req.agent_id, SessionEvent::Status { status: req.status.clone(), message: req.message.clone() });
+    if let Some(handoff) = &req.report {
+        // The agent blocks on this response, so its container is still
+        // there to copy from.
+        receive_report(&cb, &req.agent_id, handoff).await;
+    }
+    let kind = match req.status.as_str() {
+        "completed" => Some(NotifyKind::Completed),
+        "failed" | "error" => Some(NotifyKind::Failed),
+        _ => None,
+    };
+    if let Some(kind) = kind {
+        cb.notifier.notify(NotifyEvent::new(kind, &req.agent_id, &req.message));
+    }
+    cb.sink.emit("sentinel://status", serde_json::json!({
+        "agent_id": req.agent_id,
+        "status": req.status,
+        "message": req.message,
+    }));
+    StatusCode::NO_CONTENT
+}
+
+async fn receive_report(cb: &CallbackState, agent_id: &str, handoff: &ReportHandoff) {
+    let fetched = match Docker::connect_with_local_defaults() {
+        Ok(docker) => tokio::time::timeout(REPORT_FETCH_TIMEOUT, report::fetch(&docker, agent_id, handoff)).await
+            .unwrap_or_else(|_| Err("Timed out copying report from container".to_string())),
+        Err(e) => Err(e.to_string()),
+    };
+    match fetched {
+        Ok(text) => {
+            cb.agents.lock().await.reports_delivered.insert(agent_id.to_string());
+            report::deliver(&cb.sessions, cb.sink.as_ref(), agent_id, &text);
+        }
+        Err(e) => {
+            // The host-side probe still runs when the container exits.
+            warn!(agent_id = %agent_id, path = %handoff.path, error = %e, "Report handoff failed");
+            let notice = format!("Could not retrieve report {}: {}", handoff.path, e);
+            messages::send(&cb.sessions, cb.sink.as_ref(), &AgentMessage::new(agent_id, MessageKind::System, notice));
+        }
+    }
+}
+
+/// Accumulate a usage delta and emit `sentinel://usage` if anything changed.
+async fn record_usage(cb: &CallbackState, agent_id: &str, delta: Option<TokenCounts>) {
+    let Some(delta) = delta else { return };
+    if !delta.is_empty() {
+        cb.sessions.record(agent_id, SessionEvent::Usage {
+            prompt_tokens: delta.prompt_tokens,
+            completion_tokens: delta.completion_tokens,
+        });
+    }
+    let report = usage::record(&mut *cb.agents.lock().await, agent_id, delta);
+    if let Some(report) = report {
+        cb.sink.emit("sentinel://usage", serde_json::to_value(report).unwrap_or_default());
+    }
+}
+
+async fn gui(State(cb): State<CallbackState>, headers: HeaderMap, Json(req): Json<GuiRequest>) -> StatusCode {
+    if let Err(code) = authorize(&cb, &headers, &req.agent_id).await {
+        return code;
+    }
+    cb.sink.emit("sentinel://gui", serde_json::json!({
+        "agent_id": req.agent_id,
+        "gui_active": req.gui_active,
+    }));
+    StatusCode::NO_CONTENT
+}
+
+async fn heartbeat(State(cb): State<CallbackState>, headers: HeaderMap, Json(req): Json<HeartbeatRequest>) -> StatusCode {
+    if let Err(code) = authorize(&cb, &headers, &req.agent_id).await {
+        return code;
+    }
+    {
+        let mut agents = cb.agents.lock().await;
+        agents.last_heartbeat.insert(req.agent_id.clone(), SystemTime::now());
+        agents.stalled.remove(&req.agent_id);
+    }
+    cb.sink.emit("sentinel://heartbeat", serde_json::json!({ "agent_id": req.agent_id }));
+    StatusCode::NO_CONTENT
+}
+
+async fn progress(State(cb): State<CallbackState>, headers: HeaderMap, Json(req): Json<ProgressRequest>) -> StatusCode {
+    if let Err(code) = authorize(&cb, &headers, &req.agent_id).await {
+        return code;
+    }
+    record_usage(&cb, &req.agent_id, req.usage).await;
+    cb.sink.emit("sentinel://progress", serde_json::json!({
+        "agent_id": req.agent_id,
+        "phase": req.phase,
+        "current": req.current,
+        "total": req.total,
+        "detail": req.detail,
+    }));
+    StatusCode::NO_CONTENT
+}
+
+async fn thought(State(cb): State<CallbackState>, headers: HeaderMap, Json(req): Json<ThoughtRequest>) -> StatusCode {
+    if let Err(code) = authorize(&cb, &headers, &req.agent_id).await {
+        return code;
+    }
+    let message = match req.tool {
+        Some(tool) => AgentMessage::new(&req.agent_id, MessageKind::ToolUse, req.message).with_title(tool),
+        None => AgentMessage::new(&req.agent_id, MessageKind::Thought, req.message),
+    };
+    messages::send(&cb.sessions, cb.sink.as_ref(), &message);
+    StatusCode::NO_CONTENT
+}
+
+/// A report section the agent sends inline, e.g. when it couldn't write a
+/// report file.
+async fn report_section(State(cb): State<CallbackState>, headers: HeaderMap, Json(req): Json<ReportRequest>) -> StatusCode {
+    if let Err(code) = authorize(&cb, &headers, &req.agent_id).await {
+        return code;
+    }
+    let mut message = AgentMessage::new(&req.agent_id, MessageKind::ReportSection, req.markdown);
+    message.title = req.title;
+    messages::send(&cb.sessions, cb.sink.as_ref(), &message);
+    StatusCode::NO_CONTENT
+}
+
+/// Forward a question to the user and block until they reply via
+/// `send_agent_message` or the ask timeout elapses.
+async fn ask(State(cb): State<CallbackState>, headers: HeaderMap, Json(req): Json<AskRequest>) -> Result<Json<AskResponse>, StatusCode> {
+    authorize(&cb, &headers, &req.agent_id).await?;
+    let (tx, rx) = oneshot::channel();
+    cb.agents.lock().await.pending_questions.insert(req.agent_id.clone(), tx);
+    messages::send(&cb.sessions, cb.sink.as_ref(), &AgentMessage::new(&req.agent_id, MessageKind::Question, req.question));
+
+    let answer = match tokio::time::timeout(cb.config.ask_timeout, rx).await {
+        Ok(Ok(answer)) => Some(answer),
+        _ => {
+            cb.agents.lock().await.pending_questions.remove(&req.agent_id);
+            None
+        }
+    };
+    Ok(Json(AskResponse { answer }))
+}
+
+async fn stream(State(cb): State<CallbackState>, headers: HeaderMap, Json(req): Json<StreamRequest>) -> StatusCode {
+    if let Err(code) = authorize(&cb, &headers, &req.agent_id).await {
+        return code;
+    }
+    cb.sink.emit("sentinel://stream", serde_json::json!({
+        "agent_id": req.agent_id,
+        "delta": req.delta,
+    }));
+    StatusCode::NO_CONTENT
+}
+
+/// Register a pending manifest and block until the user decides.
+///
+/// A timeout or a dropped sender counts as a rejection.
+async fn hitl(State(cb): State<CallbackState>, headers: HeaderMap, Json(req): Json<HitlRequest>) -> Result<Json<HitlResponse>, StatusCode> {
+    authorize(&cb, &headers, &req.agent_id).await?;
+    let manifest = PendingManifest {
+        id: uuid::Uuid::new_v4().to_string(),
+        agent_id: req.agent_id,
+        action_description: req.action_description,
+        parameters_json: serde_json::to_string_pretty(&req.parameters).unwrap_or_default(),
+        risk_level: req.risk_level,
+        created_at: SystemTime::now(),
+    };
+    let manifest_id = manifest.id.clone();
+    let (agent_id, action_description, risk_level) =
+        (manifest.agent_id.clone(), manifest.action_description.clone(), manifest.risk_level.clone());
+    info!(manifest_id = %manifest_id, agent_id = %manifest.agent_id, "HITL: manifest pending");
+
+    let (tx, rx) = oneshot::channel();
+    let payload = serde_json::to_value(&manifest).unwrap_or_default();
+    cb.hitl.insert(manifest, tx).await;
+    cb.sink.emit("sentinel://hitl-pending", payload);
+    cb.notifier.notify(NotifyEvent::new(NotifyKind::HitlPending, &agent_id, &action_description).with_risk(&risk_level));
+
+    let approved = match tokio::time::timeout(cb.config.hitl_timeout, rx).await {
+        Ok(Ok(approved)) => approved,
+        Ok(Err(_)) => false,
+        Err(_) => {
+            warn!(manifest_id = %manifest_id, "HITL: approval timed out — rejecting");
+            if let Some(manifest) = cb.hitl.remove(&manifest_id).await {
+                commands::emit_hitl_resolved(cb.sink.as_ref(), &manifest, HitlResolution::TimedOut);
+            }
+            false
+        }
+    };
+    cb.sessions.record(&agent_id, SessionEvent::Hitl {
+        manifest_id: manifest_id.clone(),
+        action_description,
+        risk_level,
+        approved,
+    });
+
+    Ok(Json(HitlResponse { manifest_id, approved }))
+}
+
+// ── Stall detection ─────────────────────────────────────────────────────────
+
+/// Agents whose last heartbeat is older than `stall_after` and that have not
+/// been reported yet. Returned agents are marked so each stall fires once;
+/// the next heartbeat clears the mark.
+pub fn take_newly_stalled(agents: &mut AgentState, now: SystemTime, stall_after: Duration) -> Vec<String> {
+    let stalled: Vec<String> = agents.last_heartbeat.iter()
+        .filter(|(id, _)| agents.active_agents.contains_key(*id) && !agents.stalled.contains(*id))
+        .filter(|(id, _)| !matches!(agents.agent_status.get(*id).map(String::as_str), Some("completed" | "failed" | "error")))
+        .filter(|(_, last)| now.duration_since(**last).unwrap_or_default() > stall_after)
+        .map(|(id, _)| id.clone())
+        .collect();
+    agents.stalled.extend(stalled.iter().cloned());
+    stalled
+}
+
+/// Whether `agent_id` can still act on a HITL decision: it is tracked and
+/// its container hasn't exited.
+fn agent_alive(agents: &AgentState, agent_id: &str) -> bool {
+    agents.active_agents.contains_key(agent_id) && !agents.exited.contains(agent_id)
+}
+
+/// Reject manifests whose agent is gone, so they don't sit in the queue
+/// until the HITL timeout.
+pub async fn expire_orphaned_manifests(cb: &CallbackState) -> Vec<PendingManifest> {
+    let pending = cb.hitl.pending().await;
+    if pending.is_empty() {
+        return Vec::new();
+    }
+    let orphaned: HashSet<String> = {
+        let agents = cb.agents.lock().await;
+        pending.iter()
+            .filter(|m| !agent_alive(&agents, &m.agent_id))
+            .map(|m| m.agent_id.clone())
+            .collect()
+    };
+    if orphaned.is_empty() {
+        return Vec::new();
+    }
+    let expired = cb.hitl.take_where(false, |m| orphaned.contains(&m.agent_id)).await;
+    for manifest in &expired {
+        warn!(manifest_id = %manifest.id, agent_id = %manifest.agent_id, "HITL: agent exited — manifest expired");
+        commands::emit_hitl_resolved(cb.sink.as_ref(), manifest, HitlResolution::AgentExited);
+    }
+    expired
+}
+
+/// Periodically reconcile agent state: announce stalled agents and expire
+/// manifests left behind by exited ones.
+pub async fn reconcile(cb: CallbackState, interval: Duration) {
+    let mut ticker = tokio::time::interval(interval);
+    loop {
+        ticker.tick().await;
+        expire_orphaned_manifests(&cb).await;
+        let stalled = take_newly_stalled(&mut *cb.agents.lock().await, SystemTime::now(), cb.config.stall_after);
+        for agent_id in stalled {
+            warn!(agent_id = %agent_id, "Agent stalled — no heartbeat");
+            let detail = format!("No heartbeat for over {} seconds.", cb.config.stall_after.as_secs());
+            cb.sink.emit("sentinel://stalled", serde_json::json!({ "agent_id": agent_id, "detail": detail }));
+            cb.notifier.notify(NotifyEvent::new(NotifyKind::Stalled, agent_id, detail));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::SettingsStore;
+
+    const AGENT: &str = "sentinel-test";
+    const SECRET: &str = "s3cret";
+
+    #[derive(Default)]
+    struct RecordingSink(std::sync::Mutex<Vec<(String, serde_json::Value)>>);
+
+    impl RecordingSink {
+        fn events(&self) -> Vec<(String, serde_json::Value)> {
+            self.0.lock().unwrap().clone()
+        }
+    }
+
+    impl EventSink for RecordingSink {
+        fn emit(&self, event: &str, payload: serde_json::Value) {
+            self.0.lock().unwrap().push((event.to_string(), payload));
+        }
+    }
+
+    struct TestServer {
+        url: String,
+        state: CallbackState,
+        sink: Arc<RecordingSink>,
+        _sessions_dir: tempfile::TempDir,
+    }
+
+    impl TestServer {
+        async fn start(config: CallbackConfig) -> Self {
+            let mut agents = AgentState::default();
+            agents.callback_secrets.insert(AGENT.to_string(), SECRET.to_string());
+            let sink = Arc::new(RecordingSink::default());
+            let sessions_dir = tempfile::tempdir().unwrap();
+            let settings = Arc::new(SettingsStore::load(sessions_dir.path().join("settings.json")));
+            let state = CallbackState {
+                agents: Arc::new(Mutex::new(agents)),
+                hitl: Arc::new(HitlPendingSenders::default()),
+                sink: sink.clone(),
+                sessions: Arc::new(session::SessionStore::new(sessions_dir.path().join("sessions"))),
+                notifier: Arc::new(Notifier::new(settings)),
+                config,
+            };
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let url = format!("http://{}", listener.local_addr().unwrap());
+            let app = router(state.clone());
+            tokio::spawn(async move { axum::serve(listener, app).await });
+            Self { url, state, sink, _sessions_dir: sessions_dir }
+        }
+
+        fn post(&self, route: &str, body: serde_json::Value) -> reqwest::RequestBuilder {
+            reqwest::Client::new()
+                .post(format!("{}{}", self.url, route))
+                .header(SECRET_HEADER, SECRET)
+                .json(&body)
+        }
+    }
+
+    async fn wait_for_pending(hitl: &HitlPendingSenders) -> PendingManifest {
+        for _ in 0..100 {
+            if let Some(m) = hitl.pending().await.into_iter().next() {
+                return m;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("manifest never became pending");
+    }
+
+    #[tokio::test]
+    async fn test_log_updates_state_and_emits() {
+        let server = TestServer::start(CallbackConfig::default()).await;
+        let resp = server.post("/log", serde_json::json!({
+            "agent_id": AGENT, "level": "info", "target": "agent", "message": "hello",
+        })).send().await.unwrap();
+        assert_eq!(resp.status(), 204);
+
+        let agents = server.state.agents.lock().await;
+        assert_eq!(agents.agent_logs[AGENT][0].message, "hello");
+        let events = server.sink.events();
+        assert_eq!(events[0].0, "sentinel://log");
+        assert_eq!(events[0].1["agent_id"], AGENT);
+        let history = server.state.sessions.history(AGENT).unwrap();
+        assert_eq!(history[0].event, SessionEvent::Log {
+            level: "info".into(), target: "agent".into(), message: "hello".into(),
+        });
+    }
+
+    #[tokio::test]
+    async fn test_status_and_heartbeat_update_state() {
+        let server = TestServer::start(CallbackConfig::default()).await;
+        server.post("/status", serde_json::json!({ "agent_id": AGENT, "status": "running" })).send().await.unwrap();
+        server.post("/heartbeat", serde_json::json!({ "agent_id": AGENT })).send().await.unwrap();
+        server.post("/progress", serde_json::json!({
+            "agent_id": AGENT, "phase": "analysis", "current": 2, "total": 5,
+        })).send().await.unwrap();
+
+        let agents = server.state.agents.lock().await;
+        assert_eq!(agents.agent_status[AGENT], "running");
+        assert!(agents.last_heartbeat.contains_key(AGENT));
+        let kinds: Vec<String> = server.sink.events().into_iter().map(|(e, _)| e).collect();
+        assert_eq!(kinds, ["sentinel://status", "sentinel://heartbeat", "sentinel://progress"]);
+    }
+
+    #[tokio::test]
+    async fn test_usage_accumulates_and_emits() {
+        let server = TestServer::start(CallbackConfig::default()).await;
+        {
+            let mut agents = server.state.agents.lock().await;
+            agents.usage.insert(AGENT.into(), usage::AgentUsage::new("openai", "gpt-4o"));
+            agents.usage.insert("sentinel-local".into(), usage::AgentUsage::new("ollama", "qwen2.5:7b"));
+            usage::record(&mut agents, "sentinel-local", TokenCounts::new(5_000, 1_000));
+        }
+
+        server.post("/progress", serde_json::json!({
+            "agent_id": AGENT, "phase": "tool-loop", "current": 1, "total": 20,
+            "usage": { "prompt_tokens": 400_000, "completion_tokens": 50_000 },
+        })).send().await.unwrap();
+        // No usage, or an empty delta, changes nothing and emits nothing.
+        server.post("/progress", serde_json::json!({
+            "agent_id": AGENT, "phase": "tool-loop", "current": 2, "total": 20,
+        })).send().await.unwrap();
+        server.post("/progress", serde_json::json!({
+            "agent_id": AGENT, "phase": "tool-loop", "current": 3, "total": 20,
+            "usage": { "prompt_tokens": 0, "completion_tokens": 0 },
+        })).send().await.unwrap();
+        server.post("/status", serde_json::json!({
+            "agent_id": AGENT, "status": "completed",
+            "usage": { "prompt_tokens": 600_000, "completion_tokens": 50_000 },
+        })).send().await.unwrap();
+
+        let usage_events: Vec<serde_json::Value> = server.sink.events().into_iter()
+            .filter(|(e, _)| e == "sentinel://usage")
+            .map(|(_, payload)| payload)
+            .collect();
+        assert_eq!(usage_events.len(), 2);
+        assert_eq!(usage_events[0]["usage"]["total_tokens"], 450_000);
+        assert_eq!(usage_events[1]["agent_id"], AGENT);
+        assert_eq!(usage_events[1]["usage"]["prompt_tokens"], 1_000_000);
+        assert_eq!(usage_events[1]["usage"]["completion_tokens"], 100_000);
+        assert_eq!(usage_events[1]["usage"]["estimated_cost_usd"], 3.5);
+
+        let agents = server.state.agents.lock().await;
+        let session = usage::session_total(&agents);
+        assert_eq!(session.total_tokens, 1_106_000);
+        assert_eq!(session.estimated_cost_usd, 3.5);
+        assert_eq!(session.unpriced_agents, 0);
+    }
+
+    #[tokio::test]
+    async fn test_spoofed_agent_rejected() {
+        let server = TestServer::start(CallbackConfig::default()).await;
+        let wrong_secret = reqwest::Client::new()
+            .post(format!("{}/log", server.url))
+            .header(SECRET_HEADER, "guess")
+            .json(&serde_json::json!({ "agent_id": AGENT, "level": "info", "target": "x", "message": "spoof" }))
+            .send().await.unwrap();
+        assert_eq!(wrong_secret.status(), 401);
+
+        let extended_secret = reqwest::Client::new()
+            .post(format!("{}/log", server.url))
+            .header(SECRET_HEADER, format!("{}0", SECRET))
+            .json(&serde_json::json!({ "agent_id": AGENT, "level": "info", "target": "x", "message": "spoof" }))
+            .send().await.unwrap();
+        assert_eq!(extended_secret.status(), 401);
+
+        let unknown_agent = server.post("/log", serde_json::json!({
+            "agent_id": "sentinel-other", "level": "info", "target": "x", "message": "spoof",
+        })).send().await.unwrap();
+        assert_eq!(unknown_agent.status(), 401);
+
+        assert!(server.state.agents.lock().await.agent_logs.is_empty());
+        assert!(server.sink.events().is_empty());
+    }
+
+    #[test]
+    fn test_agent_url_follows_bound_port() {
+        assert_eq!(CallbackConfig::default().agent_url(), "http://host.docker.internal:9876");
+        let config = CallbackConfig { addr: "0.0.0.0:19876".parse().unwrap(), ..Default::default() };
+        assert_eq!(config.agent_url(), "http://host.docker.internal:19876");
+    }
+
+    #[tokio::test]
+    async fn test_first_callback_releases_key_file() {
+        let server = TestServer::start(CallbackConfig::default()).await;
+        let dir = tempfile::tempdir().unwrap();
+        let key_file = crate::keys::KeyFile::write(dir.path(), "sk-test").unwrap();
+        let path = key_file.path().to_path_buf();
+        server.state.agents.lock().await.key_files.insert(AGENT.to_string(), key_file);
+
+        let spoofed = reqwest::Client::new()
+            .post(format!("{}/heartbeat", server.url))
+            .header(SECRET_HEADER, "guess")
+            .json(&serde_json::json!({ "agent_id": AGENT }))
+            .send().await.unwrap();
+        assert_eq!(spoofed.status(), 401);
+        assert!(path.exists());
+
+        server.post("/heartbeat", serde_json::json!({ "agent_id": AGENT })).send().await.unwrap();
+        assert!(!path.exists());
+        assert!(server.state.agents.lock().await.key_files.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_ask_round_trip() {
+        let server = TestServer::start(CallbackConfig::default()).await;
+        let request = server.post("/ask", serde_json::json!({ "agent_id": AGENT, "question": "Which branch?" })).send();
+        let request = tokio::spawn(async move { request.await.unwrap().json::<AskResponse>().await.unwrap() });
+
+        let tx = loop {
+            if let Some(tx) = server.state.agents.lock().await.pending_questions.remove(AGENT) {
+                break tx;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        };
+        tx.send("main".to_string()).unwrap();
+
+        assert_eq!(request.await.unwrap().answer.as_deref(), Some("main"));
+        let events = server.sink.events();
+        assert_eq!(events[0].0, messages::MESSAGE_EVENT);
+        assert_eq!(events[0].1["kind"], "question");
+    }
+
+    #[tokio::test]
+    async fn test_scripted_run_emits_typed_messages() {
+        let server = TestServer::start(CallbackConfig::default()).await;
+        for (route, body) in [
+            ("/thought", serde_json::json!({ "agent_id": AGENT, "message": "Task received: **audit src/**" })),
+            ("/thought", serde_json::json!({ "agent_id": AGENT, "message": "src/", "tool": "list_dir" })),
+            ("/log", serde_json::json!({
+                "agent_id": AGENT, "level": "info", "target": "agent", "message": "Tool result (grep): THOUGHT: 3 matches",
+            })),
+            ("/report", serde_json::json!({ "agent_id": AGENT, "title": "Findings", "markdown": "- No issues" })),
+        ] {
+            assert_eq!(server.post(route, body).send().await.unwrap().status(), 204, "{}", route);
+        }
+        let request = server.post("/ask", serde_json::json!({ "agent_id": AGENT, "question": "Include tests?" })).send();
+        let request = tokio::spawn(async move { request.await.unwrap().json::<AskResponse>().await.unwrap() });
+        let tx = loop {
+            if let Some(tx) = server.state.agents.lock().await.pending_questions.remove(AGENT) {
+                break tx;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        };
+        tx.send("yes".to_string()).unwrap();
+        request.await.unwrap();
+        let report = "# Sentinel Agent Report\n\n".to_string() + &"Finding.\n\n".repeat(1000);
+        report::deliver(&server.state.sessions, server.sink.as_ref(), AGENT, &report);
+
+        let events = server.sink.events();
+        let chat: Vec<&serde_json::Value> = events.iter()
+            .filter(|(name, _)| name == messages::MESSAGE_EVENT)
+            .map(|(_, payload)| payload)
+            .collect();
+        let kinds: Vec<&str> = chat.iter().map(|m| m["kind"].as_str().unwrap()).collect();
+        assert_eq!(kinds, ["thought", "tool-use", "report-section", "question", "report"]);
+        assert_eq!(chat[1]["title"], "list_dir");
+        assert_eq!(chat[2]["title"], "Findings");
+        assert_eq!(chat[4]["message"], report.as_str());
+
+        // A tool result that merely contains the marker stays a log line.
+        let logs: Vec<_> = events.iter().filter(|(name, _)| name == "sentinel://log").collect();
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].1["message"], "Tool result (grep): THOUGHT: 3 matches");
+
+        let history = server.state.sessions.history(AGENT).unwrap();
+        assert!(matches!(&history[0].event, SessionEvent::Thought { .. }));
+        assert!(matches!(&history[1].event, SessionEvent::ToolUse { tool, .. } if tool == "list_dir"));
+        assert!(matches!(&history.last().unwrap().event, SessionEvent::Report { message } if *message == report));
+    }
+
+    #[tokio::test]
+    async fn test_legacy_thought_log_translated() {
+        let server = TestServer::start(CallbackConfig::default()).await;
+        let resp = server.post("/log", serde_json::json!({
+            "agent_id": AGENT, "level": "info", "target": "sentinel-test::agent", "message": "THOUGHT: Reading **main.rs**",
+        })).send().await.unwrap();
+        assert_eq!(resp.status(), 204);
+
+        let events = server.sink.events();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].0, messages::MESSAGE_EVENT);
+        assert_eq!(events[0].1["kind"], "thought");
+        assert_eq!(events[0].1["message"], "Reading **main.rs**");
+        assert!(server.state.agents.lock().await.agent_logs.is_empty());
+        assert_eq!(server.state.sessions.history(AGENT).unwrap()[0].event, SessionEvent::Thought { message: "Reading **main.rs**".into() });
+    }
+
+    #[tokio::test]
+    async fn test_hitl_round_trip_approved() {
+        let server = TestServer::start(CallbackConfig::default()).await;
+        let request = server.post("/hitl", serde_json::json!({
+            "agent_id": AGENT,
+            "action_description": "Run shell command: rm -rf build",
+            "parameters": { "command": "rm -rf build" },
+            "risk_level": "High",
+        })).send();
+        let request = tokio::spawn(async move { request.await.unwrap().json::<HitlResponse>().await.unwrap() });
+
+        let hitl = server.state.hitl.clone();
+        let manifest = wait_for_pending(&hitl).await;
+        assert_eq!(manifest.agent_id, AGENT);
+        assert!(hitl.resolve(&manifest.id, true).await);
+
+        let response = request.await.unwrap();
+        assert!(response.approved);
+        assert_eq!(response.manifest_id, manifest.id);
+        assert!(hitl.pending().await.is_empty());
+
+        let events = server.sink.events();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].0, "sentinel://hitl-pending");
+        assert_eq!(events[0].1["id"], manifest.id);
+
+        let history = server.state.sessions.history(AGENT).unwrap();
+        assert!(matches!(&history[0].event, SessionEvent::Hitl { approved: true, manifest_id, .. } if *manifest_id == manifest.id));
+    }
+
+    #[tokio::test]
+    async fn test_hitl_timeout_rejects() {
+        let config = CallbackConfig { hitl_timeout: Duration::from_millis(50), ..Default::default() };
+        let server = TestServer::start(config).await;
+        let response = server.post("/hitl", serde_json::json!({
+            "agent_id": AGENT,
+            "action_description": "Write file: src/main.rs",
+            "risk_level": "Medium",
+        })).send().await.unwrap().json::<HitlResponse>().await.unwrap();
+
+        assert!(!response.approved);
+        assert!(server.state.hitl.pending().await.is_empty());
+        assert!(!server.state.hitl.resolve(&response.manifest_id, true).await);
+    }
+
+    fn manifest(id: &str, agent_id: &str, created_at: SystemTime) -> PendingManifest {
+        PendingManifest {
+            id: id.to_string(),
+            agent_id: agent_id.to_string(),
+            action_description: format!("action {}", id),
+            parameters_json: "{}".to_string(),
+            risk_level: "Medium".to_string(),
+            created_at,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reject_all_resolves_oldest_first() {
+        let hitl = HitlPendingSenders::default();
+        let now = SystemTime::now();
+        let mut receivers = Vec::new();
+        for (id, agent, age) in [("m2", AGENT, 20), ("m1", AGENT, 30), ("other", "sentinel-other", 40), ("m3", AGENT, 10)] {
+            let (tx, rx) = oneshot::channel();
+            hitl.insert(manifest(id, agent, now - Duration::from_secs(age)), tx).await;
+            receivers.push((id, rx));
+        }
+
+        let rejected = hitl.take_where(false, |m| m.agent_id == AGENT).await;
+        assert_eq!(rejected.iter().map(|m| m.id.as_str()).collect::<Vec<_>>(), ["m1", "m2", "m3"]);
+        assert_eq!(rejected[0].age_secs(now), 30);
+        for (id, rx) in receivers {
+            if id == "other" {
+                continue;
+            }
+            assert_eq!(rx.await, Ok(false));
+        }
+        assert_eq!(hitl.pending().await.iter().map(|m| m.id.as_str()).collect::<Vec<_>>(), ["other"]);
+    }
+
+    #[tokio::test]
+    async fn test_double_resolution_is_noop() {
+        let hitl = HitlPendingSenders::default();
+        let (tx, rx) = oneshot::channel();
+        hitl.insert(manifest("m1", AGENT, SystemTime::now()), tx).await;
+
+        assert_eq!(hitl.take("m1", false).await.map(|m| m.id), Some("m1".to_string()));
+        assert!(hitl.take("m1", false).await.is_none());
+        assert!(!hitl.resolve("m1", true).await);
+        assert!(hitl.take_where(true, |_| true).await.is_empty());
+        assert_eq!(rx.await, Ok(false));
+    }
+
+    #[tokio::test]
+    async fn test_manifests_of_exited_agent_expire() {
+        let server = TestServer::start(CallbackConfig::default()).await;
+        server.state.agents.lock().await.active_agents.insert(AGENT.to_string(), AGENT.to_string());
+        let request = server.post("/hitl", serde_json::json!({
+            "agent_id": AGENT,
+            "action_description": "Run shell command: make deploy",
+            "risk_level": "High",
+        })).send();
+        let request = tokio::spawn(async move { request.await.unwrap().json::<HitlResponse>().await.unwrap() });
+        let pending = wait_for_pending(&server.state.hitl).await;
+
+        assert!(expire_orphaned_manifests(&server.state).await.is_empty());
+        server.state.agents.lock().await.exited.insert(AGENT.to_string());
+        let expired = expire_orphaned_manifests(&server.state).await;
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].id, pending.id);
+
+        let response = request.await.unwrap();
+        assert!(!response.approved);
+        assert!(server.state.hitl.pending().await.is_empty());
+
+        let events = server.sink.events();
+        let (name, payload) = events.last().unwrap();
+        assert_eq!(name, "sentinel://hitl-resolved");
+        assert_eq!(payload["manifest_id"], pending.id);
+        assert_eq!(payload["resolution"], "agent_exited");
+        assert_eq!(payload["approved"], false);
+    }
+
+    #[test]
+    fn test_stall_reported_once_until_heartbeat() {
+        let now = SystemTime::now();
+        let mut agents = AgentState::default();
+        for id in ["sentinel-quiet", "sentinel-busy", "sentinel-done"] {
+            agents.active_agents.insert(id.to_string(), id.to_string());
+        }
+        agents.last_heartbeat.insert("sentinel-quiet".into(), now - Duration::from_secs(300));
+        agents.last_heartbeat.insert("sentinel-busy".into(), now - Duration::from_secs(10));
+        agents.last_heartbeat.insert("sentinel-done".into(), now - Duration::from_secs(300));
+        agents.agent_status.insert("sentinel-done".into(), "completed".into());
+
+        let stall_after = Duration::from_secs(180);
+        assert_eq!(take_newly_stalled(&mut agents, now, stall_after), vec!["sentinel-quiet".to_string()]);
+        assert!(take_newly_stalled(&mut agents, now, stall_after).is_empty());
+
+        agents.stalled.remove("sentinel-quiet");
+        assert_eq!(take_newly_stalled(&mut agents, now, stall_after).len(), 1);
+    }
+}
@@ -1,20 +1,72 @@
 //! Tauri commands for managing SENTINEL agents and state.
  
  use serde::{Deserialize, Serialize};
- use std::collections::HashMap;
- use tokio::sync::Mutex;
- use tauri::State;
+ use std::collections::{HashMap, HashSet};
+ use std::path::PathBuf;
+ use std::sync::Arc;
+ use std::time::SystemTime;
+ use tokio::sync::{oneshot, Mutex};
+ use tauri::{Manager, State};
  use bollard::Docker;
- use bollard::container::{Config, HostConfig, CreateContainerOptions, StartContainerOptions, LogOptions};
- use bollard::models::HostConfigLogConfig;
+ use bollard::container::{Config, HostConfig, CreateContainerOptions, StartContainerOptions, LogOptions, RemoveContainerOptions};
+ use bollard::models::{DeviceRequest, HostConfigLogConfig};
  use futures_util::StreamExt;
+ use crate::diagnostics::{self, DiagnosticsTarget};
+ use crate::gpu::{self, GpuSelection};
+ use crate::callback_server::{CallbackConfig, EventSink};
+ use crate::image;
+ use crate::keys::{KeyFile, SharedKeys};
+ use crate::limits::{LimitOverrides, ResourceLimits};
+ use crate::messages::{self, AgentMessage, MessageKind};
+ use crate::mounts::{self, MountSpec};
+ use crate::network::{self, NetworkMode, NetworkRegistry, NetworkSetup};
+ use crate::notifications::{Notifier, NotifyEvent, NotifyKind};
+ use crate::ports::{self, PortAllocator};
+ use crate::report;
+ use crate::restart::{self, ExitDecision, RestartPolicy, RestartState};
+ use crate::presets::{self, LaunchArgs, SharedPresets};
+ use crate::session::{self, SessionEvent, SharedSessions};
+ use crate::stats::{self, AgentStats};
+ use crate::settings::SharedSettings;
+ use crate::usage::AgentUsage;
  
  #[derive(Default)]
  pub struct AgentState {
      pub active_agents: HashMap<String, String>, // ID -> ContainerID
      pub agent_logs: HashMap<String, Vec<LogEntry>>,
+     pub callback_secrets: HashMap<String, String>, // ID -> SENTINEL_CALLBACK_SECRET
+     pub agent_status: HashMap<String, String>,
+     pub last_heartbeat: HashMap<String, SystemTime>,
+     pub stalled: HashSet<String>, // agents already reported as stalled
+     pub pending_questions: HashMap<String, oneshot::Sender<String>>,
+     pub ports: PortAllocator,
+     pub networks: NetworkRegistry,
+     pub usage: HashMap<String, AgentUsage>,
+     pub reports_delivered: HashSet<String>,
+     /// Host side of each agent's first writable mount, probed for a report
+     /// if the agent exits without handing one off.
+     pub report_dirs: HashMap<String, PathBuf>,
+     /// Host side of each agent's primary mount, kept after exit so the
+     /// workspace can still be browsed.
+     pub workspaces: HashMap<String, PathBuf>,
+     /// Agents whose container has exited; their pending manifests expire.
+     pub exited: HashSet<String>,
+     /// Agents the user (or dashboard exit) stopped; never restarted.
+     pub stop_requested: HashSet<String>,
+     /// Launch config and retry count for agents with a restart policy.
+     pub restarts: HashMap<String, RestartState>,
+     /// Staged API key files of agents without a restart policy, kept until
+     /// the agent's first callback or its exit.
+     pub key_files: HashMap<String, KeyFile>,
+     /// Latest CPU/memory sample per running agent.
+     pub stats: HashMap<String, AgentStats>,
  }
  
+ pub type SharedAgentState = Arc<Mutex<AgentState>>;
+ 
+ /// Directory under the app data dir for staged API key files.
+ const KEYS_DIR: &str = "keys";
+ 
  #[derive(Clone, Serialize, Deserialize, Debug)]
  pub struct LogEntry {
      pub level: String,
@@ -22,76 +74,366 @@
      pub message: String,
  }
  
+ /// A HITL request from an agent container awaiting the user's decision.
+ #[derive(Clone, Serialize, Deserialize, Debug)]
+ pub struct PendingManifest {
+     pub id: String,
+     pub agent_id: String,
+     pub action_description: String,
+     pub parameters_json: String,
+     pub risk_level: String,
+     pub created_at: SystemTime,
+ }
+ 
+ impl PendingManifest {
+     pub fn age_secs(&self, now: SystemTime) -> u64 {
+         now.duration_since(self.created_at).unwrap_or_default().as_secs()
+     }
+ }
+ 
+ /// A pending manifest as the UI lists it, with how long it has waited.
+ #[derive(Clone, Serialize, Debug)]
+ pub struct PendingManifestStatus {
+     #[serde(flatten)]
+     pub manifest: PendingManifest,
+     pub age_secs: u64,
+ }
+ 
+ /// How a manifest left the queue, sent with `sentinel://hitl-resolved`.
+ #[derive(Clone, Copy, Serialize, Debug, PartialEq, Eq)]
+ #[serde(rename_all = "snake_case")]
+ pub enum HitlResolution {
+     Approved,
+     Rejected,
+     Cancelled,
+     TimedOut,
+     AgentExited,
+ }
+ 
+ /// Tell the frontend a manifest is no longer pending so it can drop it
+ /// from its queue.
+ pub fn emit_hitl_resolved(sink: &dyn EventSink, manifest: &PendingManifest, resolution: HitlResolution) {
+     sink.emit("sentinel://hitl-resolved", serde_json::json!({
+         "manifest_id": manifest.id,
+         "agent_id": manifest.agent_id,
+         "approved": resolution == HitlResolution::Approved,
+         "resolution": resolution,
+     }));
+ }
+ 
+ /// Pending manifests keyed by ID, each with the sender that unblocks the
+ /// agent's `/hitl` request.
  #[derive(Default)]
- pub struct HitlPendingSenders(pub Mutex<HashMap<String, tokio::sync::oneshot::Sender<bool>>>);
+ pub struct HitlPendingSenders(pub Mutex<HashMap<String, (PendingManifest, oneshot::Sender<bool>)>>);
+ 
+ impl HitlPendingSenders {
+     pub async fn insert(&self, manifest: PendingManifest, tx: oneshot::Sender<bool>) {
+         self.0.lock().await.insert(manifest.id.clone(), (manifest, tx));
+     }
+ 
+     pub async fn remove(&self, manifest_id: &str) -> Option<PendingManifest> {
+         self.0.lock().await.remove(manifest_id).map(|(m, _)| m)
+     }
+ 
+     /// Deliver a decision. Returns `false` if the manifest is unknown or
+     /// was already resolved.
+     pub async fn resolve(&self, manifest_id: &str, approved: bool) -> bool {
+         self.take(manifest_id, approved).await.is_some()
+     }
+ 
+     /// Remove a manifest and deliver `approved` to its agent. `None` if it
+     /// is unknown or already resolved, so resolving twice is a no-op.
+     pub async fn take(&self, manifest_id: &str, approved: bool) -> Option<PendingManifest> {
+         let (manifest, tx) = self.0.lock().await.remove(manifest_id)?;
+         // The agent may have gone away; the manifest is resolved either way.
+         let _ = tx.send(approved);
+         Some(manifest)
+     }
+ 
+     /// Resolve every manifest matching `filter`, oldest first, with the same
+     /// decision. Returns the resolved manifests in that order.
+     pub async fn take_where(&self, approved: bool, filter: impl Fn(&PendingManifest) -> bool) -> Vec<PendingManifest> {
+         let mut pending = self.0.lock().await;
+         let mut ids: Vec<(SystemTime, String)> = pending.values()
+             .filter(|(m, _)| filter(m))
+             .map(|(m, _)| (m.created_at, m.id.clone()))
+             .collect();
+         ids.sort();
+         ids.into_iter()
+             .filter_map(|(_, id)| pending.remove(&id))
+             .map(|(manifest, tx)| {
+                 let _ = tx.send(approved);
+                 manifest
+             })
+             .collect()
+     }
+ 
+     /// Pending manifests, oldest first.
+     pub async fn pending(&self) -> Vec<PendingManifest> {
+         let mut pending: Vec<PendingManifest> = self.0.lock().await.values().map(|(m, _)| m.clone()).collect();
+         pending.sort_by_key(|m| m.created_at);
+         pending
+     }
+ }
+ 
+ /// Returned by `start_agent` once the container is running.
+ #[derive(Clone, Serialize, Debug)]
+ pub struct AgentLaunch {
+     pub agent_id: String,
+     /// `None` when the network mode doesn't allow publishing the live view.
+     pub novnc_port: Option<u16>,
+ }
+ 
+ /// Everything `start_agent` accepts; scheduled runs launch through it too.
+ #[derive(Default)]
+ pub struct LaunchRequest {
+     pub task: String,
+     pub preset_id: Option<String>,
+     pub provider: Option<String>,
+     pub model: Option<String>,
+     pub api_key: Option<String>,
+     pub target_dir: Option<String>,
+     pub mounts: Option<Vec<MountSpec>>,
+     pub limits: Option<LimitOverrides>,
+     pub network_mode: Option<NetworkMode>,
+     pub autonomy: Option<String>,
+     pub max_iterations: Option<u32>,
+     pub max_tokens: Option<u32>,
+     pub gpu: Option<GpuSelection>,
+     pub preflight: Option<bool>,
+     pub restart_policy: Option<RestartPolicy>,
+ }
  
  #[tauri::command]
  pub async fn start_agent(
-     state: State<'_, Mutex<AgentState>>,
+     app: tauri::AppHandle,
      task: String,
-     provider: String,
-     model: String,
-     api_key: String,
+     preset_id: Option<String>,
+     provider: Option<String>,
+     model: Option<String>,
+     api_key: Option<String>,
      target_dir: Option<String>,
-     autonomy: String,
- ) -> Result<String, String> {
-     let docker = Docker::connect_with_local_defaults().map_err(|e| e.to_string())?;
-     let agent_id = format!("sentinel-{}", uuid::Uuid::new_v4().to_string()[..8].to_string());
- 
-     let mut env = vec![
-         format!("SENTINEL_AGENT_ID={}", agent_id),
-         format!("SENTINEL_TASK={}", task),
-         format!("SENTINEL_PROVIDER={}", provider),
-         format!("SENTINEL_MODEL={}", model),
-         format!("SENTINEL_API_KEY={}", api_key),
-         format!("SENTINEL_AUTONOMY={}", autonomy),
-         "SENTINEL_CALLBACK_URL=http://host.docker.internal:9876".to_string(),
-     ];
+     mounts: Option<Vec<MountSpec>>,
+     limits: Option<LimitOverrides>,
+     network_mode: Option<NetworkMode>,
+     autonomy: Option<String>,
+     max_iterations: Option<u32>,
+     max_tokens: Option<u32>,
+     gpu: Option<GpuSelection>,
+     preflight: Option<bool>,
+     restart_policy: Option<RestartPolicy>,
+ ) -> Result<AgentLaunch, String> {
+     launch_agent(&app, LaunchRequest {
+         task,
+         preset_id,
+         provider,
+         model,
+         api_key,
+         target_dir,
+         mounts,
+         limits,
+         network_mode,
+         autonomy,
+         max_iterations,
+         max_tokens,
+         gpu,
+         preflight,
+         restart_policy,
+     }).await
+ }
  
-     let mut host_config = HostConfig {
-         auto_remove: Some(true),
-         extra_hosts: Some(vec!["host.docker.internal:host-gateway".to_string()]),
-         ..Default::default()
+ /// Start an agent container for `request` and follow it.
+ pub async fn launch_agent(app: &tauri::AppHandle, request: LaunchRequest) -> Result<AgentLaunch, String> {
+     let LaunchRequest {
+         task,
+         preset_id,
+         provider,
+         model,
+         api_key,
+         target_dir,
+         mounts,
+         limits,
+         network_mode,
+         autonomy,
+         max_iterations,
+         max_tokens,
+         gpu,
+         preflight,
+         restart_policy,
+     } = request;
+     let state = app.state::<SharedAgentState>();
+     let sessions = app.state::<SharedSessions>();
+     let preset = match preset_id.filter(|id| !id.is_empty()) {
+         Some(id) => Some(app.state::<SharedPresets>().get(&id, &providers()).await?),
+         None => None,
      };
+     let launch = presets::resolve_launch(preset.as_ref(), LaunchArgs {
+         provider,
+         model,
+         autonomy,
+         max_iterations,
+         max_tokens,
+         mounts,
+         network_mode,
+     })?;
+     let (provider, model, autonomy) = (launch.provider, launch.model, launch.autonomy);
+     let mounts = mounts::validate_mounts(&mounts::requested_specs(launch.mounts, target_dir))?;
+     let settings = app.state::<SharedSettings>().get().await;
+     let limits = settings.resource_limits.with_overrides(&limits.unwrap_or_default());
+     limits.validate()?;
+     let network_mode = launch.network_mode.unwrap_or(settings.network_mode);
+     let restart_policy = restart_policy.unwrap_or(settings.restart_policy);
+     let api_key = app.state::<SharedKeys>().resolve(&provider, api_key)?;
  
-     if let Some(dir) = target_dir {
-         if !dir.is_empty() {
-             host_config.binds = Some(vec![format!("{}:/workspace", dir)]);
-             env.push("SENTINEL_TARGET_DIR=/workspace".to_string());
+     let device_requests = gpu.as_ref().and_then(gpu::device_requests);
+     // With a GPU, Ollama runs inside the container rather than on the host.
+     let provider = if device_requests.is_some() && provider == "ollama" {
+         gpu::IN_CONTAINER_OLLAMA.to_string()
+     } else {
+         provider
+     };
+     if preflight.unwrap_or(false) {
+         let target = DiagnosticsTarget { provider: Some(provider.clone()), model: Some(model.clone()) };
+         if let Some(refusal) = diagnostics::run_with_app(app, &target).await?.refusal() {
+             return Err(refusal);
          }
      }
+     image::ensure_with_settings(app, false).await?;
  
-     let config = Config {
-         image: Some("sentinel-agent:latest".to_string()),
-         env: Some(env),
-         host_config: Some(host_config),
-         ..Default::default()
+     let docker = Docker::connect_with_local_defaults().map_err(|e| e.to_string())?;
+     if device_requests.is_some() {
+         gpu::ensure_available(&docker).await?;
+     }
+     let agent_id = format!("sentinel-{}", uuid::Uuid::new_v4().to_string()[..8].to_string());
+     let callback_secret = uuid::Uuid::new_v4().to_string();
+     let callback = app.state::<CallbackConfig>();
+ 
+     let key_file = match &api_key {
+         Some(key) => {
+             let dir = app.path().app_data_dir().map_err(|e| e.to_string())?.join(KEYS_DIR);
+             Some(KeyFile::write(&dir, key).map_err(|e| format!("Failed to stage API key: {}", e))?)
+         }
+         None => None,
      };
  
-     docker.create_container(
-         Some(CreateContainerOptions { name: &agent_id, platform: None }),
-         config
-     ).await.map_err(|e| e.to_string())?;
+     let (agent_network, net) = network::prepare(&docker, &agent_id, network_mode, &provider, callback.addr.port()).await?;
  
-     docker.start_container(&agent_id, None::<StartContainerOptions<String>>)
-         .await.map_err(|e| e.to_string())?;
+     // Register the secret before the container can make its first callback.
+     state.lock().await.callback_secrets.insert(agent_id.clone(), callback_secret.clone());
+ 
+     let base_config = container_config(&ContainerSpec {
+         agent_id: &agent_id,
+         callback_secret: &callback_secret,
+         callback_url: &callback.agent_url(),
+         task: &task,
+         provider: &provider,
+         model: &model,
+         autonomy: &autonomy,
+         max_iterations: launch.max_iterations,
+         max_tokens: launch.max_tokens,
+         mounts: &mounts,
+         limits: &limits,
+         device_requests,
+         restart_policy,
+         net: &net,
+         key_file: key_file.as_ref(),
+     });
  
+     let launched = if net.publish_ports {
+         // Docker only binds published ports at start, so a port taken after our
+         // probe surfaces there; drop the created container and try the next one.
+         ports::with_port_retry(state.inner().as_ref(), &agent_id, |port| {
+             create_and_start(docker.clone(), agent_id.clone(), with_novnc_port(base_config.clone(), port))
+         }).await.map(|((), port)| Some(port))
+     } else {
+         create_and_start(docker.clone(), agent_id.clone(), base_config.clone()).await.map(|()| None)
+     };
+ 
+     let novnc_port = match launched {
+         Ok(port) => port,
+         Err(e) => {
+             state.lock().await.callback_secrets.remove(&agent_id);
+             network::teardown(&docker, agent_network).await;
+             return Err(e);
+         }
+     };
+ 
+     sessions.record(&agent_id, SessionEvent::Launch { task, provider: provider.clone(), model: model.clone() });
      let mut s = state.lock().await;
+     if restart_policy.enabled() {
+         let config = match novnc_port {
+             Some(port) => with_novnc_port(base_config, port),
+             None => base_config,
+         };
+         s.restarts.insert(agent_id.clone(), RestartState::new(restart_policy, config, key_file));
+     } else if let Some(file) = key_file {
+         // The agent may not have read it yet; the first callback releases it.
+         s.key_files.insert(agent_id.clone(), file);
+     }
      s.active_agents.insert(agent_id.clone(), agent_id.clone());
-     s.agent_logs.insert(agent_id.clone(), Vec::new());
+     s.usage.insert(agent_id.clone(), AgentUsage::new(&provider, &model));
+     s.networks.register(&agent_id, agent_network);
+     if let Some(primary) = mounts.first() {
+         s.workspaces.insert(agent_id.clone(), primary.host_path.clone());
+     }
+     if let Some(dir) = mounts.iter().find(|m| m.mode == mounts::MountMode::Rw) {
+         s.report_dirs.insert(agent_id.clone(), dir.host_path.clone());
+     }
+     let limits_entry = LogEntry {
+         level: "info".to_string(),
+         target: "system".to_string(),
+         message: limits.describe(),
+     };
+     tracing::info!(agent_id = %agent_id, "{}", limits_entry.message);
+     sessions.record(&agent_id, session::event_from_log(&limits_entry.level, &limits_entry.target, &limits_entry.message));
+     s.agent_logs.insert(agent_id.clone(), vec![limits_entry]);
+ 
+     drop(s);
+     follow_logs(LogFollower::new(app, docker), agent_id.clone(), "all");
+ 
+     Ok(AgentLaunch { agent_id, novnc_port })
+ }
  
-     // Spawn log follow task
-     let state_clone = state.inner().clone();
-     let agent_id_clone = agent_id.clone();
-     let docker_clone = docker.clone();
+ /// What a log follower needs to record output and act on the exit.
+ #[derive(Clone)]
+ pub(crate) struct LogFollower {
+     pub state: SharedAgentState,
+     pub sessions: SharedSessions,
+     pub sink: Arc<dyn EventSink>,
+     pub notifier: Arc<Notifier>,
+     pub docker: Docker,
+ }
  
+ impl LogFollower {
+     pub fn new(app: &tauri::AppHandle, docker: Docker) -> Self {
+         Self {
+             state: app.state::<SharedAgentState>().inner().clone(),
+             sessions: app.state::<SharedSessions>().inner().clone(),
+             sink: Arc::new(app.clone()),
+             notifier: app.state::<Arc<Notifier>>().inner().clone(),
+             docker,
+         }
+     }
+ }
+ 
+ /// Follow a container's output into `agent_logs` and the session log,
+ /// starting `tail` lines back ("all", or "0" for new output only), and poll
+ /// its resource usage meanwhile. When the stream ends the container has
+ /// exited; a crashed agent with a restart policy is re-created and followed
+ /// again. Otherwise its port and network are released and, if no report was
+ /// handed off, the host side of its mount is probed.
+ pub(crate) fn follow_logs(follower: LogFollower, agent_id: String, tail: &str) {
+     let tail = tail.to_string();
      tokio::spawn(async move {
-         let mut logs = docker_clone.logs(
-             &agent_id_clone,
+         let LogFollower { state, sessions, sink, notifier, docker } = follower.clone();
+         let poller = stats::spawn_poller(state.clone(), sink.clone(), notifier.clone(), docker.clone(), agent_id.clone());
+         let mut logs = docker.logs(
+             &agent_id,
              Some(LogOptions {
                  follow: true,
                  stdout: true,
                  stderr: true,
+                 tail,
                  ..Default::default()
              }),
          );
@@ -99,8 +441,9 @@
          while let Some(msg) = logs.next().await {
              if let Ok(m) = msg {
                  let text = String::from_utf8_lossy(&m.into_bytes()).to_string();
-                 let mut s = state_clone.lock().await;
-                 if let Some(agent_logs) = s.agent_logs.get_mut(&agent_id_clone) {
+                 sessions.record(&agent_id, session::event_from_log("info", "container", &text));
+                 let mut s = state.lock().await;
+                 if let Some(agent_logs) = s.agent_logs.get_mut(&agent_id) {
                      agent_logs.push(LogEntry {
                          level: "info".to_string(),
                          target: "container".to_string(),
@@ -109,24 +452,196 @@
                  }
              }
          }
+ 
+         poller.abort();
+         state.lock().await.stats.remove(&agent_id);
+         let exit_code = restart::collect_exit(&docker, &agent_id).await;
+         let decision = restart::on_exit(&mut *state.lock().await, &agent_id, exit_code);
+         match decision {
+             ExitDecision::Restart { attempt, max_retries, delay } => {
+                 let notice = format!(
+                     "Agent exited with code {}; restarting in {}s (attempt {} of {})",
+                     exit_code.unwrap_or_default(), delay.as_secs(), attempt, max_retries,
+                 );
+                 tracing::warn!(agent_id = %agent_id, "{}", notice);
+                 state.lock().await.agent_status.insert(agent_id.clone(), "restarting".to_string());
+                 messages::send(&sessions, sink.as_ref(), &AgentMessage::new(&agent_id, MessageKind::System, notice));
+                 tokio::time::sleep(delay).await;
+                 // A stop during the backoff wins.
+                 if !state.lock().await.stop_requested.contains(&agent_id) {
+                     match recreate(&follower, &agent_id).await {
+                         Ok(()) => {
+                             sink.emit(restart::RESTARTED_EVENT, serde_json::json!({
+                                 "agent_id": agent_id,
+                                 "attempt": attempt,
+                                 "max_retries": max_retries,
+                                 "exit_code": exit_code,
+                             }));
+                             follow_logs(follower, agent_id, "all");
+                             return;
+                         }
+                         Err(e) => {
+                             tracing::error!(agent_id = %agent_id, error = %e, "Restart failed");
+                             notifier.notify(NotifyEvent::new(NotifyKind::Failed, &agent_id, format!("Restart {} failed: {}", attempt, e)));
+                         }
+                     }
+                 }
+             }
+             ExitDecision::GiveUp { attempts } => {
+                 let detail = format!(
+                     "Agent exited with code {} after {} restart{}; giving up",
+                     exit_code.unwrap_or_default(), attempts, if attempts == 1 { "" } else { "s" },
+                 );
+                 messages::send(&sessions, sink.as_ref(), &AgentMessage::new(&agent_id, MessageKind::System, detail.clone()));
+                 notifier.notify(NotifyEvent::new(NotifyKind::Failed, &agent_id, detail));
+             }
+             ExitDecision::Done => {}
+         }
+ 
+         let (resources, probe_dir) = {
+             let mut s = state.lock().await;
+             s.ports.release(&agent_id);
+             s.exited.insert(agent_id.clone());
+             // Drops the retained key file, if any.
+             s.restarts.remove(&agent_id);
+             s.key_files.remove(&agent_id);
+             s.stop_requested.remove(&agent_id);
+             let delivered = s.reports_delivered.contains(&agent_id);
+             let dir = s.report_dirs.remove(&agent_id);
+             (s.networks.take(&agent_id), dir.filter(|_| !delivered))
+         };
+         if let Some(resources) = resources {
+             network::teardown(&docker, resources).await;
+         }
+         if let Some(text) = probe_dir.as_deref().and_then(report::probe_host) {
+             tracing::info!(agent_id = %agent_id, "No report handoff received; using report found in workspace");
+             report::deliver(&sessions, sink.as_ref(), &agent_id, &text);
+         }
      });
+ }
+ 
+ /// Re-create a crashed agent's container from its stored launch config.
+ async fn recreate(follower: &LogFollower, agent_id: &str) -> Result<(), String> {
+     let config = follower.state.lock().await.restarts.get(agent_id)
+         .map(|r| restart::resume_config(r.config.clone()))
+         .ok_or("No launch config retained")?;
+     create_and_start(follower.docker.clone(), agent_id.to_string(), config).await?;
+     let mut s = follower.state.lock().await;
+     s.agent_status.insert(agent_id.to_string(), "running".to_string());
+     s.last_heartbeat.insert(agent_id.to_string(), SystemTime::now());
+     s.stalled.remove(agent_id);
+     Ok(())
+ }
  
-     Ok(agent_id)
+ /// Everything that goes into an agent container, settled before it is created.
+ pub struct ContainerSpec<'a> {
+     pub agent_id: &'a str,
+     pub callback_secret: &'a str,
+     pub callback_url: &'a str,
+     pub task: &'a str,
+     pub provider: &'a str,
+     pub model: &'a str,
+     pub autonomy: &'a str,
+     pub max_iterations: Option<u32>,
+     pub max_tokens: Option<u32>,
+     pub mounts: &'a [mounts::Mount],
+     pub limits: &'a ResourceLimits,
+     pub device_requests: Option<Vec<DeviceRequest>>,
+     pub restart_policy: RestartPolicy,
+     pub net: &'a NetworkSetup,
+     pub key_file: Option<&'a KeyFile>,
+ }
+ 
+ /// The container config for `spec`, without the noVNC port.
+ pub fn container_config(spec: &ContainerSpec<'_>) -> Config<String> {
+     let mut env = vec![
+         format!("SENTINEL_AGENT_ID={}", spec.agent_id),
+         format!("SENTINEL_CALLBACK_SECRET={}", spec.callback_secret),
+         format!("SENTINEL_TASK={}", spec.task),
+         format!("SENTINEL_PROVIDER={}", spec.provider),
+         format!("SENTINEL_MODEL={}", spec.model),
+         format!("SENTINEL_AUTONOMY={}", spec.autonomy),
+         format!("SENTINEL_CALLBACK_URL={}", spec.callback_url),
+     ];
+     if let Some(n) = spec.max_iterations {
+         env.push(format!("SENTINEL_MAX_ITERATIONS={}", n));
+     }
+     if let Some(n) = spec.max_tokens {
+         env.push(format!("SENTINEL_MAX_TOKENS={}", n));
+     }
+     env.extend(spec.net.env.iter().cloned());
+ 
+     let mut host_config = HostConfig {
+         // Restartable containers stay around so their exit code can be read.
+         auto_remove: Some(!spec.restart_policy.enabled()),
+         extra_hosts: spec.net.host_gateway.then(|| vec!["host.docker.internal:host-gateway".to_string()]),
+         network_mode: spec.net.network_mode.clone(),
+         device_requests: spec.device_requests.clone(),
+         ..Default::default()
+     };
+     spec.limits.apply(&mut host_config);
+ 
+     let mut binds = mounts::bind_strings(spec.mounts);
+     if let Some(primary) = spec.mounts.first() {
+         env.push(format!("{}={}", mounts::MOUNTS_ENV, mounts::mounts_env(spec.mounts)));
+         // Older agent images only read the single target directory.
+         env.push(format!("SENTINEL_TARGET_DIR={}", primary.container_path));
+     }
+ 
+     // The key goes in as a file, never as env visible to `docker inspect`.
+     if let Some(file) = spec.key_file {
+         let (bind, var) = file.container_args();
+         binds.push(bind);
+         env.push(var);
+     }
+     host_config.binds = (!binds.is_empty()).then_some(binds);
+ 
+     Config {
+         image: Some(image::AGENT_IMAGE.to_string()),
+         env: Some(env),
+         host_config: Some(host_config),
+         ..Default::default()
+     }
+ }
+ 
+ fn with_novnc_port(mut config: Config<String>, port: u16) -> Config<String> {
+     config.exposed_ports = Some(HashMap::from([(format!("{}/tcp", ports::CONTAINER_NOVNC_PORT), HashMap::new())]));
+     if let Some(host_config) = config.host_config.as_mut() {
+         host_config.port_bindings = Some(ports::novnc_port_bindings(port));
+     }
+     config
+ }
+ 
+ async fn create_and_start(docker: Docker, agent_id: String, config: Config<String>) -> Result<(), String> {
+     docker.create_container(
+         Some(CreateContainerOptions { name: agent_id.as_str(), platform: None }),
+         config
+     ).await.map_err(|e| e.to_string())?;
+ 
+     if let Err(e) = docker.start_container(&agent_id, None::<StartContainerOptions<String>>).await {
+         let _ = docker.remove_container(&agent_id, Some(RemoveContainerOptions { force: true, ..Default::default() })).await;
+         return Err(e.to_string());
+     }
+     Ok(())
  }
  
  #[tauri::command]
- pub async fn get_novnc_port(agent_id: String) -> Result<u16, String> {
-     // For now, noVNC is on 6080 inside the container, we should ideally map it
-     // This is a placeholder since we currently use host networking or hardcoded ports
-     Ok(6080)
+ pub async fn get_novnc_port(
+     state: State<'_, SharedAgentState>,
+     agent_id: String,
+ ) -> Result<u16, String> {
+     state.lock().await.ports.get(&agent_id)
+         .ok_or_else(|| format!("No noVNC port allocated for {}", agent_id))
  }
  
  #[tauri::command]
  pub async fn send_agent_message(
-     state: State<'_, Mutex<AgentState>>,
+     state: State<'_, SharedAgentState>,
+     sessions: State<'_, SharedSessions>,
      agent_id: String,
      message: String,
  ) -> Result<(), String> {
+     sessions.record(&agent_id, SessionEvent::User { message: message.clone() });
      let mut s = state.lock().await;
      if let Some(logs) = s.agent_logs.get_mut(&agent_id) {
          logs.push(LogEntry {
@@ -135,6 +650,10 @@
              message: format!("USER: {}", message),
          });
      }
+     // Answer a question the agent is blocked on via `/ask`.
+     if let Some(tx) = s.pending_questions.remove(&agent_id) {
+         let _ = tx.send(message);
+     }
      Ok(())
  }
  
@@ -145,20 +664,58 @@
  
  #[tauri::command]
  pub async fn handle_hitl_approval(
+     app: tauri::AppHandle,
      manifest_id: String,
      approved: bool,
-     senders: State<'_, HitlPendingSenders>,
+     senders: State<'_, Arc<HitlPendingSenders>>,
  ) -> Result<(), String> {
-     let mut s = senders.0.lock().await;
-     if let Some(tx) = s.remove(&manifest_id) {
-         let _ = tx.send(approved);
-     }
+     let manifest = senders.take(&manifest_id, approved).await
+         .ok_or_else(|| format!("Manifest not pending: {}", manifest_id))?;
+     let resolution = if approved { HitlResolution::Approved } else { HitlResolution::Rejected };
+     emit_hitl_resolved(&app, &manifest, resolution);
      Ok(())
  }
  
+ /// Reject every manifest `agent_id` is waiting on. Returns the rejected
+ /// manifest IDs, oldest first.
+ #[tauri::command]
+ pub async fn reject_all_pending(
+     app: tauri::AppHandle,
+     agent_id: String,
+     senders: State<'_, Arc<HitlPendingSenders>>,
+ ) -> Result<Vec<String>, String> {
+     let rejected = senders.take_where(false, |m| m.agent_id == agent_id).await;
+     for manifest in &rejected {
+         emit_hitl_resolved(&app, manifest, HitlResolution::Rejected);
+     }
+     Ok(rejected.into_iter().map(|m| m.id).collect())
+ }
+ 
+ /// Withdraw a manifest; the agent sees a rejection. Returns `false` if it
+ /// was already resolved.
+ #[tauri::command]
+ pub async fn cancel_manifest(
+     app: tauri::AppHandle,
+     manifest_id: String,
+     senders: State<'_, Arc<HitlPendingSenders>>,
+ ) -> Result<bool, String> {
+     match senders.take(&manifest_id, false).await {
+         Some(manifest) => {
+             emit_hitl_resolved(&app, &manifest, HitlResolution::Cancelled);
+             Ok(true)
+         }
+         None => Ok(false),
+     }
+ }
+ 
  #[tauri::command]
  pub async fn get_providers() -> Result<Vec<ProviderInfo>, String> {
-     Ok(vec![
+     Ok(providers())
+ }
+ 
+ /// Providers and models offered by the dashboard.
+ pub fn providers() -> Vec<ProviderInfo> {
+     vec![
          ProviderInfo {
              id: "ollama".into(),
              name: "Ollama".into(),
@@ -189,10 +746,10 @@
              name: "xAI Grok".into(),
              models: vec!["grok-beta".into()],
          },
-     ])
+     ]
  }
  
- #[derive(Serialize)]
+ #[derive(Serialize, Clone, Debug)]
  pub struct ProviderInfo {
      pub id: String,
      pub name: String,
@@ -200,13 +757,18 @@
  }
  
  #[tauri::command]
- pub async fn get_pending_manifests() -> Result<Vec<String>, String> {
-     Ok(vec![])
+ pub async fn get_pending_manifests(
+     senders: State<'_, Arc<HitlPendingSenders>>,
+ ) -> Result<Vec<PendingManifestStatus>, String> {
+     let now = SystemTime::now();
+     Ok(senders.pending().await.into_iter()
+         .map(|manifest| PendingManifestStatus { age_secs: manifest.age_secs(now), manifest })
+         .collect())
  }
  
  #[tauri::command]
  pub async fn get_agent_logs(
-     state: State<'_, Mutex<AgentState>>,
+     state: State<'_, SharedAgentState>,
      agent_id: String,
  ) -> Result<Vec<LogEntry>, String> {
      let s = state.lock().await;
@@ -219,12 +781,22 @@
  
  #[tauri::command]
  pub async fn stop_agent(
-     state: State<'_, Mutex<AgentState>>,
+     state: State<'_, SharedAgentState>,
      agent_id: String,
  ) -> Result<(), String> {
      let docker = Docker::connect_with_local_defaults().map_err(|e| e.to_string())?;
+     state.lock().await.stop_requested.insert(agent_id.clone());
      let _ = docker.stop_container(&agent_id, None).await;
-     let mut s = state.lock().await;
-     s.active_agents.remove(&agent_id);
+     let resources = {
+         let mut s = state.lock().await;
+         s.active_agents.remove(&agent_id);
+         s.ports.release(&agent_id);
+         s.callback_secrets.remove(&agent_id);
+         s.pending_questions.remove(&agent_id);
+         s.networks.take(&agent_id)
+     };
+     if let Some(resources) = resources {
+         network::teardown(&docker, resources).await;
+     }
      Ok(())
  }
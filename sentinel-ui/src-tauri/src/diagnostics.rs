@@ -0,0 +1,561 @@
+//! Environment health checks.
+//!
+//! A missing Docker daemon, an unbuilt image, a stopped Ollama or a taken
+//! callback port each surface as a different, unrelated-looking launch
+//! error. `run_diagnostics` checks them all up front and returns a
+//! checklist where every problem carries a suggested fix; `start_agent`
+//! can run the same checks as a pre-flight and refuse on failures.
+//! Probes sit behind a trait so tests can script each one.
+
+use crate::callback_server::{self, CallbackConfig};
+use crate::gpu::IN_CONTAINER_OLLAMA;
+use crate::image::AGENT_IMAGE;
+use bollard::container::{Config, CreateContainerOptions, RemoveContainerOptions, StartContainerOptions, WaitContainerOptions};
+use bollard::models::HostConfig;
+use bollard::Docker;
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::Manager;
+
+/// Ollama as seen from the host.
+pub const OLLAMA_URL: &str = "http://127.0.0.1:11434";
+
+/// An agent image older than this probably predates agent fixes.
+const STALE_IMAGE_AGE: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+/// Below this, session logs and reports may fail to write.
+const MIN_FREE_BYTES: u64 = 200 * 1024 * 1024;
+const LOW_FREE_BYTES: u64 = 1024 * 1024 * 1024;
+
+const OLLAMA_TIMEOUT: Duration = Duration::from_secs(3);
+const GATEWAY_PROBE_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+    /// Not run because something it depends on failed or doesn't apply.
+    Skipped,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Check {
+    pub id: &'static str,
+    pub label: &'static str,
+    pub status: CheckStatus,
+    pub detail: String,
+    pub fix: Option<String>,
+}
+
+impl Check {
+    fn new(id: &'static str, label: &'static str, status: CheckStatus, detail: impl Into<String>) -> Self {
+        Self { id, label, status, detail: detail.into(), fix: None }
+    }
+
+    fn with_fix(mut self, fix: impl Into<String>) -> Self {
+        self.fix = Some(fix.into());
+        self
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Diagnostics {
+    /// `false` if any check failed; warnings don't count.
+    pub ok: bool,
+    pub checks: Vec<Check>,
+}
+
+impl Diagnostics {
+    fn new(checks: Vec<Check>) -> Self {
+        let ok = checks.iter().all(|c| c.status != CheckStatus::Fail);
+        Self { ok, checks }
+    }
+
+    pub fn failures(&self) -> impl Iterator<Item = &Check> {
+        self.checks.iter().filter(|c| c.status == CheckStatus::Fail)
+    }
+
+    /// The launch error listing each failed check and its fix, or `None` if
+    /// nothing failed.
+    pub fn refusal(&self) -> Option<String> {
+        if self.ok {
+            return None;
+        }
+        let lines: Vec<String> = self.failures()
+            .map(|c| match &c.fix {
+                Some(fix) => format!("- {}: {} — {}", c.label, c.detail, fix),
+                None => format!("- {}: {}", c.label, c.detail),
+            })
+            .collect();
+        Some(format!("Pre-flight checks failed:\n{}", lines.join("\n")))
+    }
+}
+
+/// What the checks run against.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct DiagnosticsTarget {
+    /// Provider the next launch will use; Ollama is only required for
+    /// `ollama`, and only optional when unset.
+    pub provider: Option<String>,
+    pub model: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ImageInfo {
+    pub age: Option<Duration>,
+}
+
+/// The environment probes; mocked in tests.
+#[async_trait::async_trait]
+pub trait Probes: Send + Sync {
+    async fn docker_version(&self) -> Result<String, String>;
+    /// `None` if the agent image isn't present.
+    async fn agent_image(&self) -> Result<Option<ImageInfo>, String>;
+    /// Resolve `host.docker.internal` from a throwaway agent container.
+    async fn host_gateway(&self) -> Result<(), String>;
+    async fn ollama_models(&self) -> Result<Vec<String>, String>;
+    /// `Ok` if the port is free or already held by our own callback server.
+    async fn callback_port(&self, addr: SocketAddr) -> Result<(), String>;
+    async fn free_disk_bytes(&self, dir: &Path) -> Result<u64, String>;
+}
+
+pub async fn run(probes: &dyn Probes, target: &DiagnosticsTarget, callback_addr: SocketAddr, data_dir: &Path) -> Diagnostics {
+    let docker = probes.docker_version().await;
+    let image = match docker {
+        Ok(_) => Some(probes.agent_image().await),
+        Err(_) => None,
+    };
+    let gateway = match image {
+        Some(Ok(Some(_))) => Some(probes.host_gateway().await),
+        _ => None,
+    };
+    let provider = target.provider.as_deref().filter(|p| !p.is_empty());
+    let ollama = match provider {
+        None | Some("ollama") => Some(probes.ollama_models().await),
+        _ => None,
+    };
+
+    Diagnostics::new(vec![
+        docker_check(&docker),
+        image_check(image.as_ref()),
+        gateway_check(gateway.as_ref(), image.as_ref()),
+        ollama_check(ollama.as_ref(), provider, target.model.as_deref()),
+        port_check(&probes.callback_port(callback_addr).await, callback_addr),
+        disk_check(&probes.free_disk_bytes(data_dir).await, data_dir),
+    ])
+}
+
+fn docker_check(docker: &Result<String, String>) -> Check {
+    const ID: &str = "docker";
+    const LABEL: &str = "Docker daemon";
+    match docker {
+        Ok(version) => Check::new(ID, LABEL, CheckStatus::Pass, format!("Docker {}", version)),
+        Err(e) => Check::new(ID, LABEL, CheckStatus::Fail, format!("Not reachable: {}", e))
+            .with_fix("Start Docker Desktop (or `sudo systemctl start docker` on Linux) and re-run the checks."),
+    }
+}
+
+fn image_check(image: Option<&Result<Option<ImageInfo>, String>>) -> Check {
+    const ID: &str = "agent_image";
+    const LABEL: &str = "Agent image";
+    match image {
+        None => Check::new(ID, LABEL, CheckStatus::Skipped, "Docker is not reachable"),
+        Some(Err(e)) => Check::new(ID, LABEL, CheckStatus::Fail, format!("Could not inspect {}: {}", AGENT_IMAGE, e))
+            .with_fix("Check that Docker is healthy, then re-run the checks."),
+        // Not fatal: the first launch builds it.
+        Some(Ok(None)) => Check::new(ID, LABEL, CheckStatus::Warn, format!("{} is not built yet", AGENT_IMAGE))
+            .with_fix("It is built on the first launch, which takes a few minutes; or build it now from Settings."),
+        Some(Ok(Some(info))) => match info.age {
+            Some(age) if age > STALE_IMAGE_AGE => Check::new(ID, LABEL, CheckStatus::Warn, format!("Built {} ago", format_age(age)))
+                .with_fix("Rebuild the agent image from Settings to pick up agent updates."),
+            Some(age) => Check::new(ID, LABEL, CheckStatus::Pass, format!("Built {} ago", format_age(age))),
+            None => Check::new(ID, LABEL, CheckStatus::Pass, format!("{} present", AGENT_IMAGE)),
+        },
+    }
+}
+
+fn gateway_check(gateway: Option<&Result<(), String>>, image: Option<&Result<Option<ImageInfo>, String>>) -> Check {
+    const ID: &str = "host_gateway";
+    const LABEL: &str = "Container → host connectivity";
+    match gateway {
+        Some(Ok(())) => Check::new(ID, LABEL, CheckStatus::Pass, "host.docker.internal resolves inside containers"),
+        Some(Err(e)) => Check::new(ID, LABEL, CheckStatus::Fail, format!("host.docker.internal did not resolve: {}", e))
+            .with_fix("Update Docker to 20.10 or newer, which supports the host-gateway mapping agents use to reach the dashboard."),
+        None => {
+            let why = match image {
+                Some(Ok(None)) => "Needs the agent image",
+                _ => "Docker is not reachable",
+            };
+            Check::new(ID, LABEL, CheckStatus::Skipped, why)
+        }
+    }
+}
+
+fn ollama_check(models: Option<&Result<Vec<String>, String>>, provider: Option<&str>, model: Option<&str>) -> Check {
+    const ID: &str = "ollama";
+    const LABEL: &str = "Ollama";
+    // Only an explicit Ollama launch needs it; otherwise it's informational.
+    let missing = if provider == Some("ollama") { CheckStatus::Fail } else { CheckStatus::Warn };
+    match models {
+        None => {
+            let detail = match provider {
+                Some(IN_CONTAINER_OLLAMA) => "Runs inside the agent container".to_string(),
+                Some(p) => format!("Not used by provider {}", p),
+                None => "Not used".to_string(),
+            };
+            Check::new(ID, LABEL, CheckStatus::Skipped, detail)
+        }
+        Some(Err(e)) => Check::new(ID, LABEL, missing, format!("Not reachable at {}: {}", OLLAMA_URL, e))
+            .with_fix("Start Ollama (`ollama serve`, or open the Ollama app) to use local models."),
+        Some(Ok(models)) => match model.filter(|m| !m.is_empty()) {
+            Some(wanted) if !model_available(models, wanted) => {
+                Check::new(ID, LABEL, missing, format!("Model {} is not installed", wanted))
+                    .with_fix(format!("Run `ollama pull {}`.", wanted))
+            }
+            Some(wanted) => Check::new(ID, LABEL, CheckStatus::Pass, format!("Serving {}", wanted)),
+            None => Check::new(ID, LABEL, CheckStatus::Pass, format!("{} model(s) installed", models.len())),
+        },
+    }
+}
+
+fn port_check(port: &Result<(), String>, addr: SocketAddr) -> Check {
+    const ID: &str = "callback_port";
+    const LABEL: &str = "Callback port";
+    match port {
+        Ok(()) => Check::new(ID, LABEL, CheckStatus::Pass, format!("{} available", addr)),
+        Err(e) => Check::new(ID, LABEL, CheckStatus::Fail, format!("Cannot bind {}: {}", addr, e))
+            .with_fix(format!(
+                "Quit the program holding port {} (often a second Sentinel dashboard), then restart the dashboard.",
+                addr.port()
+            )),
+    }
+}
+
+fn disk_check(free: &Result<u64, String>, dir: &Path) -> Check {
+    const ID: &str = "disk_space";
+    const LABEL: &str = "Disk space";
+    let fix = format!("Free up space on the drive holding {}.", dir.display());
+    match free {
+        Ok(bytes) if *bytes < MIN_FREE_BYTES => Check::new(ID, LABEL, CheckStatus::Fail, format!("Only {} free", format_bytes(*bytes))).with_fix(fix),
+        Ok(bytes) if *bytes < LOW_FREE_BYTES => Check::new(ID, LABEL, CheckStatus::Warn, format!("{} free", format_bytes(*bytes))).with_fix(fix),
+        Ok(bytes) => Check::new(ID, LABEL, CheckStatus::Pass, format!("{} free", format_bytes(*bytes))),
+        Err(e) => Check::new(ID, LABEL, CheckStatus::Warn, format!("Could not determine free space: {}", e)),
+    }
+}
+
+/// Whether Ollama has `wanted`; an untagged name means `:latest`.
+fn model_available(models: &[String], wanted: &str) -> bool {
+    let wanted = if wanted.contains(':') { wanted.to_string() } else { format!("{}:latest", wanted) };
+    models.iter().any(|m| *m == wanted)
+}
+
+fn format_age(age: Duration) -> String {
+    let secs = age.as_secs();
+    match secs {
+        s if s < 3600 => format!("{} min", s / 60),
+        s if s < 2 * 86_400 => format!("{} h", s / 3600),
+        s => format!("{} days", s / 86_400),
+    }
+}
+
+fn format_bytes(bytes: u64) -> String {
+    const GIB: f64 = 1024.0 * 1024.0 * 1024.0;
+    if bytes as f64 >= GIB {
+        format!("{:.1} GiB", bytes as f64 / GIB)
+    } else {
+        format!("{} MiB", bytes / (1024 * 1024))
+    }
+}
+
+/// Seconds since the epoch for a Docker RFC 3339 timestamp such as
+/// `2024-05-01T12:34:56.123456789Z`. Offsets other than `Z` are rejected.
+fn parse_docker_time(ts: &str) -> Option<SystemTime> {
+    let ts = ts.strip_suffix('Z')?;
+    let (date, time) = ts.split_once('T')?;
+    let mut date = date.splitn(3, '-').map(|p| p.parse::<i64>().ok());
+    let (y, m, d) = (date.next()??, date.next()??, date.next()??);
+    let time = time.split('.').next()?;
+    let mut time = time.splitn(3, ':').map(|p| p.parse::<u64>().ok());
+    let (hh, mm, ss) = (time.next()??, time.next()??, time.next()??);
+    // Days from civil date (Howard Hinnant's algorithm).
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * (m + if m > 2 { -3 } else { 9 }) + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = u64::try_from(era * 146_097 + doe - 719_468).ok()?;
+    Some(UNIX_EPOCH + Duration::from_secs(days * 86_400 + hh * 3600 + mm * 60 + ss))
+}
+
+// ── System probes ───────────────────────────────────────────────────────────
+
+pub struct SystemProbes {
+    docker: Result<Docker, String>,
+    http: reqwest::Client,
+}
+
+impl SystemProbes {
+    pub fn new() -> Self {
+        Self {
+            docker: Docker::connect_with_local_defaults().map_err(|e| e.to_string()),
+            http: reqwest::Client::builder().timeout(OLLAMA_TIMEOUT).build().unwrap_or_default(),
+        }
+    }
+
+    fn docker(&self) -> Result<&Docker, String> {
+        self.docker.as_ref().map_err(Clone::clone)
+    }
+}
+
+#[derive(Deserialize)]
+struct OllamaTags {
+    models: Vec<OllamaModel>,
+}
+
+#[derive(Deserialize)]
+struct OllamaModel {
+    name: String,
+}
+
+#[async_trait::async_trait]
+impl Probes for SystemProbes {
+    async fn docker_version(&self) -> Result<String, String> {
+        let version = self.docker()?.version().await.map_err(|e| e.to_string())?;
+        Ok(version.version.unwrap_or_else(|| "(unknown version)".to_string()))
+    }
+
+    async fn agent_image(&self) -> Result<Option<ImageInfo>, String> {
+        match self.docker()?.inspect_image(AGENT_IMAGE).await {
+            Ok(image) => {
+                let age = image.created.as_deref()
+                    .and_then(parse_docker_time)
+                    .and_then(|created| SystemTime::now().duration_since(created).ok());
+                Ok(Some(ImageInfo { age }))
+            }
+            Err(bollard::errors::Error::DockerResponseServerError { status_code: 404, .. }) => Ok(None),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    async fn host_gateway(&self) -> Result<(), String> {
+        let docker = self.docker()?;
+        let config = Config {
+            image: Some(AGENT_IMAGE.to_string()),
+            entrypoint: Some(vec!["getent".to_string()]),
+            cmd: Some(vec!["hosts".to_string(), "host.docker.internal".to_string()]),
+            host_config: Some(HostConfig {
+                extra_hosts: Some(vec!["host.docker.internal:host-gateway".to_string()]),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        // Unnamed, so it is never mistaken for an agent.
+        let id = docker.create_container(None::<CreateContainerOptions<String>>, config)
+            .await.map_err(|e| e.to_string())?.id;
+        let result = tokio::time::timeout(GATEWAY_PROBE_TIMEOUT, async {
+            docker.start_container(&id, None::<StartContainerOptions<String>>).await.map_err(|e| e.to_string())?;
+            match docker.wait_container(&id, None::<WaitContainerOptions<String>>).next().await {
+                Some(Ok(exit)) if exit.status_code == 0 => Ok(()),
+                Some(Ok(exit)) => Err(format!("lookup exited with code {}", exit.status_code)),
+                Some(Err(bollard::errors::Error::DockerContainerWaitError { code, .. })) => Err(format!("lookup exited with code {}", code)),
+                Some(Err(e)) => Err(e.to_string()),
+                None => Err("probe container vanished".to_string()),
+            }
+        }).await.unwrap_or_else(|_| Err("timed out".to_string()));
+        let _ = docker.remove_container(&id, Some(RemoveContainerOptions { force: true, ..Default::default() })).await;
+        result
+    }
+
+    async fn ollama_models(&self) -> Result<Vec<String>, String> {
+        let tags: OllamaTags = self.http.get(format!("{}/api/tags", OLLAMA_URL))
+            .send().await.map_err(|e| e.to_string())?
+            .error_for_status().map_err(|e| e.to_string())?
+            .json().await.map_err(|e| e.to_string())?;
+        Ok(tags.models.into_iter().map(|m| m.name).collect())
+    }
+
+    async fn callback_port(&self, addr: SocketAddr) -> Result<(), String> {
+        if callback_server::is_listening() {
+            return Ok(());
+        }
+        tokio::net::TcpListener::bind(addr).await.map(drop).map_err(|e| e.to_string())
+    }
+
+    async fn free_disk_bytes(&self, dir: &Path) -> Result<u64, String> {
+        // The data dir may not exist before the first launch.
+        let existing = dir.ancestors().find(|p| p.exists()).unwrap_or(dir);
+        fs2::available_space(existing).map_err(|e| e.to_string())
+    }
+}
+
+/// Run every check with the production probes.
+pub async fn run_with_app(app: &tauri::AppHandle, target: &DiagnosticsTarget) -> Result<Diagnostics, String> {
+    let data_dir: PathBuf = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    Ok(run(&SystemProbes::new(), target, app.state::<CallbackConfig>().addr, &data_dir).await)
+}
+
+#[tauri::command]
+pub async fn run_diagnostics(app: tauri::AppHandle, target: Option<DiagnosticsTarget>) -> Result<Diagnostics, String> {
+    run_with_app(&app, &target.unwrap_or_default()).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: Duration = Duration::from_secs(86_400);
+
+    struct MockProbes {
+        docker: Result<String, String>,
+        image: Result<Option<ImageInfo>, String>,
+        gateway: Result<(), String>,
+        ollama: Result<Vec<String>, String>,
+        port: Result<(), String>,
+        disk: Result<u64, String>,
+        calls: std::sync::Mutex<Vec<&'static str>>,
+    }
+
+    impl Default for MockProbes {
+        fn default() -> Self {
+            Self {
+                docker: Ok("27.3.1".into()),
+                image: Ok(Some(ImageInfo { age: Some(2 * DAY) })),
+                gateway: Ok(()),
+                ollama: Ok(vec!["llama3.1:8b".into(), "qwen2.5:latest".into()]),
+                port: Ok(()),
+                disk: Ok(50 * LOW_FREE_BYTES),
+                calls: Default::default(),
+            }
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl Probes for MockProbes {
+        async fn docker_version(&self) -> Result<String, String> {
+            self.calls.lock().unwrap().push("docker");
+            self.docker.clone()
+        }
+        async fn agent_image(&self) -> Result<Option<ImageInfo>, String> {
+            self.calls.lock().unwrap().push("image");
+            self.image.clone()
+        }
+        async fn host_gateway(&self) -> Result<(), String> {
+            self.calls.lock().unwrap().push("gateway");
+            self.gateway.clone()
+        }
+        async fn ollama_models(&self) -> Result<Vec<String>, String> {
+            self.calls.lock().unwrap().push("ollama");
+            self.ollama.clone()
+        }
+        async fn callback_port(&self, _addr: SocketAddr) -> Result<(), String> {
+            self.port.clone()
+        }
+        async fn free_disk_bytes(&self, _dir: &Path) -> Result<u64, String> {
+            self.disk.clone()
+        }
+    }
+
+    fn addr() -> SocketAddr {
+        SocketAddr::from(([127, 0, 0, 1], 9876))
+    }
+
+    fn target(provider: &str, model: &str) -> DiagnosticsTarget {
+        DiagnosticsTarget { provider: Some(provider.into()), model: Some(model.into()) }
+    }
+
+    async fn run_mock(probes: &MockProbes, target: &DiagnosticsTarget) -> Diagnostics {
+        run(probes, target, addr(), Path::new("/data/sentinel")).await
+    }
+
+    fn status(diag: &Diagnostics, id: &str) -> CheckStatus {
+        diag.checks.iter().find(|c| c.id == id).unwrap().status
+    }
+
+    #[tokio::test]
+    async fn test_all_healthy() {
+        let diag = run_mock(&MockProbes::default(), &target("ollama", "llama3.1:8b")).await;
+        assert!(diag.ok);
+        assert!(diag.checks.iter().all(|c| c.status == CheckStatus::Pass), "{:?}", diag.checks);
+        assert_eq!(diag.refusal(), None);
+        assert_eq!(diag.checks[0].detail, "Docker 27.3.1");
+    }
+
+    #[tokio::test]
+    async fn test_docker_down_skips_dependent_checks() {
+        let probes = MockProbes { docker: Err("connection refused".into()), ..Default::default() };
+        let diag = run_mock(&probes, &DiagnosticsTarget::default()).await;
+        assert!(!diag.ok);
+        assert_eq!(status(&diag, "docker"), CheckStatus::Fail);
+        assert_eq!(status(&diag, "agent_image"), CheckStatus::Skipped);
+        assert_eq!(status(&diag, "host_gateway"), CheckStatus::Skipped);
+        assert_eq!(*probes.calls.lock().unwrap(), ["docker", "ollama"]);
+
+        let refusal = diag.refusal().unwrap();
+        assert!(refusal.starts_with("Pre-flight checks failed:\n- Docker daemon: Not reachable: connection refused — Start Docker"));
+        assert_eq!(refusal.lines().count(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_missing_image_warns_and_skips_gateway() {
+        let probes = MockProbes { image: Ok(None), ..Default::default() };
+        let diag = run_mock(&probes, &DiagnosticsTarget::default()).await;
+        assert!(diag.ok);
+        assert_eq!(status(&diag, "agent_image"), CheckStatus::Warn);
+        let gateway = diag.checks.iter().find(|c| c.id == "host_gateway").unwrap();
+        assert_eq!((gateway.status, gateway.detail.as_str()), (CheckStatus::Skipped, "Needs the agent image"));
+
+        let stale = MockProbes { image: Ok(Some(ImageInfo { age: Some(45 * DAY) })), ..Default::default() };
+        let diag = run_mock(&stale, &DiagnosticsTarget::default()).await;
+        let image = &diag.checks[1];
+        assert_eq!((image.status, image.detail.as_str()), (CheckStatus::Warn, "Built 45 days ago"));
+        assert!(image.fix.as_deref().unwrap().contains("Rebuild"));
+    }
+
+    #[tokio::test]
+    async fn test_ollama_fix_depends_on_provider() {
+        let down = MockProbes { ollama: Err("connection refused".into()), ..Default::default() };
+        assert_eq!(status(&run_mock(&down, &target("ollama", "llama3.1:8b")).await, "ollama"), CheckStatus::Fail);
+        assert_eq!(status(&run_mock(&down, &DiagnosticsTarget::default()).await, "ollama"), CheckStatus::Warn);
+
+        let probes = MockProbes::default();
+        let diag = run_mock(&probes, &target("anthropic", "claude-3-5-sonnet-latest")).await;
+        assert_eq!(status(&diag, "ollama"), CheckStatus::Skipped);
+        assert!(!probes.calls.lock().unwrap().contains(&"ollama"));
+
+        let diag = run_mock(&MockProbes::default(), &target("ollama", "mistral")).await;
+        let ollama = diag.checks.iter().find(|c| c.id == "ollama").unwrap();
+        assert_eq!(ollama.status, CheckStatus::Fail);
+        assert_eq!(ollama.fix.as_deref(), Some("Run `ollama pull mistral`."));
+        // Untagged names mean `:latest`.
+        assert!(run_mock(&MockProbes::default(), &target("ollama", "qwen2.5")).await.ok);
+    }
+
+    #[tokio::test]
+    async fn test_port_and_disk() {
+        let probes = MockProbes { port: Err("address in use".into()), disk: Ok(100 * 1024 * 1024), ..Default::default() };
+        let diag = run_mock(&probes, &DiagnosticsTarget::default()).await;
+        let failed: Vec<&str> = diag.failures().map(|c| c.id).collect();
+        assert_eq!(failed, ["callback_port", "disk_space"]);
+        assert!(diag.checks[4].fix.as_deref().unwrap().contains("port 9876"));
+        assert!(diag.checks[5].fix.as_deref().unwrap().contains("/data/sentinel"));
+
+        let low = MockProbes { disk: Ok(512 * 1024 * 1024), ..Default::default() };
+        assert_eq!(status(&run_mock(&low, &DiagnosticsTarget::default()).await, "disk_space"), CheckStatus::Warn);
+    }
+
+    #[test]
+    fn test_parse_docker_time() {
+        assert_eq!(parse_docker_time("1970-01-02T00:00:01Z"), Some(UNIX_EPOCH + Duration::from_secs(86_401)));
+        assert_eq!(
+            parse_docker_time("2024-05-01T12:34:56.123456789Z"),
+            Some(UNIX_EPOCH + Duration::from_secs(1_714_566_896))
+        );
+        assert_eq!(parse_docker_time("2024-05-01T12:34:56+02:00"), None);
+        assert_eq!(parse_docker_time("not a time"), None);
+    }
+}
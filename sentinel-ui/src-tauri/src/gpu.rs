@@ -0,0 +1,141 @@
+//! GPU passthrough for agents that run their model inside the container.
+//!
+//! The equivalent of `docker run --gpus all` (or `--gpus '"device=0,1"'`):
+//! a `DeviceRequest` for the NVIDIA driver with the `gpu` capability. It only
+//! works when the daemon has the NVIDIA container runtime, so launches check
+//! `docker info` first rather than failing inside Docker.
+
+use bollard::models::{DeviceRequest, SystemInfo};
+use bollard::Docker;
+use serde::{Deserialize, Serialize};
+
+/// Provider id the agent uses for an Ollama server inside its own container.
+pub const IN_CONTAINER_OLLAMA: &str = "ollama-local";
+
+const NVIDIA_RUNTIME: &str = "nvidia";
+
+/// The `gpu` launch option: `true` for every GPU, or specific device ids
+/// (indices or UUIDs as `nvidia-smi -L` prints them).
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(untagged)]
+pub enum GpuSelection {
+    All(bool),
+    Devices(Vec<String>),
+}
+
+impl GpuSelection {
+    pub fn is_requested(&self) -> bool {
+        match self {
+            GpuSelection::All(all) => *all,
+            GpuSelection::Devices(ids) => !ids.is_empty(),
+        }
+    }
+}
+
+/// `HostConfig.device_requests` for `selection`, `None` if no GPU is wanted.
+pub fn device_requests(selection: &GpuSelection) -> Option<Vec<DeviceRequest>> {
+    let (count, device_ids) = match selection {
+        GpuSelection::All(false) => return None,
+        GpuSelection::Devices(ids) if ids.is_empty() => return None,
+        GpuSelection::All(true) => (Some(-1), None),
+        GpuSelection::Devices(ids) => (None, Some(ids.clone())),
+    };
+    Some(vec![DeviceRequest {
+        driver: Some(NVIDIA_RUNTIME.to_string()),
+        count,
+        device_ids,
+        capabilities: Some(vec![vec!["gpu".to_string()]]),
+        options: None,
+    }])
+}
+
+/// What the Docker host can offer agents, for the Settings UI.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct HostCapabilities {
+    pub gpu: bool,
+    pub runtimes: Vec<String>,
+    pub default_runtime: Option<String>,
+}
+
+impl HostCapabilities {
+    pub fn from_info(info: &SystemInfo) -> Self {
+        let mut runtimes: Vec<String> = info.runtimes.as_ref()
+            .map(|r| r.keys().cloned().collect())
+            .unwrap_or_default();
+        runtimes.sort();
+        let default_runtime = info.default_runtime.clone();
+        let gpu = runtimes.iter().any(|r| r == NVIDIA_RUNTIME) || default_runtime.as_deref() == Some(NVIDIA_RUNTIME);
+        Self { gpu, runtimes, default_runtime }
+    }
+}
+
+pub async fn detect(docker: &Docker) -> Result<HostCapabilities, String> {
+    let info = docker.info().await.map_err(|e| format!("Failed to query Docker: {}", e))?;
+    Ok(HostCapabilities::from_info(&info))
+}
+
+/// Fail early, with a fix, if the daemon can't satisfy a GPU request.
+pub async fn ensure_available(docker: &Docker) -> Result<(), String> {
+    if detect(docker).await?.gpu {
+        Ok(())
+    } else {
+        Err("GPU passthrough requested, but the Docker daemon reports no NVIDIA runtime. \
+             Install the NVIDIA Container Toolkit and restart Docker.".to_string())
+    }
+}
+
+#[tauri::command]
+pub async fn get_host_capabilities() -> Result<HostCapabilities, String> {
+    let docker = Docker::connect_with_local_defaults().map_err(|e| e.to_string())?;
+    detect(&docker).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_device_requests() {
+        let all = device_requests(&GpuSelection::All(true)).unwrap();
+        assert_eq!(all, vec![DeviceRequest {
+            driver: Some("nvidia".into()),
+            count: Some(-1),
+            device_ids: None,
+            capabilities: Some(vec![vec!["gpu".into()]]),
+            options: None,
+        }]);
+
+        let some = device_requests(&GpuSelection::Devices(vec!["0".into(), "GPU-3f2a".into()])).unwrap();
+        assert_eq!(some[0].count, None);
+        assert_eq!(some[0].device_ids, Some(vec!["0".to_string(), "GPU-3f2a".to_string()]));
+
+        assert_eq!(device_requests(&GpuSelection::All(false)), None);
+        assert_eq!(device_requests(&GpuSelection::Devices(vec![])), None);
+    }
+
+    #[test]
+    fn test_selection_accepts_bool_or_devices() {
+        assert_eq!(serde_json::from_str::<GpuSelection>("true").unwrap(), GpuSelection::All(true));
+        assert_eq!(serde_json::from_str::<GpuSelection>(r#"["1"]"#).unwrap(), GpuSelection::Devices(vec!["1".into()]));
+    }
+
+    #[test]
+    fn test_capability_detection() {
+        let with_nvidia: SystemInfo = serde_json::from_value(serde_json::json!({
+            "DefaultRuntime": "runc",
+            "Runtimes": { "runc": { "path": "runc" }, "nvidia": { "path": "nvidia-container-runtime" } },
+        })).unwrap();
+        assert_eq!(HostCapabilities::from_info(&with_nvidia), HostCapabilities {
+            gpu: true,
+            runtimes: vec!["nvidia".into(), "runc".into()],
+            default_runtime: Some("runc".into()),
+        });
+
+        let plain: SystemInfo = serde_json::from_value(serde_json::json!({
+            "DefaultRuntime": "runc",
+            "Runtimes": { "runc": { "path": "runc" } },
+        })).unwrap();
+        assert!(!HostCapabilities::from_info(&plain).gpu);
+        assert!(!HostCapabilities::from_info(&SystemInfo::default()).gpu);
+    }
+}
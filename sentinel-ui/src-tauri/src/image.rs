@@ -0,0 +1,347 @@
+//! Agent image provisioning.
+//!
+//! `start_agent` needs `sentinel-agent:latest` locally. If it is missing the
+//! image is pulled from a configured registry reference or built from the
+//! Dockerfile context bundled with the app, streaming progress lines as
+//! `sentinel://image-progress` events. A successful check is cached for the
+//! rest of the session.
+
+use crate::callback_server::EventSink;
+use crate::settings::SharedSettings;
+use bollard::image::{BuildImageOptions, CreateImageOptions, TagImageOptions};
+use bollard::Docker;
+use futures_util::StreamExt;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tauri::{AppHandle, Manager};
+use tokio::sync::Mutex;
+use tracing::info;
+
+pub const AGENT_IMAGE: &str = "sentinel-agent:latest";
+
+/// Log lines kept for the error message when a build or pull fails.
+const ERROR_TAIL_LINES: usize = 20;
+
+/// Directory under the app's resources holding the agent build context
+/// (`sentinel-agent/`, copied there by `bundle.resources`).
+pub const BUNDLED_CONTEXT_DIR: &str = "agent-image";
+
+/// Where a missing image comes from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImageSource {
+    Pull(String),
+    Build(PathBuf),
+}
+
+/// What `ensure_image` ended up doing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImageOutcome {
+    Present,
+    Pulled,
+    Built,
+}
+
+/// The Docker operations image provisioning needs; mocked in tests.
+#[async_trait::async_trait]
+pub trait ImageBackend: Send + Sync {
+    async fn image_exists(&self, image: &str) -> Result<bool, String>;
+    async fn pull(&self, reference: &str, progress: &mut (dyn FnMut(String) + Send)) -> Result<(), String>;
+    async fn build(&self, context: &Path, tag: &str, progress: &mut (dyn FnMut(String) + Send)) -> Result<(), String>;
+}
+
+/// Caches a successful check and serializes concurrent provisioning.
+#[derive(Default)]
+pub struct ImageManager {
+    ready: Mutex<bool>,
+}
+
+impl ImageManager {
+    /// Make sure `AGENT_IMAGE` exists, pulling or building it if needed.
+    pub async fn ensure_image(
+        &self,
+        backend: &dyn ImageBackend,
+        source: &ImageSource,
+        force_rebuild: bool,
+        sink: &dyn EventSink,
+    ) -> Result<ImageOutcome, String> {
+        let mut ready = self.ready.lock().await;
+        if *ready && !force_rebuild {
+            return Ok(ImageOutcome::Present);
+        }
+        if !force_rebuild && backend.image_exists(AGENT_IMAGE).await? {
+            *ready = true;
+            return Ok(ImageOutcome::Present);
+        }
+
+        let mut tail: VecDeque<String> = VecDeque::with_capacity(ERROR_TAIL_LINES);
+        let mut progress = |line: String| {
+            sink.emit("sentinel://image-progress", serde_json::json!({ "line": line }));
+            if tail.len() == ERROR_TAIL_LINES {
+                tail.pop_front();
+            }
+            tail.push_back(line);
+        };
+
+        let (result, outcome, verb) = match source {
+            ImageSource::Pull(reference) => {
+                info!(reference = %reference, "Pulling agent image");
+                (backend.pull(reference, &mut progress).await, ImageOutcome::Pulled, "pull")
+            }
+            ImageSource::Build(context) => {
+                info!(context = %context.display(), "Building agent image");
+                (backend.build(context, AGENT_IMAGE, &mut progress).await, ImageOutcome::Built, "build")
+            }
+        };
+
+        if let Err(e) = result {
+            let lines: Vec<String> = tail.into_iter().collect();
+            return Err(format!(
+                "Agent image {} failed: {}\n--- last {} log lines ---\n{}",
+                verb, e, lines.len(), lines.join("\n")
+            ));
+        }
+        *ready = true;
+        Ok(outcome)
+    }
+}
+
+/// Resolve the image source from settings: a registry reference wins,
+/// otherwise build from the configured or bundled Dockerfile context.
+pub fn resolve_source(registry: Option<&str>, build_context: Option<&Path>, bundled: &Path) -> ImageSource {
+    match (registry.filter(|r| !r.trim().is_empty()), build_context) {
+        (Some(reference), _) => ImageSource::Pull(reference.trim().to_string()),
+        (None, Some(context)) => ImageSource::Build(context.to_path_buf()),
+        (None, None) => ImageSource::Build(bundled.to_path_buf()),
+    }
+}
+
+// ── Docker Backend ──────────────────────────────────────────────────────────
+
+pub struct DockerImageBackend(pub Docker);
+
+#[async_trait::async_trait]
+impl ImageBackend for DockerImageBackend {
+    async fn image_exists(&self, image: &str) -> Result<bool, String> {
+        match self.0.inspect_image(image).await {
+            Ok(_) => Ok(true),
+            Err(bollard::errors::Error::DockerResponseServerError { status_code: 404, .. }) => Ok(false),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    async fn pull(&self, reference: &str, progress: &mut (dyn FnMut(String) + Send)) -> Result<(), String> {
+        let options = CreateImageOptions { from_image: reference, ..Default::default() };
+        let mut stream = self.0.create_image(Some(options), None, None);
+        while let Some(item) = stream.next().await {
+            let info = item.map_err(|e| e.to_string())?;
+            if let Some(err) = info.error {
+                return Err(err);
+            }
+            let line = [info.id, info.status, info.progress].into_iter().flatten().collect::<Vec<_>>().join(" ");
+            if !line.is_empty() {
+                progress(line);
+            }
+        }
+        let (repo, tag) = AGENT_IMAGE.split_once(':').unwrap_or((AGENT_IMAGE, "latest"));
+        self.0.tag_image(reference, Some(TagImageOptions { repo, tag }))
+            .await.map_err(|e| e.to_string())
+    }
+
+    async fn build(&self, context: &Path, tag: &str, progress: &mut (dyn FnMut(String) + Send)) -> Result<(), String> {
+        let tarball = tar_context(context).map_err(|e| format!("Cannot package {}: {}", context.display(), e))?;
+        let options = BuildImageOptions { dockerfile: "Dockerfile", t: tag, rm: true, ..Default::default() };
+        let mut stream = self.0.build_image(options, None, Some(tarball.into()));
+        while let Some(item) = stream.next().await {
+            let info = item.map_err(|e| e.to_string())?;
+            if let Some(err) = info.error {
+                return Err(err);
+            }
+            if let Some(line) = info.stream.or(info.status) {
+                let line = line.trim_end().to_string();
+                if !line.is_empty() {
+                    progress(line);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Package a build context directory as an uncompressed tarball.
+fn tar_context(dir: &Path) -> std::io::Result<Vec<u8>> {
+    let mut builder = tar::Builder::new(Vec::new());
+    builder.append_dir_all(".", dir)?;
+    builder.into_inner()
+}
+
+/// Resolve the source from settings and ensure the image with the
+/// production Docker backend.
+pub async fn ensure_with_settings(app: &AppHandle, force_rebuild: bool) -> Result<ImageOutcome, String> {
+    let settings = app.state::<SharedSettings>().get().await;
+    let bundled = app.path().resource_dir().map_err(|e| e.to_string())?.join(BUNDLED_CONTEXT_DIR);
+    let source = resolve_source(
+        settings.agent_image_registry.as_deref(),
+        settings.agent_build_context.as_deref(),
+        &bundled,
+    );
+    let docker = Docker::connect_with_local_defaults().map_err(|e| e.to_string())?;
+    let manager = app.state::<Arc<ImageManager>>();
+    manager.ensure_image(&DockerImageBackend(docker), &source, force_rebuild, app).await
+}
+
+#[tauri::command]
+pub async fn ensure_agent_image(
+    app: AppHandle,
+    force_rebuild: bool,
+) -> Result<ImageOutcome, String> {
+    ensure_with_settings(&app, force_rebuild).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct NullSink(std::sync::Mutex<Vec<String>>);
+
+    impl EventSink for NullSink {
+        fn emit(&self, event: &str, payload: serde_json::Value) {
+            assert_eq!(event, "sentinel://image-progress");
+            self.0.lock().unwrap().push(payload["line"].as_str().unwrap().to_string());
+        }
+    }
+
+    #[derive(Default)]
+    struct MockBackend {
+        exists: bool,
+        fail: bool,
+        calls: std::sync::Mutex<Vec<String>>,
+    }
+
+    #[async_trait::async_trait]
+    impl ImageBackend for MockBackend {
+        async fn image_exists(&self, _image: &str) -> Result<bool, String> {
+            self.calls.lock().unwrap().push("inspect".into());
+            Ok(self.exists)
+        }
+
+        async fn pull(&self, reference: &str, progress: &mut (dyn FnMut(String) + Send)) -> Result<(), String> {
+            self.calls.lock().unwrap().push(format!("pull {}", reference));
+            progress("Downloading".into());
+            if self.fail { Err("manifest unknown".into()) } else { Ok(()) }
+        }
+
+        async fn build(&self, context: &Path, _tag: &str, progress: &mut (dyn FnMut(String) + Send)) -> Result<(), String> {
+            self.calls.lock().unwrap().push(format!("build {}", context.display()));
+            for i in 0..30 {
+                progress(format!("Step {}", i));
+            }
+            if self.fail { Err("exit code 1".into()) } else { Ok(()) }
+        }
+    }
+
+    fn calls(backend: &MockBackend) -> Vec<String> {
+        backend.calls.lock().unwrap().clone()
+    }
+
+    #[tokio::test]
+    async fn test_present_image_is_used_and_cached() {
+        let backend = MockBackend { exists: true, ..Default::default() };
+        let manager = ImageManager::default();
+        let source = ImageSource::Build("ctx".into());
+        let sink = NullSink::default();
+        assert_eq!(manager.ensure_image(&backend, &source, false, &sink).await, Ok(ImageOutcome::Present));
+        assert_eq!(manager.ensure_image(&backend, &source, false, &sink).await, Ok(ImageOutcome::Present));
+        assert_eq!(calls(&backend), ["inspect"]);
+    }
+
+    #[tokio::test]
+    async fn test_missing_image_is_pulled() {
+        let backend = MockBackend::default();
+        let sink = NullSink::default();
+        let source = ImageSource::Pull("ghcr.io/example/sentinel-agent:1".into());
+        let outcome = ImageManager::default().ensure_image(&backend, &source, false, &sink).await;
+        assert_eq!(outcome, Ok(ImageOutcome::Pulled));
+        assert_eq!(calls(&backend), ["inspect", "pull ghcr.io/example/sentinel-agent:1"]);
+        assert_eq!(sink.0.lock().unwrap().as_slice(), ["Downloading"]);
+    }
+
+    #[tokio::test]
+    async fn test_missing_image_is_built() {
+        let backend = MockBackend::default();
+        let source = ImageSource::Build("bundle/agent-image".into());
+        let outcome = ImageManager::default().ensure_image(&backend, &source, false, &NullSink::default()).await;
+        assert_eq!(outcome, Ok(ImageOutcome::Built));
+        assert_eq!(calls(&backend), ["inspect", "build bundle/agent-image"]);
+    }
+
+    #[tokio::test]
+    async fn test_force_rebuild_skips_inspect() {
+        let backend = MockBackend { exists: true, ..Default::default() };
+        let source = ImageSource::Build("ctx".into());
+        let outcome = ImageManager::default().ensure_image(&backend, &source, true, &NullSink::default()).await;
+        assert_eq!(outcome, Ok(ImageOutcome::Built));
+        assert_eq!(calls(&backend), ["build ctx"]);
+    }
+
+    #[tokio::test]
+    async fn test_build_failure_reports_last_lines() {
+        let backend = MockBackend { fail: true, ..Default::default() };
+        let manager = ImageManager::default();
+        let source = ImageSource::Build("ctx".into());
+        let err = manager.ensure_image(&backend, &source, false, &NullSink::default()).await.unwrap_err();
+        assert!(err.contains("exit code 1"));
+        assert!(err.contains("Step 29"));
+        assert!(err.contains("Step 10"));
+        assert!(!err.contains("Step 9\n"));
+
+        // A failure is not cached.
+        let _ = manager.ensure_image(&backend, &source, false, &NullSink::default()).await;
+        assert_eq!(calls(&backend).iter().filter(|c| *c == "inspect").count(), 2);
+    }
+
+    #[test]
+    fn test_resolve_source() {
+        let bundled = Path::new("/res/agent-image");
+        assert_eq!(resolve_source(Some("reg/img:1"), None, bundled), ImageSource::Pull("reg/img:1".into()));
+        assert_eq!(resolve_source(Some("  "), Some(Path::new("/ctx")), bundled), ImageSource::Build("/ctx".into()));
+        assert_eq!(resolve_source(None, None, bundled), ImageSource::Build(bundled.into()));
+    }
+
+    fn copy_resource(src: &Path, dest: &Path) {
+        if src.is_dir() {
+            std::fs::create_dir_all(dest).unwrap();
+            for entry in std::fs::read_dir(src).unwrap() {
+                let entry = entry.unwrap();
+                copy_resource(&entry.path(), &dest.join(entry.file_name()));
+            }
+        } else {
+            std::fs::create_dir_all(dest.parent().unwrap()).unwrap();
+            std::fs::copy(src, dest).unwrap_or_else(|e| panic!("{}: {}", src.display(), e));
+        }
+    }
+
+    #[test]
+    fn test_bundled_context_has_dockerfile() {
+        // Lay out `bundle.resources` the way the bundler does, then resolve
+        // the default source against it.
+        let conf: serde_json::Value = serde_json::from_str(include_str!("../tauri.conf.json")).unwrap();
+        let resources = conf["bundle"]["resources"].as_object().expect("bundle.resources");
+        let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let resource_dir = tempfile::tempdir().unwrap();
+        for (src, dest) in resources {
+            copy_resource(&manifest_dir.join(src), &resource_dir.path().join(dest.as_str().unwrap()));
+        }
+
+        let bundled = resource_dir.path().join(BUNDLED_CONTEXT_DIR);
+        let ImageSource::Build(context) = resolve_source(None, None, &bundled) else {
+            panic!("expected a build source");
+        };
+        assert!(context.join("Dockerfile").is_file());
+        assert!(context.join("Cargo.toml").is_file());
+        assert!(context.join("src/main.rs").is_file());
+    }
+}
@@ -0,0 +1,214 @@
+//! Provider API keys: kept in the OS keychain, handed to containers as a
+//! file.
+//!
+//! Keys never go into container env, where `docker inspect` would show them.
+//! `start_agent` writes the key to a 0600 file, bind-mounts it read-only at
+//! `CONTAINER_KEY_PATH`, and deletes the host copy on the agent's first
+//! callback, by which time it has read the key, or on its exit. Agents with
+//! a restart policy keep the file until they can no longer be re-created.
+//! The agent reads the path from `SENTINEL_API_KEY_FILE`.
+
+use crate::commands;
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tauri::State;
+use tracing::warn;
+
+/// Keychain service name; each provider id is an account under it.
+pub const KEYRING_SERVICE: &str = "sentinel-dashboard";
+
+/// Where the key file appears inside the container.
+pub const CONTAINER_KEY_PATH: &str = "/run/secrets/sentinel_api_key";
+
+/// Env var telling the agent where to read its key.
+pub const KEY_FILE_ENV: &str = "SENTINEL_API_KEY_FILE";
+
+/// Keychain-backed key storage. Entries are cached per provider so the
+/// keyring mock store (one credential per `Entry`) behaves like the real one.
+#[derive(Default)]
+pub struct KeyStore {
+    entries: Mutex<HashMap<String, keyring::Entry>>,
+}
+
+pub type SharedKeys = Arc<KeyStore>;
+
+impl KeyStore {
+    fn with_entry<T>(&self, provider: &str, f: impl FnOnce(&keyring::Entry) -> keyring::Result<T>) -> Result<T, String> {
+        if !commands::providers().iter().any(|p| p.id == provider) {
+            return Err(format!("Unknown provider: {}", provider));
+        }
+        let mut entries = self.entries.lock().unwrap();
+        if !entries.contains_key(provider) {
+            let entry = keyring::Entry::new(KEYRING_SERVICE, provider).map_err(|e| e.to_string())?;
+            entries.insert(provider.to_string(), entry);
+        }
+        f(&entries[provider]).map_err(|e| format!("Keychain error: {}", e))
+    }
+
+    /// Store `key`, or remove the stored key when `key` is empty.
+    pub fn set(&self, provider: &str, key: &str) -> Result<(), String> {
+        let key = key.trim();
+        if key.is_empty() {
+            return self.with_entry(provider, |e| match e.delete_credential() {
+                Err(keyring::Error::NoEntry) => Ok(()),
+                other => other,
+            });
+        }
+        self.with_entry(provider, |e| e.set_password(key))
+    }
+
+    pub fn get(&self, provider: &str) -> Result<Option<String>, String> {
+        self.with_entry(provider, |e| match e.get_password() {
+            Ok(key) => Ok(Some(key)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(e),
+        })
+    }
+
+    pub fn has(&self, provider: &str) -> Result<bool, String> {
+        self.get(provider).map(|k| k.is_some())
+    }
+
+    /// The key for a launch: an explicit non-empty argument wins, otherwise
+    /// the keychain. `None` for providers that run without a key.
+    pub fn resolve(&self, provider: &str, explicit: Option<String>) -> Result<Option<String>, String> {
+        match explicit.map(|k| k.trim().to_string()).filter(|k| !k.is_empty()) {
+            Some(key) => Ok(Some(key)),
+            None => self.get(provider).or_else(|e| {
+                // Keyless providers (ollama) shouldn't fail on a locked keychain.
+                warn!(provider = %provider, error = %e, "Keychain unavailable");
+                Ok(None)
+            }),
+        }
+    }
+}
+
+/// A host file holding one API key, removed on drop.
+pub struct KeyFile {
+    path: PathBuf,
+}
+
+impl KeyFile {
+    /// Write `key` to a fresh owner-only file in `dir`.
+    pub fn write(dir: &Path, key: &str) -> std::io::Result<Self> {
+        std::fs::create_dir_all(dir)?;
+        let path = dir.join(format!("key-{}", uuid::Uuid::new_v4()));
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut file = options.open(&path)?;
+        let key_file = Self { path };
+        file.write_all(key.as_bytes())?;
+        Ok(key_file)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Bind entry and env var exposing the key to the container.
+    pub fn container_args(&self) -> (String, String) {
+        (
+            format!("{}:{}:ro", self.path.display(), CONTAINER_KEY_PATH),
+            format!("{}={}", KEY_FILE_ENV, CONTAINER_KEY_PATH),
+        )
+    }
+}
+
+impl Drop for KeyFile {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_file(&self.path) {
+            warn!(path = %self.path.display(), error = %e, "Failed to remove API key file");
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn set_provider_key(keys: State<'_, SharedKeys>, provider: String, key: String) -> Result<(), String> {
+    keys.set(&provider, &key)
+}
+
+#[tauri::command]
+pub async fn has_provider_key(keys: State<'_, SharedKeys>, provider: String) -> Result<bool, String> {
+    keys.has(&provider)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::{container_config, ContainerSpec};
+    use crate::limits::ResourceLimits;
+    use crate::mounts::{Mount, MountMode};
+    use crate::network::NetworkSetup;
+    use crate::restart::RestartPolicy;
+
+    const KEY: &str = "sk-test-4f9a1c2b7e3d";
+
+    fn mock_store() -> KeyStore {
+        keyring::set_default_credential_builder(keyring::mock::default_credential_builder());
+        KeyStore::default()
+    }
+
+    #[test]
+    fn test_keychain_round_trip_and_resolution() {
+        let keys = mock_store();
+        assert!(!keys.has("openai").unwrap());
+        assert_eq!(keys.resolve("openai", None).unwrap(), None);
+
+        keys.set("openai", KEY).unwrap();
+        assert!(keys.has("openai").unwrap());
+        assert_eq!(keys.resolve("openai", Some(" ".into())).unwrap().as_deref(), Some(KEY));
+        assert_eq!(keys.resolve("openai", Some("sk-explicit".into())).unwrap().as_deref(), Some("sk-explicit"));
+
+        keys.set("openai", "").unwrap();
+        assert!(!keys.has("openai").unwrap());
+        assert!(keys.set("not-a-provider", KEY).is_err());
+    }
+
+    #[test]
+    fn test_key_never_in_container_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let key_file = KeyFile::write(dir.path(), KEY).unwrap();
+        let mounts = [Mount { host_path: "/home/me/project".into(), container_path: "/workspace".into(), mode: MountMode::Rw }];
+        let config = container_config(&ContainerSpec {
+            agent_id: "sentinel-test",
+            callback_secret: "secret",
+            callback_url: "http://host.docker.internal:9876",
+            task: "Summarize",
+            provider: "openai",
+            model: "gpt-4o",
+            autonomy: "read_report",
+            max_iterations: None,
+            max_tokens: None,
+            mounts: &mounts,
+            limits: &ResourceLimits::default(),
+            device_requests: None,
+            restart_policy: RestartPolicy::default(),
+            net: &NetworkSetup::default(),
+            key_file: Some(&key_file),
+        });
+
+        assert!(!format!("{:?}", config).contains(KEY));
+        let env = config.env.unwrap();
+        assert!(env.iter().all(|var| !var.contains(KEY)));
+        assert!(env.contains(&format!("{}={}", KEY_FILE_ENV, CONTAINER_KEY_PATH)));
+        let binds = config.host_config.unwrap().binds.unwrap();
+        assert_eq!(binds.len(), 2);
+        assert_eq!(binds[1], format!("{}:{}:ro", key_file.path().display(), CONTAINER_KEY_PATH));
+        assert!(binds.iter().all(|bind| !bind.contains(KEY)));
+
+        assert_eq!(std::fs::read_to_string(key_file.path()).unwrap(), KEY);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(key_file.path()).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        let path = key_file.path().to_path_buf();
+        drop(key_file);
+        assert!(!path.exists());
+    }
+}
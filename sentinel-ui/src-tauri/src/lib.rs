@@ -1 +1,24 @@
+pub mod attach;
+pub mod callback_server;
 pub mod commands;
+pub mod diagnostics;
+pub mod gpu;
+pub mod image;
+pub mod keys;
+pub mod limits;
+pub mod messages;
+pub mod mounts;
+pub mod network;
+pub mod notifications;
+pub mod ports;
+pub mod presets;
+pub mod reaper;
+pub mod report;
+pub mod restart;
+pub mod scheduler;
+pub mod session;
+pub mod settings;
+pub mod stats;
+pub mod transcript;
+pub mod usage;
+pub mod workspace;
@@ -0,0 +1,146 @@
+//! Resource limits for agent containers.
+//!
+//! Memory (with swap pinned to the same value), CPU, PID count, and the size
+//! of the `/tmp` tmpfs. A value of `0` means "unlimited" for every field.
+
+use bollard::container::HostConfig;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+const MIB: i64 = 1024 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ResourceLimits {
+    /// Memory in MiB; swap is capped at the same value so it can't be used
+    /// to exceed it.
+    pub memory_mb: u64,
+    /// CPUs, fractional allowed (`1.5`).
+    pub cpu_limit: f64,
+    pub pids_limit: i64,
+    /// Size of the `/tmp` tmpfs in MiB. With `0`, `/tmp` is still a tmpfs
+    /// but Docker's default size applies.
+    pub tmp_size_mb: u64,
+}
+
+impl Default for ResourceLimits {
+    fn default() -> Self {
+        Self { memory_mb: 2048, cpu_limit: 2.0, pids_limit: 512, tmp_size_mb: 512 }
+    }
+}
+
+/// Per-launch overrides from `start_agent`; unset fields keep the Settings value.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct LimitOverrides {
+    pub memory_mb: Option<u64>,
+    pub cpu_limit: Option<f64>,
+    pub pids_limit: Option<i64>,
+    pub tmp_size_mb: Option<u64>,
+}
+
+impl ResourceLimits {
+    pub fn with_overrides(self, o: &LimitOverrides) -> Self {
+        Self {
+            memory_mb: o.memory_mb.unwrap_or(self.memory_mb),
+            cpu_limit: o.cpu_limit.unwrap_or(self.cpu_limit),
+            pids_limit: o.pids_limit.unwrap_or(self.pids_limit),
+            tmp_size_mb: o.tmp_size_mb.unwrap_or(self.tmp_size_mb),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.cpu_limit.is_finite() || self.cpu_limit < 0.0 {
+            return Err(format!("Invalid CPU limit: {}", self.cpu_limit));
+        }
+        if self.pids_limit < 0 {
+            return Err(format!("Invalid PID limit: {}", self.pids_limit));
+        }
+        Ok(())
+    }
+
+    /// Set the limit fields of `host_config`.
+    pub fn apply(&self, host_config: &mut HostConfig) {
+        let memory = (self.memory_mb > 0).then(|| self.memory_mb as i64 * MIB);
+        host_config.memory = memory;
+        host_config.memory_swap = memory;
+        host_config.nano_cpus = (self.cpu_limit > 0.0).then(|| (self.cpu_limit * 1e9) as i64);
+        host_config.pids_limit = (self.pids_limit > 0).then_some(self.pids_limit);
+
+        let mut tmp_opts = "rw,nosuid,nodev".to_string();
+        if self.tmp_size_mb > 0 {
+            tmp_opts.push_str(&format!(",size={}m", self.tmp_size_mb));
+        }
+        host_config.tmpfs = Some(HashMap::from([("/tmp".to_string(), tmp_opts)]));
+    }
+
+    /// Human-readable summary for the agent's first log entry.
+    pub fn describe(&self) -> String {
+        fn or_unlimited(set: bool, value: String) -> String {
+            if set { value } else { "unlimited".to_string() }
+        }
+        format!(
+            "Resource limits — memory: {}, CPUs: {}, PIDs: {}, /tmp: {}",
+            or_unlimited(self.memory_mb > 0, format!("{} MB (no swap)", self.memory_mb)),
+            or_unlimited(self.cpu_limit > 0.0, format!("{}", self.cpu_limit)),
+            or_unlimited(self.pids_limit > 0, self.pids_limit.to_string()),
+            or_unlimited(self.tmp_size_mb > 0, format!("{} MB", self.tmp_size_mb)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn applied(limits: &ResourceLimits) -> HostConfig {
+        let mut host_config = HostConfig { auto_remove: Some(true), ..Default::default() };
+        limits.apply(&mut host_config);
+        host_config
+    }
+
+    #[test]
+    fn test_defaults() {
+        let hc = applied(&ResourceLimits::default());
+        assert_eq!(hc.memory, Some(2048 * MIB));
+        assert_eq!(hc.memory_swap, hc.memory);
+        assert_eq!(hc.nano_cpus, Some(2_000_000_000));
+        assert_eq!(hc.pids_limit, Some(512));
+        assert_eq!(hc.tmpfs.unwrap()["/tmp"], "rw,nosuid,nodev,size=512m");
+        assert_eq!(hc.auto_remove, Some(true));
+    }
+
+    #[test]
+    fn test_unlimited_sentinels() {
+        let hc = applied(&ResourceLimits { memory_mb: 0, cpu_limit: 0.0, pids_limit: 0, tmp_size_mb: 0 });
+        assert_eq!(hc.memory, None);
+        assert_eq!(hc.memory_swap, None);
+        assert_eq!(hc.nano_cpus, None);
+        assert_eq!(hc.pids_limit, None);
+        assert_eq!(hc.tmpfs.unwrap()["/tmp"], "rw,nosuid,nodev");
+    }
+
+    #[test]
+    fn test_overrides_and_fractional_cpu() {
+        let limits = ResourceLimits::default().with_overrides(&LimitOverrides {
+            cpu_limit: Some(0.5),
+            memory_mb: Some(0),
+            ..Default::default()
+        });
+        let hc = applied(&limits);
+        assert_eq!(hc.nano_cpus, Some(500_000_000));
+        assert_eq!(hc.memory, None);
+        assert_eq!(hc.pids_limit, Some(512));
+        assert_eq!(
+            limits.describe(),
+            "Resource limits — memory: unlimited, CPUs: 0.5, PIDs: 512, /tmp: 512 MB"
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(ResourceLimits::default().validate().is_ok());
+        assert!(ResourceLimits { cpu_limit: -1.0, ..Default::default() }.validate().is_err());
+        assert!(ResourceLimits { cpu_limit: f64::NAN, ..Default::default() }.validate().is_err());
+        assert!(ResourceLimits { pids_limit: -1, ..Default::default() }.validate().is_err());
+    }
+}
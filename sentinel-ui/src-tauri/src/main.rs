@@ -4,23 +4,133 @@
 
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-use sentinel_ui_lib::commands;
+use sentinel_ui_lib::{attach, callback_server, commands, diagnostics, gpu, image, keys, network, notifications, presets, reaper, scheduler, session, settings, stats, transcript, usage, workspace};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{Manager, RunEvent, WindowEvent};
 
 fn main() {
-    tauri::Builder::default()
+    let agents: commands::SharedAgentState = Default::default();
+    let hitl = Arc::new(commands::HitlPendingSenders::default());
+    let (shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel::<()>();
+    let shutdown_tx = Mutex::new(Some(shutdown_tx));
+
+    let app = tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_dialog::init())
-        .manage(tokio::sync::Mutex::new(commands::AgentState::default()))
-        .manage(commands::HitlPendingSenders::default())
+        .plugin(tauri_plugin_notification::init())
+        .manage(agents.clone())
+        .manage(hitl.clone())
+        .manage(Arc::new(image::ImageManager::default()))
+        .manage(keys::SharedKeys::default())
+        .setup(move |app| {
+            let data_dir = app.path().app_data_dir()?;
+            let store: settings::SharedSettings = Arc::new(settings::SettingsStore::load(data_dir.join(settings::SETTINGS_FILE)));
+            app.manage(store.clone());
+            let sessions: session::SharedSessions = Arc::new(session::SessionStore::new(data_dir.join(session::SESSIONS_DIR)));
+            app.manage(sessions.clone());
+            let presets: presets::SharedPresets = Arc::new(presets::PresetStore::load(data_dir.join(presets::PRESETS_FILE)));
+            app.manage(presets);
+            let schedules: scheduler::SharedSchedules = Arc::new(scheduler::ScheduleStore::load(data_dir.join(scheduler::SCHEDULES_FILE)));
+            app.manage(schedules);
+            let desktop = Arc::new(notifications::TauriDesktopNotifier::new(app.handle().clone()));
+            app.manage(desktop.clone());
+            let notifier = Arc::new(notifications::Notifier::new(store.clone()).with_desktop(desktop));
+            app.manage(notifier.clone());
+            // Launches read the bound address for the agents' callback URL.
+            let callback_config = callback_server::CallbackConfig::from_env();
+            app.manage(callback_config.clone());
+
+            let handle = app.handle().clone();
+            let reaper_agents = agents.clone();
+            tauri::async_runtime::spawn(async move {
+                reaper::reap_orphans(reaper_agents, store.get().await, &handle).await;
+            });
+            tauri::async_runtime::spawn(scheduler::run(app.handle().clone()));
+
+            let state = callback_server::CallbackState {
+                agents,
+                hitl,
+                sink: Arc::new(app.handle().clone()),
+                sessions,
+                notifier,
+                config: callback_config,
+            };
+            tauri::async_runtime::spawn(callback_server::reconcile(state.clone(), Duration::from_secs(30)));
+            // Bound here, before the event loop starts, so the first agent
+            // callback never races the listener.
+            match callback_server::bind(state.config.addr) {
+                Ok(listener) => {
+                    tauri::async_runtime::spawn(async move {
+                        let shutdown = async { let _ = shutdown_rx.await; };
+                        if let Err(e) = callback_server::serve(listener, state, shutdown).await {
+                            tracing::error!(error = %e, "Callback server failed");
+                        }
+                    });
+                }
+                Err(e) => tracing::error!(addr = %state.config.addr, error = %e, "Callback server could not bind"),
+            }
+            Ok(())
+        })
+        .on_window_event(|window, event| {
+            if let WindowEvent::Focused(true) = event {
+                if let Some(desktop) = window.try_state::<Arc<notifications::TauriDesktopNotifier>>() {
+                    desktop.on_focus();
+                }
+            }
+        })
         .invoke_handler(tauri::generate_handler![
             commands::start_agent,
+            commands::stop_agent,
             commands::get_novnc_port,
             commands::send_agent_message,
             commands::get_active_tokens,
             commands::handle_hitl_approval,
+            commands::reject_all_pending,
+            commands::cancel_manifest,
             commands::get_providers,
             commands::get_pending_manifests,
+            image::ensure_agent_image,
+            settings::get_settings,
+            settings::update_settings,
+            session::get_session_history,
+            session::export_session,
+            transcript::export_transcript,
+            notifications::send_test_notification,
+            network::get_network_modes,
+            presets::save_preset,
+            presets::list_presets,
+            presets::delete_preset,
+            scheduler::create_schedule,
+            scheduler::list_schedules,
+            scheduler::delete_schedule,
+            usage::get_agent_usage,
+            stats::get_agent_stats,
+            workspace::list_workspace,
+            workspace::read_workspace_file,
+            attach::attach_agent,
+            attach::list_agents,
+            gpu::get_host_capabilities,
+            keys::set_provider_key,
+            keys::has_provider_key,
+            diagnostics::run_diagnostics,
         ])
-        .run(tauri::generate_context!())
-        .expect("failed to run SENTINEL Dashboard");
+        .build(tauri::generate_context!())
+        .expect("failed to build SENTINEL Dashboard");
+
+    app.run(move |app, event| match event {
+        RunEvent::ExitRequested { .. } => {
+            let agents = app.state::<commands::SharedAgentState>().inner().clone();
+            let store = app.state::<settings::SharedSettings>().inner().clone();
+            tauri::async_runtime::block_on(async move {
+                reaper::cleanup_on_exit(agents, store.get().await).await;
+            });
+        }
+        RunEvent::Exit => {
+            if let Some(tx) = shutdown_tx.lock().unwrap().take() {
+                let _ = tx.send(());
+            }
+        }
+        _ => {}
+    });
 }
@@ -0,0 +1,105 @@
+//! Typed chat messages, emitted as `sentinel://message`.
+//!
+//! Agents post thoughts, questions and report sections to dedicated
+//! callback endpoints, and the dashboard adds tool-use, system and report
+//! messages of its own. Each becomes one event with an explicit `kind`, so
+//! the frontend never has to guess from message text. Older agent images
+//! still send `THOUGHT: `-prefixed log lines; those are translated here.
+
+use crate::callback_server::EventSink;
+use crate::session::{SessionEvent, SessionStore};
+use serde::{Deserialize, Serialize};
+
+pub const MESSAGE_EVENT: &str = "sentinel://message";
+
+/// Prefix older agents put on log lines meant as chat bubbles.
+const LEGACY_THOUGHT_PREFIX: &str = "THOUGHT: ";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MessageKind {
+    Thought,
+    Question,
+    /// Part of a report the agent sends inline.
+    ReportSection,
+    /// The complete final report, as Markdown.
+    Report,
+    ToolUse,
+    System,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AgentMessage {
+    pub agent_id: String,
+    pub kind: MessageKind,
+    pub message: String,
+    /// Section heading for report sections, tool name for tool use.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+}
+
+impl AgentMessage {
+    pub fn new(agent_id: &str, kind: MessageKind, message: impl Into<String>) -> Self {
+        Self { agent_id: agent_id.to_string(), kind, message: message.into(), title: None }
+    }
+
+    pub fn with_title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// A thought from an older agent image, if `line` carries the legacy
+    /// prefix.
+    pub fn from_legacy_log(agent_id: &str, line: &str) -> Option<Self> {
+        line.strip_prefix(LEGACY_THOUGHT_PREFIX)
+            .map(|thought| Self::new(agent_id, MessageKind::Thought, thought))
+    }
+
+    pub fn session_event(&self) -> SessionEvent {
+        let message = self.message.clone();
+        match self.kind {
+            MessageKind::Thought => SessionEvent::Thought { message },
+            MessageKind::Question => SessionEvent::Question { message },
+            MessageKind::ReportSection => SessionEvent::ReportSection { title: self.title.clone(), message },
+            MessageKind::Report => SessionEvent::Report { message },
+            MessageKind::ToolUse => SessionEvent::ToolUse { tool: self.title.clone().unwrap_or_default(), message },
+            MessageKind::System => SessionEvent::System { message },
+        }
+    }
+}
+
+/// Record `message` in the agent's session and send it to the frontend.
+pub fn send(sessions: &SessionStore, sink: &dyn EventSink, message: &AgentMessage) {
+    sessions.record(&message.agent_id, message.session_event());
+    sink.emit(MESSAGE_EVENT, serde_json::to_value(message).unwrap_or_default());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_legacy_translation() {
+        let thought = AgentMessage::from_legacy_log("sentinel-a", "THOUGHT: Reading **main.rs**").unwrap();
+        assert_eq!(thought.kind, MessageKind::Thought);
+        assert_eq!(thought.message, "Reading **main.rs**");
+        assert_eq!(AgentMessage::from_legacy_log("sentinel-a", "Tool result (read_file): 12 chars"), None);
+        // Only a leading prefix marks a thought; tool output may contain it.
+        assert_eq!(AgentMessage::from_legacy_log("sentinel-a", "grep: THOUGHT: found"), None);
+    }
+
+    #[test]
+    fn test_serialized_shape() {
+        let msg = AgentMessage::new("sentinel-a", MessageKind::ToolUse, "src/main.rs").with_title("read_file");
+        assert_eq!(serde_json::to_value(&msg).unwrap(), serde_json::json!({
+            "agent_id": "sentinel-a",
+            "kind": "tool-use",
+            "message": "src/main.rs",
+            "title": "read_file",
+        }));
+        assert_eq!(msg.session_event(), SessionEvent::ToolUse { tool: "read_file".into(), message: "src/main.rs".into() });
+        let report = serde_json::to_value(AgentMessage::new("sentinel-a", MessageKind::ReportSection, "x")).unwrap();
+        assert_eq!(report["kind"], "report-section");
+        assert!(report.get("title").is_none());
+    }
+}
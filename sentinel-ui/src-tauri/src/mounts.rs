@@ -0,0 +1,237 @@
+//! Workspace mounts for agent containers.
+//!
+//! The UI sends a list of `{host_path, container_path, mode}` specs. They are
+//! validated here, turned into Docker bind strings, and described to the
+//! agent through `SENTINEL_MOUNTS` so its tools know every root instead of
+//! assuming `/workspace`.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Component, Path, PathBuf};
+
+/// Env var carrying the mount list (JSON) into the container.
+pub const MOUNTS_ENV: &str = "SENTINEL_MOUNTS";
+
+/// Where the legacy single `target_dir` is mounted.
+pub const DEFAULT_CONTAINER_PATH: &str = "/workspace";
+
+/// Container paths a mount may not cover: the agent image itself lives here.
+const RESERVED_PATHS: &[&str] = &[
+    "/", "/bin", "/boot", "/dev", "/etc", "/lib", "/lib64", "/proc", "/root",
+    "/run", "/sbin", "/sys", "/tmp", "/usr", "/var",
+];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MountMode {
+    Ro,
+    #[default]
+    Rw,
+}
+
+impl MountMode {
+    fn as_str(self) -> &'static str {
+        match self {
+            MountMode::Ro => "ro",
+            MountMode::Rw => "rw",
+        }
+    }
+}
+
+/// A mount as requested by the UI.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MountSpec {
+    pub host_path: String,
+    pub container_path: String,
+    #[serde(default)]
+    pub mode: MountMode,
+}
+
+impl MountSpec {
+    /// Compatibility shim for the single-directory `target_dir` argument.
+    pub fn legacy(target_dir: &str) -> Self {
+        Self {
+            host_path: target_dir.to_string(),
+            container_path: DEFAULT_CONTAINER_PATH.to_string(),
+            mode: MountMode::Rw,
+        }
+    }
+}
+
+/// A validated mount: canonical host path, normalized container path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mount {
+    pub host_path: PathBuf,
+    pub container_path: String,
+    pub mode: MountMode,
+}
+
+/// What the agent sees in `SENTINEL_MOUNTS`.
+#[derive(Debug, Serialize)]
+struct AgentMount<'a> {
+    path: &'a str,
+    mode: MountMode,
+}
+
+/// Normalize an absolute container path, rejecting `..` and relative paths.
+fn normalize_container_path(raw: &str) -> Result<String, String> {
+    let path = Path::new(raw.trim());
+    if !path.is_absolute() {
+        return Err(format!("Container path must be absolute: {}", raw));
+    }
+    let mut parts = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::RootDir | Component::CurDir => {}
+            Component::Normal(part) => {
+                let part = part.to_str().ok_or_else(|| format!("Container path is not UTF-8: {}", raw))?;
+                if part.contains(':') {
+                    return Err(format!("Container path may not contain ':': {}", raw));
+                }
+                parts.push(part);
+            }
+            _ => return Err(format!("Container path may not contain '..': {}", raw)),
+        }
+    }
+    Ok(format!("/{}", parts.join("/")))
+}
+
+/// Whether one container path equals or contains the other.
+fn overlaps(a: &str, b: &str) -> bool {
+    let nested = |outer: &str, inner: &str| inner == outer || inner.starts_with(&format!("{}/", outer));
+    nested(a, b) || nested(b, a)
+}
+
+/// Validate mount specs: host paths must be existing directories (they are
+/// canonicalized), container paths absolute, outside system directories,
+/// and not nested in one another.
+pub fn validate_mounts(specs: &[MountSpec]) -> Result<Vec<Mount>, String> {
+    let mut mounts: Vec<Mount> = Vec::with_capacity(specs.len());
+    for spec in specs {
+        let host_path = std::fs::canonicalize(spec.host_path.trim())
+            .map_err(|e| format!("Mount source {} is not accessible: {}", spec.host_path, e))?;
+        if !host_path.is_dir() {
+            return Err(format!("Mount source {} is not a directory", spec.host_path));
+        }
+        if host_path.to_str().map_or(true, |p| p.contains(':') && !cfg!(windows)) {
+            return Err(format!("Mount source {} cannot be expressed as a Docker bind", spec.host_path));
+        }
+
+        let container_path = normalize_container_path(&spec.container_path)?;
+        if let Some(reserved) = RESERVED_PATHS.iter().find(|r| container_path == **r || (**r != "/" && overlaps(r, &container_path))) {
+            return Err(format!("Container path {} conflicts with system directory {}", container_path, reserved));
+        }
+        if let Some(other) = mounts.iter().find(|m| overlaps(&m.container_path, &container_path)) {
+            return Err(format!("Container paths {} and {} overlap", other.container_path, container_path));
+        }
+
+        mounts.push(Mount { host_path, container_path, mode: spec.mode });
+    }
+    Ok(mounts)
+}
+
+/// Docker `binds` entries (`host:container:mode`).
+pub fn bind_strings(mounts: &[Mount]) -> Vec<String> {
+    mounts.iter()
+        .map(|m| format!("{}:{}:{}", m.host_path.display(), m.container_path, m.mode.as_str()))
+        .collect()
+}
+
+/// `SENTINEL_MOUNTS` value: `[{"path": "/workspace", "mode": "rw"}, ...]`.
+/// The first entry is the agent's primary root.
+pub fn mounts_env(mounts: &[Mount]) -> String {
+    let list: Vec<AgentMount> = mounts.iter()
+        .map(|m| AgentMount { path: &m.container_path, mode: m.mode })
+        .collect();
+    serde_json::to_string(&list).unwrap_or_else(|_| "[]".to_string())
+}
+
+/// Combine the new `mounts` argument with the legacy `target_dir`.
+pub fn requested_specs(mounts: Option<Vec<MountSpec>>, target_dir: Option<String>) -> Vec<MountSpec> {
+    match mounts {
+        Some(mounts) if !mounts.is_empty() => mounts,
+        _ => target_dir
+            .filter(|d| !d.trim().is_empty())
+            .map(|d| vec![MountSpec::legacy(&d)])
+            .unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(host: &Path, container: &str, mode: MountMode) -> MountSpec {
+        MountSpec { host_path: host.display().to_string(), container_path: container.to_string(), mode }
+    }
+
+    #[test]
+    fn test_valid_mounts_bind_and_env() {
+        let notes = tempfile::tempdir().unwrap();
+        let project = tempfile::tempdir().unwrap();
+        let mounts = validate_mounts(&[
+            spec(project.path(), "/workspace/", MountMode::Rw),
+            spec(&notes.path().join("."), "/notes", MountMode::Ro),
+        ]).unwrap();
+
+        let project_real = std::fs::canonicalize(project.path()).unwrap();
+        let notes_real = std::fs::canonicalize(notes.path()).unwrap();
+        assert_eq!(bind_strings(&mounts), vec![
+            format!("{}:/workspace:rw", project_real.display()),
+            format!("{}:/notes:ro", notes_real.display()),
+        ]);
+        assert_eq!(mounts_env(&mounts), r#"[{"path":"/workspace","mode":"rw"},{"path":"/notes","mode":"ro"}]"#);
+    }
+
+    #[test]
+    fn test_validation_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("file.txt");
+        std::fs::write(&file, "x").unwrap();
+
+        let missing = validate_mounts(&[spec(&dir.path().join("nope"), "/data", MountMode::Rw)]);
+        assert!(missing.unwrap_err().contains("not accessible"));
+
+        let not_dir = validate_mounts(&[spec(&file, "/data", MountMode::Rw)]);
+        assert!(not_dir.unwrap_err().contains("not a directory"));
+
+        let relative = validate_mounts(&[spec(dir.path(), "data", MountMode::Rw)]);
+        assert!(relative.unwrap_err().contains("absolute"));
+
+        let escape = validate_mounts(&[spec(dir.path(), "/data/../etc", MountMode::Rw)]);
+        assert!(escape.unwrap_err().contains(".."));
+
+        let system = validate_mounts(&[spec(dir.path(), "/usr/local/src", MountMode::Rw)]);
+        assert!(system.unwrap_err().contains("system directory"));
+
+        let overlap = validate_mounts(&[
+            spec(dir.path(), "/workspace", MountMode::Rw),
+            spec(dir.path(), "/workspace/docs", MountMode::Ro),
+        ]);
+        assert!(overlap.unwrap_err().contains("overlap"));
+
+        // Sibling prefixes are not nested.
+        assert!(validate_mounts(&[
+            spec(dir.path(), "/work", MountMode::Rw),
+            spec(dir.path(), "/workspace", MountMode::Ro),
+        ]).is_ok());
+    }
+
+    #[test]
+    fn test_legacy_target_dir_shim() {
+        assert_eq!(requested_specs(None, Some("/home/me/project".into())), vec![MountSpec {
+            host_path: "/home/me/project".into(),
+            container_path: "/workspace".into(),
+            mode: MountMode::Rw,
+        }]);
+        assert!(requested_specs(Some(vec![]), Some(" ".into())).is_empty());
+
+        let explicit = vec![MountSpec { host_path: "/a".into(), container_path: "/a".into(), mode: MountMode::Ro }];
+        assert_eq!(requested_specs(Some(explicit.clone()), Some("/ignored".into())), explicit);
+    }
+
+    #[test]
+    fn test_mode_defaults_to_rw() {
+        let spec: MountSpec = serde_json::from_str(r#"{"host_path":"/a","container_path":"/a"}"#).unwrap();
+        assert_eq!(spec.mode, MountMode::Rw);
+    }
+}
@@ -0,0 +1,608 @@
+//! Network isolation for agent containers.
+//!
+//! - `full`: the default bridge with outbound internet (the original behaviour).
+//! - `llm-only`: a dedicated `internal` Docker network with no default route.
+//!   The only way out is a small host-side forward proxy, passed to the agent
+//!   as `HTTP(S)_PROXY`, that admits the configured LLM endpoint and the
+//!   dashboard callback server and refuses everything else.
+//! - `none`: networking disabled entirely.
+//!
+//! Networks and proxies are recorded per agent in [`NetworkRegistry`] so they
+//! are torn down when the agent stops or its container exits.
+
+use bollard::network::CreateNetworkOptions;
+use bollard::Docker;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+/// Name prefix for per-agent networks.
+pub const NETWORK_PREFIX: &str = "sentinel-net-";
+
+/// Hostname the agent uses for the host machine.
+const HOST_GATEWAY: &str = "host.docker.internal";
+
+/// The container's own loopback: never proxied, never allowlisted.
+const LOOPBACK_HOSTS: &[&str] = &["localhost", "127.0.0.1", "::1"];
+
+/// Largest request head the proxy will buffer.
+const MAX_HEAD_BYTES: usize = 16 * 1024;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum NetworkMode {
+    #[default]
+    Full,
+    LlmOnly,
+    None,
+}
+
+// ── Allowlist ───────────────────────────────────────────────────────────────
+
+/// Base URL the agent uses for `provider` (mirrors the agent's `LlmClient`);
+/// unknown providers are treated as a custom base URL.
+pub fn llm_base_url(provider: &str) -> &str {
+    match provider {
+        "ollama" => "http://host.docker.internal:11434",
+        "ollama-local" => "http://localhost:11434",
+        "openai" => "https://api.openai.com/v1",
+        "anthropic" => "https://api.anthropic.com/v1",
+        "deepseek" => "https://api.deepseek.com/v1",
+        "grok" => "https://api.x.ai/v1",
+        "google" => "https://generativelanguage.googleapis.com/v1beta/openai",
+        other => other,
+    }
+}
+
+/// `(host, port)` of an `http(s)://` URL.
+pub fn parse_endpoint(url: &str) -> Option<(String, u16)> {
+    let (scheme, rest) = url.split_once("://")?;
+    let default_port = match scheme.to_ascii_lowercase().as_str() {
+        "http" => 80,
+        "https" => 443,
+        _ => return None,
+    };
+    let authority = rest.split(['/', '?', '#']).next()?;
+    let authority = authority.rsplit_once('@').map_or(authority, |(_, a)| a);
+    parse_authority(authority, default_port)
+}
+
+fn parse_authority(authority: &str, default_port: u16) -> Option<(String, u16)> {
+    let (host, port) = if let Some(rest) = authority.strip_prefix('[') {
+        // IPv6 literal: `[::1]` or `[::1]:443`
+        let (host, after) = rest.split_once(']')?;
+        match after.strip_prefix(':') {
+            Some(port) => (host, port.parse().ok()?),
+            None if after.is_empty() => (host, default_port),
+            None => return None,
+        }
+    } else {
+        match authority.rsplit_once(':') {
+            Some((host, port)) => (host, port.parse().ok()?),
+            None => (authority, default_port),
+        }
+    };
+    (!host.is_empty()).then(|| (host.to_ascii_lowercase(), port))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct AllowRule {
+    host: String,
+    port: u16,
+    /// Where the proxy actually connects.
+    upstream: (String, u16),
+}
+
+/// Destinations the `llm-only` proxy will connect to.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Allowlist {
+    rules: Vec<AllowRule>,
+}
+
+impl Allowlist {
+    /// Allow `(host, port)`. The host gateway name maps to loopback, since
+    /// the proxy runs on the host itself.
+    pub fn allow(&mut self, host: &str, port: u16) {
+        let host = host.to_ascii_lowercase();
+        let upstream_host = if host == HOST_GATEWAY { "127.0.0.1".to_string() } else { host.clone() };
+        self.rules.push(AllowRule { host, port, upstream: (upstream_host, port) });
+    }
+
+    /// The LLM endpoint for `provider` plus the dashboard callback server.
+    /// An endpoint on the container's own loopback needs no rule.
+    pub fn for_agent(provider: &str, callback_port: u16) -> Result<Self, String> {
+        let base_url = llm_base_url(provider);
+        let (host, port) = parse_endpoint(base_url)
+            .ok_or_else(|| format!("Cannot isolate network: unrecognized LLM endpoint {}", base_url))?;
+        let mut allowlist = Self::default();
+        if !LOOPBACK_HOSTS.contains(&host.as_str()) {
+            allowlist.allow(&host, port);
+        }
+        allowlist.allow(HOST_GATEWAY, callback_port);
+        Ok(allowlist)
+    }
+
+    /// The upstream to connect to, or `None` if the destination is denied.
+    pub fn decide(&self, host: &str, port: u16) -> Option<(String, u16)> {
+        let host = host.trim_end_matches('.').to_ascii_lowercase();
+        self.rules.iter()
+            .find(|r| r.host == host && r.port == port)
+            .map(|r| r.upstream.clone())
+    }
+}
+
+// ── Proxy ───────────────────────────────────────────────────────────────────
+
+/// A running egress proxy; stopped explicitly or when dropped.
+pub struct ProxyHandle {
+    pub addr: SocketAddr,
+    shutdown: Option<oneshot::Sender<()>>,
+    task: JoinHandle<()>,
+}
+
+impl ProxyHandle {
+    pub fn stop(mut self) {
+        self.shutdown_now();
+    }
+
+    fn shutdown_now(&mut self) {
+        if let Some(tx) = self.shutdown.take() {
+            let _ = tx.send(());
+        }
+        self.task.abort();
+    }
+}
+
+impl Drop for ProxyHandle {
+    fn drop(&mut self) {
+        self.shutdown_now();
+    }
+}
+
+/// Start a forward proxy on `bind` (port chosen by the OS) that only
+/// connects to destinations in `allowlist`.
+pub async fn start_proxy(bind: IpAddr, allowlist: Allowlist) -> std::io::Result<ProxyHandle> {
+    start_proxy_at(SocketAddr::new(bind, 0), allowlist).await
+}
+
+/// Like `start_proxy`, on a fixed address: re-attached agents already have
+/// the proxy URL baked into their environment.
+pub async fn start_proxy_at(bind: SocketAddr, allowlist: Allowlist) -> std::io::Result<ProxyHandle> {
+    let listener = TcpListener::bind(bind).await?;
+    let addr = listener.local_addr()?;
+    let allowlist = Arc::new(allowlist);
+    let (tx, mut rx) = oneshot::channel();
+    let task = tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = &mut rx => break,
+                accepted = listener.accept() => {
+                    let Ok((stream, _)) = accepted else { continue };
+                    let allowlist = allowlist.clone();
+                    tokio::spawn(async move {
+                        if let Err(e) = handle_connection(stream, &allowlist).await {
+                            warn!(error = %e, "Egress proxy connection failed");
+                        }
+                    });
+                }
+            }
+        }
+    });
+    info!(addr = %addr, "Egress proxy listening");
+    Ok(ProxyHandle { addr, shutdown: Some(tx), task })
+}
+
+/// What a client asked the proxy for.
+#[derive(Debug, PartialEq, Eq)]
+enum ProxyRequest {
+    /// `CONNECT host:port` (HTTPS tunnelling).
+    Connect { host: String, port: u16 },
+    /// Absolute-form plain HTTP; `head` is rewritten to origin-form.
+    Forward { host: String, port: u16, head: Vec<u8> },
+}
+
+fn parse_request(head: &str) -> Result<ProxyRequest, String> {
+    let (request_line, rest) = head.split_once("\r\n").unwrap_or((head, ""));
+    let mut parts = request_line.split_whitespace();
+    let (method, target, version) = match (parts.next(), parts.next(), parts.next()) {
+        (Some(m), Some(t), Some(v)) => (m, t, v),
+        _ => return Err(format!("Malformed request line: {}", request_line)),
+    };
+    if method.eq_ignore_ascii_case("CONNECT") {
+        let (host, port) = parse_authority(target, 443).ok_or("Bad CONNECT target")?;
+        return Ok(ProxyRequest::Connect { host, port });
+    }
+    let (host, port) = parse_endpoint(target).ok_or("Proxy requests must use an absolute http:// URL")?;
+    if !target.to_ascii_lowercase().starts_with("http://") {
+        return Err("Only plain HTTP may be forwarded; use CONNECT for HTTPS".into());
+    }
+    let after_scheme = &target["http://".len()..];
+    let path = after_scheme.find('/').map_or("/", |i| &after_scheme[i..]);
+    let head = format!("{} {} {}\r\n{}", method, path, version, rest).into_bytes();
+    Ok(ProxyRequest::Forward { host, port, head })
+}
+
+async fn handle_connection(mut client: TcpStream, allowlist: &Allowlist) -> std::io::Result<()> {
+    let mut buf = Vec::with_capacity(1024);
+    let head_end = loop {
+        let mut chunk = [0u8; 1024];
+        let n = client.read(&mut chunk).await?;
+        if n == 0 {
+            return Ok(());
+        }
+        buf.extend_from_slice(&chunk[..n]);
+        if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos + 4;
+        }
+        if buf.len() > MAX_HEAD_BYTES {
+            return client.write_all(b"HTTP/1.1 431 Request Header Fields Too Large\r\n\r\n").await;
+        }
+    };
+    let head = String::from_utf8_lossy(&buf[..head_end]).into_owned();
+    let request = match parse_request(&head) {
+        Ok(r) => r,
+        Err(e) => {
+            let body = format!("HTTP/1.1 400 Bad Request\r\nContent-Length: {}\r\n\r\n{}", e.len(), e);
+            return client.write_all(body.as_bytes()).await;
+        }
+    };
+
+    let (host, port) = match &request {
+        ProxyRequest::Connect { host, port } | ProxyRequest::Forward { host, port, .. } => (host.clone(), *port),
+    };
+    let Some((up_host, up_port)) = allowlist.decide(&host, port) else {
+        warn!(host = %host, port, "Egress proxy denied destination");
+        let msg = "Blocked by SENTINEL network isolation (llm-only)";
+        let resp = format!("HTTP/1.1 403 Forbidden\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", msg.len(), msg);
+        return client.write_all(resp.as_bytes()).await;
+    };
+
+    let mut upstream = match TcpStream::connect((up_host.as_str(), up_port)).await {
+        Ok(s) => s,
+        Err(e) => {
+            warn!(host = %host, port, error = %e, "Egress proxy upstream unreachable");
+            return client.write_all(b"HTTP/1.1 502 Bad Gateway\r\nConnection: close\r\n\r\n").await;
+        }
+    };
+    match request {
+        ProxyRequest::Connect { .. } => {
+            client.write_all(b"HTTP/1.1 200 Connection Established\r\n\r\n").await?;
+        }
+        ProxyRequest::Forward { head, .. } => {
+            upstream.write_all(&head).await?;
+        }
+    }
+    // Anything the client pipelined after the head (e.g. a request body or
+    // TLS ClientHello) belongs to the upstream.
+    upstream.write_all(&buf[head_end..]).await?;
+    tokio::io::copy_bidirectional(&mut client, &mut upstream).await?;
+    Ok(())
+}
+
+// ── Docker networks and bookkeeping ─────────────────────────────────────────
+
+/// Per-agent network resources to release on stop.
+#[derive(Default)]
+pub struct AgentNetwork {
+    pub network: Option<String>,
+    pub proxy: Option<ProxyHandle>,
+}
+
+#[derive(Default)]
+pub struct NetworkRegistry {
+    agents: HashMap<String, AgentNetwork>,
+}
+
+impl NetworkRegistry {
+    pub fn register(&mut self, agent_id: &str, resources: AgentNetwork) {
+        if resources.network.is_some() || resources.proxy.is_some() {
+            self.agents.insert(agent_id.to_string(), resources);
+        }
+    }
+
+    /// Hand over an agent's resources for teardown; `None` once taken.
+    pub fn take(&mut self, agent_id: &str) -> Option<AgentNetwork> {
+        self.agents.remove(agent_id)
+    }
+
+    pub fn take_all(&mut self) -> Vec<AgentNetwork> {
+        self.agents.drain().map(|(_, r)| r).collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.agents.is_empty()
+    }
+}
+
+/// How to configure the container for a network mode.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct NetworkSetup {
+    /// `HostConfig.network_mode`; `None` keeps Docker's default bridge.
+    pub network_mode: Option<String>,
+    pub env: Vec<String>,
+    /// Whether the noVNC port can be published (not on internal networks).
+    pub publish_ports: bool,
+    /// Whether `host.docker.internal` should be mapped to the host gateway.
+    pub host_gateway: bool,
+}
+
+/// Create whatever `mode` needs for `agent_id`.
+pub async fn prepare(
+    docker: &Docker,
+    agent_id: &str,
+    mode: NetworkMode,
+    provider: &str,
+    callback_port: u16,
+) -> Result<(AgentNetwork, NetworkSetup), String> {
+    match mode {
+        NetworkMode::Full => Ok((AgentNetwork::default(), NetworkSetup {
+            publish_ports: true,
+            host_gateway: true,
+            ..Default::default()
+        })),
+        NetworkMode::None => Ok((AgentNetwork::default(), NetworkSetup {
+            network_mode: Some("none".to_string()),
+            ..Default::default()
+        })),
+        NetworkMode::LlmOnly => {
+            let allowlist = Allowlist::for_agent(provider, callback_port)?;
+            let name = format!("{}{}", NETWORK_PREFIX, agent_id);
+            docker.create_network(CreateNetworkOptions {
+                name: name.as_str(),
+                driver: "bridge",
+                internal: true,
+                ..Default::default()
+            }).await.map_err(|e| format!("Failed to create isolated network: {}", e))?;
+            let resources = AgentNetwork { network: Some(name.clone()), proxy: None };
+
+            // The host's address on the new bridge is the only thing the
+            // container can reach, so the proxy listens there.
+            let gateway = match network_gateway(docker, &name).await {
+                Ok(ip) => ip,
+                Err(e) => {
+                    teardown(docker, resources).await;
+                    return Err(e);
+                }
+            };
+            let proxy = match start_proxy(gateway, allowlist).await {
+                Ok(proxy) => proxy,
+                Err(e) => {
+                    teardown(docker, resources).await;
+                    return Err(format!("Failed to start egress proxy on {}: {}", gateway, e));
+                }
+            };
+            let proxy_url = format!("http://{}", proxy.addr);
+            let env = ["HTTP_PROXY", "HTTPS_PROXY", "http_proxy", "https_proxy"]
+                .iter()
+                .map(|var| format!("{}={}", var, proxy_url))
+                .chain(["NO_PROXY", "no_proxy"].iter().map(|var| format!("{}={}", var, LOOPBACK_HOSTS.join(","))))
+                .collect();
+            info!(agent_id = %agent_id, network = %name, proxy = %proxy_url, "Agent network isolated (llm-only)");
+            Ok((
+                AgentNetwork { network: Some(name.clone()), proxy: Some(proxy) },
+                NetworkSetup { network_mode: Some(name), env, publish_ports: false, host_gateway: true },
+            ))
+        }
+    }
+}
+
+/// Restart the egress proxy for an llm-only agent found running after a
+/// dashboard restart. The container keeps its network; only the proxy died
+/// with the previous process.
+pub async fn resume(
+    agent_id: &str,
+    network: String,
+    proxy_addr: SocketAddr,
+    provider: &str,
+    callback_port: u16,
+) -> Result<AgentNetwork, String> {
+    let allowlist = Allowlist::for_agent(provider, callback_port)?;
+    let proxy = start_proxy_at(proxy_addr, allowlist).await
+        .map_err(|e| format!("Failed to restart egress proxy on {}: {}", proxy_addr, e))?;
+    info!(agent_id = %agent_id, network = %network, proxy = %proxy.addr, "Egress proxy resumed");
+    Ok(AgentNetwork { network: Some(network), proxy: Some(proxy) })
+}
+
+async fn network_gateway(docker: &Docker, name: &str) -> Result<IpAddr, String> {
+    let network = docker.inspect_network::<String>(name, None).await.map_err(|e| e.to_string())?;
+    network.ipam
+        .and_then(|ipam| ipam.config)
+        .into_iter()
+        .flatten()
+        .filter_map(|c| c.gateway)
+        .find_map(|gw| gw.parse().ok())
+        .ok_or_else(|| format!("Network {} has no gateway address", name))
+}
+
+/// Stop the proxy and remove the network. Errors are logged, not returned:
+/// teardown runs on paths (exit, container death) that can't act on them.
+pub async fn teardown(docker: &Docker, resources: AgentNetwork) {
+    if let Some(proxy) = resources.proxy {
+        proxy.stop();
+    }
+    if let Some(network) = resources.network {
+        if let Err(e) = docker.remove_network(&network).await {
+            warn!(network = %network, error = %e, "Failed to remove agent network");
+        }
+    }
+}
+
+// ── Settings tooltip data ───────────────────────────────────────────────────
+
+#[derive(Debug, Serialize)]
+pub struct Reachability {
+    pub target: &'static str,
+    pub reachable: bool,
+    pub note: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NetworkModeInfo {
+    pub mode: NetworkMode,
+    pub label: &'static str,
+    pub description: &'static str,
+    pub reachability: Vec<Reachability>,
+}
+
+fn reach(target: &'static str, reachable: bool, note: &'static str) -> Reachability {
+    Reachability { target, reachable, note }
+}
+
+/// What an agent can reach under each network mode.
+#[tauri::command]
+pub fn get_network_modes() -> Vec<NetworkModeInfo> {
+    vec![
+        NetworkModeInfo {
+            mode: NetworkMode::Full,
+            label: "Full",
+            description: "Unrestricted outbound internet.",
+            reachability: vec![
+                reach("Cloud LLM providers", true, ""),
+                reach("Ollama on this machine", true, "via host.docker.internal"),
+                reach("Other internet hosts", true, ""),
+                reach("Dashboard callbacks", true, ""),
+                reach("Live view (noVNC)", true, ""),
+            ],
+        },
+        NetworkModeInfo {
+            mode: NetworkMode::LlmOnly,
+            label: "LLM only",
+            description: "Isolated network; a host proxy admits only the selected provider's API.",
+            reachability: vec![
+                reach("Cloud LLM providers", true, "only the provider selected for this agent"),
+                reach("Ollama on this machine", true, "when Ollama is the selected provider"),
+                reach("Other internet hosts", false, "blocked by the proxy; browse and search fail"),
+                reach("Dashboard callbacks", true, "via the proxy"),
+                reach("Live view (noVNC)", false, "ports cannot be published on an internal network"),
+            ],
+        },
+        NetworkModeInfo {
+            mode: NetworkMode::None,
+            label: "None",
+            description: "No network at all. Only useful for tasks that need no LLM calls.",
+            reachability: vec![
+                reach("Cloud LLM providers", false, ""),
+                reach("Ollama on this machine", false, "unless exposed through a mounted socket"),
+                reach("Other internet hosts", false, ""),
+                reach("Dashboard callbacks", false, "progress is visible only through container logs"),
+                reach("Live view (noVNC)", false, ""),
+            ],
+        },
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    #[test]
+    fn test_allowlist_decisions() {
+        let allow = Allowlist::for_agent("anthropic", 9876).unwrap();
+        assert_eq!(allow.decide("api.anthropic.com", 443), Some(("api.anthropic.com".into(), 443)));
+        assert_eq!(allow.decide("API.Anthropic.com.", 443), Some(("api.anthropic.com".into(), 443)));
+        assert_eq!(allow.decide("api.anthropic.com", 80), None);
+        assert_eq!(allow.decide("api.openai.com", 443), None);
+        assert_eq!(allow.decide("evil.api.anthropic.com", 443), None);
+        assert_eq!(allow.decide("host.docker.internal", 9876), Some(("127.0.0.1".into(), 9876)));
+        assert_eq!(allow.decide("host.docker.internal", 22), None);
+
+        let ollama = Allowlist::for_agent("ollama", 9876).unwrap();
+        assert_eq!(ollama.decide("host.docker.internal", 11434), Some(("127.0.0.1".into(), 11434)));
+
+        // In-container Ollama: only the callback server is reachable.
+        let local = Allowlist::for_agent("ollama-local", 9876).unwrap();
+        assert_eq!(local.decide("localhost", 11434), None);
+        assert_eq!(local.decide("host.docker.internal", 9876), Some(("127.0.0.1".into(), 9876)));
+
+        let custom = Allowlist::for_agent("https://llm.internal:8443/v1", 9876).unwrap();
+        assert_eq!(custom.decide("llm.internal", 8443), Some(("llm.internal".into(), 8443)));
+        assert!(Allowlist::for_agent("not-a-url", 9876).is_err());
+
+        let moved = Allowlist::for_agent("anthropic", 19876).unwrap();
+        assert_eq!(moved.decide("host.docker.internal", 19876), Some(("127.0.0.1".into(), 19876)));
+        assert_eq!(moved.decide("host.docker.internal", 9876), None);
+    }
+
+    #[test]
+    fn test_parse_request() {
+        assert_eq!(
+            parse_request("CONNECT api.openai.com:443 HTTP/1.1\r\nHost: api.openai.com:443\r\n\r\n").unwrap(),
+            ProxyRequest::Connect { host: "api.openai.com".into(), port: 443 }
+        );
+        let ProxyRequest::Forward { host, port, head } =
+            parse_request("POST http://host.docker.internal:9876/log HTTP/1.1\r\nHost: x\r\n\r\n").unwrap()
+        else { panic!("expected forward") };
+        assert_eq!((host.as_str(), port), ("host.docker.internal", 9876));
+        assert_eq!(String::from_utf8(head).unwrap(), "POST /log HTTP/1.1\r\nHost: x\r\n\r\n");
+        assert!(parse_request("GET /relative HTTP/1.1\r\n\r\n").is_err());
+    }
+
+    async fn proxy_request(proxy: SocketAddr, request: &str) -> String {
+        let mut stream = TcpStream::connect(proxy).await.unwrap();
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = Vec::new();
+        let _ = tokio::time::timeout(std::time::Duration::from_secs(2), stream.read_to_end(&mut response)).await;
+        String::from_utf8_lossy(&response).into_owned()
+    }
+
+    #[tokio::test]
+    async fn test_proxy_forwards_allowed_and_blocks_others() {
+        let upstream = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let upstream_port = upstream.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (mut s, _) = upstream.accept().await.unwrap();
+            let mut buf = [0u8; 1024];
+            let n = s.read(&mut buf).await.unwrap();
+            let line = String::from_utf8_lossy(&buf[..n]).lines().next().unwrap_or("").to_string();
+            s.write_all(format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{}", line.len(), line).as_bytes()).await.unwrap();
+        });
+
+        let mut allow = Allowlist::default();
+        allow.allow("host.docker.internal", upstream_port);
+        let proxy = start_proxy(IpAddr::V4(Ipv4Addr::LOCALHOST), allow).await.unwrap();
+
+        let ok = proxy_request(proxy.addr, &format!(
+            "GET http://host.docker.internal:{}/status HTTP/1.1\r\nHost: host.docker.internal\r\n\r\n", upstream_port
+        )).await;
+        assert!(ok.starts_with("HTTP/1.1 200 OK"), "{}", ok);
+        assert!(ok.ends_with("GET /status HTTP/1.1"), "{}", ok);
+
+        let denied = proxy_request(proxy.addr, "CONNECT example.com:443 HTTP/1.1\r\n\r\n").await;
+        assert!(denied.starts_with("HTTP/1.1 403"), "{}", denied);
+
+        let addr = proxy.addr;
+        proxy.stop();
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        assert!(TcpStream::connect(addr).await.is_err());
+    }
+
+    #[test]
+    fn test_registry_teardown_bookkeeping() {
+        let mut registry = NetworkRegistry::default();
+        registry.register("sentinel-full", AgentNetwork::default());
+        assert!(registry.is_empty(), "modes without resources are not tracked");
+
+        registry.register("sentinel-a", AgentNetwork { network: Some("sentinel-net-sentinel-a".into()), proxy: None });
+        registry.register("sentinel-b", AgentNetwork { network: Some("sentinel-net-sentinel-b".into()), proxy: None });
+        let a = registry.take("sentinel-a").unwrap();
+        assert_eq!(a.network.as_deref(), Some("sentinel-net-sentinel-a"));
+        assert!(registry.take("sentinel-a").is_none(), "resources are torn down once");
+
+        assert_eq!(registry.take_all().len(), 1);
+        assert!(registry.is_empty());
+    }
+
+    #[test]
+    fn test_mode_serde() {
+        assert_eq!(serde_json::to_string(&NetworkMode::LlmOnly).unwrap(), r#""llm-only""#);
+        assert_eq!(serde_json::from_str::<NetworkMode>(r#""none""#).unwrap(), NetworkMode::None);
+        assert_eq!(get_network_modes().len(), 3);
+    }
+}
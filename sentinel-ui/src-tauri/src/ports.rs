@@ -0,0 +1,225 @@
+//! noVNC host port allocation.
+//!
+//! Each agent container publishes its noVNC server (container port 6080) on
+//! a distinct host port. The allocator scans a configurable range, probes
+//! each candidate with a real bind so ports held by other processes are
+//! skipped, and remembers which agent owns which port until it is released.
+
+use crate::commands::AgentState;
+use bollard::models::PortBinding;
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::net::{Ipv4Addr, TcpListener};
+use std::ops::RangeInclusive;
+use tracing::{info, warn};
+
+/// noVNC port inside the agent image.
+pub const CONTAINER_NOVNC_PORT: u16 = 6080;
+
+/// Default host range, overridable via `SENTINEL_NOVNC_PORT_RANGE=start-end`.
+pub const DEFAULT_PORT_RANGE: RangeInclusive<u16> = 6080..=6179;
+
+/// How many ports to try when Docker reports a bind conflict at start.
+pub const MAX_PORT_ATTEMPTS: usize = 5;
+
+#[derive(Debug)]
+pub struct PortAllocator {
+    range: RangeInclusive<u16>,
+    allocated: HashMap<String, u16>,
+}
+
+impl Default for PortAllocator {
+    fn default() -> Self {
+        let range = std::env::var("SENTINEL_NOVNC_PORT_RANGE")
+            .ok()
+            .and_then(|r| parse_range(&r))
+            .unwrap_or(DEFAULT_PORT_RANGE);
+        Self::new(range)
+    }
+}
+
+impl PortAllocator {
+    pub fn new(range: RangeInclusive<u16>) -> Self {
+        Self { range, allocated: HashMap::new() }
+    }
+
+    /// Reserve the first free port for `agent_id`, skipping ports that are
+    /// already allocated, listed in `skip`, or bound by another process.
+    pub fn allocate(&mut self, agent_id: &str, skip: &[u16]) -> Result<u16, String> {
+        if let Some(&port) = self.allocated.get(agent_id) {
+            if !skip.contains(&port) {
+                return Ok(port);
+            }
+            self.allocated.remove(agent_id);
+        }
+        let taken: HashSet<u16> = self.allocated.values().copied().collect();
+        let port = self.range.clone()
+            .filter(|p| !taken.contains(p) && !skip.contains(p))
+            .find(|&p| is_port_free(p))
+            .ok_or_else(|| format!(
+                "No free noVNC port in {}-{}",
+                self.range.start(), self.range.end()
+            ))?;
+        self.allocated.insert(agent_id.to_string(), port);
+        info!(agent_id = %agent_id, port, "noVNC port allocated");
+        Ok(port)
+    }
+
+    /// Record a port Docker already published for `agent_id` (re-attach).
+    pub fn adopt(&mut self, agent_id: &str, port: u16) {
+        self.allocated.insert(agent_id.to_string(), port);
+    }
+
+    /// Return the agent's port to the pool.
+    pub fn release(&mut self, agent_id: &str) -> Option<u16> {
+        self.allocated.remove(agent_id)
+    }
+
+    pub fn get(&self, agent_id: &str) -> Option<u16> {
+        self.allocated.get(agent_id).copied()
+    }
+}
+
+/// Publish the container's noVNC port on `host_port`, loopback only.
+pub fn novnc_port_bindings(host_port: u16) -> HashMap<String, Option<Vec<PortBinding>>> {
+    HashMap::from([(
+        format!("{}/tcp", CONTAINER_NOVNC_PORT),
+        Some(vec![PortBinding {
+            host_ip: Some("127.0.0.1".to_string()),
+            host_port: Some(host_port.to_string()),
+        }]),
+    )])
+}
+
+/// Probe a port by binding it on all interfaces, as Docker would.
+pub fn is_port_free(port: u16) -> bool {
+    TcpListener::bind((Ipv4Addr::UNSPECIFIED, port)).is_ok()
+}
+
+/// Whether a Docker error means the published host port was taken.
+pub fn is_port_conflict(err: &str) -> bool {
+    let err = err.to_lowercase();
+    err.contains("port is already allocated") || err.contains("address already in use")
+}
+
+/// Run `attempt` with successive ports until it succeeds or fails for a
+/// reason other than a port conflict.
+///
+/// Covers the race between our bind probe and Docker binding the port.
+pub async fn with_port_retry<F, Fut, T>(
+    state: &tokio::sync::Mutex<AgentState>,
+    agent_id: &str,
+    mut attempt: F,
+) -> Result<(T, u16), String>
+where
+    F: FnMut(u16) -> Fut,
+    Fut: Future<Output = Result<T, String>>,
+{
+    let mut conflicted = Vec::new();
+    for _ in 0..MAX_PORT_ATTEMPTS {
+        let port = state.lock().await.ports.allocate(agent_id, &conflicted)?;
+        match attempt(port).await {
+            Ok(value) => return Ok((value, port)),
+            Err(e) if is_port_conflict(&e) => {
+                warn!(agent_id = %agent_id, port, "noVNC port taken at start — retrying");
+                conflicted.push(port);
+            }
+            Err(e) => {
+                state.lock().await.ports.release(agent_id);
+                return Err(e);
+            }
+        }
+    }
+    state.lock().await.ports.release(agent_id);
+    Err(format!("Could not bind a noVNC port after {} attempts", MAX_PORT_ATTEMPTS))
+}
+
+fn parse_range(s: &str) -> Option<RangeInclusive<u16>> {
+    let (start, end) = s.split_once('-')?;
+    let (start, end) = (start.trim().parse().ok()?, end.trim().parse().ok()?);
+    (start <= end).then_some(start..=end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::Mutex;
+
+    fn state_with_range(range: RangeInclusive<u16>) -> Mutex<AgentState> {
+        Mutex::new(AgentState { ports: PortAllocator::new(range), ..Default::default() })
+    }
+
+    /// Find a run of `len` consecutive free ports to use as a test range.
+    fn free_range(len: u16) -> RangeInclusive<u16> {
+        (20000..60000u16).step_by(len as usize)
+            .find(|&start| (start..start + len).all(is_port_free))
+            .map(|start| start..=start + len - 1)
+            .expect("no free port range")
+    }
+
+    #[test]
+    fn test_allocate_and_release() {
+        let range = free_range(3);
+        let mut ports = PortAllocator::new(range.clone());
+        let a = ports.allocate("agent-a", &[]).unwrap();
+        let b = ports.allocate("agent-b", &[]).unwrap();
+        assert_ne!(a, b);
+        assert_eq!(ports.allocate("agent-a", &[]).unwrap(), a);
+        assert_eq!(ports.get("agent-b"), Some(b));
+
+        assert_eq!(ports.release("agent-a"), Some(a));
+        assert_eq!(ports.get("agent-a"), None);
+        assert_eq!(ports.allocate("agent-c", &[]).unwrap(), a);
+    }
+
+    #[test]
+    fn test_skips_occupied_ports() {
+        let range = free_range(2);
+        let _occupied = TcpListener::bind((Ipv4Addr::UNSPECIFIED, *range.start())).unwrap();
+        let mut ports = PortAllocator::new(range.clone());
+        assert_eq!(ports.allocate("agent-a", &[]).unwrap(), *range.end());
+        assert!(ports.allocate("agent-b", &[]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_retry_on_conflict() {
+        let range = free_range(3);
+        let state = state_with_range(range.clone());
+        let mut held = None;
+
+        // Simulate another process grabbing the port between our probe and
+        // Docker's bind on the first attempt.
+        let (bound, port) = with_port_retry(&state, "agent-a", |port| {
+            let first = held.is_none();
+            if first {
+                held = Some(TcpListener::bind((Ipv4Addr::UNSPECIFIED, port)).unwrap());
+            }
+            async move {
+                if first {
+                    Err("Bind for 0.0.0.0 failed: port is already allocated".to_string())
+                } else {
+                    TcpListener::bind((Ipv4Addr::UNSPECIFIED, port)).map_err(|e| e.to_string())
+                }
+            }
+        }).await.unwrap();
+
+        assert_eq!(port, range.start() + 1);
+        assert_eq!(bound.local_addr().unwrap().port(), port);
+        assert_eq!(state.lock().await.ports.get("agent-a"), Some(port));
+    }
+
+    #[tokio::test]
+    async fn test_non_conflict_error_releases() {
+        let state = state_with_range(free_range(2));
+        let result = with_port_retry(&state, "agent-a", |_| async { Err::<(), _>("No such image".to_string()) }).await;
+        assert_eq!(result.unwrap_err(), "No such image");
+        assert_eq!(state.lock().await.ports.get("agent-a"), None);
+    }
+
+    #[test]
+    fn test_parse_range() {
+        assert_eq!(parse_range("7000-7010"), Some(7000..=7010));
+        assert_eq!(parse_range("7010-7000"), None);
+        assert_eq!(parse_range("nope"), None);
+    }
+}
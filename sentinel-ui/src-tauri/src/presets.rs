@@ -0,0 +1,304 @@
+//! Saved launch presets.
+//!
+//! A preset bundles the launch parameters for a kind of run ("quick local
+//! triage", "deep paid audit"). `start_agent` accepts a `preset_id`; the
+//! preset seeds every parameter and explicit arguments still win.
+
+use crate::commands::{self, ProviderInfo};
+use crate::mounts::MountSpec;
+use crate::network::NetworkMode;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tauri::State;
+use tokio::sync::Mutex;
+use tracing::warn;
+
+pub const PRESETS_FILE: &str = "presets.json";
+
+/// Autonomy levels the agent understands.
+pub const AUTONOMY_LEVELS: &[&str] = &["full", "read_report", "ask_write", "read_only"];
+
+/// Used when neither the preset nor the caller picks an autonomy level.
+pub const DEFAULT_AUTONOMY: &str = "read_report";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LaunchPreset {
+    /// Assigned on first save when empty.
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    pub provider: String,
+    pub model: String,
+    pub autonomy: String,
+    #[serde(default)]
+    pub max_iterations: Option<u32>,
+    /// Per-response token cap passed to the LLM.
+    #[serde(default)]
+    pub max_tokens: Option<u32>,
+    #[serde(default)]
+    pub mounts: Vec<MountSpec>,
+    #[serde(default)]
+    pub network_mode: Option<NetworkMode>,
+}
+
+impl LaunchPreset {
+    /// Check the preset against the providers the dashboard currently offers.
+    pub fn validate(&self, providers: &[ProviderInfo]) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("Preset name is required".to_string());
+        }
+        if !providers.iter().any(|p| p.id == self.provider) {
+            return Err(format!("Unknown provider: {}", self.provider));
+        }
+        if self.model.trim().is_empty() {
+            return Err("Preset model is required".to_string());
+        }
+        if !AUTONOMY_LEVELS.contains(&self.autonomy.as_str()) {
+            return Err(format!("Unknown autonomy level: {}", self.autonomy));
+        }
+        if self.max_iterations == Some(0) || self.max_tokens == Some(0) {
+            return Err("Budgets must be greater than zero".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// A preset as listed to the UI, flagged if it no longer validates.
+#[derive(Debug, Clone, Serialize)]
+pub struct PresetSummary {
+    #[serde(flatten)]
+    pub preset: LaunchPreset,
+    pub error: Option<String>,
+}
+
+/// Launch parameters given explicitly to `start_agent`.
+#[derive(Debug, Clone, Default)]
+pub struct LaunchArgs {
+    pub provider: Option<String>,
+    pub model: Option<String>,
+    pub autonomy: Option<String>,
+    pub max_iterations: Option<u32>,
+    pub max_tokens: Option<u32>,
+    pub mounts: Option<Vec<MountSpec>>,
+    pub network_mode: Option<NetworkMode>,
+}
+
+/// Parameters after merging a preset with explicit arguments.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedLaunch {
+    pub provider: String,
+    pub model: String,
+    pub autonomy: String,
+    pub max_iterations: Option<u32>,
+    pub max_tokens: Option<u32>,
+    /// `None` falls back to the legacy `target_dir`.
+    pub mounts: Option<Vec<MountSpec>>,
+    /// `None` falls back to the Settings default.
+    pub network_mode: Option<NetworkMode>,
+}
+
+/// Merge `preset` under `args`: explicit values win, empty strings and
+/// empty mount lists count as unset.
+pub fn resolve_launch(preset: Option<&LaunchPreset>, args: LaunchArgs) -> Result<ResolvedLaunch, String> {
+    let pick = |explicit: Option<String>, seeded: Option<&String>| {
+        explicit.filter(|v| !v.trim().is_empty()).or_else(|| seeded.cloned())
+    };
+    let provider = pick(args.provider, preset.map(|p| &p.provider)).ok_or("A provider is required")?;
+    let model = pick(args.model, preset.map(|p| &p.model)).ok_or("A model is required")?;
+    let autonomy = pick(args.autonomy, preset.map(|p| &p.autonomy)).unwrap_or_else(|| DEFAULT_AUTONOMY.to_string());
+    let mounts = args.mounts.filter(|m| !m.is_empty())
+        .or_else(|| preset.map(|p| p.mounts.clone()).filter(|m| !m.is_empty()));
+    Ok(ResolvedLaunch {
+        provider,
+        model,
+        autonomy,
+        max_iterations: args.max_iterations.or(preset.and_then(|p| p.max_iterations)),
+        max_tokens: args.max_tokens.or(preset.and_then(|p| p.max_tokens)),
+        mounts,
+        network_mode: args.network_mode.or(preset.and_then(|p| p.network_mode)),
+    })
+}
+
+pub struct PresetStore {
+    path: PathBuf,
+    presets: Mutex<Vec<LaunchPreset>>,
+}
+
+pub type SharedPresets = Arc<PresetStore>;
+
+impl PresetStore {
+    /// Load presets from `path`; a missing or unreadable file means none.
+    pub fn load(path: PathBuf) -> Self {
+        let presets = match std::fs::read_to_string(&path) {
+            Ok(raw) => serde_json::from_str(&raw).unwrap_or_else(|e| {
+                warn!(path = %path.display(), error = %e, "Invalid presets file, ignoring");
+                Vec::new()
+            }),
+            Err(_) => Vec::new(),
+        };
+        Self { path, presets: Mutex::new(presets) }
+    }
+
+    /// All presets, each validated against `providers`.
+    pub async fn list(&self, providers: &[ProviderInfo]) -> Vec<PresetSummary> {
+        self.presets.lock().await.iter()
+            .map(|p| PresetSummary { preset: p.clone(), error: p.validate(providers).err() })
+            .collect()
+    }
+
+    /// A preset by id, rejected if it no longer validates.
+    pub async fn get(&self, id: &str, providers: &[ProviderInfo]) -> Result<LaunchPreset, String> {
+        let preset = self.presets.lock().await.iter().find(|p| p.id == id).cloned()
+            .ok_or_else(|| format!("Unknown preset: {}", id))?;
+        preset.validate(providers).map_err(|e| format!("Preset '{}' is invalid: {}", preset.name, e))?;
+        Ok(preset)
+    }
+
+    /// Insert or replace (by id) a preset and persist.
+    pub async fn save(&self, mut preset: LaunchPreset, providers: &[ProviderInfo]) -> Result<LaunchPreset, String> {
+        preset.validate(providers)?;
+        if preset.id.is_empty() {
+            preset.id = uuid::Uuid::new_v4().to_string();
+        }
+        let mut presets = self.presets.lock().await;
+        let mut updated = presets.clone();
+        match updated.iter_mut().find(|p| p.id == preset.id) {
+            Some(existing) => *existing = preset.clone(),
+            None => updated.push(preset.clone()),
+        }
+        self.write(&updated).await?;
+        *presets = updated;
+        Ok(preset)
+    }
+
+    pub async fn delete(&self, id: &str) -> Result<(), String> {
+        let mut presets = self.presets.lock().await;
+        let updated: Vec<LaunchPreset> = presets.iter().filter(|p| p.id != id).cloned().collect();
+        if updated.len() == presets.len() {
+            return Err(format!("Unknown preset: {}", id));
+        }
+        self.write(&updated).await?;
+        *presets = updated;
+        Ok(())
+    }
+
+    async fn write(&self, presets: &[LaunchPreset]) -> Result<(), String> {
+        if let Some(parent) = self.path.parent() {
+            tokio::fs::create_dir_all(parent).await.map_err(|e| e.to_string())?;
+        }
+        let raw = serde_json::to_string_pretty(presets).map_err(|e| e.to_string())?;
+        tokio::fs::write(&self.path, raw).await.map_err(|e| e.to_string())
+    }
+}
+
+#[tauri::command]
+pub async fn save_preset(store: State<'_, SharedPresets>, preset: LaunchPreset) -> Result<LaunchPreset, String> {
+    store.save(preset, &commands::providers()).await
+}
+
+#[tauri::command]
+pub async fn list_presets(store: State<'_, SharedPresets>) -> Result<Vec<PresetSummary>, String> {
+    Ok(store.list(&commands::providers()).await)
+}
+
+#[tauri::command]
+pub async fn delete_preset(store: State<'_, SharedPresets>, id: String) -> Result<(), String> {
+    store.delete(&id).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mounts::MountMode;
+
+    fn triage() -> LaunchPreset {
+        LaunchPreset {
+            id: String::new(),
+            name: "Quick local triage".into(),
+            provider: "ollama".into(),
+            model: "qwen2.5:7b".into(),
+            autonomy: "read_only".into(),
+            max_iterations: Some(10),
+            max_tokens: None,
+            mounts: vec![MountSpec { host_path: "/src".into(), container_path: "/workspace".into(), mode: MountMode::Ro }],
+            network_mode: Some(NetworkMode::LlmOnly),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_persistence_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(PRESETS_FILE);
+        let providers = commands::providers();
+
+        let store = PresetStore::load(path.clone());
+        let saved = store.save(triage(), &providers).await.unwrap();
+        assert!(!saved.id.is_empty());
+        let audit = LaunchPreset { name: "Deep paid audit".into(), provider: "anthropic".into(), model: "claude-3-5-sonnet-20241022".into(), autonomy: "read_report".into(), max_iterations: Some(40), ..triage() };
+        let audit = store.save(audit, &providers).await.unwrap();
+
+        // Re-saving with the same id replaces rather than duplicates.
+        store.save(LaunchPreset { max_iterations: Some(12), ..saved.clone() }, &providers).await.unwrap();
+
+        let reloaded = PresetStore::load(path.clone());
+        let listed = reloaded.list(&providers).await;
+        assert_eq!(listed.len(), 2);
+        assert_eq!(listed[0].preset.max_iterations, Some(12));
+        assert_eq!(listed[0].preset.mounts, triage().mounts);
+        assert_eq!(listed[1].preset, audit);
+
+        reloaded.delete(&saved.id).await.unwrap();
+        assert!(reloaded.delete(&saved.id).await.is_err());
+        assert_eq!(PresetStore::load(path).list(&providers).await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_validation_on_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(PRESETS_FILE);
+        let stale = LaunchPreset { id: "old".into(), provider: "retired-llm".into(), ..triage() };
+        std::fs::write(&path, serde_json::to_string(&vec![stale]).unwrap()).unwrap();
+
+        let store = PresetStore::load(path);
+        let providers = commands::providers();
+        let listed = store.list(&providers).await;
+        assert_eq!(listed[0].error.as_deref(), Some("Unknown provider: retired-llm"));
+        assert!(store.get("old", &providers).await.unwrap_err().contains("invalid"));
+        assert!(store.save(LaunchPreset { autonomy: "yolo".into(), ..triage() }, &providers).await.is_err());
+    }
+
+    #[test]
+    fn test_override_precedence() {
+        let preset = triage();
+        let seeded = resolve_launch(Some(&preset), LaunchArgs::default()).unwrap();
+        assert_eq!(seeded.provider, "ollama");
+        assert_eq!(seeded.autonomy, "read_only");
+        assert_eq!(seeded.max_iterations, Some(10));
+        assert_eq!(seeded.mounts, Some(preset.mounts.clone()));
+        assert_eq!(seeded.network_mode, Some(NetworkMode::LlmOnly));
+
+        let overridden = resolve_launch(Some(&preset), LaunchArgs {
+            model: Some("llama3.3:latest".into()),
+            provider: Some(String::new()),
+            max_iterations: Some(3),
+            network_mode: Some(NetworkMode::Full),
+            mounts: Some(vec![]),
+            ..Default::default()
+        }).unwrap();
+        assert_eq!(overridden.provider, "ollama", "empty explicit values don't clear the preset");
+        assert_eq!(overridden.model, "llama3.3:latest");
+        assert_eq!(overridden.max_iterations, Some(3));
+        assert_eq!(overridden.network_mode, Some(NetworkMode::Full));
+        assert_eq!(overridden.mounts, Some(preset.mounts));
+
+        let bare = resolve_launch(None, LaunchArgs {
+            provider: Some("openai".into()),
+            model: Some("gpt-4o".into()),
+            ..Default::default()
+        }).unwrap();
+        assert_eq!(bare.autonomy, DEFAULT_AUTONOMY);
+        assert_eq!(bare.mounts, None);
+        assert!(resolve_launch(None, LaunchArgs::default()).is_err());
+    }
+}
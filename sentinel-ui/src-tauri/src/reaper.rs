@@ -0,0 +1,255 @@
+//! Agent container cleanup.
+//!
+//! Two paths keep `sentinel-*` containers from piling up:
+//! - on exit, every container tracked in `AgentState` is stopped and removed
+//!   (unless the user chose to leave them running);
+//! - at startup, untracked containers are reaped when exited or older than
+//!   the configured age, and still-running ones are offered for adoption via
+//!   `sentinel://orphans-found`.
+//!
+//! Docker may be unavailable at either point (daemon starting, laptop just
+//! woke up); both paths log and carry on rather than failing the app.
+
+use crate::callback_server::EventSink;
+use crate::commands::SharedAgentState;
+use crate::network;
+use crate::settings::DashboardSettings;
+use bollard::container::{ListContainersOptions, RemoveContainerOptions, StopContainerOptions};
+use bollard::models::ContainerSummary;
+use bollard::Docker;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{info, warn};
+
+/// Container name prefix for agents started by the dashboard.
+pub const CONTAINER_PREFIX: &str = "sentinel-";
+
+const DOCKER_ATTEMPTS: u32 = 3;
+const DOCKER_RETRY_DELAY: Duration = Duration::from_secs(2);
+const STOP_TIMEOUT_SECS: i64 = 5;
+
+/// The parts of a Docker container listing the reaper decides on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContainerEntry {
+    pub id: String,
+    pub name: String,
+    pub state: String,
+    /// Creation time, seconds since the Unix epoch.
+    pub created: i64,
+}
+
+impl ContainerEntry {
+    fn from_summary(summary: ContainerSummary) -> Option<Self> {
+        let name = summary.names?.into_iter().next()?.trim_start_matches('/').to_string();
+        Some(Self {
+            id: summary.id?,
+            name,
+            state: summary.state.unwrap_or_default(),
+            created: summary.created.unwrap_or(0),
+        })
+    }
+}
+
+/// A running container the user may re-attach to.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct OrphanInfo {
+    pub container_id: String,
+    pub name: String,
+    pub age_secs: u64,
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ReapPlan {
+    pub remove: Vec<ContainerEntry>,
+    pub adopt: Vec<OrphanInfo>,
+}
+
+/// Decide what to do with each listed container.
+///
+/// Only `sentinel-*` containers not already tracked are considered. Anything
+/// not running (exited, dead, created) or older than `max_age` is removed;
+/// the rest are adoption candidates.
+pub fn plan_reap(
+    containers: Vec<ContainerEntry>,
+    tracked: &HashSet<String>,
+    now: SystemTime,
+    max_age: Duration,
+) -> ReapPlan {
+    let now_secs = now.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() as i64;
+    let mut plan = ReapPlan::default();
+    for c in containers {
+        if !c.name.starts_with(CONTAINER_PREFIX) || tracked.contains(&c.name) || tracked.contains(&c.id) {
+            continue;
+        }
+        let age_secs = (now_secs - c.created).max(0) as u64;
+        if c.state != "running" || age_secs > max_age.as_secs() {
+            plan.remove.push(c);
+        } else {
+            plan.adopt.push(OrphanInfo { container_id: c.id, name: c.name, age_secs });
+        }
+    }
+    plan
+}
+
+/// Connect to Docker and list `sentinel-*` containers, retrying briefly.
+async fn list_agent_containers() -> Option<(Docker, Vec<ContainerEntry>)> {
+    let options = ListContainersOptions::<String> {
+        all: true,
+        filters: HashMap::from([("name".to_string(), vec![CONTAINER_PREFIX.to_string()])]),
+        ..Default::default()
+    };
+    for attempt in 1..=DOCKER_ATTEMPTS {
+        let listed = match Docker::connect_with_local_defaults() {
+            Ok(docker) => docker.list_containers(Some(options.clone())).await
+                .map(|list| (docker, list))
+                .map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+        match listed {
+            Ok((docker, list)) => {
+                let entries = list.into_iter().filter_map(ContainerEntry::from_summary).collect();
+                return Some((docker, entries));
+            }
+            Err(e) => {
+                warn!(attempt, error = %e, "Docker unavailable while listing agent containers");
+                if attempt < DOCKER_ATTEMPTS {
+                    tokio::time::sleep(DOCKER_RETRY_DELAY).await;
+                }
+            }
+        }
+    }
+    None
+}
+
+async fn stop_and_remove(docker: &Docker, id: &str) {
+    let _ = docker.stop_container(id, Some(StopContainerOptions { t: STOP_TIMEOUT_SECS })).await;
+    if let Err(e) = docker.remove_container(id, Some(RemoveContainerOptions { force: true, ..Default::default() })).await {
+        // Containers started with auto_remove are already gone after stop.
+        info!(container = %id, error = %e, "Container not removed (may already be gone)");
+    }
+}
+
+/// Startup reaper: remove stale containers and announce adoptable ones.
+pub async fn reap_orphans(agents: SharedAgentState, settings: DashboardSettings, sink: &dyn EventSink) {
+    let Some((docker, containers)) = list_agent_containers().await else {
+        warn!("Skipping orphan reaping — Docker is not reachable");
+        return;
+    };
+    let tracked: HashSet<String> = agents.lock().await.active_agents.keys().cloned().collect();
+    let plan = plan_reap(containers, &tracked, SystemTime::now(), Duration::from_secs(settings.orphan_max_age_secs));
+
+    for c in &plan.remove {
+        info!(container = %c.name, state = %c.state, "Reaping orphaned agent container");
+        stop_and_remove(&docker, &c.id).await;
+    }
+    if !plan.adopt.is_empty() {
+        info!(count = plan.adopt.len(), "Found running orphaned agent containers");
+        sink.emit("sentinel://orphans-found", serde_json::json!(plan.adopt));
+    }
+}
+
+/// Exit hook: stop and remove every tracked container unless configured
+/// to leave them running.
+pub async fn cleanup_on_exit(agents: SharedAgentState, settings: DashboardSettings) {
+    if settings.leave_containers_running {
+        info!("Leaving agent containers running on exit");
+        return;
+    }
+    let ids: Vec<String> = {
+        let mut s = agents.lock().await;
+        let ids: Vec<String> = s.active_agents.values().cloned().collect();
+        // These exits are ours; don't let a restart policy revive them.
+        s.stop_requested.extend(s.active_agents.keys().cloned().collect::<Vec<_>>());
+        ids
+    };
+    if ids.is_empty() {
+        return;
+    }
+    let docker = match Docker::connect_with_local_defaults() {
+        Ok(docker) => docker,
+        Err(e) => {
+            warn!(error = %e, "Docker unavailable on exit — agent containers left running");
+            return;
+        }
+    };
+    info!(count = ids.len(), "Stopping agent containers on exit");
+    futures_util::future::join_all(ids.iter().map(|id| stop_and_remove(&docker, id))).await;
+
+    // Isolated networks can only be removed once their containers are gone.
+    let networks = agents.lock().await.networks.take_all();
+    futures_util::future::join_all(networks.into_iter().map(|n| network::teardown(&docker, n))).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: u64 = 1_700_000_000;
+    const HOUR: i64 = 3600;
+
+    fn entry(name: &str, state: &str, age_secs: i64) -> ContainerEntry {
+        ContainerEntry {
+            id: format!("id-{}", name),
+            name: name.to_string(),
+            state: state.to_string(),
+            created: NOW as i64 - age_secs,
+        }
+    }
+
+    fn plan(containers: Vec<ContainerEntry>, tracked: &[&str]) -> ReapPlan {
+        let tracked = tracked.iter().map(|s| s.to_string()).collect();
+        plan_reap(containers, &tracked, UNIX_EPOCH + Duration::from_secs(NOW), Duration::from_secs(24 * HOUR as u64))
+    }
+
+    #[test]
+    fn test_ignores_foreign_and_tracked_containers() {
+        let result = plan(vec![
+            entry("postgres", "exited", 100 * HOUR),
+            entry("my-sentinel-db", "running", 100 * HOUR),
+            entry("sentinel-aaaa1111", "running", 100 * HOUR),
+        ], &["sentinel-aaaa1111"]);
+        assert_eq!(result, ReapPlan::default());
+    }
+
+    #[test]
+    fn test_removes_exited_and_stale() {
+        let result = plan(vec![
+            entry("sentinel-exited", "exited", HOUR),
+            entry("sentinel-created", "created", 10),
+            entry("sentinel-dead", "dead", HOUR),
+            entry("sentinel-stale", "running", 25 * HOUR),
+        ], &[]);
+        let removed: Vec<&str> = result.remove.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(removed, ["sentinel-exited", "sentinel-created", "sentinel-dead", "sentinel-stale"]);
+        assert!(result.adopt.is_empty());
+    }
+
+    #[test]
+    fn test_offers_recent_running_for_adoption() {
+        let result = plan(vec![
+            entry("sentinel-recent", "running", 2 * HOUR),
+            entry("sentinel-old", "exited", 2 * HOUR),
+        ], &[]);
+        assert_eq!(result.adopt, vec![OrphanInfo {
+            container_id: "id-sentinel-recent".to_string(),
+            name: "sentinel-recent".to_string(),
+            age_secs: 2 * HOUR as u64,
+        }]);
+        assert_eq!(result.remove.len(), 1);
+    }
+
+    #[test]
+    fn test_from_summary_strips_slash() {
+        let summary = ContainerSummary {
+            id: Some("abc".to_string()),
+            names: Some(vec!["/sentinel-1234abcd".to_string()]),
+            state: Some("running".to_string()),
+            created: Some(42),
+            ..Default::default()
+        };
+        let entry = ContainerEntry::from_summary(summary).unwrap();
+        assert_eq!(entry.name, "sentinel-1234abcd");
+        assert_eq!(entry.created, 42);
+    }
+}
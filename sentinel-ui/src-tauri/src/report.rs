@@ -0,0 +1,174 @@
+//! Post-run report retrieval.
+//!
+//! The agent's final `/status` callback names the report it wrote (a path
+//! inside the container) and its SHA-256. While the agent waits on that
+//! callback the host copies the file out through Docker's archive API, so
+//! it works for read-only workspaces and runs with no workspace at all.
+//! The verified report is stored in the session dir and sent to the chat
+//! as a single `report` message. Only if no handoff ever arrived does the
+//! host probe the primary writable mount on its own side.
+
+use crate::callback_server::EventSink;
+use crate::messages::{self, AgentMessage, MessageKind};
+use crate::session::{SharedSessions, REPORT_FILE};
+use bollard::container::DownloadFromContainerOptions;
+use bollard::Docker;
+use futures_util::StreamExt;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::io::Read;
+use std::path::{Component, Path};
+use tracing::{info, warn};
+
+/// Largest report the host will accept.
+pub const MAX_REPORT_BYTES: u64 = 5 * 1024 * 1024;
+
+/// The report announced in the agent's final status callback.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ReportHandoff {
+    /// Absolute path inside the container.
+    pub path: String,
+    /// Lowercase hex SHA-256 of the file contents.
+    pub sha256: String,
+}
+
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Check `bytes` against the agent's hash; a mismatch means a truncated
+/// copy or a file rewritten after the handoff.
+pub fn verify(bytes: &[u8], expected: &str) -> Result<(), String> {
+    let actual = sha256_hex(bytes);
+    if actual.eq_ignore_ascii_case(expected.trim()) {
+        Ok(())
+    } else {
+        Err(format!("Report hash mismatch: expected {}, got {}", expected.trim(), actual))
+    }
+}
+
+fn validate_container_path(path: &str) -> Result<(), String> {
+    let p = Path::new(path);
+    if !p.is_absolute() || p.components().any(|c| matches!(c, Component::ParentDir)) {
+        return Err(format!("Invalid report path: {}", path));
+    }
+    Ok(())
+}
+
+/// The single regular file in a Docker archive (`GET /containers/{id}/archive`).
+pub fn extract_file(archive: &[u8]) -> Result<Vec<u8>, String> {
+    let mut archive = tar::Archive::new(archive);
+    for entry in archive.entries().map_err(|e| e.to_string())? {
+        let entry = entry.map_err(|e| e.to_string())?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        if entry.size() > MAX_REPORT_BYTES {
+            return Err(format!("Report exceeds {} bytes", MAX_REPORT_BYTES));
+        }
+        let mut contents = Vec::with_capacity(entry.size() as usize);
+        entry.take(MAX_REPORT_BYTES).read_to_end(&mut contents).map_err(|e| e.to_string())?;
+        return Ok(contents);
+    }
+    Err("Archive contains no file".to_string())
+}
+
+/// Copy the report out of the container and verify it.
+pub async fn fetch(docker: &Docker, container: &str, handoff: &ReportHandoff) -> Result<String, String> {
+    validate_container_path(&handoff.path)?;
+    let mut stream = docker.download_from_container(container, Some(DownloadFromContainerOptions { path: handoff.path.as_str() }));
+    let mut archive = Vec::new();
+    while let Some(chunk) = stream.next().await {
+        archive.extend_from_slice(&chunk.map_err(|e| format!("Failed to copy report from container: {}", e))?);
+        // The tar wrapper adds headers and padding on top of the file.
+        if archive.len() as u64 > MAX_REPORT_BYTES + 64 * 1024 {
+            return Err(format!("Report exceeds {} bytes", MAX_REPORT_BYTES));
+        }
+    }
+    let bytes = extract_file(&archive)?;
+    verify(&bytes, &handoff.sha256)?;
+    String::from_utf8(bytes).map_err(|_| "Report is not valid UTF-8".to_string())
+}
+
+/// Store the report in the session dir and send it to the chat as one
+/// `report` message.
+pub fn deliver(sessions: &SharedSessions, sink: &dyn EventSink, agent_id: &str, report: &str) {
+    match sessions.save_report(agent_id, report) {
+        Ok(path) => info!(agent_id = %agent_id, path = %path.display(), "Report stored"),
+        Err(e) => warn!(agent_id = %agent_id, error = %e, "Failed to store report"),
+    }
+    messages::send(sessions, sink, &AgentMessage::new(agent_id, MessageKind::Report, report));
+}
+
+/// Fallback for agents that exited without a handoff (older images, a
+/// crash after writing): read `SENTINEL_REPORT.md` from the host side of
+/// the primary writable mount.
+pub fn probe_host(mount_dir: &Path) -> Option<String> {
+    let path = mount_dir.join(REPORT_FILE);
+    let meta = std::fs::metadata(&path).ok()?;
+    if !meta.is_file() || meta.len() > MAX_REPORT_BYTES {
+        return None;
+    }
+    std::fs::read_to_string(path).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn archive_of(name: &str, contents: &[u8]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(Vec::new());
+        let mut dir = tar::Header::new_gnu();
+        dir.set_entry_type(tar::EntryType::Directory);
+        dir.set_size(0);
+        dir.set_mode(0o755);
+        dir.set_cksum();
+        builder.append_data(&mut dir, "out/", std::io::empty()).unwrap();
+        let mut header = tar::Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder.append_data(&mut header, name, contents).unwrap();
+        builder.into_inner().unwrap()
+    }
+
+    #[test]
+    fn test_extract_and_verify() {
+        let report = b"# Sentinel Agent Report\n\nAll good.\n";
+        let bytes = extract_file(&archive_of("SENTINEL_REPORT.md", report)).unwrap();
+        assert_eq!(bytes, report);
+        assert!(verify(&bytes, &sha256_hex(report)).is_ok());
+        assert!(verify(&bytes, &sha256_hex(report).to_uppercase()).is_ok());
+    }
+
+    #[test]
+    fn test_hash_mismatch_rejected() {
+        let bytes = extract_file(&archive_of("SENTINEL_REPORT.md", b"rewritten after handoff")).unwrap();
+        let err = verify(&bytes, &sha256_hex(b"original")).unwrap_err();
+        assert!(err.contains("hash mismatch"));
+    }
+
+    #[test]
+    fn test_extract_errors() {
+        assert!(extract_file(&archive_of("SENTINEL_REPORT.md", b"")).unwrap().is_empty());
+        let empty = tar::Builder::new(Vec::new()).into_inner().unwrap();
+        assert_eq!(extract_file(&empty).unwrap_err(), "Archive contains no file");
+        assert!(extract_file(b"not a tar archive at all").is_err());
+    }
+
+    #[test]
+    fn test_report_path_validation() {
+        assert!(validate_container_path("/workspace/SENTINEL_REPORT.md").is_ok());
+        assert!(validate_container_path("SENTINEL_REPORT.md").is_err());
+        assert!(validate_container_path("/workspace/../etc/shadow").is_err());
+    }
+
+    #[test]
+    fn test_probe_tolerates_trailing_slash() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join(REPORT_FILE), "report").unwrap();
+        let with_slash = format!("{}/", dir.path().display());
+        assert_eq!(probe_host(Path::new(&with_slash)).as_deref(), Some("report"));
+        assert_eq!(probe_host(&dir.path().join("missing")), None);
+    }
+}
@@ -0,0 +1,166 @@
+//! Automatic restart of crashed agents.
+//!
+//! With an on-failure policy the container is launched without
+//! `auto_remove`, so when its log stream ends the exit code can still be
+//! inspected. A nonzero exit re-creates it from the stored launch config
+//! with `SENTINEL_RESUME=auto`, after an exponential backoff, until the
+//! retry budget runs out. Clean exits and user-initiated stops never restart.
+
+use crate::commands::AgentState;
+use crate::keys::KeyFile;
+use bollard::container::{Config, InspectContainerOptions, RemoveContainerOptions};
+use bollard::Docker;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+pub const RESTARTED_EVENT: &str = "sentinel://agent-restarted";
+
+/// Env entry telling a re-created agent to pick up where it left off.
+pub const RESUME_ENV: &str = "SENTINEL_RESUME=auto";
+
+const BASE_BACKOFF: Duration = Duration::from_secs(2);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "kebab-case")]
+pub enum RestartPolicy {
+    #[default]
+    Off,
+    OnFailure { max_retries: u32 },
+}
+
+impl RestartPolicy {
+    pub fn enabled(self) -> bool {
+        matches!(self, RestartPolicy::OnFailure { max_retries } if max_retries > 0)
+    }
+}
+
+/// What an agent needs to be re-created after a crash.
+pub struct RestartState {
+    pub policy: RestartPolicy,
+    /// Restarts made so far.
+    pub attempts: u32,
+    pub config: Config<String>,
+    /// Kept on the host while a re-created container may need to mount it.
+    pub key_file: Option<KeyFile>,
+}
+
+impl RestartState {
+    pub fn new(policy: RestartPolicy, config: Config<String>, key_file: Option<KeyFile>) -> Self {
+        Self { policy, attempts: 0, config, key_file }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitDecision {
+    /// Clean exit, user stop, or no restart policy: clean up as usual.
+    Done,
+    Restart { attempt: u32, max_retries: u32, delay: Duration },
+    /// The retry budget is spent.
+    GiveUp { attempts: u32 },
+}
+
+/// Delay before restart `attempt` (1-based): 2s, 4s, 8s, ... capped at 60s.
+pub fn backoff(attempt: u32) -> Duration {
+    let factor = 1u32.checked_shl(attempt.saturating_sub(1)).unwrap_or(u32::MAX);
+    BASE_BACKOFF.saturating_mul(factor).min(MAX_BACKOFF)
+}
+
+/// Decide what happens after `agent_id`'s container exited with
+/// `exit_code` (`None` if Docker had already removed it), counting the
+/// attempt when it restarts.
+pub fn on_exit(state: &mut AgentState, agent_id: &str, exit_code: Option<i64>) -> ExitDecision {
+    if state.stop_requested.contains(agent_id) || matches!(exit_code, None | Some(0)) {
+        return ExitDecision::Done;
+    }
+    let Some(restart) = state.restarts.get_mut(agent_id) else {
+        return ExitDecision::Done;
+    };
+    match restart.policy {
+        RestartPolicy::OnFailure { max_retries } if restart.attempts < max_retries => {
+            restart.attempts += 1;
+            ExitDecision::Restart { attempt: restart.attempts, max_retries, delay: backoff(restart.attempts) }
+        }
+        RestartPolicy::OnFailure { .. } => ExitDecision::GiveUp { attempts: restart.attempts },
+        RestartPolicy::Off => ExitDecision::Done,
+    }
+}
+
+/// `config` for a re-created container: same launch, resuming.
+pub fn resume_config(mut config: Config<String>) -> Config<String> {
+    let env = config.env.get_or_insert_with(Vec::new);
+    if !env.iter().any(|e| e == RESUME_ENV) {
+        env.push(RESUME_ENV.to_string());
+    }
+    config
+}
+
+/// Exit code of a stopped container, which is then removed.
+pub async fn collect_exit(docker: &Docker, agent_id: &str) -> Option<i64> {
+    let code = docker.inspect_container(agent_id, None::<InspectContainerOptions>).await.ok()
+        .and_then(|c| c.state)
+        .and_then(|s| s.exit_code);
+    // Containers launched with auto_remove are usually gone already.
+    let _ = docker.remove_container(agent_id, Some(RemoveContainerOptions { force: true, ..Default::default() })).await;
+    code
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AGENT: &str = "sentinel-a";
+
+    fn state_with(policy: RestartPolicy) -> AgentState {
+        let mut state = AgentState::default();
+        state.restarts.insert(AGENT.to_string(), RestartState::new(policy, Config::default(), None));
+        state
+    }
+
+    #[test]
+    fn test_retry_counter_and_give_up() {
+        let mut state = state_with(RestartPolicy::OnFailure { max_retries: 2 });
+        assert_eq!(on_exit(&mut state, AGENT, Some(1)), ExitDecision::Restart { attempt: 1, max_retries: 2, delay: backoff(1) });
+        assert_eq!(on_exit(&mut state, AGENT, Some(137)), ExitDecision::Restart { attempt: 2, max_retries: 2, delay: backoff(2) });
+        assert_eq!(on_exit(&mut state, AGENT, Some(1)), ExitDecision::GiveUp { attempts: 2 });
+        assert_eq!(state.restarts[AGENT].attempts, 2);
+    }
+
+    #[test]
+    fn test_backoff_doubles_and_caps() {
+        assert_eq!(backoff(1), Duration::from_secs(2));
+        assert_eq!(backoff(2), Duration::from_secs(4));
+        assert_eq!(backoff(4), Duration::from_secs(16));
+        assert_eq!(backoff(6), MAX_BACKOFF);
+        assert_eq!(backoff(40), MAX_BACKOFF);
+    }
+
+    #[test]
+    fn test_no_restart_on_clean_exit_or_manual_stop() {
+        let mut state = state_with(RestartPolicy::OnFailure { max_retries: 3 });
+        assert_eq!(on_exit(&mut state, AGENT, Some(0)), ExitDecision::Done);
+        assert_eq!(on_exit(&mut state, AGENT, None), ExitDecision::Done);
+
+        // `docker stop` exits nonzero (SIGTERM); the stop flag wins.
+        state.stop_requested.insert(AGENT.to_string());
+        assert_eq!(on_exit(&mut state, AGENT, Some(143)), ExitDecision::Done);
+        assert_eq!(state.restarts[AGENT].attempts, 0);
+
+        let mut off = state_with(RestartPolicy::Off);
+        assert_eq!(on_exit(&mut off, AGENT, Some(1)), ExitDecision::Done);
+        assert_eq!(on_exit(&mut AgentState::default(), AGENT, Some(1)), ExitDecision::Done);
+    }
+
+    #[test]
+    fn test_resume_config_and_policy_shape() {
+        let config = Config { env: Some(vec!["SENTINEL_TASK=x".to_string()]), ..Default::default() };
+        let resumed = resume_config(resume_config(config));
+        assert_eq!(resumed.env.unwrap(), vec!["SENTINEL_TASK=x".to_string(), RESUME_ENV.to_string()]);
+
+        let policy: RestartPolicy = serde_json::from_value(serde_json::json!({ "mode": "on-failure", "max_retries": 3 })).unwrap();
+        assert_eq!(policy, RestartPolicy::OnFailure { max_retries: 3 });
+        assert!(policy.enabled());
+        assert!(!RestartPolicy::OnFailure { max_retries: 0 }.enabled());
+        assert_eq!(serde_json::to_value(RestartPolicy::Off).unwrap(), serde_json::json!({ "mode": "off" }));
+    }
+}
@@ -0,0 +1,560 @@
+//! Scheduled and recurring agent runs.
+//!
+//! A schedule pairs a launch preset and a task with a cron expression. A
+//! background task checks once per minute and launches due schedules
+//! through the same path as `start_agent`, skipping a run while the
+//! schedule's previous one is still active. Results go through the
+//! notification system, since nobody may be watching the window.
+//!
+//! Cron expressions are the usual five fields (minute, hour, day of month,
+//! month, day of week) plus `@hourly`, `@daily`/`@midnight`, `@weekly`,
+//! `@monthly` and `@yearly`/`@annually`. They are matched against local
+//! wall-clock time one minute at a time, with no special handling of DST
+//! changes: a wall time skipped by a spring-forward change doesn't run that
+//! day, and one repeated by a fall-back change runs once.
+
+use crate::commands::{self, AgentState, LaunchRequest, SharedAgentState};
+use crate::notifications::{Notifier, NotifyEvent, NotifyKind};
+use crate::presets::SharedPresets;
+use chrono::{Datelike, Duration, Local, NaiveDateTime, TimeZone, Timelike};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager, State};
+use tokio::sync::Mutex;
+use tracing::{info, warn};
+
+pub const SCHEDULES_FILE: &str = "schedules.json";
+
+/// How often the scheduler wakes; each minute is still evaluated once.
+const TICK: std::time::Duration = std::time::Duration::from_secs(15);
+
+/// How far `next_after` searches before deciding an expression never fires
+/// (e.g. `0 0 30 2 *`).
+const SEARCH_DAYS: i64 = 366 * 5;
+
+// ── Cron ────────────────────────────────────────────────────────────────────
+
+/// A parsed cron expression; each field is a bitmask of allowed values.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cron {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    /// Bit 0 is Sunday.
+    weekdays: u64,
+    /// Set when day of month / day of week were anything but `*`; if both
+    /// are, either may match (as in Vixie cron, where `*/2` restricts too).
+    day_restricted: bool,
+    weekday_restricted: bool,
+}
+
+impl FromStr for Cron {
+    type Err = String;
+
+    fn from_str(expr: &str) -> Result<Self, String> {
+        let expr = match expr.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            other => other,
+        };
+        let fields: Vec<&str> = expr.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields.as_slice() else {
+            return Err(format!("Expected 5 cron fields, got {}", fields.len()));
+        };
+        let mut weekdays = parse_field(weekday, 0, 7).map_err(|e| format!("Day of week: {}", e))?;
+        // 7 is Sunday too.
+        if weekdays & (1 << 7) != 0 {
+            weekdays = (weekdays & !(1 << 7)) | 1;
+        }
+        Ok(Self {
+            minutes: parse_field(minute, 0, 59).map_err(|e| format!("Minute: {}", e))?,
+            hours: parse_field(hour, 0, 23).map_err(|e| format!("Hour: {}", e))?,
+            days: parse_field(day, 1, 31).map_err(|e| format!("Day of month: {}", e))?,
+            months: parse_field(month, 1, 12).map_err(|e| format!("Month: {}", e))?,
+            weekdays,
+            day_restricted: *day != "*",
+            weekday_restricted: *weekday != "*",
+        })
+    }
+}
+
+/// Parse one field (`*`, `a`, `a-b`, with optional `/step`, comma-separated)
+/// into a bitmask over `min..=max`.
+fn parse_field(field: &str, min: u32, max: u32) -> Result<u64, String> {
+    let value = |s: &str| -> Result<u32, String> {
+        let v: u32 = s.parse().map_err(|_| format!("'{}' is not a number", s))?;
+        if v < min || v > max {
+            return Err(format!("{} is outside {}-{}", v, min, max));
+        }
+        Ok(v)
+    };
+    let mut mask = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, Some(step)),
+            None => (part, None),
+        };
+        let (start, end) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((a, b)) => (value(a)?, value(b)?),
+                // `a/n` runs from a to the end of the range.
+                None if step.is_some() => (value(range)?, max),
+                None => (value(range)?, value(range)?),
+            },
+        };
+        if start > end {
+            return Err(format!("Range {} is backwards", range));
+        }
+        let step = match step {
+            Some(s) => s.parse::<u32>().ok().filter(|&n| n > 0).ok_or_else(|| format!("Bad step '{}'", s))?,
+            None => 1,
+        };
+        for v in (start..=end).step_by(step as usize) {
+            mask |= 1 << v;
+        }
+    }
+    Ok(mask)
+}
+
+impl Cron {
+    fn day_matches(&self, t: &NaiveDateTime) -> bool {
+        let day = self.days & (1 << t.day()) != 0;
+        let weekday = self.weekdays & (1 << t.weekday().num_days_from_sunday()) != 0;
+        if self.day_restricted && self.weekday_restricted {
+            day || weekday
+        } else {
+            day && weekday
+        }
+    }
+
+    pub fn matches(&self, t: &NaiveDateTime) -> bool {
+        self.minutes & (1 << t.minute()) != 0
+            && self.hours & (1 << t.hour()) != 0
+            && self.months & (1 << t.month()) != 0
+            && self.day_matches(t)
+    }
+
+    /// First matching minute strictly after `after`.
+    pub fn next_after(&self, after: NaiveDateTime) -> Option<NaiveDateTime> {
+        let mut t = truncate_to_minute(after) + Duration::minutes(1);
+        let limit = t + Duration::days(SEARCH_DAYS);
+        while t < limit {
+            if self.months & (1 << t.month()) == 0 || !self.day_matches(&t) {
+                t = t.date().succ_opt()?.and_hms_opt(0, 0, 0)?;
+            } else if self.hours & (1 << t.hour()) == 0 {
+                t = (t + Duration::hours(1)).with_minute(0)?;
+            } else if self.minutes & (1 << t.minute()) == 0 {
+                t += Duration::minutes(1);
+            } else {
+                return Some(t);
+            }
+        }
+        None
+    }
+}
+
+fn truncate_to_minute(t: NaiveDateTime) -> NaiveDateTime {
+    t.with_second(0).and_then(|t| t.with_nanosecond(0)).unwrap_or(t)
+}
+
+// ── Schedules ───────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RunStatus {
+    Running,
+    Completed,
+    Failed,
+    /// Not started because the previous run was still active.
+    Skipped,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LastRun {
+    /// Unix seconds when the status was recorded.
+    pub at: u64,
+    pub status: RunStatus,
+    pub agent_id: Option<String>,
+    pub detail: Option<String>,
+}
+
+impl LastRun {
+    fn new(status: RunStatus, agent_id: Option<String>, detail: Option<String>) -> Self {
+        let at = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        Self { at, status, agent_id, detail }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Schedule {
+    pub id: String,
+    pub preset_id: String,
+    pub task: String,
+    pub cron: String,
+    pub enabled: bool,
+    /// Agent started by the latest run, until it exits.
+    #[serde(default)]
+    pub active_agent: Option<String>,
+    #[serde(default)]
+    pub last_run: Option<LastRun>,
+}
+
+/// A schedule as listed to the UI.
+#[derive(Debug, Clone, Serialize)]
+pub struct ScheduleSummary {
+    #[serde(flatten)]
+    pub schedule: Schedule,
+    /// RFC 3339, local time; `None` when disabled or never due.
+    pub next_run: Option<String>,
+}
+
+impl ScheduleSummary {
+    fn new(schedule: Schedule, now: NaiveDateTime) -> Self {
+        let next_run = schedule.enabled
+            .then(|| schedule.cron.parse::<Cron>().ok()?.next_after(now))
+            .flatten()
+            .and_then(|t| Local.from_local_datetime(&t).earliest())
+            .map(|t| t.to_rfc3339());
+        Self { schedule, next_run }
+    }
+}
+
+fn agent_running(agents: &AgentState, agent_id: &str) -> bool {
+    agents.active_agents.contains_key(agent_id) && !agents.exited.contains(agent_id)
+}
+
+/// Whether `schedule`'s previous run is still going.
+pub fn previous_run_active(schedule: &Schedule, agents: &AgentState) -> bool {
+    schedule.active_agent.as_deref().is_some_and(|id| agent_running(agents, id))
+}
+
+/// Enabled schedules due at `minute`.
+pub fn due(schedules: &[Schedule], minute: NaiveDateTime) -> Vec<Schedule> {
+    schedules.iter()
+        .filter(|s| s.enabled && s.cron.parse::<Cron>().is_ok_and(|c| c.matches(&minute)))
+        .cloned()
+        .collect()
+}
+
+pub struct ScheduleStore {
+    path: PathBuf,
+    schedules: Mutex<Vec<Schedule>>,
+}
+
+pub type SharedSchedules = Arc<ScheduleStore>;
+
+impl ScheduleStore {
+    /// Load schedules from `path`; a missing or unreadable file means none.
+    pub fn load(path: PathBuf) -> Self {
+        let schedules = match std::fs::read_to_string(&path) {
+            Ok(raw) => serde_json::from_str(&raw).unwrap_or_else(|e| {
+                warn!(path = %path.display(), error = %e, "Invalid schedules file, ignoring");
+                Vec::new()
+            }),
+            Err(_) => Vec::new(),
+        };
+        Self { path, schedules: Mutex::new(schedules) }
+    }
+
+    pub async fn list(&self) -> Vec<Schedule> {
+        self.schedules.lock().await.clone()
+    }
+
+    pub async fn create(&self, preset_id: String, task: String, cron: String, enabled: bool) -> Result<Schedule, String> {
+        cron.parse::<Cron>()?;
+        if task.trim().is_empty() {
+            return Err("A task is required".to_string());
+        }
+        let schedule = Schedule {
+            id: uuid::Uuid::new_v4().to_string(),
+            preset_id,
+            task,
+            cron: cron.trim().to_string(),
+            enabled,
+            active_agent: None,
+            last_run: None,
+        };
+        self.modify(|schedules| {
+            schedules.push(schedule.clone());
+            Ok(())
+        }).await?;
+        Ok(schedule)
+    }
+
+    pub async fn delete(&self, id: &str) -> Result<(), String> {
+        self.modify(|schedules| {
+            let before = schedules.len();
+            schedules.retain(|s| s.id != id);
+            if schedules.len() == before {
+                return Err(format!("Unknown schedule: {}", id));
+            }
+            Ok(())
+        }).await
+    }
+
+    /// Record a run outcome; `active_agent` replaces the tracked agent.
+    pub async fn record(&self, id: &str, active_agent: Option<String>, run: LastRun) -> Result<(), String> {
+        self.modify(|schedules| {
+            let schedule = schedules.iter_mut().find(|s| s.id == id)
+                .ok_or_else(|| format!("Unknown schedule: {}", id))?;
+            schedule.active_agent = active_agent;
+            schedule.last_run = Some(run);
+            Ok(())
+        }).await
+    }
+
+    /// Apply `f` to a copy and persist it before it becomes current.
+    async fn modify(&self, f: impl FnOnce(&mut Vec<Schedule>) -> Result<(), String>) -> Result<(), String> {
+        let mut schedules = self.schedules.lock().await;
+        let mut updated = schedules.clone();
+        f(&mut updated)?;
+        if let Some(parent) = self.path.parent() {
+            tokio::fs::create_dir_all(parent).await.map_err(|e| e.to_string())?;
+        }
+        let raw = serde_json::to_string_pretty(&updated).map_err(|e| e.to_string())?;
+        tokio::fs::write(&self.path, raw).await.map_err(|e| e.to_string())?;
+        *schedules = updated;
+        Ok(())
+    }
+}
+
+// ── Runner ──────────────────────────────────────────────────────────────────
+
+/// Launch due schedules and record finished runs, forever.
+pub async fn run(app: AppHandle) {
+    let store = app.state::<SharedSchedules>().inner().clone();
+    let agents = app.state::<SharedAgentState>().inner().clone();
+    let notifier = app.state::<Arc<Notifier>>().inner().clone();
+    let mut ticker = tokio::time::interval(TICK);
+    let mut last_minute = None;
+    loop {
+        ticker.tick().await;
+        record_finished(&store, &agents, &notifier).await;
+
+        let minute = truncate_to_minute(Local::now().naive_local());
+        if last_minute == Some(minute) {
+            continue;
+        }
+        last_minute = Some(minute);
+        for schedule in due(&store.list().await, minute) {
+            fire(&app, &store, &agents, &notifier, schedule).await;
+        }
+    }
+}
+
+async fn fire(app: &AppHandle, store: &ScheduleStore, agents: &SharedAgentState, notifier: &Arc<Notifier>, schedule: Schedule) {
+    if previous_run_active(&schedule, &*agents.lock().await) {
+        info!(schedule = %schedule.id, "Previous scheduled run still active; skipping");
+        let run = LastRun::new(RunStatus::Skipped, None, Some("Previous run still active".to_string()));
+        if let Err(e) = store.record(&schedule.id, schedule.active_agent.clone(), run).await {
+            warn!(schedule = %schedule.id, error = %e, "Failed to record skipped run");
+        }
+        return;
+    }
+    let request = LaunchRequest {
+        task: schedule.task.clone(),
+        preset_id: Some(schedule.preset_id.clone()),
+        ..Default::default()
+    };
+    let (active_agent, run) = match commands::launch_agent(app, request).await {
+        Ok(launch) => {
+            info!(schedule = %schedule.id, agent_id = %launch.agent_id, "Started scheduled run");
+            (Some(launch.agent_id.clone()), LastRun::new(RunStatus::Running, Some(launch.agent_id), None))
+        }
+        Err(e) => {
+            warn!(schedule = %schedule.id, error = %e, "Scheduled run failed to start");
+            let detail = format!("Scheduled run failed to start: {}", e);
+            notifier.notify(NotifyEvent::new(NotifyKind::Failed, format!("schedule {}", schedule.id), &detail));
+            (None, LastRun::new(RunStatus::Failed, None, Some(detail)))
+        }
+    };
+    if let Err(e) = store.record(&schedule.id, active_agent, run).await {
+        warn!(schedule = %schedule.id, error = %e, "Failed to record scheduled run");
+    }
+}
+
+/// Record the outcome of scheduled runs whose agent has exited. Agents that
+/// reported a final status were already notified about by the callback
+/// server; one that exited silently is reported as failed here.
+async fn record_finished(store: &ScheduleStore, agents: &SharedAgentState, notifier: &Arc<Notifier>) {
+    let schedules = store.list().await;
+    let finished: Vec<(String, String, Option<String>)> = {
+        let a = agents.lock().await;
+        schedules.into_iter()
+            .filter_map(|s| {
+                let agent_id = s.active_agent?;
+                a.exited.contains(&agent_id).then(|| {
+                    let status = a.agent_status.get(&agent_id).cloned();
+                    (s.id, agent_id, status)
+                })
+            })
+            .collect()
+    };
+    for (id, agent_id, status) in finished {
+        let (status, detail) = match status.as_deref() {
+            Some("completed") => (RunStatus::Completed, None),
+            Some("failed") | Some("error") => (RunStatus::Failed, None),
+            _ => {
+                let detail = "Scheduled run exited without reporting a result".to_string();
+                notifier.notify(NotifyEvent::new(NotifyKind::Failed, &agent_id, &detail));
+                (RunStatus::Failed, Some(detail))
+            }
+        };
+        if let Err(e) = store.record(&id, None, LastRun::new(status, Some(agent_id), detail)).await {
+            warn!(schedule = %id, error = %e, "Failed to record finished run");
+        }
+    }
+}
+
+// ── Commands ────────────────────────────────────────────────────────────────
+
+/// Create a schedule running `task` with `preset_id`'s launch settings.
+#[tauri::command]
+pub async fn create_schedule(
+    store: State<'_, SharedSchedules>,
+    presets: State<'_, SharedPresets>,
+    preset_id: String,
+    task: String,
+    cron_expr: String,
+    enabled: bool,
+) -> Result<ScheduleSummary, String> {
+    presets.get(&preset_id, &commands::providers()).await?;
+    let schedule = store.create(preset_id, task, cron_expr, enabled).await?;
+    Ok(ScheduleSummary::new(schedule, Local::now().naive_local()))
+}
+
+#[tauri::command]
+pub async fn list_schedules(store: State<'_, SharedSchedules>) -> Result<Vec<ScheduleSummary>, String> {
+    let now = Local::now().naive_local();
+    Ok(store.list().await.into_iter().map(|s| ScheduleSummary::new(s, now)).collect())
+}
+
+#[tauri::command]
+pub async fn delete_schedule(store: State<'_, SharedSchedules>, id: String) -> Result<(), String> {
+    store.delete(&id).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn at(y: i32, m: u32, d: u32, h: u32, min: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap().and_hms_opt(h, min, 0).unwrap()
+    }
+
+    fn cron(expr: &str) -> Cron {
+        expr.parse().unwrap()
+    }
+
+    #[test]
+    fn test_cron_parsing_edge_cases() {
+        let nightly = cron("0 2 * * *");
+        assert!(nightly.matches(&at(2025, 3, 4, 2, 0)));
+        assert!(!nightly.matches(&at(2025, 3, 4, 2, 1)));
+        assert_eq!(cron("@daily"), cron("0 0 * * *"));
+
+        // Steps, ranges and lists.
+        let busy = cron("*/15 9-17/4 * * 1-5");
+        assert!(busy.matches(&at(2025, 3, 4, 13, 45))); // Tuesday
+        assert!(!busy.matches(&at(2025, 3, 4, 11, 45)));
+        assert!(!busy.matches(&at(2025, 3, 8, 9, 0))); // Saturday
+        assert!(cron("5/20 * * * *").matches(&at(2025, 1, 1, 0, 45)));
+        assert!(cron("0 0 * * 1,3,5").matches(&at(2025, 3, 5, 0, 0)));
+
+        // 7 and 0 are both Sunday.
+        assert_eq!(cron("0 0 * * 7"), cron("0 0 * * 0"));
+        assert!(cron("0 0 * * 7").matches(&at(2025, 3, 9, 0, 0)));
+
+        // Day of month and day of week restricted together: either matches.
+        let either = cron("0 0 13 * 5");
+        assert!(either.matches(&at(2025, 3, 13, 0, 0))); // Thursday the 13th
+        assert!(either.matches(&at(2025, 3, 14, 0, 0))); // Friday the 14th
+        assert!(!either.matches(&at(2025, 3, 15, 0, 0)));
+        // A stepped `*` restricts as well.
+        let odd_days_or_monday = cron("0 0 */2 * 1");
+        assert!(odd_days_or_monday.matches(&at(2025, 3, 10, 0, 0))); // Monday the 10th
+        assert!(odd_days_or_monday.matches(&at(2025, 3, 11, 0, 0))); // Tuesday the 11th
+        assert!(!odd_days_or_monday.matches(&at(2025, 3, 12, 0, 0)));
+
+        for bad in ["", "* * * *", "* * * * * *", "60 * * * *", "* 24 * * *", "* * 0 * *", "* * * 13 *", "* * * * 8", "5-1 * * * *", "*/0 * * * *", "a * * * *"] {
+            assert!(bad.parse::<Cron>().is_err(), "{:?} should not parse", bad);
+        }
+    }
+
+    #[test]
+    fn test_next_run() {
+        let nightly = cron("0 2 * * *");
+        assert_eq!(nightly.next_after(at(2025, 3, 4, 1, 59)), Some(at(2025, 3, 4, 2, 0)));
+        assert_eq!(nightly.next_after(at(2025, 3, 4, 2, 0)), Some(at(2025, 3, 5, 2, 0)));
+        assert_eq!(nightly.next_after(at(2025, 12, 31, 23, 0)), Some(at(2026, 1, 1, 2, 0)));
+        // Leap day, up to four years out.
+        assert_eq!(cron("30 12 29 2 *").next_after(at(2025, 3, 1, 0, 0)), Some(at(2028, 2, 29, 12, 30)));
+        assert_eq!(cron("0 0 30 2 *").next_after(at(2025, 1, 1, 0, 0)), None);
+    }
+
+    fn schedule(active_agent: Option<&str>) -> Schedule {
+        Schedule {
+            id: "nightly".into(),
+            preset_id: "audit".into(),
+            task: "Audit the workspace".into(),
+            cron: "0 2 * * *".into(),
+            enabled: true,
+            active_agent: active_agent.map(String::from),
+            last_run: None,
+        }
+    }
+
+    #[test]
+    fn test_skip_while_previous_run_active() {
+        let mut agents = AgentState::default();
+        assert!(!previous_run_active(&schedule(None), &agents));
+        assert!(!previous_run_active(&schedule(Some("sentinel-a")), &agents), "unknown agent");
+
+        agents.active_agents.insert("sentinel-a".into(), "sentinel-a".into());
+        assert!(previous_run_active(&schedule(Some("sentinel-a")), &agents));
+        agents.exited.insert("sentinel-a".into());
+        assert!(!previous_run_active(&schedule(Some("sentinel-a")), &agents));
+
+        let mut disabled = schedule(None);
+        disabled.enabled = false;
+        let due_now = due(&[schedule(None), disabled], at(2025, 3, 4, 2, 0));
+        assert_eq!(due_now.len(), 1);
+        assert!(due(&[schedule(None)], at(2025, 3, 4, 3, 0)).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_persistence_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(SCHEDULES_FILE);
+
+        let store = ScheduleStore::load(path.clone());
+        let nightly = store.create("audit".into(), "Audit".into(), " 0 2 * * * ".into(), true).await.unwrap();
+        assert_eq!(nightly.cron, "0 2 * * *");
+        let weekly = store.create("triage".into(), "Triage".into(), "@weekly".into(), false).await.unwrap();
+        assert!(store.create("audit".into(), "Audit".into(), "0 25 * * *".into(), true).await.is_err());
+        assert!(store.create("audit".into(), " ".into(), "@daily".into(), true).await.is_err());
+
+        let run = LastRun::new(RunStatus::Running, Some("sentinel-a".into()), None);
+        store.record(&nightly.id, Some("sentinel-a".into()), run.clone()).await.unwrap();
+
+        let reloaded = ScheduleStore::load(path.clone());
+        let listed = reloaded.list().await;
+        assert_eq!(listed.len(), 2);
+        assert_eq!(listed[0].active_agent.as_deref(), Some("sentinel-a"));
+        assert_eq!(listed[0].last_run, Some(run));
+        assert_eq!(listed[1], weekly);
+
+        reloaded.delete(&nightly.id).await.unwrap();
+        assert!(reloaded.delete(&nightly.id).await.is_err());
+        assert_eq!(ScheduleStore::load(path).list().await, vec![weekly]);
+    }
+}
@@ -0,0 +1,382 @@
+//! Per-agent session persistence.
+//!
+//! Every log line, chat message, status change, user message, and HITL decision
+//! is appended to `<app_data_dir>/sessions/<agent_id>/session.jsonl` so a
+//! dashboard restart (or a failed report write) doesn't lose the run.
+//! Files rotate at a size cap and only a few rotations are kept, bounding
+//! disk usage per agent.
+
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, State};
+use tauri_plugin_dialog::DialogExt;
+use tracing::warn;
+
+pub const SESSIONS_DIR: &str = "sessions";
+const SESSION_FILE: &str = "session";
+/// The agent's final report, stored beside its session log.
+pub const REPORT_FILE: &str = "SENTINEL_REPORT.md";
+
+/// Rotate the active file once it exceeds this size.
+pub const DEFAULT_MAX_FILE_BYTES: u64 = 5 * 1024 * 1024;
+/// Rotated files kept besides the active one.
+pub const DEFAULT_MAX_ROTATIONS: usize = 3;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SessionEvent {
+    /// What the agent was started with; the first record of a run.
+    Launch { task: String, provider: String, model: String },
+    Log { level: String, target: String, message: String },
+    Thought { message: String },
+    Question { message: String },
+    ReportSection {
+        #[serde(default)]
+        title: Option<String>,
+        message: String,
+    },
+    Report { message: String },
+    ToolUse { tool: String, message: String },
+    System { message: String },
+    Status { status: String, message: String },
+    User { message: String },
+    Hitl { manifest_id: String, action_description: String, risk_level: String, approved: bool },
+    /// Tokens used since the previous usage record.
+    Usage { prompt_tokens: u64, completion_tokens: u64 },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionRecord {
+    /// Milliseconds since the Unix epoch.
+    pub ts_ms: u64,
+    #[serde(flatten)]
+    pub event: SessionEvent,
+}
+
+impl SessionRecord {
+    pub fn now(event: SessionEvent) -> Self {
+        let ts_ms = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64;
+        Self { ts_ms, event }
+    }
+}
+
+/// Classify a log line: older agents mark chat bubbles with a `THOUGHT: `
+/// prefix.
+pub fn event_from_log(level: &str, target: &str, message: &str) -> SessionEvent {
+    match message.strip_prefix("THOUGHT: ") {
+        Some(thought) => SessionEvent::Thought { message: thought.to_string() },
+        None => SessionEvent::Log { level: level.to_string(), target: target.to_string(), message: message.to_string() },
+    }
+}
+
+pub struct SessionStore {
+    root: PathBuf,
+    max_file_bytes: u64,
+    max_rotations: usize,
+    /// Serializes appends so concurrent writers never interleave lines.
+    write_lock: std::sync::Mutex<()>,
+}
+
+pub type SharedSessions = Arc<SessionStore>;
+
+impl SessionStore {
+    pub fn new(root: PathBuf) -> Self {
+        Self::with_limits(root, DEFAULT_MAX_FILE_BYTES, DEFAULT_MAX_ROTATIONS)
+    }
+
+    pub fn with_limits(root: PathBuf, max_file_bytes: u64, max_rotations: usize) -> Self {
+        Self { root, max_file_bytes, max_rotations, write_lock: std::sync::Mutex::new(()) }
+    }
+
+    pub fn agent_dir(&self, agent_id: &str) -> Result<PathBuf, String> {
+        let valid = !agent_id.is_empty()
+            && agent_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            return Err(format!("Invalid agent id: {}", agent_id));
+        }
+        Ok(self.root.join(agent_id))
+    }
+
+    fn file(dir: &Path, rotation: usize) -> PathBuf {
+        if rotation == 0 {
+            dir.join(format!("{}.jsonl", SESSION_FILE))
+        } else {
+            dir.join(format!("{}.{}.jsonl", SESSION_FILE, rotation))
+        }
+    }
+
+    /// Append a record, logging rather than failing — persistence must
+    /// never take down the live session.
+    pub fn record(&self, agent_id: &str, event: SessionEvent) {
+        if let Err(e) = self.append(agent_id, &SessionRecord::now(event)) {
+            warn!(agent_id = %agent_id, error = %e, "Failed to persist session record");
+        }
+    }
+
+    pub fn append(&self, agent_id: &str, record: &SessionRecord) -> Result<(), String> {
+        let dir = self.agent_dir(agent_id)?;
+        let _guard = self.write_lock.lock().unwrap();
+        fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+
+        let active = Self::file(&dir, 0);
+        if fs::metadata(&active).map(|m| m.len() >= self.max_file_bytes).unwrap_or(false) {
+            self.rotate(&dir).map_err(|e| e.to_string())?;
+        }
+
+        let mut line = serde_json::to_string(record).map_err(|e| e.to_string())?;
+        line.push('\n');
+        let mut file = OpenOptions::new().create(true).append(true).open(&active).map_err(|e| e.to_string())?;
+        file.write_all(line.as_bytes()).map_err(|e| e.to_string())
+    }
+
+    /// Shift `session.N.jsonl` up by one, dropping the oldest.
+    fn rotate(&self, dir: &Path) -> std::io::Result<()> {
+        if self.max_rotations == 0 {
+            return fs::remove_file(Self::file(dir, 0));
+        }
+        let _ = fs::remove_file(Self::file(dir, self.max_rotations));
+        for n in (0..self.max_rotations).rev() {
+            let from = Self::file(dir, n);
+            if from.exists() {
+                fs::rename(&from, Self::file(dir, n + 1))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The report stored by `save_report`, if any.
+    pub fn report(&self, agent_id: &str) -> Option<String> {
+        fs::read_to_string(self.agent_dir(agent_id).ok()?.join(REPORT_FILE)).ok()
+    }
+
+    /// Store the agent's final report next to its session log.
+    pub fn save_report(&self, agent_id: &str, report: &str) -> Result<PathBuf, String> {
+        let dir = self.agent_dir(agent_id)?;
+        fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+        let path = dir.join(REPORT_FILE);
+        fs::write(&path, report).map_err(|e| e.to_string())?;
+        Ok(path)
+    }
+
+    /// All retained records for an agent, oldest first. Unparseable lines
+    /// (e.g. a torn final write) are skipped.
+    pub fn history(&self, agent_id: &str) -> Result<Vec<SessionRecord>, String> {
+        let dir = self.agent_dir(agent_id)?;
+        let mut records = Vec::new();
+        for n in (0..=self.max_rotations).rev() {
+            let Ok(file) = fs::File::open(Self::file(&dir, n)) else { continue };
+            for line in BufReader::new(file).lines().map_while(Result::ok) {
+                if let Ok(record) = serde_json::from_str(&line) {
+                    records.push(record);
+                }
+            }
+        }
+        Ok(records)
+    }
+}
+
+// ── Export ──────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Markdown,
+    Jsonl,
+}
+
+impl ExportFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Markdown => "md",
+            ExportFormat::Jsonl => "jsonl",
+        }
+    }
+}
+
+pub(crate) fn format_ts(ts_ms: u64) -> String {
+    let secs = ts_ms / 1000;
+    let (h, m, s) = ((secs / 3600) % 24, (secs / 60) % 60, secs % 60);
+    format!("{:02}:{:02}:{:02}", h, m, s)
+}
+
+/// Render a session as a readable Markdown transcript.
+pub fn render_markdown(agent_id: &str, records: &[SessionRecord]) -> String {
+    let mut out = format!("# Sentinel Session — {}\n\n", agent_id);
+    for r in records {
+        let ts = format_ts(r.ts_ms);
+        let entry = match &r.event {
+            SessionEvent::Launch { task, provider, model } => format!("> `{}` launched on {}/{}: {}\n", ts, provider, model, task),
+            SessionEvent::Usage { .. } => continue,
+            SessionEvent::Thought { message } => format!("**Agent** ({} UTC)\n\n{}\n", ts, message),
+            SessionEvent::Question { message } => format!("**Agent asks** ({} UTC)\n\n{}\n", ts, message),
+            SessionEvent::ReportSection { title, message } => format!(
+                "**{}** ({} UTC)\n\n{}\n", title.as_deref().unwrap_or("Report"), ts, message
+            ),
+            SessionEvent::Report { message } => format!("**Report** ({} UTC)\n\n{}\n", ts, message),
+            SessionEvent::ToolUse { tool, message } => format!("> `{}` tool **{}** {}\n", ts, tool, message.trim_end()),
+            SessionEvent::System { message } => format!("> `{}` {}\n", ts, message),
+            SessionEvent::User { message } => format!("**You** ({} UTC)\n\n{}\n", ts, message),
+            SessionEvent::Status { status, message } => format!("> `{}` status: **{}** — {}\n", ts, status, message),
+            SessionEvent::Hitl { manifest_id, action_description, risk_level, approved } => format!(
+                "> `{}` HITL {} [{}]: {} (`{}`)\n",
+                ts, if *approved { "✅ approved" } else { "🚫 rejected" }, risk_level, action_description, manifest_id
+            ),
+            SessionEvent::Log { level, target, message } => format!(
+                "`{} {} {}` {}\n", ts, level.to_uppercase(), target, message.trim_end()
+            ),
+        };
+        out.push_str(&entry);
+        out.push('\n');
+    }
+    out
+}
+
+pub fn render_jsonl(records: &[SessionRecord]) -> String {
+    records.iter()
+        .filter_map(|r| serde_json::to_string(r).ok())
+        .map(|line| line + "\n")
+        .collect()
+}
+
+#[tauri::command]
+pub async fn get_session_history(
+    sessions: State<'_, SharedSessions>,
+    agent_id: String,
+) -> Result<Vec<SessionRecord>, String> {
+    sessions.history(&agent_id)
+}
+
+/// Export a session to a file the user picks. Returns the written path, or
+/// `None` if the dialog was cancelled.
+#[tauri::command]
+pub async fn export_session(
+    app: AppHandle,
+    sessions: State<'_, SharedSessions>,
+    agent_id: String,
+    format: ExportFormat,
+) -> Result<Option<String>, String> {
+    let records = sessions.history(&agent_id)?;
+    let content = match format {
+        ExportFormat::Markdown => render_markdown(&agent_id, &records),
+        ExportFormat::Jsonl => render_jsonl(&records),
+    };
+
+    let Some(path) = pick_save_path(&app, &format!("{}.{}", agent_id, format.extension()), format.extension()).await? else {
+        return Ok(None);
+    };
+    tokio::fs::write(&path, content).await.map_err(|e| e.to_string())?;
+    Ok(Some(path.display().to_string()))
+}
+
+/// Ask for a save location with the native dialog; `None` if cancelled.
+pub(crate) async fn pick_save_path(app: &AppHandle, file_name: &str, extension: &str) -> Result<Option<PathBuf>, String> {
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    app.dialog()
+        .file()
+        .set_file_name(file_name)
+        .add_filter(extension, &[extension])
+        .save_file(move |path| { let _ = tx.send(path); });
+    match rx.await.map_err(|e| e.to_string())? {
+        Some(path) => path.into_path().map(Some).map_err(|e| e.to_string()),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(ts_ms: u64, event: SessionEvent) -> SessionRecord {
+        SessionRecord { ts_ms, event }
+    }
+
+    fn log(message: &str) -> SessionEvent {
+        SessionEvent::Log { level: "info".into(), target: "agent".into(), message: message.into() }
+    }
+
+    #[test]
+    fn test_append_and_history_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = SessionStore::new(dir.path().to_path_buf());
+        store.append("sentinel-a", &record(1, log("one"))).unwrap();
+        store.append("sentinel-a", &record(2, SessionEvent::User { message: "hi".into() })).unwrap();
+        store.append("sentinel-b", &record(3, log("other"))).unwrap();
+
+        let history = store.history("sentinel-a").unwrap();
+        assert_eq!(history, vec![record(1, log("one")), record(2, SessionEvent::User { message: "hi".into() })]);
+        assert!(store.history("sentinel-none").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_rotation_bounds_files_and_keeps_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = SessionStore::with_limits(dir.path().to_path_buf(), 100, 2);
+        for i in 0..20 {
+            store.append("sentinel-a", &record(i, log(&format!("line {:02}", i)))).unwrap();
+        }
+
+        let agent_dir = dir.path().join("sentinel-a");
+        let files = fs::read_dir(&agent_dir).unwrap().count();
+        assert_eq!(files, 3);
+        assert!(!agent_dir.join("session.3.jsonl").exists());
+
+        let history = store.history("sentinel-a").unwrap();
+        let ts: Vec<u64> = history.iter().map(|r| r.ts_ms).collect();
+        assert!(ts.windows(2).all(|w| w[0] < w[1]), "out of order: {:?}", ts);
+        assert_eq!(*ts.last().unwrap(), 19);
+        assert!(ts.len() < 20);
+    }
+
+    #[test]
+    fn test_rejects_path_like_agent_ids() {
+        let store = SessionStore::new(PathBuf::from("/tmp/unused"));
+        assert!(store.agent_dir("../etc").is_err());
+        assert!(store.agent_dir("a/b").is_err());
+        assert!(store.agent_dir("").is_err());
+    }
+
+    #[test]
+    fn test_event_from_log_splits_thoughts() {
+        assert_eq!(event_from_log("info", "agent", "THOUGHT: hello"), SessionEvent::Thought { message: "hello".into() });
+        assert_eq!(event_from_log("info", "agent", "plain"), log("plain"));
+    }
+
+    #[test]
+    fn test_render_markdown() {
+        let records = vec![
+            record(3_600_000, SessionEvent::User { message: "Audit src/".into() }),
+            record(3_601_000, SessionEvent::Thought { message: "Looking at **main.rs**".into() }),
+            record(3_602_000, log("Tool result (read_file): 120 chars\n")),
+            record(3_603_000, SessionEvent::Hitl {
+                manifest_id: "m-1".into(),
+                action_description: "Write file: fix.patch".into(),
+                risk_level: "Medium".into(),
+                approved: false,
+            }),
+            record(3_604_000, SessionEvent::Status { status: "completed".into(), message: "Task completed".into() }),
+        ];
+        let expected = "\
+# Sentinel Session — sentinel-a
+
+**You** (01:00:00 UTC)
+
+Audit src/
+
+**Agent** (01:00:01 UTC)
+
+Looking at **main.rs**
+
+`01:00:02 INFO agent` Tool result (read_file): 120 chars
+
+> `01:00:03` HITL 🚫 rejected [Medium]: Write file: fix.patch (`m-1`)
+
+> `01:00:04` status: **completed** — Task completed
+
+";
+        assert_eq!(render_markdown("sentinel-a", &records), expected);
+    }
+}
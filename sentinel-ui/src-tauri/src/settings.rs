@@ -0,0 +1,105 @@
+//! Backend settings persisted as JSON in the app data dir.
+//!
+//! These are the knobs the backend itself acts on (cleanup, reaping, ...);
+//! purely visual preferences stay in the frontend.
+
+use crate::limits::ResourceLimits;
+use crate::network::NetworkMode;
+use crate::notifications::{EventToggles, NotificationSettings};
+use crate::restart::RestartPolicy;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tauri::State;
+use tokio::sync::Mutex;
+use tracing::warn;
+
+pub const SETTINGS_FILE: &str = "settings.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DashboardSettings {
+    /// Leave agent containers running when the dashboard exits.
+    pub leave_containers_running: bool,
+    /// Running `sentinel-*` containers older than this are reaped at startup.
+    pub orphan_max_age_secs: u64,
+    /// Registry reference to pull the agent image from when it is missing.
+    pub agent_image_registry: Option<String>,
+    /// Dockerfile context to build from instead of the bundled one.
+    pub agent_build_context: Option<PathBuf>,
+    /// Discord / Slack / Telegram targets and which events each receives.
+    pub notifications: NotificationSettings,
+    /// Native notification categories; never shown while the window is focused.
+    pub desktop_notifications: EventToggles,
+    /// Default container limits; `start_agent` may override per launch.
+    pub resource_limits: ResourceLimits,
+    /// Default network isolation for new agents.
+    pub network_mode: NetworkMode,
+    /// Default restart policy for crashed agents; `start_agent` may override.
+    pub restart_policy: RestartPolicy,
+}
+
+impl Default for DashboardSettings {
+    fn default() -> Self {
+        Self {
+            leave_containers_running: false,
+            orphan_max_age_secs: 24 * 60 * 60,
+            agent_image_registry: None,
+            agent_build_context: None,
+            notifications: NotificationSettings::default(),
+            desktop_notifications: EventToggles::default(),
+            resource_limits: ResourceLimits::default(),
+            network_mode: NetworkMode::default(),
+            restart_policy: RestartPolicy::default(),
+        }
+    }
+}
+
+pub struct SettingsStore {
+    path: PathBuf,
+    settings: Mutex<DashboardSettings>,
+}
+
+pub type SharedSettings = Arc<SettingsStore>;
+
+impl SettingsStore {
+    /// Load settings from `path`, falling back to defaults if the file is
+    /// missing or unreadable.
+    pub fn load(path: PathBuf) -> Self {
+        let settings = match std::fs::read_to_string(&path) {
+            Ok(raw) => serde_json::from_str(&raw).unwrap_or_else(|e| {
+                warn!(path = %path.display(), error = %e, "Invalid settings file, using defaults");
+                DashboardSettings::default()
+            }),
+            Err(_) => DashboardSettings::default(),
+        };
+        Self { path, settings: Mutex::new(settings) }
+    }
+
+    pub async fn get(&self) -> DashboardSettings {
+        self.settings.lock().await.clone()
+    }
+
+    pub async fn update(&self, settings: DashboardSettings) -> Result<(), String> {
+        if let Some(parent) = self.path.parent() {
+            tokio::fs::create_dir_all(parent).await.map_err(|e| e.to_string())?;
+        }
+        let raw = serde_json::to_string_pretty(&settings).map_err(|e| e.to_string())?;
+        tokio::fs::write(&self.path, raw).await.map_err(|e| e.to_string())?;
+        *self.settings.lock().await = settings;
+        Ok(())
+    }
+}
+
+#[tauri::command]
+pub async fn get_settings(store: State<'_, SharedSettings>) -> Result<DashboardSettings, String> {
+    Ok(store.get().await)
+}
+
+#[tauri::command]
+pub async fn update_settings(
+    store: State<'_, SharedSettings>,
+    settings: DashboardSettings,
+) -> Result<(), String> {
+    store.update(settings).await
+}
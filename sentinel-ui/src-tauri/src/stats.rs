@@ -0,0 +1,277 @@
+//! Live CPU and memory usage per agent container.
+//!
+//! Each followed container gets a poller taking one-shot Docker stats every
+//! few seconds. One-shot samples carry no previous CPU reading, so CPU
+//! percent comes from the delta to the poller's own last sample. The latest
+//! values are kept in `AgentState` and emitted as `sentinel://stats`; a
+//! separate warning fires once each time memory climbs past
+//! `MEMORY_WARN_PERCENT` of the limit, ahead of an OOM kill.
+
+use crate::callback_server::EventSink;
+use crate::commands::SharedAgentState;
+use crate::notifications::{Notifier, NotifyEvent, NotifyKind};
+use bollard::container::StatsOptions;
+use bollard::Docker;
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::State;
+
+pub const STATS_EVENT: &str = "sentinel://stats";
+pub const MEMORY_WARNING_EVENT: &str = "sentinel://stats-warning";
+
+pub const POLL_INTERVAL: Duration = Duration::from_secs(3);
+pub const MEMORY_WARN_PERCENT: f64 = 90.0;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AgentStats {
+    pub agent_id: String,
+    pub cpu_percent: f64,
+    pub memory_bytes: u64,
+    /// 0 when Docker reports no limit.
+    pub memory_limit_bytes: u64,
+    pub memory_percent: f64,
+    /// Unix seconds of the sample.
+    pub sampled_at: u64,
+}
+
+/// The parts of Docker's stats JSON used here.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct RawStats {
+    pub cpu_stats: CpuSample,
+    pub precpu_stats: CpuSample,
+    pub memory_stats: MemorySample,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct CpuSample {
+    pub cpu_usage: CpuUsage,
+    pub system_cpu_usage: Option<u64>,
+    pub online_cpus: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct CpuUsage {
+    pub total_usage: u64,
+    pub percpu_usage: Option<Vec<u64>>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct MemorySample {
+    pub usage: Option<u64>,
+    pub limit: Option<u64>,
+    pub stats: Option<MemoryDetail>,
+}
+
+/// Page cache counters: `total_inactive_file` on cgroup v1,
+/// `inactive_file` on v2.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct MemoryDetail {
+    pub total_inactive_file: Option<u64>,
+    pub inactive_file: Option<u64>,
+}
+
+impl CpuSample {
+    fn cpus(&self) -> u64 {
+        self.online_cpus
+            .or_else(|| self.cpu_usage.percpu_usage.as_ref().map(|p| p.len() as u64))
+            .filter(|&n| n > 0)
+            .unwrap_or(1)
+    }
+
+    /// Usage between `prev` and this sample, as a percentage of one CPU
+    /// (so a busy 4-core container reads up to 400%).
+    pub fn percent_since(&self, prev: &CpuSample) -> f64 {
+        let (Some(system), Some(prev_system)) = (self.system_cpu_usage, prev.system_cpu_usage) else {
+            return 0.0;
+        };
+        let cpu_delta = self.cpu_usage.total_usage.saturating_sub(prev.cpu_usage.total_usage);
+        let system_delta = system.saturating_sub(prev_system);
+        if cpu_delta == 0 || system_delta == 0 {
+            return 0.0;
+        }
+        cpu_delta as f64 / system_delta as f64 * self.cpus() as f64 * 100.0
+    }
+}
+
+impl MemorySample {
+    /// Usage minus reclaimable page cache, as `docker stats` reports it.
+    pub fn used(&self) -> u64 {
+        let usage = self.usage.unwrap_or(0);
+        let cache = self.stats.as_ref()
+            .and_then(|s| s.total_inactive_file.or(s.inactive_file))
+            .filter(|&c| c < usage)
+            .unwrap_or(0);
+        usage - cache
+    }
+
+    pub fn percent(&self) -> f64 {
+        match self.limit {
+            Some(limit) if limit > 0 => self.used() as f64 / limit as f64 * 100.0,
+            _ => 0.0,
+        }
+    }
+}
+
+impl RawStats {
+    pub fn from_docker(stats: &bollard::container::Stats) -> Option<Self> {
+        serde_json::from_value(serde_json::to_value(stats).ok()?).ok()
+    }
+
+    /// Stats for `agent_id`. CPU is measured against `prev` when given
+    /// (one-shot samples), otherwise against Docker's own `precpu_stats`.
+    pub fn summarize(&self, agent_id: &str, prev: Option<&CpuSample>, sampled_at: u64) -> AgentStats {
+        AgentStats {
+            agent_id: agent_id.to_string(),
+            cpu_percent: self.cpu_stats.percent_since(prev.unwrap_or(&self.precpu_stats)),
+            memory_bytes: self.memory_stats.used(),
+            memory_limit_bytes: self.memory_stats.limit.unwrap_or(0),
+            memory_percent: self.memory_stats.percent(),
+            sampled_at,
+        }
+    }
+}
+
+/// Edge-triggered memory warning: true when `stats` crosses the threshold
+/// while not already warned. `warned` re-arms once usage drops back.
+pub fn memory_warning(stats: &AgentStats, warned: &mut bool) -> bool {
+    let high = stats.memory_percent >= MEMORY_WARN_PERCENT;
+    let fire = high && !*warned;
+    *warned = high;
+    fire
+}
+
+/// Poll `agent_id`'s stats until the container goes away. The caller
+/// aborts the returned task when the container exits.
+pub fn spawn_poller(
+    state: SharedAgentState,
+    sink: Arc<dyn EventSink>,
+    notifier: Arc<Notifier>,
+    docker: Docker,
+    agent_id: String,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut prev: Option<CpuSample> = None;
+        let mut warned = false;
+        let mut ticker = tokio::time::interval(POLL_INTERVAL);
+        loop {
+            ticker.tick().await;
+            let options = StatsOptions { stream: false, one_shot: true };
+            let raw = match docker.stats(&agent_id, Some(options)).next().await {
+                Some(Ok(stats)) => RawStats::from_docker(&stats),
+                _ => break,
+            };
+            let Some(raw) = raw else { continue };
+            let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+            let stats = raw.summarize(&agent_id, prev.as_ref(), now);
+            prev = Some(raw.cpu_stats);
+
+            if memory_warning(&stats, &mut warned) {
+                let detail = format!(
+                    "Memory at {:.0}% of limit ({} of {} MiB)",
+                    stats.memory_percent, stats.memory_bytes >> 20, stats.memory_limit_bytes >> 20,
+                );
+                tracing::warn!(agent_id = %agent_id, "{}", detail);
+                sink.emit(MEMORY_WARNING_EVENT, serde_json::to_value(&stats).unwrap_or_default());
+                notifier.notify(NotifyEvent::new(NotifyKind::MemoryHigh, &agent_id, detail));
+            }
+            sink.emit(STATS_EVENT, serde_json::to_value(&stats).unwrap_or_default());
+            state.lock().await.stats.insert(agent_id.clone(), stats);
+        }
+    })
+}
+
+/// Latest stats sample for `agent_id`, if one has been taken.
+#[tauri::command]
+pub async fn get_agent_stats(
+    state: State<'_, SharedAgentState>,
+    agent_id: String,
+) -> Result<Option<AgentStats>, String> {
+    Ok(state.lock().await.stats.get(&agent_id).cloned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn fixture() -> RawStats {
+        // Trimmed from `GET /containers/{id}/stats` on a cgroup v2 host.
+        serde_json::from_value(json!({
+            "read": "2024-05-01T12:00:05.000000000Z",
+            "pids_stats": { "current": 12 },
+            "cpu_stats": {
+                "cpu_usage": { "total_usage": 2_400_000_000u64, "usage_in_kernelmode": 400_000_000u64 },
+                "system_cpu_usage": 104_000_000_000u64,
+                "online_cpus": 4
+            },
+            "precpu_stats": {
+                "cpu_usage": { "total_usage": 2_000_000_000u64 },
+                "system_cpu_usage": 100_000_000_000u64,
+                "online_cpus": 4
+            },
+            "memory_stats": {
+                "usage": 1_000_000_000u64,
+                "limit": 1_073_741_824u64,
+                "stats": { "inactive_file": 100_000_000u64, "anon": 850_000_000u64 }
+            }
+        })).unwrap()
+    }
+
+    #[test]
+    fn test_percentages_from_fixture() {
+        let stats = fixture().summarize("sentinel-a", None, 5);
+        // 0.4s of CPU over 4s of system time on 4 cores.
+        assert!((stats.cpu_percent - 40.0).abs() < 1e-9);
+        assert_eq!(stats.memory_bytes, 900_000_000);
+        assert_eq!(stats.memory_limit_bytes, 1_073_741_824);
+        assert!((stats.memory_percent - 83.819).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_one_shot_cpu_uses_previous_sample() {
+        let raw = fixture();
+        // One-shot samples leave precpu empty.
+        let mut one_shot = raw.clone();
+        one_shot.precpu_stats = CpuSample::default();
+        assert_eq!(one_shot.summarize("sentinel-a", None, 0).cpu_percent, 0.0);
+
+        let prev = CpuSample {
+            cpu_usage: CpuUsage { total_usage: 1_600_000_000, percpu_usage: None },
+            system_cpu_usage: Some(96_000_000_000),
+            online_cpus: None,
+        };
+        let stats = one_shot.summarize("sentinel-a", Some(&prev), 0);
+        assert!((stats.cpu_percent - 40.0).abs() < 1e-9);
+
+        // cgroup v1 counters, no limit reported.
+        let v1: RawStats = serde_json::from_value(json!({
+            "memory_stats": { "usage": 500u64, "stats": { "total_inactive_file": 200u64, "cache": 300u64 } }
+        })).unwrap();
+        assert_eq!(v1.memory_stats.used(), 300);
+        assert_eq!(v1.memory_stats.percent(), 0.0);
+    }
+
+    #[test]
+    fn test_memory_warning_fires_once_per_crossing() {
+        let mut stats = fixture().summarize("sentinel-a", None, 0);
+        let mut warned = false;
+        assert!(!memory_warning(&stats, &mut warned));
+
+        stats.memory_percent = 91.5;
+        assert!(memory_warning(&stats, &mut warned));
+        stats.memory_percent = 97.0;
+        assert!(!memory_warning(&stats, &mut warned));
+
+        stats.memory_percent = 70.0;
+        assert!(!memory_warning(&stats, &mut warned));
+        stats.memory_percent = MEMORY_WARN_PERCENT;
+        assert!(memory_warning(&stats, &mut warned));
+    }
+}
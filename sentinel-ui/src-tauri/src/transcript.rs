@@ -0,0 +1,358 @@
+//! Shareable transcripts of a whole run, as Markdown or HTML.
+//!
+//! Unlike `export_session`, which dumps every record in order, a transcript
+//! is meant for teammates: a metadata header (provider, model, duration,
+//! tokens), the chat exchange with tool calls and HITL decisions, the final
+//! report, and the container log at the end. Secrets are redacted once more
+//! over the finished document.
+
+use crate::notifications::redact;
+use crate::session::{self, SessionEvent, SessionRecord, SharedSessions};
+use sentinel_shared::pricing::{self, TokenCounts};
+use serde::Deserialize;
+use tauri::{AppHandle, State};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TranscriptFormat {
+    Markdown,
+    Html,
+}
+
+impl TranscriptFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            TranscriptFormat::Markdown => "md",
+            TranscriptFormat::Html => "html",
+        }
+    }
+}
+
+/// Header facts, derived from the session records.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TranscriptMeta {
+    pub agent_id: String,
+    pub task: Option<String>,
+    pub provider: Option<String>,
+    pub model: Option<String>,
+    pub started_ms: Option<u64>,
+    pub ended_ms: Option<u64>,
+    pub tokens: TokenCounts,
+}
+
+impl TranscriptMeta {
+    pub fn from_records(agent_id: &str, records: &[SessionRecord]) -> Self {
+        let mut meta = Self {
+            agent_id: agent_id.to_string(),
+            started_ms: records.first().map(|r| r.ts_ms),
+            ended_ms: records.last().map(|r| r.ts_ms),
+            ..Default::default()
+        };
+        for r in records {
+            match &r.event {
+                SessionEvent::Launch { task, provider, model } if meta.task.is_none() => {
+                    meta.task = Some(task.clone());
+                    meta.provider = Some(provider.clone());
+                    meta.model = Some(model.clone());
+                }
+                SessionEvent::Usage { prompt_tokens, completion_tokens } => {
+                    meta.tokens.add(TokenCounts::new(*prompt_tokens, *completion_tokens));
+                }
+                _ => {}
+            }
+        }
+        meta
+    }
+
+    fn cost(&self) -> Option<f64> {
+        pricing::estimate_cost(self.provider.as_deref()?, self.model.as_deref()?, &self.tokens)
+    }
+
+    /// `(label, value)` rows of the header table.
+    fn rows(&self) -> Vec<(&'static str, String)> {
+        let unknown = || "—".to_string();
+        let cost = match (self.provider.is_some(), self.cost()) {
+            (_, Some(cost)) => format!("${:.4}", cost),
+            (true, None) => "Unpriced model".to_string(),
+            (false, None) => unknown(),
+        };
+        vec![
+            ("Task", self.task.clone().unwrap_or_else(unknown)),
+            ("Provider", self.provider.clone().unwrap_or_else(unknown)),
+            ("Model", self.model.clone().unwrap_or_else(unknown)),
+            ("Started", self.started_ms.map(format_date).unwrap_or_else(unknown)),
+            ("Duration", match (self.started_ms, self.ended_ms) {
+                (Some(start), Some(end)) => format_duration(end.saturating_sub(start) / 1000),
+                _ => unknown(),
+            }),
+            ("Tokens", format!(
+                "{} prompt · {} completion · {} total",
+                group(self.tokens.prompt_tokens), group(self.tokens.completion_tokens), group(self.tokens.total()),
+            )),
+            ("Estimated cost", cost),
+        ]
+    }
+}
+
+pub struct Transcript {
+    pub meta: TranscriptMeta,
+    pub records: Vec<SessionRecord>,
+    /// The stored report, or else the last one sent in the session.
+    pub report: Option<String>,
+}
+
+impl Transcript {
+    pub fn new(agent_id: &str, records: Vec<SessionRecord>, stored_report: Option<String>) -> Self {
+        let report = stored_report.or_else(|| records.iter().rev().find_map(|r| match &r.event {
+            SessionEvent::Report { message } => Some(message.clone()),
+            _ => None,
+        }));
+        Self { meta: TranscriptMeta::from_records(agent_id, &records), records, report }
+    }
+
+    fn logs(&self) -> Vec<String> {
+        self.records.iter().filter_map(|r| match &r.event {
+            SessionEvent::Log { level, target, message } => Some(format!(
+                "{} {} {} {}", session::format_ts(r.ts_ms), level.to_uppercase(), target, message.trim_end()
+            )),
+            _ => None,
+        }).collect()
+    }
+}
+
+fn format_date(ts_ms: u64) -> String {
+    chrono::DateTime::from_timestamp_millis(ts_ms as i64)
+        .map(|t| t.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+        .unwrap_or_default()
+}
+
+fn format_duration(secs: u64) -> String {
+    let (h, m, s) = (secs / 3600, (secs / 60) % 60, secs % 60);
+    match (h, m) {
+        (0, 0) => format!("{}s", s),
+        (0, _) => format!("{}m {:02}s", m, s),
+        _ => format!("{}h {:02}m {:02}s", h, m, s),
+    }
+}
+
+/// `1234567` → `1,234,567`.
+fn group(n: u64) -> String {
+    let digits = n.to_string();
+    let mut out = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            out.push(',');
+        }
+        out.push(c);
+    }
+    out
+}
+
+// ── Markdown ────────────────────────────────────────────────────────────────
+
+fn table_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
+
+/// Prefix every line of `text` so it stays inside one blockquote.
+fn quote(text: &str) -> String {
+    text.trim_end().replace('\n', "\n> ")
+}
+
+fn markdown_entry(r: &SessionRecord) -> Option<String> {
+    let ts = session::format_ts(r.ts_ms);
+    Some(match &r.event {
+        SessionEvent::User { message } => format!("**You** · {} UTC\n\n{}\n", ts, message.trim_end()),
+        SessionEvent::Thought { message } => format!("**Agent** · {} UTC\n\n{}\n", ts, message.trim_end()),
+        SessionEvent::Question { message } => format!("**Agent asks** · {} UTC\n\n{}\n", ts, message.trim_end()),
+        SessionEvent::ReportSection { title, message } => format!(
+            "**{}** · {} UTC\n\n{}\n", title.as_deref().unwrap_or("Report"), ts, message.trim_end()
+        ),
+        SessionEvent::ToolUse { tool, message } => format!("> `{}` 🔧 **{}** {}\n", ts, tool, quote(message)),
+        SessionEvent::System { message } => format!("> `{}` {}\n", ts, quote(message)),
+        SessionEvent::Status { status, message } => format!("> `{}` Status: **{}** — {}\n", ts, status, quote(message)),
+        SessionEvent::Hitl { manifest_id, action_description, risk_level, approved } => format!(
+            "> `{}` 🛡️ HITL **{}** [{}]: {} (`{}`)\n",
+            ts, if *approved { "approved" } else { "rejected" }, risk_level, action_description, manifest_id
+        ),
+        // Header, final report and log section cover these.
+        SessionEvent::Launch { .. } | SessionEvent::Usage { .. } | SessionEvent::Report { .. } | SessionEvent::Log { .. } => return None,
+    })
+}
+
+pub fn render_markdown(t: &Transcript) -> String {
+    let mut out = format!("# Sentinel Transcript — {}\n\n| Field | Value |\n|---|---|\n", t.meta.agent_id);
+    for (label, value) in t.meta.rows() {
+        out.push_str(&format!("| {} | {} |\n", label, table_cell(&value)));
+    }
+    out.push_str("\n## Conversation\n\n");
+    for entry in t.records.iter().filter_map(markdown_entry) {
+        out.push_str(&entry);
+        out.push('\n');
+    }
+    if let Some(report) = &t.report {
+        out.push_str(&format!("## Final Report\n\n{}\n\n", report.trim()));
+    }
+    let logs = t.logs();
+    if !logs.is_empty() {
+        out.push_str(&format!("## Container Log\n\n```text\n{}\n```\n", logs.join("\n")));
+    }
+    redact(&out)
+}
+
+// ── HTML ────────────────────────────────────────────────────────────────────
+
+const HTML_TEMPLATE: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>{{title}}</title>
+<style>
+body { font: 15px/1.5 -apple-system, "Segoe UI", sans-serif; max-width: 860px; margin: 2rem auto; padding: 0 1rem; color: #1d1d1f; }
+table { border-collapse: collapse; margin-bottom: 1.5rem; }
+th, td { text-align: left; padding: 4px 12px; border-bottom: 1px solid #e5e5e5; vertical-align: top; }
+.msg { margin: 1rem 0; }
+.who { font-weight: 600; }
+.ts { color: #86868b; font-size: 12px; font-family: ui-monospace, monospace; }
+.text, pre { white-space: pre-wrap; }
+.event { color: #515154; margin: 0.5rem 0; }
+details { margin: 0.5rem 0; }
+summary { cursor: pointer; }
+pre { background: #f5f5f7; padding: 8px 12px; border-radius: 6px; font-size: 13px; overflow-x: auto; }
+</style>
+</head>
+<body>
+{{body}}
+</body>
+</html>
+"#;
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn html_entry(r: &SessionRecord) -> Option<String> {
+    let ts = format!("<span class=\"ts\">{} UTC</span>", session::format_ts(r.ts_ms));
+    let message = |who: &str, text: &str| format!(
+        "<div class=\"msg\"><div><span class=\"who\">{}</span> {}</div><div class=\"text\">{}</div></div>\n",
+        escape(who), ts, escape(text.trim_end())
+    );
+    Some(match &r.event {
+        SessionEvent::User { message: text } => message("You", text),
+        SessionEvent::Thought { message: text } => message("Agent", text),
+        SessionEvent::Question { message: text } => message("Agent asks", text),
+        SessionEvent::ReportSection { title, message: text } => message(title.as_deref().unwrap_or("Report"), text),
+        SessionEvent::ToolUse { tool, message: text } => format!(
+            "<details><summary>🔧 <b>{}</b> {}</summary><pre>{}</pre></details>\n", escape(tool), ts, escape(text.trim_end())
+        ),
+        SessionEvent::System { message: text } => format!("<div class=\"event\">{} {}</div>\n", ts, escape(text)),
+        SessionEvent::Status { status, message: text } => format!(
+            "<div class=\"event\">{} Status: <b>{}</b> — {}</div>\n", ts, escape(status), escape(text)
+        ),
+        SessionEvent::Hitl { manifest_id, action_description, risk_level, approved } => format!(
+            "<div class=\"event\">{} 🛡️ HITL <b>{}</b> [{}]: {} (<code>{}</code>)</div>\n",
+            ts, if *approved { "approved" } else { "rejected" }, escape(risk_level), escape(action_description), escape(manifest_id)
+        ),
+        SessionEvent::Launch { .. } | SessionEvent::Usage { .. } | SessionEvent::Report { .. } | SessionEvent::Log { .. } => return None,
+    })
+}
+
+pub fn render_html(t: &Transcript) -> String {
+    let title = format!("Sentinel Transcript — {}", t.meta.agent_id);
+    let mut body = format!("<h1>{}</h1>\n<table>\n", escape(&title));
+    for (label, value) in t.meta.rows() {
+        body.push_str(&format!("<tr><th>{}</th><td>{}</td></tr>\n", label, escape(&value)));
+    }
+    body.push_str("</table>\n<h2>Conversation</h2>\n");
+    for entry in t.records.iter().filter_map(html_entry) {
+        body.push_str(&entry);
+    }
+    if let Some(report) = &t.report {
+        body.push_str(&format!("<h2>Final Report</h2>\n<div class=\"text\">{}</div>\n", escape(report.trim())));
+    }
+    let logs = t.logs();
+    if !logs.is_empty() {
+        body.push_str(&format!(
+            "<h2>Container Log</h2>\n<details><summary>{} lines</summary><pre>{}</pre></details>\n",
+            logs.len(), escape(&logs.join("\n"))
+        ));
+    }
+    redact(&HTML_TEMPLATE.replace("{{title}}", &escape(&title)).replace("{{body}}", &body))
+}
+
+/// Export `agent_id`'s transcript to `path`, or to a location picked in a
+/// save dialog when no path is given. Returns the written path, or `None`
+/// if the dialog was cancelled.
+#[tauri::command]
+pub async fn export_transcript(
+    app: AppHandle,
+    sessions: State<'_, SharedSessions>,
+    agent_id: String,
+    format: TranscriptFormat,
+    path: Option<String>,
+) -> Result<Option<String>, String> {
+    let transcript = Transcript::new(&agent_id, sessions.history(&agent_id)?, sessions.report(&agent_id));
+    let content = match format {
+        TranscriptFormat::Markdown => render_markdown(&transcript),
+        TranscriptFormat::Html => render_html(&transcript),
+    };
+    let path = match path.filter(|p| !p.trim().is_empty()) {
+        Some(path) => path.into(),
+        None => {
+            let file_name = format!("{}-transcript.{}", agent_id, format.extension());
+            match session::pick_save_path(&app, &file_name, format.extension()).await? {
+                Some(path) => path,
+                None => return Ok(None),
+            }
+        }
+    };
+    tokio::fs::write(&path, content).await.map_err(|e| e.to_string())?;
+    Ok(Some(path.display().to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = include_str!("../testdata/transcript/session.jsonl");
+    const GOLDEN: &str = include_str!("../testdata/transcript/transcript.md");
+
+    fn fixture() -> Transcript {
+        let records = FIXTURE.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+        Transcript::new("sentinel-4f2a9c1e", records, None)
+    }
+
+    #[test]
+    fn test_markdown_matches_golden() {
+        assert_eq!(render_markdown(&fixture()), GOLDEN);
+    }
+
+    #[test]
+    fn test_metadata_from_records() {
+        let meta = fixture().meta;
+        assert_eq!(meta.provider.as_deref(), Some("openai"));
+        assert_eq!(meta.tokens, TokenCounts::new(16_000, 2_000));
+        assert_eq!(format_duration(3_723), "1h 02m 03s");
+        assert_eq!(format_duration(75), "1m 15s");
+        assert_eq!(group(1_234_567), "1,234,567");
+        assert_eq!(group(999), "999");
+    }
+
+    #[test]
+    fn test_html_escapes_and_collapses_tool_output() {
+        let mut t = fixture();
+        t.report = Some("<script>alert(1)</script>".into());
+        let html = render_html(&t);
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("&lt;script&gt;alert(1)&lt;/script&gt;"));
+        assert!(!html.contains("<script>"));
+        assert!(html.contains("<details><summary>🔧 <b>read_file</b>"));
+        assert!(html.contains("<details><summary>1 lines</summary>"));
+        assert!(html.contains("<tr><th>Estimated cost</th><td>$0.0600</td></tr>"));
+        assert!(!html.contains("sk-proj-"));
+
+        // A stored report wins over the one in the session.
+        let stored = Transcript::new("sentinel-4f2a9c1e", fixture().records, Some("# Stored\n".into()));
+        assert_eq!(stored.report.as_deref(), Some("# Stored\n"));
+    }
+}
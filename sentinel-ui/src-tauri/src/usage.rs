@@ -0,0 +1,96 @@
+//! Live token and cost counters.
+//!
+//! Agents attach a `usage` delta (tokens consumed since their last report)
+//! to `/status` and `/progress` callbacks. The callback server folds it into
+//! the per-agent totals here and emits `sentinel://usage`.
+
+use crate::commands::{AgentState, SharedAgentState};
+use sentinel_shared::pricing::{self, TokenCounts};
+use serde::Serialize;
+use tauri::State;
+
+/// Cumulative usage for one agent.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct AgentUsage {
+    pub provider: String,
+    pub model: String,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub total_tokens: u64,
+    /// `None` when the model isn't in the pricing table.
+    pub estimated_cost_usd: Option<f64>,
+}
+
+impl AgentUsage {
+    pub fn new(provider: &str, model: &str) -> Self {
+        let mut usage = Self { provider: provider.to_string(), model: model.to_string(), ..Default::default() };
+        usage.add(TokenCounts::default());
+        usage
+    }
+
+    pub fn add(&mut self, delta: TokenCounts) {
+        let mut tokens = TokenCounts::new(self.prompt_tokens, self.completion_tokens);
+        tokens.add(delta);
+        self.prompt_tokens = tokens.prompt_tokens;
+        self.completion_tokens = tokens.completion_tokens;
+        self.total_tokens = tokens.total();
+        self.estimated_cost_usd = pricing::estimate_cost(&self.provider, &self.model, &tokens);
+    }
+}
+
+/// Totals across every agent launched this dashboard session.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct SessionUsage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub total_tokens: u64,
+    /// Sum over priced agents only; see `unpriced_agents`.
+    pub estimated_cost_usd: f64,
+    pub unpriced_agents: usize,
+}
+
+/// Payload of `sentinel://usage` and `get_agent_usage`.
+#[derive(Debug, Clone, Serialize)]
+pub struct UsageReport {
+    pub agent_id: String,
+    pub usage: AgentUsage,
+    pub session: SessionUsage,
+}
+
+pub fn session_total(agents: &AgentState) -> SessionUsage {
+    let mut total = SessionUsage::default();
+    for usage in agents.usage.values() {
+        total.prompt_tokens += usage.prompt_tokens;
+        total.completion_tokens += usage.completion_tokens;
+        total.total_tokens += usage.total_tokens;
+        match usage.estimated_cost_usd {
+            Some(cost) => total.estimated_cost_usd += cost,
+            None if usage.total_tokens > 0 => total.unpriced_agents += 1,
+            None => {}
+        }
+    }
+    total
+}
+
+/// Fold `delta` into `agent_id`'s totals. Returns the new report, or `None`
+/// if the delta was empty and nothing changed.
+pub fn record(agents: &mut AgentState, agent_id: &str, delta: TokenCounts) -> Option<UsageReport> {
+    if delta.is_empty() {
+        return None;
+    }
+    let usage = agents.usage.entry(agent_id.to_string()).or_default();
+    usage.add(delta);
+    let usage = usage.clone();
+    Some(UsageReport { agent_id: agent_id.to_string(), usage, session: session_total(agents) })
+}
+
+#[tauri::command]
+pub async fn get_agent_usage(
+    state: State<'_, SharedAgentState>,
+    agent_id: String,
+) -> Result<UsageReport, String> {
+    let agents = state.lock().await;
+    let usage = agents.usage.get(&agent_id).cloned()
+        .ok_or_else(|| format!("Unknown agent: {}", agent_id))?;
+    Ok(UsageReport { agent_id, usage, session: session_total(&agents) })
+}
@@ -0,0 +1,237 @@
+//! Read-only browsing of an agent's workspace from the host side.
+//!
+//! Lets the UI preview files an agent mentions (`src/auth.rs:88`) without
+//! leaving the app. Everything is read from the host directory behind the
+//! agent's primary mount, so it keeps working after the container exits.
+//! Paths are relative to that directory and must stay inside it.
+
+use crate::attach;
+use crate::commands::SharedAgentState;
+use bollard::container::InspectContainerOptions;
+use bollard::Docker;
+use sentinel_shared::paths::{self, PathError};
+use serde::Serialize;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use tauri::State;
+
+/// Read limit when the caller doesn't give one.
+pub const DEFAULT_MAX_BYTES: usize = 256 * 1024;
+
+/// Upper bound on `max_bytes`; previews aren't meant for huge files.
+const MAX_READ_BYTES: usize = 4 * 1024 * 1024;
+
+/// How much of a file is checked for NUL bytes.
+const BINARY_SNIFF_BYTES: usize = 8 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WorkspaceEntry {
+    pub name: String,
+    /// Relative to the workspace root, `/`-separated.
+    pub path: String,
+    pub is_dir: bool,
+    pub size: u64,
+    /// Unix seconds, when the platform reports it.
+    pub modified: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FileContent {
+    Text {
+        path: String,
+        content: String,
+        size: u64,
+        /// Set when only the first `max_bytes` were returned.
+        truncated: bool,
+    },
+    /// Not shown; only its size is reported.
+    Binary { path: String, size: u64 },
+}
+
+fn path_error(e: PathError) -> String {
+    e.to_string()
+}
+
+/// `path` relative to `root`, `/`-separated.
+fn relative(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root).unwrap_or(path)
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Entries of `subpath` under `root`: directories first, then by name.
+pub fn list_dir(root: &Path, subpath: &str) -> Result<Vec<WorkspaceEntry>, String> {
+    let root = paths::resolve_within(root, "").map_err(path_error)?;
+    let dir = paths::resolve_within(&root, subpath).map_err(path_error)?;
+    if !dir.is_dir() {
+        return Err(format!("Not a directory: {}", subpath));
+    }
+    let mut entries = Vec::new();
+    for entry in std::fs::read_dir(&dir).map_err(|e| e.to_string())? {
+        let Ok(entry) = entry else { continue };
+        // Follows symlinks; dangling ones are left out.
+        let Ok(meta) = entry.metadata() else { continue };
+        entries.push(WorkspaceEntry {
+            name: entry.file_name().to_string_lossy().to_string(),
+            path: relative(&root, &entry.path()),
+            is_dir: meta.is_dir(),
+            size: if meta.is_dir() { 0 } else { meta.len() },
+            modified: meta.modified().ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_secs()),
+        });
+    }
+    entries.sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then_with(|| a.name.cmp(&b.name)));
+    Ok(entries)
+}
+
+/// Whether `bytes` (possibly cut off at `max_bytes`) look like binary data.
+/// Returns the length of valid text otherwise.
+fn text_len(bytes: &[u8]) -> Option<usize> {
+    if bytes[..bytes.len().min(BINARY_SNIFF_BYTES)].contains(&0) {
+        return None;
+    }
+    match std::str::from_utf8(bytes) {
+        Ok(_) => Some(bytes.len()),
+        // A multi-byte character split by the read limit is still text.
+        Err(e) if e.error_len().is_none() => Some(e.valid_up_to()),
+        Err(_) => None,
+    }
+}
+
+/// Up to `max_bytes` of `path` under `root` as text, or `Binary`.
+pub fn read_file(root: &Path, path: &str, max_bytes: usize) -> Result<FileContent, String> {
+    let file_path = paths::resolve_within(root, path).map_err(path_error)?;
+    if !file_path.is_file() {
+        return Err(format!("Not a file: {}", path));
+    }
+    let file = std::fs::File::open(&file_path).map_err(|e| e.to_string())?;
+    let size = file.metadata().map_err(|e| e.to_string())?.len();
+    let limit = max_bytes.clamp(1, MAX_READ_BYTES);
+    let mut bytes = Vec::with_capacity(limit.min(size as usize));
+    file.take(limit as u64).read_to_end(&mut bytes).map_err(|e| e.to_string())?;
+
+    let path = path.to_string();
+    Ok(match text_len(&bytes) {
+        Some(len) => {
+            bytes.truncate(len);
+            FileContent::Text {
+                path,
+                content: String::from_utf8(bytes).unwrap_or_default(),
+                size,
+                truncated: (len as u64) < size,
+            }
+        }
+        None => FileContent::Binary { path, size },
+    })
+}
+
+/// Host directory behind `agent_id`'s primary mount. Agents launched or
+/// attached in this session are known; otherwise the container, if it
+/// still exists, is inspected.
+async fn workspace_root(state: &SharedAgentState, agent_id: &str) -> Result<PathBuf, String> {
+    if let Some(dir) = state.lock().await.workspaces.get(agent_id) {
+        return Ok(dir.clone());
+    }
+    let docker = Docker::connect_with_local_defaults().map_err(|e| e.to_string())?;
+    let inspect = docker.inspect_container(agent_id, None::<InspectContainerOptions>).await
+        .map_err(|_| format!("Unknown agent: {}", agent_id))?;
+    attach::reconstruct(&inspect)?.info.target_dir
+        .map(PathBuf::from)
+        .ok_or_else(|| format!("{} has no workspace mount", agent_id))
+}
+
+#[tauri::command]
+pub async fn list_workspace(
+    state: State<'_, SharedAgentState>,
+    agent_id: String,
+    subpath: Option<String>,
+) -> Result<Vec<WorkspaceEntry>, String> {
+    let root = workspace_root(state.inner(), &agent_id).await?;
+    list_dir(&root, subpath.as_deref().unwrap_or(""))
+}
+
+#[tauri::command]
+pub async fn read_workspace_file(
+    state: State<'_, SharedAgentState>,
+    agent_id: String,
+    path: String,
+    max_bytes: Option<usize>,
+) -> Result<FileContent, String> {
+    let root = workspace_root(state.inner(), &agent_id).await?;
+    read_file(&root, &path, max_bytes.unwrap_or(DEFAULT_MAX_BYTES))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("workspace");
+        std::fs::create_dir_all(root.join("src/auth")).unwrap();
+        std::fs::write(root.join("src/auth.rs"), "fn check() {}\n".repeat(10)).unwrap();
+        std::fs::write(root.join("README.md"), "# Demo\n").unwrap();
+        std::fs::write(root.join("logo.png"), [0x89, b'P', b'N', b'G', 0, 0, 0, 13]).unwrap();
+        std::fs::write(dir.path().join("secret.env"), "TOKEN=x").unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_listing_fixture_tree() {
+        let dir = fixture();
+        let root = dir.path().join("workspace");
+        let names: Vec<(String, bool)> = list_dir(&root, "").unwrap().into_iter().map(|e| (e.path, e.is_dir)).collect();
+        assert_eq!(names, vec![
+            ("src".to_string(), true),
+            ("README.md".to_string(), false),
+            ("logo.png".to_string(), false),
+        ]);
+
+        let src = list_dir(&root, "src").unwrap();
+        assert_eq!(src[0].path, "src/auth");
+        assert_eq!(src[1].path, "src/auth.rs");
+        assert_eq!(src[1].size, 140);
+        assert!(src[1].modified.is_some());
+        assert!(list_dir(&root, "README.md").is_err());
+    }
+
+    #[test]
+    fn test_containment_rejected() {
+        let dir = fixture();
+        let root = dir.path().join("workspace");
+        assert!(read_file(&root, "../secret.env", 100).unwrap_err().contains("escapes"));
+        assert!(read_file(&root, "/etc/passwd", 100).is_err());
+        assert!(list_dir(&root, "src/../..").unwrap_err().contains("escapes"));
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(dir.path().join("secret.env"), root.join("link.env")).unwrap();
+            assert!(read_file(&root, "link.env", 100).unwrap_err().contains("escapes"));
+        }
+    }
+
+    #[test]
+    fn test_binary_detection_and_truncation() {
+        let dir = fixture();
+        let root = dir.path().join("workspace");
+        assert_eq!(read_file(&root, "logo.png", 1024).unwrap(), FileContent::Binary { path: "logo.png".into(), size: 8 });
+
+        let FileContent::Text { content, size, truncated, .. } = read_file(&root, "src/auth.rs", 14).unwrap() else {
+            panic!("expected text");
+        };
+        assert_eq!((content.as_str(), size, truncated), ("fn check() {}\n", 140, true));
+
+        // A limit landing inside a multi-byte character keeps the text.
+        std::fs::write(root.join("notes.md"), "café").unwrap();
+        let FileContent::Text { content, .. } = read_file(&root, "notes.md", 4).unwrap() else {
+            panic!("expected text");
+        };
+        assert_eq!(content, "caf");
+        std::fs::write(root.join("latin1.txt"), [b'c', b'a', b'f', 0xe9, b'!']).unwrap();
+        assert!(matches!(read_file(&root, "latin1.txt", 100).unwrap(), FileContent::Binary { .. }));
+    }
+}
@@ -5,30 +5,107 @@ import LaunchPanel from "./components/LaunchPanel";
 import LogFeed from "./components/LogFeed";
 import HitlModal from "./components/HitlModal";
 
-interface LogEntry { level: string; target: string; message: string; }
-interface ManifestInfo { id: string; action_description: string; parameters_json: string; risk_level: string; }
+type MessageKind = "thought" | "question" | "report-section" | "report" | "tool-use" | "system";
+interface LogEntry { level: string; target: string; message: string; kind?: MessageKind; title?: string; }
+interface AgentMessage { agent_id: string; kind: MessageKind; message: string; title?: string; }
+interface SessionUsage { total_tokens: number; estimated_cost_usd: number; unpriced_agents: number; }
+interface UsageReport { agent_id: string; session: SessionUsage; }
+interface AgentStats { agent_id: string; cpu_percent: number; memory_bytes: number; memory_limit_bytes: number; memory_percent: number; }
+interface AgentListing { agent_id: string; container_id: string; attached: boolean; }
+interface SessionRecord { kind: string; level?: string; target?: string; message?: string; title?: string; tool?: string; }
+
+// Session record kinds that are chat messages, and their message kind.
+const MESSAGE_RECORDS: Record<string, MessageKind> = {
+    thought: "thought", question: "question", report_section: "report-section",
+    report: "report", tool_use: "tool-use", system: "system",
+};
+interface ManifestInfo { id: string; agent_id: string; action_description: string; parameters_json: string; risk_level: string; age_secs?: number; }
+interface HitlResolved { manifest_id: string; agent_id: string; approved: boolean; resolution: string; }
 
 function App() {
     const [logs, setLogs] = useState<LogEntry[]>([]);
     const [isRunning, setIsRunning] = useState(false);
+    // The agent the stop button stops: the last one launched from this window.
+    const [agentId, setAgentId] = useState<string | null>(null);
     const [hitlRequest, setHitlRequest] = useState<ManifestInfo | null>(null);
+    const [usage, setUsage] = useState<SessionUsage | null>(null);
+    const [stats, setStats] = useState<AgentStats | null>(null);
 
     useEffect(() => {
         const unlistenLog = listen<LogEntry>("sentinel://log", (event) => {
             setLogs((prev) => [...prev.slice(-500), event.payload]);
         });
-        const unlistenHitl = listen<ManifestInfo>("sentinel://hitl-request", (event) => {
+        const unlistenMessage = listen<AgentMessage>("sentinel://message", (event) => {
+            const { kind, message, title } = event.payload;
+            setLogs((prev) => [...prev.slice(-500), { level: "info", target: kind, message, kind, title }]);
+        });
+        const unlistenHitl = listen<ManifestInfo>("sentinel://hitl-pending", (event) => {
             setHitlRequest(event.payload);
         });
-        const unlistenStop = listen("sentinel://agent-stopped", () => { setIsRunning(false); });
-        return () => { unlistenLog.then((f) => f()); unlistenHitl.then((f) => f()); unlistenStop.then((f) => f()); };
+        // Resolved elsewhere (timeout, cancel, agent exit): drop it and show the next one waiting.
+        const unlistenResolved = listen<HitlResolved>("sentinel://hitl-resolved", async (event) => {
+            const pending = await invoke<ManifestInfo[]>("get_pending_manifests");
+            setHitlRequest((current) => {
+                if (current && current.id !== event.payload.manifest_id) return current;
+                return pending[0] ?? null;
+            });
+        });
+        const unlistenUsage = listen<UsageReport>("sentinel://usage", (event) => {
+            setUsage(event.payload.session);
+        });
+        const unlistenStats = listen<AgentStats>("sentinel://stats", (event) => {
+            setStats(event.payload);
+        });
+        const unlistenStop = listen("sentinel://agent-stopped", () => { setIsRunning(false); setStats(null); setAgentId(null); });
+        // A crashed agent came back; the system message in the feed says why.
+        const unlistenRestarted = listen("sentinel://agent-restarted", () => { setIsRunning(true); });
+        // Returning from a desktop notification: surface that agent's pending approval, if any.
+        const unlistenNavigate = listen<{ agent_id: string }>("sentinel://navigate-agent", async (event) => {
+            const pending = await invoke<ManifestInfo[]>("get_pending_manifests");
+            const manifest = pending.find((m) => m.agent_id === event.payload.agent_id);
+            if (manifest) setHitlRequest(manifest);
+        });
+        // Reconnect to agents that kept running while the dashboard was closed.
+        invoke<AgentListing[]>("list_agents").then(async (agents) => {
+            for (const agent of agents.filter((a) => !a.attached)) {
+                const { history } = await invoke<{ history: SessionRecord[] }>("attach_agent", { containerId: agent.container_id });
+                const restored: LogEntry[] = history
+                    .filter((r) => r.kind === "log" || r.kind in MESSAGE_RECORDS)
+                    .map((r) => {
+                        const kind = MESSAGE_RECORDS[r.kind];
+                        return {
+                            level: r.level ?? "info",
+                            target: r.target ?? kind ?? "agent",
+                            message: r.message ?? "",
+                            kind,
+                            title: r.title ?? r.tool,
+                        };
+                    });
+                setLogs((prev) => [...prev, ...restored].slice(-500));
+                setIsRunning(true);
+            }
+        }).catch(() => { /* Docker unavailable; nothing to reconnect to */ });
+        return () => { unlistenLog.then((f) => f()); unlistenMessage.then((f) => f()); unlistenHitl.then((f) => f()); unlistenResolved.then((f) => f()); unlistenStop.then((f) => f()); unlistenRestarted.then((f) => f()); unlistenUsage.then((f) => f()); unlistenStats.then((f) => f()); unlistenNavigate.then((f) => f()); };
     }, []);
 
     const handleApprove = useCallback(async (manifestId: string, approved: boolean) => {
-        await invoke("handle_hitl_approval", { manifestId, approved });
-        setHitlRequest(null);
+        // The hitl-resolved event advances the queue; if the manifest was
+        // already gone there is nothing left to show for it.
+        await invoke("handle_hitl_approval", { manifestId, approved }).catch(() => setHitlRequest(null));
+    }, []);
+
+    const handleRejectAll = useCallback(async (agentId: string) => {
+        await invoke("reject_all_pending", { agentId });
     }, []);
 
+    const handleStop = useCallback(async () => {
+        if (!agentId) return;
+        await invoke("stop_agent", { agentId });
+        setAgentId(null);
+        setIsRunning(false);
+        setStats(null);
+    }, [agentId]);
+
     return (
         <div className="app">
             <header className="header">
@@ -37,8 +114,25 @@ function App() {
                     <span className="header-tag">v0.1.0</span>
                 </div>
                 <div className="header-status">
+                    {usage && (
+                        <span className="header-usage" title={usage.unpriced_agents > 0 ? "Excludes agents on unpriced models" : "Estimated"}>
+                            {usage.total_tokens.toLocaleString()} tokens · ~${usage.estimated_cost_usd.toFixed(2)}
+                            {usage.unpriced_agents > 0 ? "+" : ""}
+                        </span>
+                    )}
+                    {isRunning && stats && (
+                        <span className={`header-stats ${stats.memory_percent >= 90 ? "warning" : ""}`} title={stats.agent_id}>
+                            CPU {stats.cpu_percent.toFixed(0)}% · {(stats.memory_bytes / 1048576).toFixed(0)}
+                            {stats.memory_limit_bytes > 0 ? ` / ${(stats.memory_limit_bytes / 1048576).toFixed(0)}` : ""} MiB
+                        </span>
+                    )}
                     <span className={`status-dot ${isRunning ? "active" : ""}`} />
                     <span>{isRunning ? "Running" : "Idle"}</span>
+                    {agentId && (
+                        <button className="btn-stop" onClick={handleStop} title={`Stop ${agentId}`}>
+                            Stop
+                        </button>
+                    )}
                 </div>
             </header>
 
@@ -48,7 +142,7 @@ function App() {
                   Otherwise, transition the launch pad upwards or aside and show the log feed.
                 */}
                 <div className={`launch-wrapper ${isRunning || logs.length > 0 ? "minimized" : "centered"}`}>
-                    <LaunchPanel isRunning={isRunning} setIsRunning={setIsRunning} setLogs={setLogs} />
+                    <LaunchPanel isRunning={isRunning} setIsRunning={setIsRunning} setLogs={setLogs} onLaunched={setAgentId} />
                 </div>
 
                 {(isRunning || logs.length > 0) && (
@@ -58,7 +152,7 @@ function App() {
                 )}
             </main>
 
-            {hitlRequest && <HitlModal manifest={hitlRequest} onDecision={handleApprove} />}
+            {hitlRequest && <HitlModal key={hitlRequest.id} manifest={hitlRequest} onDecision={handleApprove} onRejectAll={handleRejectAll} />}
         </div>
     );
 }
@@ -2,7 +2,8 @@ import { useState, useEffect, useRef } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import Markdown from "./Markdown";
 
-interface LogEntry { level: string; target: string; message: string; }
+type MessageKind = "thought" | "question" | "report-section" | "report" | "tool-use" | "system";
+interface LogEntry { level: string; target: string; message: string; kind?: MessageKind; title?: string; }
 
 interface Props {
     agentId: string;
@@ -23,7 +24,6 @@ interface ChatItem {
 function parseLogs(logs: LogEntry[]): ChatItem[] {
     const items: ChatItem[] = [];
     let currentLogGroup: LogEntry[] = [];
-    let pendingThought = "";
 
     const flushLogGroup = () => {
         if (currentLogGroup.length > 0) {
@@ -32,65 +32,37 @@ function parseLogs(logs: LogEntry[]): ChatItem[] {
         }
     };
 
-    const flushThought = () => {
-        if (pendingThought) {
-            items.push({ type: "thought", content: pendingThought.trim() });
-            pendingThought = "";
-        }
-    };
-
     for (let i = 0; i < logs.length; i++) {
         const log = logs[i];
         const msg = log.message;
 
         // User messages
         if (msg.startsWith("USER:")) {
-            flushThought();
             flushLogGroup();
             items.push({ type: "user-message", content: msg.replace("USER:", "").trim() });
             continue;
         }
 
-        // THOUGHT messages — merge consecutive ones
-        if (msg.startsWith("THOUGHT:") || msg.startsWith("agent THOUGHT:")) {
+        // Typed agent messages (sentinel://message)
+        if (log.kind) {
             flushLogGroup();
-            const content = msg.replace("agent THOUGHT:", "").replace("THOUGHT:", "").trim();
-
-            // Skip "Waiting for LLM" messages — those go to logs
-            if (content.startsWith("Waiting for LLM")) {
-                currentLogGroup.push(log);
-                continue;
-            }
-
-            if (pendingThought) {
-                // If this is a new thought (starts with a tool or action), flush the old one
-                if (content.startsWith("Using tool:") || content.startsWith("Task received:") ||
-                    content.startsWith("✅") || content.startsWith("❌") || content.startsWith("⚠️") ||
-                    content.startsWith("🔀") || content.startsWith("This task requires")) {
-                    flushThought();
-                    pendingThought = content;
-                } else {
-                    // Continue the previous thought (multi-line LLM response)
-                    pendingThought += "\n" + content;
-                }
-            } else {
-                pendingThought = content;
+            switch (log.kind) {
+                case "report":
+                case "report-section":
+                    items.push({ type: "report", content: log.title ? `## ${log.title}\n\n${msg}` : msg });
+                    break;
+                case "tool-use":
+                    items.push({ type: "thought", content: `Using tool: **${log.title ?? ""}** ${msg}` });
+                    break;
+                case "system":
+                    items.push({ type: "phase", content: msg });
+                    break;
+                default:
+                    items.push({ type: "thought", content: msg });
             }
             continue;
         }
 
-        // Lines without any prefix that follow a THOUGHT — merge them
-        if (pendingThought && !msg.startsWith("[") && !msg.includes("GUI_ACTIVE") &&
-            !msg.includes("container stopped") && !msg.includes("Tool result") &&
-            !msg.startsWith("172.17.") && !msg.includes("websockify") && !msg.includes("connecting to:") &&
-            !msg.includes("code 404") && !msg.includes("Plain non-SSL")) {
-            pendingThought += "\n" + msg;
-            continue;
-        }
-
-        // Flush any pending thought before processing other message types
-        flushThought();
-
         if (msg.includes("GUI_ACTIVE:true") || msg.includes("Opening the live view")) {
             flushLogGroup();
             items.push({ type: "gui-start", content: "Browser view activated" });
@@ -124,7 +96,6 @@ function parseLogs(logs: LogEntry[]): ChatItem[] {
         currentLogGroup.push(log);
     }
 
-    flushThought();
     flushLogGroup();
     return items;
 }
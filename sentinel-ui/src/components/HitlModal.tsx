@@ -1,8 +1,24 @@
-interface ManifestInfo { id: string; action_description: string; parameters_json: string; risk_level: string; }
-interface Props { manifest: ManifestInfo; onDecision: (manifestId: string, approved: boolean) => void; }
+import { useEffect, useState } from "react";
 
-export default function HitlModal({ manifest, onDecision }: Props) {
+interface ManifestInfo { id: string; agent_id: string; action_description: string; parameters_json: string; risk_level: string; age_secs?: number; }
+interface Props {
+    manifest: ManifestInfo;
+    onDecision: (manifestId: string, approved: boolean) => void;
+    onRejectAll: (agentId: string) => void;
+}
+
+function formatWait(secs: number) {
+    return secs < 60 ? `${secs}s` : `${Math.floor(secs / 60)}m ${secs % 60}s`;
+}
+
+export default function HitlModal({ manifest, onDecision, onRejectAll }: Props) {
     const riskClass = manifest.risk_level.toLowerCase();
+    const [waited, setWaited] = useState(manifest.age_secs ?? 0);
+
+    useEffect(() => {
+        const timer = setInterval(() => setWaited((s) => s + 1), 1000);
+        return () => clearInterval(timer);
+    }, []);
 
     return (
         <div className="hitl-overlay">
@@ -12,6 +28,7 @@ export default function HitlModal({ manifest, onDecision }: Props) {
                         <div className="hitl-pulse-dot"></div>
                         <h2>Approval Required</h2>
                     </div>
+                    <span className="hitl-age">Waiting {formatWait(waited)}</span>
                     <span className={`hitl-risk ${riskClass}`}>{manifest.risk_level}</span>
                 </div>
                 <div className="hitl-body">
@@ -25,6 +42,7 @@ export default function HitlModal({ manifest, onDecision }: Props) {
                     </div>
                 </div>
                 <div className="hitl-actions">
+                    <button className="btn-reject-all" onClick={() => onRejectAll(manifest.agent_id)}>Reject All</button>
                     <button className="btn-reject" onClick={() => onDecision(manifest.id, false)}>Reject</button>
                     <button className="btn-approve" onClick={() => onDecision(manifest.id, true)}>Approve</button>
                 </div>
@@ -1,23 +1,30 @@
 import { useState, useEffect, useRef } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import { open } from "@tauri-apps/plugin-dialog";
+import PreflightPanel from "./PreflightPanel";
 
 interface ProviderInfo { id: string; name: string; requires_key: boolean; default_model: string; }
 interface LogEntry { level: string; target: string; message: string; }
+type RestartPolicy = { mode: "off" } | { mode: "on-failure"; max_retries: number };
+interface AgentLaunch { agent_id: string; novnc_port: number | null; }
 interface Props {
     isRunning: boolean;
     setIsRunning: (v: boolean) => void;
     setLogs: React.Dispatch<React.SetStateAction<LogEntry[]>>;
+    onLaunched: (agentId: string) => void;
 }
 
-export default function LaunchPanel({ isRunning, setIsRunning, setLogs }: Props) {
+export default function LaunchPanel({ isRunning, setIsRunning, setLogs, onLaunched }: Props) {
     const [providers, setProviders] = useState<ProviderInfo[]>([]);
     const [provider, setProvider] = useState("ollama");
     const [model, setModel] = useState("llama3.1:8b");
     const [apiKey, setApiKey] = useState("");
+    const [hasStoredKey, setHasStoredKey] = useState(false);
     const [targetDirectory, setTargetDirectory] = useState(".");
     const [taskPrompt, setTaskPrompt] = useState("");
     const [errorMsg, setErrorMsg] = useState<string | null>(null);
+    // "default" defers to the Settings policy; otherwise the retry count.
+    const [restartRetries, setRestartRetries] = useState("default");
 
     const [showSettings, setShowSettings] = useState(false);
     const settingsRef = useRef<HTMLDivElement>(null);
@@ -27,6 +34,11 @@ export default function LaunchPanel({ isRunning, setIsRunning, setLogs }: Props)
 
     useEffect(() => { invoke<ProviderInfo[]>("get_providers").then(setProviders); }, []);
 
+    useEffect(() => {
+        if (!needsKey) return;
+        invoke<boolean>("has_provider_key", { provider }).then(setHasStoredKey).catch(() => setHasStoredKey(false));
+    }, [provider, needsKey]);
+
     useEffect(() => {
         function handleClickOutside(event: MouseEvent) {
             if (settingsRef.current && !settingsRef.current.contains(event.target as Node)) {
@@ -43,13 +55,22 @@ export default function LaunchPanel({ isRunning, setIsRunning, setLogs }: Props)
         setErrorMsg(null);
         setIsRunning(true);
         try {
-            await invoke("start_agent", {
+            // Keys live in the OS keychain; the backend resolves them at launch.
+            if (needsKey && apiKey.trim()) {
+                await invoke("set_provider_key", { provider, key: apiKey });
+                setApiKey("");
+                setHasStoredKey(true);
+            }
+            const launch = await invoke<AgentLaunch>("start_agent", {
                 provider,
                 model,
-                apiKey: needsKey ? apiKey : null,
+                apiKey: null,
                 targetDirectory: targetDirectory,
                 taskPrompt: taskPrompt,
+                preflight: true,
+                restartPolicy: restartPolicy(restartRetries),
             });
+            onLaunched(launch.agent_id);
         } catch (e) {
             console.error("Launch failed:", e);
             setErrorMsg(String(e));
@@ -132,6 +153,21 @@ export default function LaunchPanel({ isRunning, setIsRunning, setLogs }: Props)
                                         disabled={isRunning}
                                     />
                                 </div>
+                                <div className="form-group">
+                                    <label className="form-label">Restart on Crash</label>
+                                    <select
+                                        className="form-select"
+                                        value={restartRetries}
+                                        onChange={(e) => setRestartRetries(e.target.value)}
+                                        disabled={isRunning}
+                                    >
+                                        <option value="default">Settings default</option>
+                                        <option value="0">Never</option>
+                                        <option value="1">Up to 1 retry</option>
+                                        <option value="3">Up to 3 retries</option>
+                                        <option value="5">Up to 5 retries</option>
+                                    </select>
+                                </div>
                                 {needsKey && (
                                     <div className="form-group">
                                         <label className="form-label">API Key</label>
@@ -140,7 +176,7 @@ export default function LaunchPanel({ isRunning, setIsRunning, setLogs }: Props)
                                             type="password"
                                             value={apiKey}
                                             onChange={(e) => setApiKey(e.target.value)}
-                                            placeholder="sk-..."
+                                            placeholder={hasStoredKey ? "Saved in keychain" : "sk-..."}
                                             disabled={isRunning}
                                         />
                                     </div>
@@ -163,6 +199,8 @@ export default function LaunchPanel({ isRunning, setIsRunning, setLogs }: Props)
                 </div>
             </div>
 
+            <PreflightPanel provider={provider} model={model} />
+
             {errorMsg && (
                 <div className="error-banner">
                     <b>Error:</b> {errorMsg}
@@ -171,3 +209,9 @@ export default function LaunchPanel({ isRunning, setIsRunning, setLogs }: Props)
         </div>
     );
 }
+
+function restartPolicy(retries: string): RestartPolicy | null {
+    if (retries === "default") return null;
+    const n = Number(retries);
+    return n > 0 ? { mode: "on-failure", max_retries: n } : { mode: "off" };
+}
@@ -1,6 +1,16 @@
 import { useEffect, useRef } from "react";
 
-interface LogEntry { level: string; target: string; message: string; }
+type MessageKind = "thought" | "question" | "report-section" | "report" | "tool-use" | "system";
+interface LogEntry { level: string; target: string; message: string; kind?: MessageKind; title?: string; }
+
+const KIND_ICONS: Record<MessageKind, string> = {
+    thought: "🧠",
+    question: "❓",
+    "report-section": "📋",
+    report: "📋",
+    "tool-use": "🔧",
+    system: "⚙️",
+};
 
 export default function LogFeed({ logs }: { logs: LogEntry[] }) {
     const endRef = useRef<HTMLDivElement>(null);
@@ -22,26 +32,24 @@ export default function LogFeed({ logs }: { logs: LogEntry[] }) {
                 {logs.length === 0 && <div className="log-empty">No output yet. Launch an agent to begin.</div>}
 
                 {logs.map((log, i) => {
-                    const isThought = log.message.startsWith("THOUGHT:");
-                    const isWaiting = log.message.includes("Waiting for LLM response");
-                    const displayMessage = isThought ? log.message.replace("THOUGHT:", "").trim() : log.message;
-
-                    if (isThought) {
+                    if (log.kind) {
                         return (
-                            <div key={i} className="thought-bubble">
-                                <div className="thought-icon">{isWaiting ? '⏳' : '🧠'}</div>
-                                <div className={`thought-text ${isWaiting ? 'pulsing' : ''}`}>
-                                    {displayMessage}
+                            <div key={i} className={`thought-bubble ${log.kind}`}>
+                                <div className="thought-icon">{KIND_ICONS[log.kind]}</div>
+                                <div className="thought-text">
+                                    {log.title && <strong>{log.title}</strong>} {log.message}
                                 </div>
                             </div>
                         );
                     }
 
+                    const isWaiting = log.message.includes("Waiting for LLM response");
+
                     return (
                         <div key={i} className={`log-entry ${levelClass(log.level)}`}>
                             <span className="log-level">{log.level.toUpperCase()}</span>
                             <span className="log-target">{log.target}</span>
-                            <span className="log-message">{displayMessage}</span>
+                            <span className={`log-message ${isWaiting ? 'pulsing' : ''}`}>{log.message}</span>
                         </div>
                     );
                 })}
@@ -0,0 +1,55 @@
+import { useState, useEffect, useCallback } from "react";
+import { invoke } from "@tauri-apps/api/core";
+
+type CheckStatus = "pass" | "warn" | "fail" | "skipped";
+interface Check { id: string; label: string; status: CheckStatus; detail: string; fix: string | null; }
+interface Diagnostics { ok: boolean; checks: Check[]; }
+interface Props { provider: string; model: string; }
+
+const STATUS_ICONS: Record<CheckStatus, string> = { pass: "✅", warn: "⚠️", fail: "❌", skipped: "⏭️" };
+
+export default function PreflightPanel({ provider, model }: Props) {
+    const [diagnostics, setDiagnostics] = useState<Diagnostics | null>(null);
+    const [running, setRunning] = useState(false);
+
+    const runChecks = useCallback(async () => {
+        setRunning(true);
+        try {
+            setDiagnostics(await invoke<Diagnostics>("run_diagnostics", { target: { provider, model } }));
+        } catch (e) {
+            console.error("Diagnostics failed:", e);
+        } finally {
+            setRunning(false);
+        }
+    }, [provider, model]);
+
+    // Debounced: each run starts a throwaway container.
+    useEffect(() => {
+        const timer = setTimeout(runChecks, 800);
+        return () => clearTimeout(timer);
+    }, [runChecks]);
+
+    // Stay out of the way once everything passes.
+    const problems = diagnostics?.checks.filter((c) => c.status === "fail" || c.status === "warn") ?? [];
+    if (!diagnostics || problems.length === 0) return null;
+
+    return (
+        <div className={`preflight-panel ${diagnostics.ok ? "" : "failing"}`}>
+            <div className="preflight-header">
+                <span>{diagnostics.ok ? "Environment warnings" : "Sentinel can't launch agents yet"}</span>
+                <button className="btn-header" onClick={runChecks} disabled={running}>
+                    {running ? "Checking..." : "Re-check"}
+                </button>
+            </div>
+            {problems.map((check) => (
+                <div key={check.id} className={`preflight-check ${check.status}`}>
+                    <span className="preflight-icon">{STATUS_ICONS[check.status]}</span>
+                    <div>
+                        <div><b>{check.label}:</b> {check.detail}</div>
+                        {check.fix && <div className="setting-hint">{check.fix}</div>}
+                    </div>
+                </div>
+            ))}
+        </div>
+    );
+}
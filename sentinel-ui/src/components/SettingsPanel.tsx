@@ -1,4 +1,11 @@
-import type { ResourceLimits, NotificationConfig } from "../App";
+import { useEffect, useState } from "react";
+ import { invoke } from "@tauri-apps/api/core";
+ import type { ResourceLimits, NotificationConfig } from "../App";
+ 
+ interface HostCapabilities { gpu: boolean; runtimes: string[]; default_runtime: string | null; }
+ type RestartPolicy = { mode: "off" } | { mode: "on-failure"; max_retries: number };
+ // Backend settings; only the fields edited here are typed.
+ interface DashboardSettings { restart_policy: RestartPolicy; [key: string]: unknown; }
  
  interface Props {
      resourceLimits: ResourceLimits;
@@ -13,6 +20,23 @@ import type { ResourceLimits, NotificationConfig } from "../App";
      notifications,
      setNotifications,
  }: Props) {
+     const [host, setHost] = useState<HostCapabilities | null>(null);
+     const [backend, setBackend] = useState<DashboardSettings | null>(null);
+ 
+     useEffect(() => {
+         invoke<HostCapabilities>("get_host_capabilities").then(setHost).catch(() => setHost(null));
+         invoke<DashboardSettings>("get_settings").then(setBackend).catch(() => setBackend(null));
+     }, []);
+ 
+     const maxRetries = backend?.restart_policy.mode === "on-failure" ? backend.restart_policy.max_retries : 0;
+     const updateRestartRetries = async (retries: number) => {
+         if (!backend) return;
+         const restart_policy: RestartPolicy = retries > 0 ? { mode: "on-failure", max_retries: retries } : { mode: "off" };
+         const next = { ...backend, restart_policy };
+         setBackend(next);
+         await invoke("update_settings", { settings: next }).catch((e) => console.error("Failed to save settings:", e));
+     };
+ 
      const updateLimit = <K extends keyof ResourceLimits>(key: K, value: ResourceLimits[K]) => {
          setResourceLimits((prev) => ({ ...prev, [key]: value }));
      };
@@ -115,6 +139,40 @@ import type { ResourceLimits, NotificationConfig } from "../App";
                              <span>180s</span>
                          </div>
                      </div>
+ 
+                     {/* Crash Restarts */}
+                     <div className="setting-card">
+                         <div className="setting-card-header">
+                             <label className="setting-label">Crash Restarts</label>
+                             <span className="setting-value-badge">{maxRetries > 0 ? `${maxRetries}×` : "Off"}</span>
+                         </div>
+                         <input
+                             type="range"
+                             className="setting-range"
+                             min={0}
+                             max={5}
+                             step={1}
+                             value={maxRetries}
+                             disabled={!backend}
+                             onChange={(e) => updateRestartRetries(Number(e.target.value))}
+                         />
+                         <p className="setting-hint">
+                             Re-create an agent that exits with an error, resuming its session. Clean exits and manual stops never restart.
+                         </p>
+                     </div>
+ 
+                     {/* GPU Passthrough */}
+                     <div className="setting-card">
+                         <div className="setting-card-header">
+                             <label className="setting-label">GPU Passthrough</label>
+                             <span className="setting-value-badge">{host === null ? "Unknown" : host.gpu ? "Available" : "Unavailable"}</span>
+                         </div>
+                         <p className="setting-hint">
+                             {host?.gpu
+                                 ? "Agents launched with a GPU run Ollama inside their container."
+                                 : "Requires the NVIDIA Container Toolkit on the Docker host."}
+                         </p>
+                     </div>
                  </div>
              </section>
  